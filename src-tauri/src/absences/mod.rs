@@ -5,9 +5,8 @@
 /// Totaux par periode pour export LSU.
 
 use serde::{Deserialize, Serialize};
-use sqlx::Connection;
 
-use crate::migrations::get_db_path;
+use crate::error::AppError;
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Types
@@ -99,7 +98,7 @@ impl From<AbsenceRow> for AbsenceV2 {
 pub async fn toggle_absence_impl(
     conn: &mut sqlx::sqlite::SqliteConnection,
     absence: &NewAbsence,
-) -> Result<Option<i64>, String> {
+) -> Result<Option<i64>, AppError> {
     // Check if already exists
     let existing: Option<(i64,)> = sqlx::query_as(
         "SELECT id FROM absences_v2 WHERE eleve_id = ? AND date = ? AND demi_journee = ?",
@@ -109,7 +108,7 @@ pub async fn toggle_absence_impl(
     .bind(&absence.demi_journee)
     .fetch_optional(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur requete absence : {}", e))?;
+    .map_err(|e| AppError::db("Erreur requete absence", e))?;
 
     if let Some((id,)) = existing {
         // Remove — eleve redevient present
@@ -117,7 +116,7 @@ pub async fn toggle_absence_impl(
             .bind(id)
             .execute(&mut *conn)
             .await
-            .map_err(|e| format!("Erreur suppression absence : {}", e))?;
+            .map_err(|e| AppError::db("Erreur suppression absence", e))?;
         Ok(None) // Removed
     } else {
         // Insert new absence
@@ -137,7 +136,7 @@ pub async fn toggle_absence_impl(
         .bind(absence.annee_scolaire_id)
         .execute(&mut *conn)
         .await
-        .map_err(|e| format!("Erreur insertion absence : {}", e))?;
+        .map_err(|e| AppError::db("Erreur insertion absence", e))?;
 
         Ok(Some(result.last_insert_rowid()))
     }
@@ -148,13 +147,13 @@ pub async fn update_absence_type_impl(
     conn: &mut sqlx::sqlite::SqliteConnection,
     id: i64,
     type_absence: &str,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     sqlx::query("UPDATE absences_v2 SET type_absence = ? WHERE id = ?")
         .bind(type_absence)
         .bind(id)
         .execute(&mut *conn)
         .await
-        .map_err(|e| format!("Erreur update type absence : {}", e))?;
+        .map_err(|e| AppError::db("Erreur update type absence", e))?;
     Ok(())
 }
 
@@ -163,13 +162,13 @@ pub async fn update_absence_motif_impl(
     conn: &mut sqlx::sqlite::SqliteConnection,
     id: i64,
     motif: &str,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     sqlx::query("UPDATE absences_v2 SET motif = ? WHERE id = ?")
         .bind(motif)
         .bind(id)
         .execute(&mut *conn)
         .await
-        .map_err(|e| format!("Erreur update motif absence : {}", e))?;
+        .map_err(|e| AppError::db("Erreur update motif absence", e))?;
     Ok(())
 }
 
@@ -180,7 +179,7 @@ pub async fn toggle_retard_impl(
     date: &str,
     demi_journee: &str,
     annee_scolaire_id: i64,
-) -> Result<bool, String> {
+) -> Result<bool, AppError> {
     // Check if retard record exists
     let existing: Option<(i64, i32)> = sqlx::query_as(
         "SELECT id, retard FROM absences_v2 WHERE eleve_id = ? AND date = ? AND demi_journee = ?",
@@ -190,7 +189,7 @@ pub async fn toggle_retard_impl(
     .bind(demi_journee)
     .fetch_optional(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur requete retard : {}", e))?;
+    .map_err(|e| AppError::db("Erreur requete retard", e))?;
 
     if let Some((id, retard)) = existing {
         // Toggle retard flag on existing absence
@@ -200,7 +199,7 @@ pub async fn toggle_retard_impl(
             .bind(id)
             .execute(&mut *conn)
             .await
-            .map_err(|e| format!("Erreur toggle retard : {}", e))?;
+            .map_err(|e| AppError::db("Erreur toggle retard", e))?;
         Ok(new_retard != 0)
     } else {
         // Create absence record with retard flag only (type justifiee par defaut car retard != absence)
@@ -214,7 +213,7 @@ pub async fn toggle_retard_impl(
         .bind(annee_scolaire_id)
         .execute(&mut *conn)
         .await
-        .map_err(|e| format!("Erreur insertion retard : {}", e))?;
+        .map_err(|e| AppError::db("Erreur insertion retard", e))?;
         Ok(true)
     }
 }
@@ -223,7 +222,7 @@ pub async fn toggle_retard_impl(
 pub async fn load_absences_impl(
     conn: &mut sqlx::sqlite::SqliteConnection,
     filter: &AbsenceFilter,
-) -> Result<Vec<AbsenceV2>, String> {
+) -> Result<Vec<AbsenceV2>, AppError> {
     let mut sql = String::from(
         "SELECT id, eleve_id, date, demi_journee, type_absence, motif, retard, annee_scolaire_id, created_at
          FROM absences_v2 WHERE annee_scolaire_id = ?",
@@ -253,7 +252,7 @@ pub async fn load_absences_impl(
     let rows = query
         .fetch_all(&mut *conn)
         .await
-        .map_err(|e| format!("Erreur chargement absences : {}", e))?;
+        .map_err(|e| AppError::db("Erreur chargement absences", e))?;
 
     Ok(rows.into_iter().map(|r| r.into()).collect())
 }
@@ -263,7 +262,7 @@ pub async fn compute_alerts_impl(
     conn: &mut sqlx::sqlite::SqliteConnection,
     annee_scolaire_id: i64,
     today: &str,
-) -> Result<Vec<AbsenceAlert>, String> {
+) -> Result<Vec<AbsenceAlert>, AppError> {
     let rows: Vec<(i64, i64)> = sqlx::query_as(
         "SELECT eleve_id, COUNT(*) as cnt
          FROM absences_v2
@@ -280,7 +279,7 @@ pub async fn compute_alerts_impl(
     .bind(today)
     .fetch_all(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur calcul alertes : {}", e))?;
+    .map_err(|e| AppError::db("Erreur calcul alertes", e))?;
 
     Ok(rows
         .into_iter()
@@ -294,7 +293,7 @@ pub async fn compute_totaux_periode_impl(
     annee_scolaire_id: i64,
     date_debut: &str,
     date_fin: &str,
-) -> Result<Vec<AbsenceTotaux>, String> {
+) -> Result<Vec<AbsenceTotaux>, AppError> {
     let rows: Vec<(i64, i64, i64)> = sqlx::query_as(
         "SELECT eleve_id,
                 SUM(CASE WHEN type_absence IN ('justifiee', 'medicale') THEN 1 ELSE 0 END) as justifiees,
@@ -311,7 +310,7 @@ pub async fn compute_totaux_periode_impl(
     .bind(date_fin)
     .fetch_all(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur calcul totaux : {}", e))?;
+    .map_err(|e| AppError::db("Erreur calcul totaux", e))?;
 
     Ok(rows
         .into_iter()
@@ -331,12 +330,8 @@ pub async fn compute_totaux_periode_impl(
 pub async fn toggle_absence_v2(
     app: tauri::AppHandle,
     absence: NewAbsence,
-) -> Result<Option<i64>, String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
+) -> Result<Option<i64>, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
     toggle_absence_impl(&mut conn, &absence).await
 }
 
@@ -345,12 +340,8 @@ pub async fn update_absence_type(
     app: tauri::AppHandle,
     id: i64,
     type_absence: String,
-) -> Result<(), String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
+) -> Result<(), AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
     update_absence_type_impl(&mut conn, id, &type_absence).await
 }
 
@@ -359,12 +350,8 @@ pub async fn update_absence_motif(
     app: tauri::AppHandle,
     id: i64,
     motif: String,
-) -> Result<(), String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
+) -> Result<(), AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
     update_absence_motif_impl(&mut conn, id, &motif).await
 }
 
@@ -375,12 +362,8 @@ pub async fn toggle_retard(
     date: String,
     demi_journee: String,
     annee_scolaire_id: i64,
-) -> Result<bool, String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
+) -> Result<bool, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
     toggle_retard_impl(&mut conn, eleve_id, &date, &demi_journee, annee_scolaire_id).await
 }
 
@@ -388,12 +371,8 @@ pub async fn toggle_retard(
 pub async fn load_absences_v2(
     app: tauri::AppHandle,
     filter: AbsenceFilter,
-) -> Result<Vec<AbsenceV2>, String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
+) -> Result<Vec<AbsenceV2>, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
     load_absences_impl(&mut conn, &filter).await
 }
 
@@ -402,12 +381,8 @@ pub async fn compute_absence_alerts(
     app: tauri::AppHandle,
     annee_scolaire_id: i64,
     today: String,
-) -> Result<Vec<AbsenceAlert>, String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
+) -> Result<Vec<AbsenceAlert>, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
     compute_alerts_impl(&mut conn, annee_scolaire_id, &today).await
 }
 
@@ -417,12 +392,8 @@ pub async fn compute_absence_totaux(
     annee_scolaire_id: i64,
     date_debut: String,
     date_fin: String,
-) -> Result<Vec<AbsenceTotaux>, String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
+) -> Result<Vec<AbsenceTotaux>, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
     compute_totaux_periode_impl(&mut conn, annee_scolaire_id, &date_debut, &date_fin).await
 }
 
@@ -433,6 +404,7 @@ pub async fn compute_absence_totaux(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sqlx::Connection;
 
     async fn setup_test_db() -> (sqlx::sqlite::SqliteConnection, tempfile::NamedTempFile) {
         let tmp = tempfile::NamedTempFile::new().unwrap();
@@ -1,50 +1,84 @@
-use sqlx::Connection;
-
-use crate::migrations::get_db_path;
+use crate::audio::retention::PurgeReport;
+use crate::error::AppError;
+use crate::events::audio::purge_event_audio_impl;
+use std::path::Path;
 
 /// Guard Rust — verifie qu'une annee scolaire n'est pas cloturee.
 /// Appelee par le frontend (invoke) avant toute ecriture scopee par annee.
 /// C'est la brique de securite centrale de la V2.1 (ADR-011).
 ///
-/// Retourne Ok(()) si l'annee est ouverte, Err(message) sinon.
+/// Retourne Ok(()) si l'annee est ouverte, Err(YearClosed | NotFound) sinon.
 pub async fn check_annee_not_closed_impl(
     conn: &mut sqlx::sqlite::SqliteConnection,
     annee_id: i64,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let cloturee: Option<i32> = sqlx::query_scalar::<_, i32>(
         "SELECT cloturee FROM annees_scolaires WHERE id = ?",
     )
     .bind(annee_id)
     .fetch_optional(&mut *conn)
     .await
-    .map_err(|e| e.to_string())?;
+    .map_err(|e| AppError::db("Erreur lecture annee scolaire", e))?;
 
     match cloturee {
-        None => Err("Annee scolaire introuvable".to_string()),
-        Some(1) => Err(
-            "L'annee scolaire est cloturee. Reouvrez-la pour modifier les donnees.".to_string(),
-        ),
+        None => Err(AppError::not_found("Annee scolaire introuvable")),
+        Some(1) => Err(AppError::YearClosed),
         Some(_) => Ok(()),
     }
 }
 
+/// Cloture une annee scolaire puis purge les enregistrements rattaches a ses
+/// evenements : la voix des eleves n'est plus conservee une fois l'annee close.
+pub async fn cloturer_annee_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    event_audio_dir: &Path,
+    keep_days: Option<u32>,
+    annee_id: i64,
+) -> Result<PurgeReport, AppError> {
+    let updated = sqlx::query("UPDATE annees_scolaires SET cloturee = 1 WHERE id = ?")
+        .bind(annee_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::db("Erreur cloture annee scolaire", e))?
+        .rows_affected();
+    if updated == 0 {
+        return Err(AppError::not_found("Annee scolaire introuvable"));
+    }
+    purge_event_audio_impl(conn, event_audio_dir, keep_days).await
+}
+
 /// Commande Tauri exposee au frontend.
 /// Le store TS appelle `invoke('check_annee_not_closed', { anneeId })` avant les ecritures critiques.
 #[tauri::command]
 pub async fn check_annee_not_closed(
     app: tauri::AppHandle,
     annee_id: i64,
-) -> Result<(), String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
+) -> Result<(), AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
 
     check_annee_not_closed_impl(&mut conn, annee_id).await
 }
 
+/// Cloture de l'annee depuis le frontend (anneeStore.cloturer), purge audio comprise.
+#[tauri::command]
+pub async fn cloturer_annee(
+    app: tauri::AppHandle,
+    annee_id: i64,
+) -> Result<(), AppError> {
+    let keep_days = crate::audio::commands::current_retention(&app).event_audio_days();
+    let audio_dir = crate::events::audio::event_audio_dir(&app)?;
+    let mut conn = crate::db::acquire(&app).await?;
+
+    let report = cloturer_annee_impl(&mut conn, &audio_dir, keep_days, annee_id).await?;
+    if report.deleted > 0 {
+        log::info!(
+            "Annee {} cloturee : {} enregistrement(s) supprime(s)",
+            annee_id, report.deleted
+        );
+    }
+    Ok(())
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Tests
 // ─────────────────────────────────────────────────────────────────────────────
@@ -52,6 +86,7 @@ pub async fn check_annee_not_closed(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sqlx::Connection;
 
     async fn setup_test_db() -> (sqlx::sqlite::SqliteConnection, tempfile::NamedTempFile) {
         let tmp = tempfile::NamedTempFile::new().unwrap();
@@ -108,8 +143,8 @@ mod tests {
         let result = check_annee_not_closed_impl(&mut conn, 1).await;
         assert!(result.is_err(), "Le guard doit bloquer une annee cloturee");
         assert!(
-            result.unwrap_err().contains("cloturee"),
-            "Le message doit mentionner la cloture"
+            matches!(result.unwrap_err(), AppError::YearClosed),
+            "Le guard doit renvoyer le code YearClosed"
         );
     }
 
@@ -119,8 +154,10 @@ mod tests {
 
         let result = check_annee_not_closed_impl(&mut conn, 999).await;
         assert!(result.is_err(), "Le guard doit rejeter un id inexistant");
+        let err = result.unwrap_err();
+        assert_eq!(err.code(), "NotFound");
         assert!(
-            result.unwrap_err().contains("introuvable"),
+            err.to_string().contains("introuvable"),
             "Le message doit mentionner introuvable"
         );
     }
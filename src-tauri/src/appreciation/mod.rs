@@ -4,10 +4,12 @@
 /// chargement courant, historique 5 versions, restauration d'une ancienne version.
 
 use serde::{Deserialize, Serialize};
-use sqlx::Connection;
 
 use crate::annee::check_annee_not_closed_impl;
-use crate::migrations::get_db_path;
+use crate::error::AppError;
+
+/// Longueur maximale d'une appréciation (CHECK de appreciations_generales.texte)
+const MAX_TEXTE_CHARS: usize = 1500;
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Types
@@ -22,6 +24,8 @@ pub struct AppreciationRow {
     pub version: i64,
     pub texte: String,
     pub generated_by: String,
+    /// Version du modèle de prompt ayant produit le texte (génération LLM)
+    pub prompt_template_id: Option<i64>,
     pub created_at: String,
 }
 
@@ -31,6 +35,8 @@ pub struct AppreciationVersion {
     pub version: i64,
     pub texte: String,
     pub generated_by: String,
+    /// Version du modèle de prompt ayant produit le texte (génération LLM)
+    pub prompt_template_id: Option<i64>,
     pub created_at: String,
 }
 
@@ -47,6 +53,7 @@ struct AppreciationDbRow {
     version: i64,
     texte: String,
     generated_by: String,
+    prompt_template_id: Option<i64>,
     created_at: String,
 }
 
@@ -60,6 +67,7 @@ impl From<AppreciationDbRow> for AppreciationRow {
             version: r.version,
             texte: r.texte,
             generated_by: r.generated_by,
+            prompt_template_id: r.prompt_template_id,
             created_at: r.created_at,
         }
     }
@@ -71,6 +79,7 @@ struct AppreciationVersionDbRow {
     version: i64,
     texte: String,
     generated_by: String,
+    prompt_template_id: Option<i64>,
     created_at: String,
 }
 
@@ -81,6 +90,7 @@ impl From<AppreciationVersionDbRow> for AppreciationVersion {
             version: r.version,
             texte: r.texte,
             generated_by: r.generated_by,
+            prompt_template_id: r.prompt_template_id,
             created_at: r.created_at,
         }
     }
@@ -99,9 +109,18 @@ pub async fn save_appreciation_impl(
     annee_scolaire_id: i64,
     texte: &str,
     generated_by: &str,
-) -> Result<AppreciationRow, String> {
+    prompt_template_id: Option<i64>,
+) -> Result<AppreciationRow, AppError> {
     check_annee_not_closed_impl(conn, annee_scolaire_id).await?;
 
+    let longueur = texte.chars().count();
+    if longueur > MAX_TEXTE_CHARS {
+        return Err(AppError::validation(format!(
+            "L'appréciation dépasse {} caractères ({}). Raccourcissez-la avant d'enregistrer.",
+            MAX_TEXTE_CHARS, longueur
+        )));
+    }
+
     // Get current max version for this combo
     let max_version: Option<i64> = sqlx::query_scalar(
         "SELECT MAX(version) FROM appreciations_generales
@@ -112,15 +131,15 @@ pub async fn save_appreciation_impl(
     .bind(annee_scolaire_id)
     .fetch_optional(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur lecture version max : {}", e))?
+    .map_err(|e| AppError::db("Erreur lecture version max", e))?
     .flatten();
 
     let next_version = max_version.unwrap_or(0) + 1;
 
     // Insert new version
     let insert_id = sqlx::query(
-        "INSERT INTO appreciations_generales (eleve_id, periode_id, annee_scolaire_id, version, texte, generated_by)
-         VALUES (?, ?, ?, ?, ?, ?)",
+        "INSERT INTO appreciations_generales (eleve_id, periode_id, annee_scolaire_id, version, texte, generated_by, prompt_template_id)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(eleve_id)
     .bind(periode_id)
@@ -128,9 +147,10 @@ pub async fn save_appreciation_impl(
     .bind(next_version)
     .bind(texte)
     .bind(generated_by)
+    .bind(prompt_template_id)
     .execute(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur insertion appréciation : {}", e))?
+    .map_err(|e| AppError::db("Erreur insertion appréciation", e))?
     .last_insert_rowid();
 
     // Cleanup: keep only 5 most recent versions
@@ -151,17 +171,17 @@ pub async fn save_appreciation_impl(
     .bind(annee_scolaire_id)
     .execute(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur cleanup appréciations : {}", e))?;
+    .map_err(|e| AppError::db("Erreur cleanup appréciations", e))?;
 
     // Return inserted row
     let row: AppreciationDbRow = sqlx::query_as(
-        "SELECT id, eleve_id, periode_id, annee_scolaire_id, version, texte, generated_by, created_at
+        "SELECT id, eleve_id, periode_id, annee_scolaire_id, version, texte, generated_by, prompt_template_id, created_at
          FROM appreciations_generales WHERE id = ?",
     )
     .bind(insert_id)
     .fetch_one(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur lecture appréciation insérée : {}", e))?;
+    .map_err(|e| AppError::db("Erreur lecture appréciation insérée", e))?;
 
     Ok(row.into())
 }
@@ -172,9 +192,9 @@ pub async fn load_appreciation_current_impl(
     eleve_id: i64,
     periode_id: i64,
     annee_scolaire_id: i64,
-) -> Result<Option<AppreciationRow>, String> {
+) -> Result<Option<AppreciationRow>, AppError> {
     let row: Option<AppreciationDbRow> = sqlx::query_as(
-        "SELECT id, eleve_id, periode_id, annee_scolaire_id, version, texte, generated_by, created_at
+        "SELECT id, eleve_id, periode_id, annee_scolaire_id, version, texte, generated_by, prompt_template_id, created_at
          FROM appreciations_generales
          WHERE eleve_id = ? AND periode_id = ? AND annee_scolaire_id = ?
          ORDER BY version DESC LIMIT 1",
@@ -184,7 +204,7 @@ pub async fn load_appreciation_current_impl(
     .bind(annee_scolaire_id)
     .fetch_optional(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur chargement appréciation courante : {}", e))?;
+    .map_err(|e| AppError::db("Erreur chargement appréciation courante", e))?;
 
     Ok(row.map(|r| r.into()))
 }
@@ -195,9 +215,9 @@ pub async fn load_appreciation_versions_impl(
     eleve_id: i64,
     periode_id: i64,
     annee_scolaire_id: i64,
-) -> Result<Vec<AppreciationVersion>, String> {
+) -> Result<Vec<AppreciationVersion>, AppError> {
     let rows: Vec<AppreciationVersionDbRow> = sqlx::query_as(
-        "SELECT id, version, texte, generated_by, created_at
+        "SELECT id, version, texte, generated_by, prompt_template_id, created_at
          FROM appreciations_generales
          WHERE eleve_id = ? AND periode_id = ? AND annee_scolaire_id = ?
          ORDER BY version DESC LIMIT 5",
@@ -207,7 +227,7 @@ pub async fn load_appreciation_versions_impl(
     .bind(annee_scolaire_id)
     .fetch_all(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur chargement versions appréciation : {}", e))?;
+    .map_err(|e| AppError::db("Erreur chargement versions appréciation", e))?;
 
     Ok(rows.into_iter().map(|r| r.into()).collect())
 }
@@ -219,7 +239,7 @@ pub async fn restore_appreciation_version_impl(
     periode_id: i64,
     annee_scolaire_id: i64,
     version_id: i64,
-) -> Result<AppreciationRow, String> {
+) -> Result<AppreciationRow, AppError> {
     check_annee_not_closed_impl(conn, annee_scolaire_id).await?;
 
     // Fetch the texte from the version to restore
@@ -229,9 +249,9 @@ pub async fn restore_appreciation_version_impl(
     .bind(version_id)
     .fetch_optional(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur lecture version à restaurer : {}", e))?;
+    .map_err(|e| AppError::db("Erreur lecture version à restaurer", e))?;
 
-    let texte = texte.ok_or_else(|| format!("Version introuvable : id={}", version_id))?;
+    let texte = texte.ok_or_else(|| AppError::not_found(format!("Version introuvable : id={}", version_id)))?;
 
     // Save as new version with generated_by='manual'
     save_appreciation_impl(
@@ -241,6 +261,7 @@ pub async fn restore_appreciation_version_impl(
         annee_scolaire_id,
         &texte,
         "manual",
+        None,
     )
     .await
 }
@@ -257,13 +278,19 @@ pub async fn save_appreciation(
     annee_scolaire_id: i64,
     texte: String,
     generated_by: String,
-) -> Result<AppreciationRow, String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
-    save_appreciation_impl(&mut conn, eleve_id, periode_id, annee_scolaire_id, &texte, &generated_by).await
+    prompt_template_id: Option<i64>,
+) -> Result<AppreciationRow, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
+    save_appreciation_impl(
+        &mut conn,
+        eleve_id,
+        periode_id,
+        annee_scolaire_id,
+        &texte,
+        &generated_by,
+        prompt_template_id,
+    )
+    .await
 }
 
 #[tauri::command]
@@ -272,12 +299,8 @@ pub async fn load_appreciation_current(
     eleve_id: i64,
     periode_id: i64,
     annee_scolaire_id: i64,
-) -> Result<Option<AppreciationRow>, String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
+) -> Result<Option<AppreciationRow>, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
     load_appreciation_current_impl(&mut conn, eleve_id, periode_id, annee_scolaire_id).await
 }
 
@@ -287,12 +310,8 @@ pub async fn load_appreciation_versions(
     eleve_id: i64,
     periode_id: i64,
     annee_scolaire_id: i64,
-) -> Result<Vec<AppreciationVersion>, String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
+) -> Result<Vec<AppreciationVersion>, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
     load_appreciation_versions_impl(&mut conn, eleve_id, periode_id, annee_scolaire_id).await
 }
 
@@ -303,12 +322,8 @@ pub async fn restore_appreciation_version(
     periode_id: i64,
     annee_scolaire_id: i64,
     version_id: i64,
-) -> Result<AppreciationRow, String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
+) -> Result<AppreciationRow, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
     restore_appreciation_version_impl(&mut conn, eleve_id, periode_id, annee_scolaire_id, version_id).await
 }
 
@@ -319,6 +334,7 @@ pub async fn restore_appreciation_version(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sqlx::Connection;
 
     async fn setup_test_db() -> (sqlx::sqlite::SqliteConnection, tempfile::NamedTempFile) {
         let tmp = tempfile::NamedTempFile::new().unwrap();
@@ -380,6 +396,7 @@ mod tests {
                 texte TEXT NOT NULL CHECK(length(texte) <= 1500),
                 version INTEGER NOT NULL DEFAULT 1,
                 generated_by TEXT DEFAULT 'manual' CHECK(generated_by IN ('llm', 'manual')),
+                prompt_template_id INTEGER DEFAULT NULL,
                 created_at TEXT DEFAULT (datetime('now'))
             )",
         )
@@ -402,13 +419,14 @@ mod tests {
     async fn test_save_increments_version() {
         let (mut conn, _tmp) = setup_test_db().await;
 
-        let a1 = save_appreciation_impl(&mut conn, 1, 1, 1, "Appréciation v1", "llm").await.unwrap();
+        let a1 = save_appreciation_impl(&mut conn, 1, 1, 1, "Appréciation v1", "llm", Some(2)).await.unwrap();
         assert_eq!(a1.version, 1);
+        assert_eq!(a1.prompt_template_id, Some(2));
 
-        let a2 = save_appreciation_impl(&mut conn, 1, 1, 1, "Appréciation v2", "manual").await.unwrap();
+        let a2 = save_appreciation_impl(&mut conn, 1, 1, 1, "Appréciation v2", "manual", None).await.unwrap();
         assert_eq!(a2.version, 2);
 
-        let a3 = save_appreciation_impl(&mut conn, 1, 1, 1, "Appréciation v3", "llm").await.unwrap();
+        let a3 = save_appreciation_impl(&mut conn, 1, 1, 1, "Appréciation v3", "llm", None).await.unwrap();
         assert_eq!(a3.version, 3);
     }
 
@@ -416,9 +434,9 @@ mod tests {
     async fn test_load_current_retourne_derniere_version() {
         let (mut conn, _tmp) = setup_test_db().await;
 
-        save_appreciation_impl(&mut conn, 1, 1, 1, "Version 1", "llm").await.unwrap();
-        save_appreciation_impl(&mut conn, 1, 1, 1, "Version 2", "manual").await.unwrap();
-        save_appreciation_impl(&mut conn, 1, 1, 1, "Version 3", "llm").await.unwrap();
+        save_appreciation_impl(&mut conn, 1, 1, 1, "Version 1", "llm", None).await.unwrap();
+        save_appreciation_impl(&mut conn, 1, 1, 1, "Version 2", "manual", None).await.unwrap();
+        save_appreciation_impl(&mut conn, 1, 1, 1, "Version 3", "llm", None).await.unwrap();
 
         let current = load_appreciation_current_impl(&mut conn, 1, 1, 1).await.unwrap();
         assert!(current.is_some());
@@ -440,7 +458,7 @@ mod tests {
         let (mut conn, _tmp) = setup_test_db().await;
 
         for i in 1..=3 {
-            save_appreciation_impl(&mut conn, 1, 1, 1, &format!("v{}", i), "llm").await.unwrap();
+            save_appreciation_impl(&mut conn, 1, 1, 1, &format!("v{}", i), "llm", None).await.unwrap();
         }
 
         let versions = load_appreciation_versions_impl(&mut conn, 1, 1, 1).await.unwrap();
@@ -455,7 +473,7 @@ mod tests {
         let (mut conn, _tmp) = setup_test_db().await;
 
         for i in 1..=7 {
-            save_appreciation_impl(&mut conn, 1, 1, 1, &format!("v{}", i), "llm").await.unwrap();
+            save_appreciation_impl(&mut conn, 1, 1, 1, &format!("v{}", i), "llm", None).await.unwrap();
         }
 
         let versions = load_appreciation_versions_impl(&mut conn, 1, 1, 1).await.unwrap();
@@ -468,13 +486,14 @@ mod tests {
     async fn test_restore_copie_texte_dans_nouvelle_version() {
         let (mut conn, _tmp) = setup_test_db().await;
 
-        let v1 = save_appreciation_impl(&mut conn, 1, 1, 1, "Texte original", "llm").await.unwrap();
-        save_appreciation_impl(&mut conn, 1, 1, 1, "Version modifiée", "manual").await.unwrap();
+        let v1 = save_appreciation_impl(&mut conn, 1, 1, 1, "Texte original", "llm", Some(2)).await.unwrap();
+        save_appreciation_impl(&mut conn, 1, 1, 1, "Version modifiée", "manual", None).await.unwrap();
 
         let restored = restore_appreciation_version_impl(&mut conn, 1, 1, 1, v1.id).await.unwrap();
         assert_eq!(restored.version, 3, "Restauration crée une nouvelle version");
         assert_eq!(restored.texte, "Texte original", "Texte copié depuis v1");
         assert_eq!(restored.generated_by, "manual");
+        assert_eq!(restored.prompt_template_id, None);
     }
 
     #[tokio::test]
@@ -486,15 +505,24 @@ mod tests {
             .await
             .unwrap();
 
-        let result = save_appreciation_impl(&mut conn, 1, 1, 1, "Appréciation", "llm").await;
+        let result = save_appreciation_impl(&mut conn, 1, 1, 1, "Appréciation", "llm", None).await;
         assert!(result.is_err(), "Année clôturée doit retourner une erreur");
     }
 
+    #[tokio::test]
+    async fn test_save_texte_trop_long_retourne_erreur_validation() {
+        let (mut conn, _tmp) = setup_test_db().await;
+
+        let err = save_appreciation_impl(&mut conn, 1, 1, 1, &"é".repeat(1501), "llm", None).await.unwrap_err();
+        assert_eq!(err.code(), "Validation");
+        assert!(save_appreciation_impl(&mut conn, 1, 1, 1, &"é".repeat(1500), "llm", None).await.is_ok());
+    }
+
     #[tokio::test]
     async fn test_restore_annee_cloturee_retourne_erreur() {
         let (mut conn, _tmp) = setup_test_db().await;
 
-        let v1 = save_appreciation_impl(&mut conn, 1, 1, 1, "Texte", "llm").await.unwrap();
+        let v1 = save_appreciation_impl(&mut conn, 1, 1, 1, "Texte", "llm", None).await.unwrap();
 
         sqlx::query("UPDATE annees_scolaires SET cloturee = 1 WHERE id = 1")
             .execute(&mut conn)
@@ -510,6 +538,6 @@ mod tests {
         let (mut conn, _tmp) = setup_test_db().await;
 
         let result = restore_appreciation_version_impl(&mut conn, 1, 1, 1, 9999).await;
-        assert!(result.is_err(), "ID introuvable doit retourner une erreur");
+        assert_eq!(result.unwrap_err().code(), "NotFound", "ID introuvable doit retourner NotFound");
     }
 }
@@ -6,44 +6,173 @@
 // Both plans produce WAV PCM 16-bit, 16kHz, mono files
 // compatible with whisper-server /inference endpoint.
 
+pub mod preprocess;
+pub mod retention;
+pub mod wav;
+
+/// Errors of WAV parsing and preprocessing
+#[derive(Debug, thiserror::Error)]
+pub enum AudioError {
+    #[error("Fichier WAV invalide : {0}")]
+    InvalidWav(String),
+
+    #[error("Format audio non supporte : {0}")]
+    Unsupported(String),
+
+    #[error("Aucune parole detectee dans l'enregistrement")]
+    NearSilent,
+
+    #[error("Enregistrement trop long ({duration_s} s, maximum {max_s} s)")]
+    TooLong { duration_s: u64, max_s: u64 },
+}
+
 pub mod commands {
-    use log::info;
+    use super::preprocess::{conform_recording, RecordingInfo, MAX_RECORDING_MS};
+    use super::retention::{
+        self, AudioStorageUsage, PurgeReport, RetentionPolicy, RECORDING_DIRS, RETENTION_FILE,
+    };
+    use crate::error::AppError;
+    use log::{info, warn};
+    use serde::Serialize;
     use std::io::Write;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
     use tauri::Manager;
 
-    /// Save raw WAV bytes (captured by Web Audio API fallback) to a temp file.
-    /// Returns the absolute path to the saved WAV file.
-    ///
-    /// Used by Plan B when tauri-plugin-mic-recorder fails.
-    /// The frontend captures audio via getUserMedia, resamples to 16kHz mono,
-    /// builds the WAV header+data, and sends the complete bytes here.
+    pub(crate) fn app_data_dir(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+        app.path()
+            .app_data_dir()
+            .map_err(|e| AppError::io("Impossible de trouver app_data_dir", e))
+    }
+
+    pub(crate) fn recording_dirs(app: &tauri::AppHandle) -> Result<Vec<PathBuf>, AppError> {
+        let data_dir = app_data_dir(app)?;
+        Ok(RECORDING_DIRS.iter().map(|d| data_dir.join(d)).collect())
+    }
+
+    /// Current retention policy (default if never set)
+    pub fn current_retention(app: &tauri::AppHandle) -> RetentionPolicy {
+        app_data_dir(app)
+            .map(|dir| retention::load_policy(&dir.join(RETENTION_FILE)))
+            .unwrap_or_default()
+    }
+
+    /// Called by transcribe_audio once whisper returned text: keep_none deletes the file.
+    pub fn apply_retention_after_transcription(app: &tauri::AppHandle, audio_path: &str) {
+        if !current_retention(app).delete_after_transcription() {
+            return;
+        }
+        match recording_dirs(app) {
+            Ok(dirs) => {
+                if let Err(e) = retention::delete_recording(&dirs, Path::new(audio_path)) {
+                    warn!("Retention audio: {}", e);
+                }
+            }
+            Err(e) => warn!("Retention audio: {}", e),
+        }
+    }
+
+    /// Startup purge of recordings older than the policy allows.
+    /// With keep_none, any leftover (crash, failed transcription) is deleted.
+    pub fn purge_stale_recordings(app: &tauri::AppHandle) {
+        let policy = current_retention(app);
+        let Ok(dirs) = recording_dirs(app) else {
+            return;
+        };
+        let report = retention::purge_older_than(&dirs, policy.max_age(), SystemTime::now());
+        if report.deleted > 0 {
+            info!(
+                "Purge audio au demarrage: {} fichier(s), {} octets liberes",
+                report.deleted, report.freed_bytes
+            );
+        }
+    }
+
     #[tauri::command]
-    pub async fn save_wav_file(
+    pub async fn get_audio_retention(app: tauri::AppHandle) -> Result<RetentionPolicy, AppError> {
+        Ok(current_retention(&app))
+    }
+
+    /// Persist a new policy and apply it immediately to existing recordings.
+    #[tauri::command]
+    pub async fn set_audio_retention(
         app: tauri::AppHandle,
-        wav_data: Vec<u8>,
-    ) -> Result<String, String> {
-        info!(
-            "Saving WAV file from Web Audio fallback ({} bytes)",
-            wav_data.len()
-        );
+        policy: RetentionPolicy,
+    ) -> Result<PurgeReport, AppError> {
+        policy.validate().map_err(AppError::Validation)?;
+        let path = app_data_dir(&app)?.join(RETENTION_FILE);
+        retention::save_policy(&path, &policy)
+            .map_err(|e| AppError::io("Impossible d'enregistrer la politique de conservation", e))?;
+        info!("Politique de conservation audio: {:?}", policy);
+
+        // Recordings attached to events follow the new policy right away
+        let mut report = crate::events::audio::purge_event_audio(&app).await?;
 
-        if wav_data.len() < 44 {
-            return Err("Données WAV invalides (trop petit)".to_string());
+        // keep_none applies to future transcriptions only: files of a dictation
+        // in progress must not disappear under the teacher's feet
+        if policy != RetentionPolicy::Discard {
+            let temp = retention::purge_older_than(
+                &recording_dirs(&app)?,
+                policy.max_age(),
+                SystemTime::now(),
+            );
+            report.deleted += temp.deleted;
+            report.freed_bytes += temp.freed_bytes;
         }
+        Ok(report)
+    }
+
+    #[tauri::command]
+    pub async fn get_audio_storage_usage(
+        app: tauri::AppHandle,
+    ) -> Result<AudioStorageUsage, AppError> {
+        let mut dirs = recording_dirs(&app)?;
+        dirs.push(crate::events::audio::event_audio_dir(&app)?);
+        Ok(retention::disk_usage(&dirs, SystemTime::now()))
+    }
 
-        // Validate WAV header magic bytes
-        if &wav_data[0..4] != b"RIFF" || &wav_data[8..12] != b"WAVE" {
-            return Err("Format WAV invalide (header incorrect)".to_string());
+    /// The teacher validated the dictation made from this recording.
+    /// With keep_until_validated, the file is deleted now.
+    #[tauri::command]
+    pub async fn mark_recording_validated(
+        app: tauri::AppHandle,
+        audio_path: String,
+    ) -> Result<(), AppError> {
+        if current_retention(&app).delete_on_validation() {
+            retention::delete_recording(&recording_dirs(&app)?, Path::new(&audio_path))
+                .map_err(|e| AppError::io("Suppression de l'enregistrement impossible", e))?;
         }
+        Ok(())
+    }
 
-        let data_dir = app
-            .path()
-            .app_data_dir()
-            .map_err(|e| format!("Impossible de trouver app_data_dir: {}", e))?;
+    /// Delete every temporary recording now, whatever the policy
+    /// (recordings attached to events are left to purge_event_audio).
+    #[tauri::command]
+    pub async fn clear_audio_recordings(app: tauri::AppHandle) -> Result<PurgeReport, AppError> {
+        let report =
+            retention::purge_older_than(&recording_dirs(&app)?, Duration::ZERO, SystemTime::now());
+        info!("Enregistrements supprimes: {}", report.deleted);
+        Ok(report)
+    }
+
+    /// Recording saved by `save_wav_file`
+    #[derive(Debug, Clone, Serialize)]
+    pub struct SavedRecording {
+        pub path: String,
+        #[serde(flatten)]
+        pub info: RecordingInfo,
+    }
 
-        let audio_dir = data_dir.join("audio_temp");
+    /// Save WAV bytes (captured by Web Audio API fallback) to a temp file.
+    /// Returns the absolute path and the audio metadata.
+    ///
+    /// Used by Plan B when tauri-plugin-mic-recorder fails.
+    /// Write a 16 kHz mono WAV into audio_temp (managed by the retention policy).
+    /// Returns the file path.
+    pub(crate) fn write_recording(app: &tauri::AppHandle, wav_data: &[u8]) -> Result<String, AppError> {
+        let audio_dir = app_data_dir(app)?.join(RECORDING_DIRS[0]);
         std::fs::create_dir_all(&audio_dir)
-            .map_err(|e| format!("Impossible de créer le répertoire audio_temp: {}", e))?;
+            .map_err(|e| AppError::io("Impossible de créer le répertoire audio_temp", e))?;
 
         let timestamp = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -53,14 +182,41 @@ pub mod commands {
         let file_path = audio_dir.join(format!("recording_{}.wav", timestamp));
 
         let mut file = std::fs::File::create(&file_path)
-            .map_err(|e| format!("Impossible de créer le fichier WAV: {}", e))?;
+            .map_err(|e| AppError::io("Impossible de créer le fichier WAV", e))?;
+
+        file.write_all(wav_data)
+            .map_err(|e| AppError::io("Impossible d'écrire le fichier WAV", e))?;
+
+        Ok(file_path.to_string_lossy().to_string())
+    }
+
+    /// The frontend captures audio via getUserMedia, resamples to 16kHz mono,
+    /// builds the WAV header+data, and sends the complete bytes here.
+    /// The full header is parsed: broken captures are rejected, longer than
+    /// MAX_RECORDING_MS are refused, and non PCM16/16kHz/mono input is converted.
+    #[tauri::command]
+    pub async fn save_wav_file(
+        app: tauri::AppHandle,
+        wav_data: Vec<u8>,
+    ) -> Result<SavedRecording, AppError> {
+        info!(
+            "Saving WAV file from Web Audio fallback ({} bytes)",
+            wav_data.len()
+        );
 
-        file.write_all(&wav_data)
-            .map_err(|e| format!("Impossible d'écrire le fichier WAV: {}", e))?;
+        let (wav_data, recording) = conform_recording(&wav_data, MAX_RECORDING_MS)?;
+        if recording.converted {
+            warn!(
+                "WAV converti: {}Hz/{}ch/{}bits -> 16000Hz/1ch/16bits",
+                recording.source_sample_rate,
+                recording.source_channels,
+                recording.source_bits_per_sample
+            );
+        }
 
-        let path_str = file_path.to_string_lossy().to_string();
-        info!("WAV file saved: {}", path_str);
+        let path_str = write_recording(&app, &wav_data)?;
+        info!("WAV file saved: {} ({}ms)", path_str, recording.duration_ms);
 
-        Ok(path_str)
+        Ok(SavedRecording { path: path_str, info: recording })
     }
 }
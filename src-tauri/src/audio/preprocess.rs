@@ -0,0 +1,418 @@
+// Audio preprocessing before whisper-server
+//
+// parse WAV → mono → resample to 16 kHz → trim leading/trailing silence (energy VAD)
+// → peak-normalise gain → PCM16 WAV.
+//
+// Near-silent recordings are rejected here with AudioError::NearSilent instead of
+// being sent to whisper, whose empty response would otherwise trigger the restart
+// watchdog in transcribe_audio.
+
+use super::wav::{encode_wav, parse_header, parse_wav, to_pcm16, WHISPER_SAMPLE_RATE};
+use super::AudioError;
+use serde::Serialize;
+
+/// Thresholds of the preprocessing stage
+#[derive(Debug, Clone, Copy)]
+pub struct PreprocessConfig {
+    /// VAD analysis frame length
+    pub frame_ms: u32,
+    /// Frames below this level (dBFS) are never speech, whatever the noise floor
+    pub vad_floor_dbfs: f32,
+    /// Frames above this level (dBFS) are always speech, even when the whole
+    /// recording is speech and the noise floor estimate is therefore too high
+    pub vad_ceiling_dbfs: f32,
+    /// A frame is speech when it is this much louder than the estimated noise floor
+    pub vad_margin_db: f32,
+    /// Silence kept before the first and after the last speech frame
+    pub padding_ms: u32,
+    /// Recordings with less detected speech than this are rejected
+    pub min_speech_ms: u32,
+    /// Peak level after normalisation
+    pub target_peak_dbfs: f32,
+    /// Cap on amplification, so background noise is not boosted into "speech"
+    pub max_gain_db: f32,
+}
+
+impl Default for PreprocessConfig {
+    fn default() -> Self {
+        Self {
+            frame_ms: 30,
+            vad_floor_dbfs: -45.0,
+            vad_ceiling_dbfs: -30.0,
+            vad_margin_db: 10.0,
+            padding_ms: 250,
+            min_speech_ms: 300,
+            target_peak_dbfs: -1.0,
+            max_gain_db: 20.0,
+        }
+    }
+}
+
+/// Longest recording accepted by save_wav_file (a dictation is a few sentences)
+pub const MAX_RECORDING_MS: u64 = 10 * 60 * 1000;
+
+/// Metadata of a saved recording, returned to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingInfo {
+    pub duration_ms: u64,
+    /// Format of the saved file (always 16 kHz mono PCM16)
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Format received from the recorder
+    pub source_sample_rate: u32,
+    pub source_channels: u16,
+    pub source_bits_per_sample: u16,
+    /// The input was not PCM16/16 kHz/mono and has been converted
+    pub converted: bool,
+}
+
+/// What preprocessing did, for logs
+#[derive(Debug, Clone, Serialize)]
+pub struct PreprocessStats {
+    pub original_sample_rate: u32,
+    pub original_channels: u16,
+    pub original_duration_ms: u64,
+    pub duration_ms: u64,
+    pub speech_ms: u64,
+    pub gain_db: f32,
+}
+
+fn to_dbfs(amplitude: f32) -> f32 {
+    20.0 * amplitude.max(1e-9).log10()
+}
+
+fn from_db(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Resample mono audio. Downsampling averages each output window (box low-pass),
+/// upsampling interpolates linearly.
+pub fn resample(input: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || input.is_empty() {
+        return input.to_vec();
+    }
+    let ratio = from_rate as f64 / to_rate as f64;
+    let out_len = (input.len() as f64 / ratio).floor() as usize;
+    let mut output = Vec::with_capacity(out_len);
+
+    for i in 0..out_len {
+        let src = i as f64 * ratio;
+        if ratio > 1.0 {
+            let start = src.floor() as usize;
+            let end = (((i + 1) as f64 * ratio).floor() as usize).clamp(start + 1, input.len());
+            let window = &input[start..end];
+            output.push(window.iter().sum::<f32>() / window.len() as f32);
+        } else {
+            let floor = src.floor() as usize;
+            let ceil = (floor + 1).min(input.len() - 1);
+            let frac = (src - floor as f64) as f32;
+            output.push(input[floor] * (1.0 - frac) + input[ceil] * frac);
+        }
+    }
+    output
+}
+
+fn frame_rms_dbfs(samples: &[f32], frame_len: usize) -> Vec<f32> {
+    samples
+        .chunks(frame_len)
+        .map(|frame| {
+            let sum: f32 = frame.iter().map(|s| s * s).sum();
+            to_dbfs((sum / frame.len() as f32).sqrt())
+        })
+        .collect()
+}
+
+/// Energy VAD: returns the speech range (in samples, padded) and the amount of speech.
+/// The noise floor is the 10th percentile of frame levels, so steady classroom noise
+/// is not mistaken for speech.
+fn detect_speech(samples: &[f32], config: &PreprocessConfig) -> Option<(usize, usize, usize)> {
+    let frame_len = (WHISPER_SAMPLE_RATE * config.frame_ms / 1000).max(1) as usize;
+    let levels = frame_rms_dbfs(samples, frame_len);
+    if levels.is_empty() {
+        return None;
+    }
+
+    let mut sorted = levels.clone();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let noise_floor = sorted[sorted.len() / 10];
+    let threshold = (noise_floor + config.vad_margin_db)
+        .clamp(config.vad_floor_dbfs, config.vad_ceiling_dbfs);
+
+    let voiced: Vec<usize> = levels
+        .iter()
+        .enumerate()
+        .filter(|(_, db)| **db >= threshold)
+        .map(|(i, _)| i)
+        .collect();
+    let (first, last) = (*voiced.first()?, *voiced.last()?);
+
+    let padding = (WHISPER_SAMPLE_RATE * config.padding_ms / 1000) as usize;
+    let start = (first * frame_len).saturating_sub(padding);
+    let end = ((last + 1) * frame_len + padding).min(samples.len());
+    Some((start, end, voiced.len() * frame_len))
+}
+
+/// Validate a recorder capture and bring it to PCM16/16 kHz/mono.
+/// Canonical input is returned untouched; anything else (other rate, stereo, float,
+/// extra chunks, unset sizes) is decoded and re-encoded.
+pub fn conform_recording(
+    bytes: &[u8],
+    max_duration_ms: u64,
+) -> Result<(Vec<u8>, RecordingInfo), AudioError> {
+    let audio = parse_wav(bytes)?;
+    let format = audio.format;
+    let duration_ms = audio.duration_ms();
+
+    if audio.frame_count() == 0 {
+        return Err(AudioError::InvalidWav("aucun echantillon audio".to_string()));
+    }
+    if duration_ms > max_duration_ms {
+        return Err(AudioError::TooLong {
+            duration_s: duration_ms / 1000,
+            max_s: max_duration_ms / 1000,
+        });
+    }
+
+    let (_, data_len) = parse_header(bytes)?;
+    let canonical = format.is_whisper_native() && bytes.len() == 44 + data_len;
+
+    let (wav, converted) = if canonical {
+        (bytes.to_vec(), false)
+    } else {
+        let samples = resample(&audio.to_mono(), format.sample_rate, WHISPER_SAMPLE_RATE);
+        (encode_wav(&to_pcm16(&samples), WHISPER_SAMPLE_RATE), true)
+    };
+
+    let info = RecordingInfo {
+        duration_ms,
+        sample_rate: WHISPER_SAMPLE_RATE,
+        channels: 1,
+        source_sample_rate: format.sample_rate,
+        source_channels: format.channels,
+        source_bits_per_sample: format.bits_per_sample,
+        converted,
+    };
+    Ok((wav, info))
+}
+
+/// Preprocess a WAV file for whisper. Returns a PCM16/16 kHz/mono WAV.
+pub fn preprocess_wav(
+    bytes: &[u8],
+    config: &PreprocessConfig,
+) -> Result<(Vec<u8>, PreprocessStats), AudioError> {
+    let audio = parse_wav(bytes)?;
+    let original_duration_ms = audio.duration_ms();
+
+    let mono = audio.to_mono();
+    let samples = resample(&mono, audio.format.sample_rate, WHISPER_SAMPLE_RATE);
+
+    let (start, end, speech_len) =
+        detect_speech(&samples, config).ok_or(AudioError::NearSilent)?;
+    let speech_ms = speech_len as u64 * 1000 / WHISPER_SAMPLE_RATE as u64;
+    if speech_ms < config.min_speech_ms as u64 {
+        return Err(AudioError::NearSilent);
+    }
+    let trimmed = &samples[start..end];
+
+    let peak = trimmed.iter().fold(0f32, |max, s| max.max(s.abs()));
+    let gain_db = (config.target_peak_dbfs - to_dbfs(peak)).min(config.max_gain_db);
+    let gain = from_db(gain_db);
+    let normalised: Vec<f32> = trimmed.iter().map(|s| s * gain).collect();
+
+    let stats = PreprocessStats {
+        original_sample_rate: audio.format.sample_rate,
+        original_channels: audio.format.channels,
+        original_duration_ms,
+        duration_ms: normalised.len() as u64 * 1000 / WHISPER_SAMPLE_RATE as u64,
+        speech_ms,
+        gain_db,
+    };
+
+    Ok((encode_wav(&to_pcm16(&normalised), WHISPER_SAMPLE_RATE), stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::wav::WavFormat;
+
+    const RATE: u32 = WHISPER_SAMPLE_RATE;
+
+    fn tone(ms: u32, amplitude: f32, rate: u32) -> Vec<f32> {
+        let n = (rate * ms / 1000) as usize;
+        (0..n)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * 220.0 * i as f32 / rate as f32).sin())
+            .collect()
+    }
+
+    fn noise(ms: u32, amplitude: f32) -> Vec<f32> {
+        // Deterministic pseudo-noise
+        let n = (RATE * ms / 1000) as usize;
+        let mut x: u32 = 12345;
+        (0..n)
+            .map(|_| {
+                x = x.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                amplitude * ((x >> 16) as f32 / 32768.0 - 1.0)
+            })
+            .collect()
+    }
+
+    fn wav(samples: &[f32]) -> Vec<u8> {
+        encode_wav(&to_pcm16(samples), RATE)
+    }
+
+    fn parse(bytes: &[u8]) -> crate::audio::wav::WavAudio {
+        parse_wav(bytes).unwrap()
+    }
+
+    #[test]
+    fn trims_silence_and_normalises() {
+        let mut samples = vec![0.0; (RATE * 2) as usize];
+        samples.extend(tone(1_000, 0.05, RATE));
+        samples.extend(vec![0.0; (RATE * 3) as usize]);
+
+        let (out, stats) = preprocess_wav(&wav(&samples), &PreprocessConfig::default()).unwrap();
+        assert_eq!(stats.original_duration_ms, 6_000);
+        // 1 s of speech + 2 × 250 ms padding (± a frame)
+        assert!((1_450..=1_560).contains(&stats.duration_ms), "{:?}", stats);
+        // -26 dBFS peak: gain capped at max_gain_db
+        assert!((stats.gain_db - 20.0).abs() < 0.01, "{:?}", stats);
+
+        let audio = parse(&out);
+        assert!(audio.format.is_whisper_native());
+        let peak = audio.samples.iter().fold(0f32, |m, s| m.max(s.abs()));
+        assert!(peak > 0.4, "peak={}", peak);
+    }
+
+    #[test]
+    fn loud_input_is_brought_to_target_peak() {
+        let mut samples = tone(1_000, 1.0, RATE);
+        samples.extend(vec![0.0; RATE as usize]);
+        let (out, stats) = preprocess_wav(&wav(&samples), &PreprocessConfig::default()).unwrap();
+        assert!(stats.gain_db < 0.0);
+        let peak = parse(&out).samples.iter().fold(0f32, |m, s| m.max(s.abs()));
+        assert!((peak - from_db(-1.0)).abs() < 0.01, "peak={}", peak);
+    }
+
+    #[test]
+    fn rejects_near_silent_recording() {
+        let quiet = vec![0.0005; (RATE * 3) as usize];
+        assert!(matches!(
+            preprocess_wav(&wav(&quiet), &PreprocessConfig::default()),
+            Err(AudioError::NearSilent)
+        ));
+
+        let digital_silence = vec![0.0; RATE as usize];
+        assert!(matches!(
+            preprocess_wav(&wav(&digital_silence), &PreprocessConfig::default()),
+            Err(AudioError::NearSilent)
+        ));
+    }
+
+    #[test]
+    fn steady_noise_alone_is_rejected_but_speech_over_noise_is_kept() {
+        let background = noise(3_000, 0.02);
+        assert!(matches!(
+            preprocess_wav(&wav(&background), &PreprocessConfig::default()),
+            Err(AudioError::NearSilent)
+        ));
+
+        let mut with_speech = noise(1_000, 0.02);
+        with_speech.extend(tone(800, 0.3, RATE));
+        with_speech.extend(noise(1_000, 0.02));
+        let (_, stats) = preprocess_wav(&wav(&with_speech), &PreprocessConfig::default()).unwrap();
+        assert!((700..=900).contains(&stats.speech_ms), "{:?}", stats);
+    }
+
+    #[test]
+    fn resamples_48k_stereo_to_16k_mono() {
+        let left = tone(1_000, 0.3, 48_000);
+        let interleaved: Vec<i16> = to_pcm16(&left)
+            .into_iter()
+            .flat_map(|s| [s, s])
+            .collect();
+        let mut bytes = b"RIFF".to_vec();
+        let data_len = (interleaved.len() * 2) as u32;
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&48_000u32.to_le_bytes());
+        bytes.extend_from_slice(&(48_000u32 * 4).to_le_bytes());
+        bytes.extend_from_slice(&4u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for s in interleaved {
+            bytes.extend_from_slice(&s.to_le_bytes());
+        }
+
+        let (out, stats) = preprocess_wav(&bytes, &PreprocessConfig::default()).unwrap();
+        assert_eq!(stats.original_sample_rate, 48_000);
+        assert_eq!(stats.original_channels, 2);
+        let audio = parse(&out);
+        assert_eq!(
+            audio.format,
+            WavFormat { format_tag: 1, channels: 1, sample_rate: RATE, bits_per_sample: 16 }
+        );
+        assert!((990..=1_000).contains(&audio.duration_ms()));
+    }
+
+    #[test]
+    fn conform_keeps_canonical_input() {
+        let input = wav(&tone(500, 0.3, RATE));
+        let (out, info) = conform_recording(&input, MAX_RECORDING_MS).unwrap();
+        assert_eq!(out, input);
+        assert!(!info.converted);
+        assert_eq!(info.duration_ms, 500);
+    }
+
+    #[test]
+    fn conform_converts_and_reports_source_format() {
+        let left = to_pcm16(&tone(1_000, 0.3, 44_100));
+        let data: Vec<u8> = left.iter().flat_map(|s| [*s, *s]).flat_map(|s| s.to_le_bytes()).collect();
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&44_100u32.to_le_bytes());
+        bytes.extend_from_slice(&(44_100u32 * 4).to_le_bytes());
+        bytes.extend_from_slice(&4u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&data);
+
+        let (out, info) = conform_recording(&bytes, MAX_RECORDING_MS).unwrap();
+        assert!(info.converted);
+        assert_eq!((info.source_sample_rate, info.source_channels), (44_100, 2));
+        assert_eq!(info.duration_ms, 1_000);
+        assert!(parse(&out).format.is_whisper_native());
+    }
+
+    #[test]
+    fn conform_rejects_empty_and_too_long() {
+        assert!(matches!(
+            conform_recording(&wav(&[]), MAX_RECORDING_MS),
+            Err(AudioError::InvalidWav(_))
+        ));
+        assert!(matches!(
+            conform_recording(&wav(&tone(3_000, 0.1, RATE)), 2_000),
+            Err(AudioError::TooLong { duration_s: 3, max_s: 2 })
+        ));
+        assert!(matches!(
+            conform_recording(b"RIFF\0\0\0\0WAVE", MAX_RECORDING_MS),
+            Err(AudioError::InvalidWav(_))
+        ));
+    }
+
+    #[test]
+    fn resample_lengths() {
+        assert_eq!(resample(&[0.0; 480], 48_000, 16_000).len(), 160);
+        assert_eq!(resample(&[0.0; 80], 8_000, 16_000).len(), 160);
+        assert_eq!(resample(&[1.0, 2.0], 16_000, 16_000), vec![1.0, 2.0]);
+    }
+}
@@ -0,0 +1,296 @@
+// Retention policy for dictation recordings
+//
+// Recordings contain children's voices: they must not pile up in app_data_dir.
+// Two directories hold them: `audio_temp` (Web Audio fallback, save_wav_file)
+// and `tauri-plugin-mic-recorder` (Plan A).
+//
+// - keep_none: deleted as soon as the transcription succeeded
+// - keep_days: kept N days (re-listening), purged at startup
+// - keep_until_validated: kept until the teacher validates the dictation
+//   (mark_recording_validated), with a safety purge after UNVALIDATED_MAX_AGE
+//
+// Only keep_days attaches recordings to their vocal events (events::audio).
+//
+// The policy is persisted as JSON in app_data_dir, like the sidecar settings.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// File name of the persisted policy (in app_data_dir)
+pub const RETENTION_FILE: &str = "audio_retention.json";
+
+/// Sub-directories of app_data_dir holding recordings
+pub const RECORDING_DIRS: &[&str] = &["audio_temp", "tauri-plugin-mic-recorder"];
+
+/// keep_until_validated: recordings never validated are purged after this age
+pub const UNVALIDATED_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 3600);
+
+const MAX_KEEP_DAYS: u32 = 365;
+const DAY: Duration = Duration::from_secs(24 * 3600);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode")]
+pub enum RetentionPolicy {
+    #[default]
+    #[serde(rename = "keep_none")]
+    Discard,
+    #[serde(rename = "keep_days")]
+    Days { days: u32 },
+    #[serde(rename = "keep_until_validated")]
+    UntilValidated,
+}
+
+impl RetentionPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if let RetentionPolicy::Days { days } = self {
+            if *days == 0 || *days > MAX_KEEP_DAYS {
+                return Err(format!(
+                    "La duree de conservation doit etre comprise entre 1 et {} jours",
+                    MAX_KEEP_DAYS
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Age above which a recording is purged at startup
+    pub fn max_age(&self) -> Duration {
+        match self {
+            RetentionPolicy::Discard => Duration::ZERO,
+            RetentionPolicy::Days { days } => DAY * *days,
+            RetentionPolicy::UntilValidated => UNVALIDATED_MAX_AGE,
+        }
+    }
+
+    pub fn delete_after_transcription(&self) -> bool {
+        matches!(self, RetentionPolicy::Discard)
+    }
+
+    pub fn delete_on_validation(&self) -> bool {
+        matches!(self, RetentionPolicy::UntilValidated)
+    }
+
+    /// Days a recording stays attached to its events (None: never attached)
+    pub fn event_audio_days(&self) -> Option<u32> {
+        match self {
+            RetentionPolicy::Days { days } => Some(*days),
+            _ => None,
+        }
+    }
+}
+
+/// Load the policy. Missing or invalid file → default (never blocks startup).
+pub fn load_policy(path: &Path) -> RetentionPolicy {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|text| serde_json::from_str::<RetentionPolicy>(&text).ok())
+        .filter(|policy| policy.validate().is_ok())
+        .unwrap_or_default()
+}
+
+pub fn save_policy(path: &Path, policy: &RetentionPolicy) -> Result<(), String> {
+    policy.validate()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Impossible de creer {}: {}", parent.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(policy).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| format!("Impossible d'ecrire {}: {}", path.display(), e))
+}
+
+/// Disk usage of the recording directories
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AudioStorageUsage {
+    pub file_count: u64,
+    pub total_bytes: u64,
+    /// Age of the oldest recording, in seconds
+    pub oldest_age_s: Option<u64>,
+}
+
+/// Result of a purge
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PurgeReport {
+    pub deleted: u64,
+    pub freed_bytes: u64,
+}
+
+fn is_wav(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("wav"))
+}
+
+/// WAV files directly inside the recording directories, with size and age
+fn list_recordings(dirs: &[PathBuf], now: SystemTime) -> Vec<(PathBuf, u64, Duration)> {
+    let mut recordings = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if !meta.is_file() || !is_wav(&path) {
+                continue;
+            }
+            let age = meta
+                .modified()
+                .ok()
+                .and_then(|m| now.duration_since(m).ok())
+                .unwrap_or(Duration::ZERO);
+            recordings.push((path, meta.len(), age));
+        }
+    }
+    recordings
+}
+
+/// True if `path` is a WAV file directly inside one of the recording directories.
+/// Guards every deletion: a path coming from the frontend can never point elsewhere.
+pub fn is_managed_recording(dirs: &[PathBuf], path: &Path) -> bool {
+    if !is_wav(path) {
+        return false;
+    }
+    let (Ok(file), Some(parent)) = (path.canonicalize(), path.parent()) else {
+        return false;
+    };
+    let Ok(parent) = parent.canonicalize() else {
+        return false;
+    };
+    file.parent() == Some(parent.as_path())
+        && dirs
+            .iter()
+            .filter_map(|d| d.canonicalize().ok())
+            .any(|d| d == parent)
+}
+
+/// Delete one recording if it is managed. Returns the freed bytes.
+pub fn delete_recording(dirs: &[PathBuf], path: &Path) -> Result<u64, String> {
+    if !is_managed_recording(dirs, path) {
+        return Ok(0);
+    }
+    let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    std::fs::remove_file(path)
+        .map_err(|e| format!("Impossible de supprimer {}: {}", path.display(), e))?;
+    Ok(size)
+}
+
+/// Delete recordings older than `max_age` (all of them for Duration::ZERO).
+pub fn purge_older_than(dirs: &[PathBuf], max_age: Duration, now: SystemTime) -> PurgeReport {
+    let mut report = PurgeReport::default();
+    for (path, size, age) in list_recordings(dirs, now) {
+        if age < max_age {
+            continue;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(()) => {
+                report.deleted += 1;
+                report.freed_bytes += size;
+            }
+            Err(e) => log::warn!("Purge audio: {} non supprime ({})", path.display(), e),
+        }
+    }
+    report
+}
+
+pub fn disk_usage(dirs: &[PathBuf], now: SystemTime) -> AudioStorageUsage {
+    let recordings = list_recordings(dirs, now);
+    AudioStorageUsage {
+        file_count: recordings.len() as u64,
+        total_bytes: recordings.iter().map(|(_, size, _)| size).sum(),
+        oldest_age_s: recordings.iter().map(|(_, _, age)| age.as_secs()).max(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (tempfile::TempDir, Vec<PathBuf>) {
+        let root = tempfile::tempdir().unwrap();
+        let dirs: Vec<PathBuf> = RECORDING_DIRS.iter().map(|d| root.path().join(d)).collect();
+        for dir in &dirs {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        (root, dirs)
+    }
+
+    #[test]
+    fn policy_roundtrip_and_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(RETENTION_FILE);
+        assert_eq!(load_policy(&path), RetentionPolicy::Discard);
+
+        save_policy(&path, &RetentionPolicy::Days { days: 14 }).unwrap();
+        assert_eq!(load_policy(&path), RetentionPolicy::Days { days: 14 });
+        let json = std::fs::read_to_string(&path).unwrap();
+        assert!(json.contains("\"mode\": \"keep_days\""), "{}", json);
+
+        assert!(save_policy(&path, &RetentionPolicy::Days { days: 0 }).is_err());
+        std::fs::write(&path, "{\"mode\":\"keep_days\",\"days\":9999}").unwrap();
+        assert_eq!(load_policy(&path), RetentionPolicy::Discard);
+    }
+
+    #[test]
+    fn purge_respects_age_and_only_touches_wav() {
+        let (_root, dirs) = setup();
+        std::fs::write(dirs[0].join("recording_1.wav"), [0u8; 100]).unwrap();
+        std::fs::write(dirs[1].join("20260101120000.wav"), [0u8; 50]).unwrap();
+        std::fs::write(dirs[0].join("notes.txt"), "keep").unwrap();
+
+        let now = SystemTime::now();
+        let report = purge_older_than(&dirs, Duration::from_secs(3600), now);
+        assert_eq!(report, PurgeReport::default(), "Fresh files are kept");
+
+        let later = now + Duration::from_secs(2 * 3600);
+        let report = purge_older_than(&dirs, Duration::from_secs(3600), later);
+        assert_eq!(report, PurgeReport { deleted: 2, freed_bytes: 150 });
+        assert!(dirs[0].join("notes.txt").exists());
+    }
+
+    #[test]
+    fn disk_usage_counts_both_directories() {
+        let (_root, dirs) = setup();
+        std::fs::write(dirs[0].join("a.wav"), [0u8; 10]).unwrap();
+        std::fs::write(dirs[1].join("b.WAV"), [0u8; 30]).unwrap();
+
+        let usage = disk_usage(&dirs, SystemTime::now() + Duration::from_secs(60));
+        assert_eq!(usage.file_count, 2);
+        assert_eq!(usage.total_bytes, 40);
+        assert!(usage.oldest_age_s.unwrap() >= 59);
+
+        let missing = vec![PathBuf::from("/nonexistent/audio_temp")];
+        assert_eq!(disk_usage(&missing, SystemTime::now()), AudioStorageUsage::default());
+    }
+
+    #[test]
+    fn delete_refuses_paths_outside_recording_dirs() {
+        let (root, dirs) = setup();
+        let outside = root.path().join("comportement.wav");
+        std::fs::write(&outside, [0u8; 10]).unwrap();
+        let traversal = dirs[0].join("..").join("comportement.wav");
+        let inside = dirs[0].join("recording_2.wav");
+        std::fs::write(&inside, [0u8; 20]).unwrap();
+
+        assert_eq!(delete_recording(&dirs, &outside).unwrap(), 0);
+        assert_eq!(delete_recording(&dirs, &traversal).unwrap(), 0);
+        assert!(outside.exists());
+
+        assert_eq!(delete_recording(&dirs, &inside).unwrap(), 20);
+        assert!(!inside.exists());
+    }
+
+    #[test]
+    fn policy_decisions() {
+        assert!(RetentionPolicy::Discard.delete_after_transcription());
+        assert_eq!(RetentionPolicy::Discard.max_age(), Duration::ZERO);
+        assert!(!RetentionPolicy::Days { days: 3 }.delete_after_transcription());
+        assert_eq!(RetentionPolicy::Days { days: 3 }.max_age(), DAY * 3);
+        assert!(RetentionPolicy::UntilValidated.delete_on_validation());
+        assert_eq!(RetentionPolicy::UntilValidated.max_age(), UNVALIDATED_MAX_AGE);
+        assert_eq!(RetentionPolicy::Days { days: 3 }.event_audio_days(), Some(3));
+        assert_eq!(RetentionPolicy::UntilValidated.event_audio_days(), None);
+    }
+}
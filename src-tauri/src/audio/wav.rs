@@ -0,0 +1,306 @@
+// WAV container parsing and encoding
+//
+// Recorders are expected to produce PCM 16-bit / 16 kHz / mono, but browser captures
+// and the mic plugin do not always honour that. The parser walks RIFF chunks (skipping
+// LIST/fact/etc.), and decodes PCM 8/16/24/32-bit and IEEE float 32-bit, including
+// WAVE_FORMAT_EXTENSIBLE headers, into interleaved f32 samples in [-1, 1].
+
+use super::AudioError;
+
+/// Sample rate expected by whisper-server
+pub const WHISPER_SAMPLE_RATE: u32 = 16_000;
+
+const FORMAT_PCM: u16 = 0x0001;
+const FORMAT_IEEE_FLOAT: u16 = 0x0003;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Header fields of the `fmt ` chunk (format tag resolved for EXTENSIBLE)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WavFormat {
+    pub format_tag: u16,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+}
+
+impl WavFormat {
+    /// Already in the format whisper-server expects
+    pub fn is_whisper_native(&self) -> bool {
+        self.format_tag == FORMAT_PCM
+            && self.channels == 1
+            && self.sample_rate == WHISPER_SAMPLE_RATE
+            && self.bits_per_sample == 16
+    }
+}
+
+/// Decoded audio: interleaved f32 samples
+#[derive(Debug, Clone)]
+pub struct WavAudio {
+    pub format: WavFormat,
+    pub samples: Vec<f32>,
+}
+
+impl WavAudio {
+    pub fn frame_count(&self) -> usize {
+        self.samples.len() / self.format.channels.max(1) as usize
+    }
+
+    pub fn duration_ms(&self) -> u64 {
+        if self.format.sample_rate == 0 {
+            return 0;
+        }
+        self.frame_count() as u64 * 1000 / self.format.sample_rate as u64
+    }
+
+    /// Average channels into one
+    pub fn to_mono(&self) -> Vec<f32> {
+        let channels = self.format.channels.max(1) as usize;
+        if channels == 1 {
+            return self.samples.clone();
+        }
+        self.samples
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect()
+    }
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+fn parse_fmt(chunk: &[u8]) -> Result<WavFormat, AudioError> {
+    if chunk.len() < 16 {
+        return Err(AudioError::InvalidWav("chunk fmt trop court".to_string()));
+    }
+    let mut format_tag = read_u16(chunk, 0);
+    let channels = read_u16(chunk, 2);
+    let sample_rate = read_u32(chunk, 4);
+    let bits_per_sample = read_u16(chunk, 14);
+
+    if format_tag == FORMAT_EXTENSIBLE {
+        // cbSize(2) validBits(2) channelMask(4) then the SubFormat GUID, whose first 2 bytes are the tag
+        if chunk.len() < 26 {
+            return Err(AudioError::InvalidWav("chunk fmt EXTENSIBLE trop court".to_string()));
+        }
+        format_tag = read_u16(chunk, 24);
+    }
+
+    if channels == 0 || sample_rate == 0 {
+        return Err(AudioError::InvalidWav(format!(
+            "{} canaux a {} Hz",
+            channels, sample_rate
+        )));
+    }
+
+    Ok(WavFormat { format_tag, channels, sample_rate, bits_per_sample })
+}
+
+/// Read only the header: format and data chunk length in bytes.
+pub fn parse_header(bytes: &[u8]) -> Result<(WavFormat, usize), AudioError> {
+    let (format, data) = locate_chunks(bytes)?;
+    Ok((format, data.len()))
+}
+
+/// Returns the format and the data chunk slice (truncated if the file is shorter than declared).
+fn locate_chunks(bytes: &[u8]) -> Result<(WavFormat, &[u8]), AudioError> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(AudioError::InvalidWav("en-tete RIFF/WAVE absent".to_string()));
+    }
+
+    let mut format = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = read_u32(bytes, offset + 4) as usize;
+        let body_start = offset + 8;
+        // Browsers streaming a WAV may leave 0 or 0xFFFFFFFF as the data size
+        let body_end = body_start.saturating_add(size).min(bytes.len());
+        let body = &bytes[body_start..body_end];
+
+        match id {
+            b"fmt " => format = Some(parse_fmt(body)?),
+            b"data" => {
+                let format = format.ok_or_else(|| {
+                    AudioError::InvalidWav("chunk data avant le chunk fmt".to_string())
+                })?;
+                let body = if size == 0 || size == u32::MAX as usize {
+                    &bytes[body_start..]
+                } else {
+                    body
+                };
+                return Ok((format, body));
+            }
+            _ => {}
+        }
+        // Chunks are word-aligned
+        offset = body_start.saturating_add(size).saturating_add(size & 1);
+    }
+
+    Err(AudioError::InvalidWav(
+        if format.is_some() { "chunk data absent" } else { "chunk fmt absent" }.to_string(),
+    ))
+}
+
+/// Decode a WAV file into f32 samples.
+pub fn parse_wav(bytes: &[u8]) -> Result<WavAudio, AudioError> {
+    let (format, data) = locate_chunks(bytes)?;
+
+    let samples: Vec<f32> = match (format.format_tag, format.bits_per_sample) {
+        (FORMAT_PCM, 8) => data.iter().map(|b| (*b as f32 - 128.0) / 128.0).collect(),
+        (FORMAT_PCM, 16) => data
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+            .collect(),
+        (FORMAT_PCM, 24) => data
+            .chunks_exact(3)
+            .map(|b| {
+                let v = i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8;
+                v as f32 / 8_388_608.0
+            })
+            .collect(),
+        (FORMAT_PCM, 32) => data
+            .chunks_exact(4)
+            .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0)
+            .collect(),
+        (FORMAT_IEEE_FLOAT, 32) => data
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]).clamp(-1.0, 1.0))
+            .collect(),
+        (tag, bits) => {
+            return Err(AudioError::Unsupported(format!(
+                "format 0x{:04X} en {} bits",
+                tag, bits
+            )))
+        }
+    };
+
+    Ok(WavAudio { format, samples })
+}
+
+/// Convert f32 samples in [-1, 1] to PCM 16-bit.
+pub fn to_pcm16(samples: &[f32]) -> Vec<i16> {
+    samples
+        .iter()
+        .map(|s| {
+            let s = s.clamp(-1.0, 1.0);
+            if s < 0.0 {
+                (s * 32768.0) as i16
+            } else {
+                (s * 32767.0) as i16
+            }
+        })
+        .collect()
+}
+
+/// Encode mono PCM 16-bit samples as a WAV file (44-byte header).
+pub fn encode_wav(samples: &[i16], sample_rate: u32) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&FORMAT_PCM.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // byte rate
+    wav.extend_from_slice(&2u16.to_le_bytes()); // block align
+    wav.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a WAV with an arbitrary fmt chunk and optional extra chunk before data
+    fn build(format_tag: u16, channels: u16, rate: u32, bits: u16, data: &[u8], extra: bool) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(b"WAVE");
+        if extra {
+            body.extend_from_slice(b"LIST");
+            body.extend_from_slice(&3u32.to_le_bytes());
+            body.extend_from_slice(b"abc\0"); // 3 bytes + pad
+        }
+        body.extend_from_slice(b"fmt ");
+        body.extend_from_slice(&16u32.to_le_bytes());
+        body.extend_from_slice(&format_tag.to_le_bytes());
+        body.extend_from_slice(&channels.to_le_bytes());
+        body.extend_from_slice(&rate.to_le_bytes());
+        let block = channels * bits / 8;
+        body.extend_from_slice(&(rate * block as u32).to_le_bytes());
+        body.extend_from_slice(&block.to_le_bytes());
+        body.extend_from_slice(&bits.to_le_bytes());
+        body.extend_from_slice(b"data");
+        body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        body.extend_from_slice(data);
+
+        let mut wav = b"RIFF".to_vec();
+        wav.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        wav.extend_from_slice(&body);
+        wav
+    }
+
+    #[test]
+    fn roundtrip_pcm16() {
+        let wav = encode_wav(&[0, 16384, -32768], WHISPER_SAMPLE_RATE);
+        assert_eq!(wav.len(), 44 + 6);
+        let audio = parse_wav(&wav).unwrap();
+        assert!(audio.format.is_whisper_native());
+        assert_eq!(audio.samples, vec![0.0, 0.5, -1.0]);
+        assert_eq!(to_pcm16(&audio.samples), vec![0, 16383, -32768]);
+    }
+
+    #[test]
+    fn skips_unknown_chunks_and_downmixes_stereo() {
+        let data: Vec<u8> = [16384i16, 0, -16384, -16384]
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect();
+        let audio = parse_wav(&build(FORMAT_PCM, 2, 48_000, 16, &data, true)).unwrap();
+        assert_eq!(audio.format.channels, 2);
+        assert_eq!(audio.frame_count(), 2);
+        assert_eq!(audio.to_mono(), vec![0.25, -0.5]);
+    }
+
+    #[test]
+    fn decodes_float32_and_24bit() {
+        let data: Vec<u8> = [0.5f32, -0.25].iter().flat_map(|s| s.to_le_bytes()).collect();
+        let audio = parse_wav(&build(FORMAT_IEEE_FLOAT, 1, 44_100, 32, &data, false)).unwrap();
+        assert_eq!(audio.samples, vec![0.5, -0.25]);
+
+        let data = [0x00, 0x00, 0x40, 0x00, 0x00, 0xC0]; // +0.5, -0.5
+        let audio = parse_wav(&build(FORMAT_PCM, 1, 16_000, 24, &data, false)).unwrap();
+        assert_eq!(audio.samples, vec![0.5, -0.5]);
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert!(matches!(parse_wav(b"not a wav"), Err(AudioError::InvalidWav(_))));
+
+        let mut no_data = encode_wav(&[1, 2], WHISPER_SAMPLE_RATE);
+        no_data[36..40].copy_from_slice(b"junk");
+        assert!(matches!(parse_wav(&no_data), Err(AudioError::InvalidWav(_))));
+
+        let alaw = build(0x0006, 1, 8_000, 8, &[0, 0], false);
+        assert!(matches!(parse_wav(&alaw), Err(AudioError::Unsupported(_))));
+    }
+
+    #[test]
+    fn unset_data_size_reads_to_end() {
+        let mut wav = encode_wav(&[100, 200, 300], WHISPER_SAMPLE_RATE);
+        wav[40..44].copy_from_slice(&0u32.to_le_bytes());
+        assert_eq!(parse_wav(&wav).unwrap().samples.len(), 3);
+    }
+}
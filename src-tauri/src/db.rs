@@ -0,0 +1,149 @@
+/// Module DB — Pool SQLite unique partagé par toutes les commandes
+///
+/// Le pool est créé une seule fois, après les migrations V2.1/V2.2, et conservé dans
+/// l'état Tauri (`DbState`). Chaque connexion applique les mêmes pragmas :
+/// journal WAL, busy_timeout et foreign_keys.
+
+use sqlx::pool::PoolConnection;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions};
+use sqlx::Sqlite;
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::OnceCell;
+
+use crate::error::AppError;
+use crate::migrations::{get_db_path, run_v2_1_migrations};
+
+/// Attente maximale sur un verrou d'écriture avant SQLITE_BUSY
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+/// SQLite n'a qu'un écrivain : quelques connexions suffisent pour les lectures concurrentes
+pub const MAX_CONNECTIONS: u32 = 4;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// État Tauri : pool initialisé paresseusement au premier accès.
+#[derive(Default)]
+pub struct DbState {
+    pool: OnceCell<SqlitePool>,
+}
+
+impl DbState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pool partagé. Au premier appel, les migrations sont d'abord appliquées
+    /// (idempotent) puis le pool est ouvert ; les appels suivants le réutilisent.
+    pub async fn pool(&self, app: &AppHandle) -> Result<SqlitePool, AppError> {
+        self.pool
+            .get_or_try_init(|| async {
+                run_v2_1_migrations(app).await?;
+                let db_path = get_db_path(app)?;
+                if let Some(dir) = db_path.parent() {
+                    std::fs::create_dir_all(dir).map_err(|e| {
+                        AppError::io(format!("Impossible de creer {}", dir.display()), e)
+                    })?;
+                }
+                log::info!("[db] Ouverture du pool SQLite : {}", db_path.display());
+                open_pool(&db_path).await
+            })
+            .await
+            .cloned()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Impls (testables, sans AppHandle)
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Options de connexion communes (WAL, busy_timeout, foreign_keys).
+pub fn connect_options(db_path: &Path) -> SqliteConnectOptions {
+    SqliteConnectOptions::new()
+        .filename(db_path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .busy_timeout(BUSY_TIMEOUT)
+        .foreign_keys(true)
+}
+
+pub async fn open_pool(db_path: &Path) -> Result<SqlitePool, AppError> {
+    SqlitePoolOptions::new()
+        .max_connections(MAX_CONNECTIONS)
+        .connect_with(connect_options(db_path))
+        .await
+        .map_err(|e| AppError::db("Impossible d'ouvrir la DB", e))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Helpers AppHandle
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Pool partagé depuis l'état Tauri.
+pub async fn pool(app: &AppHandle) -> Result<SqlitePool, AppError> {
+    app.state::<DbState>().pool(app).await
+}
+
+/// Connexion empruntée au pool partagé, pour les fonctions `_impl(&mut conn, ...)`.
+pub async fn acquire(app: &AppHandle) -> Result<PoolConnection<Sqlite>, AppError> {
+    pool(app)
+        .await?
+        .acquire()
+        .await
+        .map_err(|e| AppError::db("Impossible d'obtenir une connexion DB", e))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pool_applies_pragmas() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = open_pool(&dir.path().join("comportement.db")).await.unwrap();
+
+        let journal: String = sqlx::query_scalar("PRAGMA journal_mode")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(journal.to_lowercase(), "wal");
+
+        let fk: i64 = sqlx::query_scalar("PRAGMA foreign_keys")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(fk, 1);
+
+        let busy: i64 = sqlx::query_scalar("PRAGMA busy_timeout")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(busy, BUSY_TIMEOUT.as_millis() as i64);
+    }
+
+    #[tokio::test]
+    async fn test_pool_enforces_foreign_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = open_pool(&dir.path().join("comportement.db")).await.unwrap();
+
+        sqlx::query("CREATE TABLE parent (id INTEGER PRIMARY KEY)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE child (id INTEGER PRIMARY KEY, parent_id INTEGER NOT NULL REFERENCES parent(id))")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let orphan = sqlx::query("INSERT INTO child (parent_id) VALUES (42)")
+            .execute(&pool)
+            .await;
+        assert!(orphan.is_err(), "FK violation attendue");
+    }
+}
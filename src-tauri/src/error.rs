@@ -0,0 +1,250 @@
+/// Module Erreurs — Type d'erreur commun renvoyé par les commandes Tauri
+///
+/// Sérialisé vers le frontend en `{ code, message, details? }` :
+/// - `code` : identifiant stable (`YearClosed`, `NotFound`…), seul champ à tester côté TS
+/// - `message` : texte français affichable tel quel
+/// - `details` : détail technique optionnel (erreur SQLite, HTTP, chemin…)
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt::Display;
+
+use crate::audio::AudioError;
+use crate::sidecar::types::SidecarError;
+use crate::validation::executor::ExecutionError;
+use crate::validation::validator::ValidationError;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("L'annee scolaire est cloturee. Reouvrez-la pour modifier les donnees.")]
+    YearClosed,
+
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Validation(String),
+
+    #[error("{message}")]
+    SidecarUnavailable { message: String, details: Option<String> },
+
+    #[error("Modele IA non installe : {0}. Allez dans Parametres > Modeles IA.")]
+    ModelMissing(String),
+
+    #[error("{message}")]
+    Db { message: String, details: String },
+
+    #[error("{message}")]
+    Io { message: String, details: String },
+
+    #[error("{message}")]
+    Network { message: String, details: String },
+
+    #[error("Aucune parole detectee. Parlez plus pres du microphone et reessayez.")]
+    NoSpeech,
+
+    #[error("Operation annulee")]
+    Cancelled,
+
+    #[error("{0}")]
+    Internal(String),
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Impls
+// ─────────────────────────────────────────────────────────────────────────────
+
+impl AppError {
+    /// Code machine stable, exposé au frontend.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::YearClosed => "YearClosed",
+            AppError::NotFound(_) => "NotFound",
+            AppError::Validation(_) => "Validation",
+            AppError::SidecarUnavailable { .. } => "SidecarUnavailable",
+            AppError::ModelMissing(_) => "ModelMissing",
+            AppError::Db { .. } => "Db",
+            AppError::Io { .. } => "Io",
+            AppError::Network { .. } => "Network",
+            AppError::NoSpeech => "NoSpeech",
+            AppError::Cancelled => "Cancelled",
+            AppError::Internal(_) => "Internal",
+        }
+    }
+
+    pub fn details(&self) -> Option<&str> {
+        match self {
+            AppError::SidecarUnavailable { details, .. } => details.as_deref(),
+            AppError::Db { details, .. }
+            | AppError::Io { details, .. }
+            | AppError::Network { details, .. } => Some(details),
+            _ => None,
+        }
+    }
+
+    /// Erreur SQLite avec un message de contexte (« Erreur chargement evenements »).
+    pub fn db(message: impl Into<String>, err: impl Display) -> Self {
+        AppError::Db { message: message.into(), details: err.to_string() }
+    }
+
+    pub fn io(message: impl Into<String>, err: impl Display) -> Self {
+        AppError::Io { message: message.into(), details: err.to_string() }
+    }
+
+    pub fn network(message: impl Into<String>, err: impl Display) -> Self {
+        AppError::Network { message: message.into(), details: err.to_string() }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::NotFound(message.into())
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        AppError::Validation(message.into())
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("AppError", 3)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", &self.to_string())?;
+        s.serialize_field("details", &self.details())?;
+        s.end()
+    }
+}
+
+/// Erreurs historiques en `String` (helpers non migrés) : code générique.
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Internal(message)
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(err: sqlx::Error) -> Self {
+        match err {
+            sqlx::Error::RowNotFound => AppError::NotFound("Element introuvable".to_string()),
+            other => AppError::db("Erreur base de donnees", other),
+        }
+    }
+}
+
+impl From<SidecarError> for AppError {
+    fn from(err: SidecarError) -> Self {
+        match &err {
+            SidecarError::ModelNotFound(path) | SidecarError::BinaryNotFound(path) => {
+                AppError::ModelMissing(path.clone())
+            }
+            SidecarError::HealthcheckTimeout(_)
+            | SidecarError::StartFailed(..)
+            | SidecarError::NotRunning(_) => AppError::SidecarUnavailable {
+                message: "Le moteur IA n'est pas disponible. Reessayez dans quelques instants."
+                    .to_string(),
+                details: Some(err.to_string()),
+            },
+            SidecarError::StopFailed(..)
+            | SidecarError::TranscriptionFailed(_)
+            | SidecarError::Internal(_) => AppError::Internal(err.to_string()),
+        }
+    }
+}
+
+impl From<AudioError> for AppError {
+    fn from(err: AudioError) -> Self {
+        match err {
+            AudioError::NearSilent => AppError::NoSpeech,
+            other => AppError::Validation(other.to_string()),
+        }
+    }
+}
+
+impl From<ValidationError> for AppError {
+    fn from(err: ValidationError) -> Self {
+        AppError::Validation(err.to_string())
+    }
+}
+
+impl From<ExecutionError> for AppError {
+    fn from(err: ExecutionError) -> Self {
+        match err {
+            ExecutionError::DatabaseError(details) => AppError::Db {
+                message: "Erreur base de donnees".to_string(),
+                details,
+            },
+            ExecutionError::StudentNotFound(_) | ExecutionError::PeriodNotFound(_) => {
+                AppError::NotFound(err.to_string())
+            }
+            // Domaine cite par les observations soumises, absent du referentiel
+            ExecutionError::DomaineNotFound(_) => AppError::Validation(err.to_string()),
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sidecar::types::SidecarName;
+
+    #[test]
+    fn test_serialize_shape() {
+        let err = AppError::db("Erreur chargement evenements", "disk I/O error");
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["code"], "Db");
+        assert_eq!(json["message"], "Erreur chargement evenements");
+        assert_eq!(json["details"], "disk I/O error");
+    }
+
+    #[test]
+    fn test_serialize_without_details() {
+        let json = serde_json::to_value(AppError::YearClosed).unwrap();
+        assert_eq!(json["code"], "YearClosed");
+        assert!(json["message"].as_str().unwrap().contains("cloturee"));
+        assert!(json["details"].is_null());
+    }
+
+    #[test]
+    fn test_row_not_found_maps_to_not_found() {
+        let err: AppError = sqlx::Error::RowNotFound.into();
+        assert_eq!(err.code(), "NotFound");
+    }
+
+    #[test]
+    fn test_sidecar_errors_map_to_stable_codes() {
+        let missing: AppError = SidecarError::ModelNotFound("ggml-small.bin".into()).into();
+        assert_eq!(missing.code(), "ModelMissing");
+
+        let down: AppError = SidecarError::HealthcheckTimeout(SidecarName::Whisper).into();
+        assert_eq!(down.code(), "SidecarUnavailable");
+        assert!(down.details().unwrap().contains("healthcheck"));
+    }
+
+    #[test]
+    fn test_audio_errors_map_to_stable_codes() {
+        let silent: AppError = AudioError::NearSilent.into();
+        assert_eq!(silent.code(), "NoSpeech");
+
+        let invalid: AppError = AudioError::InvalidWav("chunk fmt absent".into()).into();
+        assert_eq!(invalid.code(), "Validation");
+    }
+
+    #[test]
+    fn test_execution_errors_map_to_stable_codes() {
+        let student: AppError = ExecutionError::StudentNotFound(3).into();
+        assert_eq!(student.code(), "NotFound");
+        let period: AppError = ExecutionError::PeriodNotFound(1).into();
+        assert_eq!(period.code(), "NotFound");
+        let domaine: AppError = ExecutionError::DomaineNotFound("Chant".into()).into();
+        assert_eq!(domaine.code(), "Validation");
+        let db: AppError = ExecutionError::DatabaseError("locked".into()).into();
+        assert_eq!(db.code(), "Db");
+    }
+}
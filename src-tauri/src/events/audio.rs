@@ -0,0 +1,392 @@
+/// Module Events/Audio — Enregistrement d'une dictée rattaché à ses événements (M017)
+///
+/// Le fichier WAV est déplacé dans app_data_dir/audio_events et référencé par
+/// evenements_audio (event_uuid → file_name). Une dictée classée en plusieurs
+/// domaines produit plusieurs événements qui partagent le même fichier.
+///
+/// Conservation : uniquement avec la politique « keep_days » ; la pièce jointe est
+/// supprimée après N jours, ou dès que l'année scolaire de l'événement est clôturée.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::annee::check_annee_not_closed_impl;
+use crate::audio::retention::PurgeReport;
+use crate::error::AppError;
+
+/// Sous-dossier de app_data_dir contenant les enregistrements rattachés
+pub const EVENT_AUDIO_DIR: &str = "audio_events";
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct EventAudio {
+    pub event_uuid: String,
+    pub file_name: String,
+    pub size_bytes: i64,
+    pub created_at: String,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Impls (testables, prennent une connexion et le dossier audio_events)
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Déplace le fichier (copie + suppression si rename impossible, ex. autre volume).
+fn move_file(from: &Path, to: &Path) -> Result<(), AppError> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(from, to)
+        .map_err(|e| AppError::io("Impossible de copier l'enregistrement", e))?;
+    std::fs::remove_file(from)
+        .map_err(|e| AppError::io("Impossible de supprimer l'enregistrement temporaire", e))
+}
+
+/// Rattache un enregistrement à un événement. Si le fichier a déjà été déplacé
+/// pour un autre événement de la même dictée, il est simplement référencé.
+pub async fn attach_audio_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    audio_dir: &Path,
+    event_uuid: &str,
+    recording: &Path,
+) -> Result<EventAudio, AppError> {
+    let annee_id: i64 = sqlx::query_scalar(
+        "SELECT annee_scolaire_id FROM evenements_pedagogiques WHERE uuid = ?",
+    )
+    .bind(event_uuid)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur lecture événement", e))?
+    .ok_or_else(|| AppError::not_found("Événement introuvable"))?;
+
+    // Guard : année non clôturée
+    check_annee_not_closed_impl(conn, annee_id).await?;
+
+    let file_name = recording
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| AppError::validation("Chemin d'enregistrement invalide"))?
+        .to_string();
+
+    std::fs::create_dir_all(audio_dir)
+        .map_err(|e| AppError::io("Impossible de créer le répertoire audio_events", e))?;
+    let target = audio_dir.join(&file_name);
+    if !target.exists() {
+        if !recording.exists() {
+            return Err(AppError::not_found("Enregistrement introuvable"));
+        }
+        move_file(recording, &target)?;
+    }
+    let size_bytes = std::fs::metadata(&target).map(|m| m.len() as i64).unwrap_or(0);
+
+    sqlx::query(
+        "INSERT OR REPLACE INTO evenements_audio (event_uuid, file_name, size_bytes) VALUES (?, ?, ?)",
+    )
+    .bind(event_uuid)
+    .bind(&file_name)
+    .bind(size_bytes)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur rattachement audio", e))?;
+
+    sqlx::query_as::<_, EventAudio>(
+        "SELECT event_uuid, file_name, size_bytes, created_at FROM evenements_audio WHERE event_uuid = ?",
+    )
+    .bind(event_uuid)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur lecture pièce jointe audio", e))
+}
+
+/// Chemin du fichier rattaché à un événement, pour la réécoute.
+/// Refusé si l'année de l'événement est clôturée (même avant la purge du fichier).
+pub async fn get_event_audio_path_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    audio_dir: &Path,
+    event_uuid: &str,
+) -> Result<PathBuf, AppError> {
+    let (file_name, annee_id): (String, i64) = sqlx::query_as(
+        "SELECT a.file_name, e.annee_scolaire_id
+         FROM evenements_audio a
+         JOIN evenements_pedagogiques e ON e.uuid = a.event_uuid
+         WHERE a.event_uuid = ?",
+    )
+    .bind(event_uuid)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur lecture pièce jointe audio", e))?
+    .ok_or_else(|| AppError::not_found("Aucun enregistrement pour cet événement"))?;
+
+    check_annee_not_closed_impl(conn, annee_id).await?;
+
+    let path = audio_dir.join(file_name);
+    if !path.exists() {
+        return Err(AppError::not_found("L'enregistrement a été supprimé"));
+    }
+    Ok(path)
+}
+
+/// Supprime les pièces jointes expirées puis les fichiers qui ne sont plus référencés.
+/// `keep_days = None` : la politique ne conserve pas l'audio, tout est supprimé.
+/// Les pièces jointes d'une année clôturée sont toujours supprimées.
+pub async fn purge_event_audio_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    audio_dir: &Path,
+    keep_days: Option<u32>,
+) -> Result<PurgeReport, AppError> {
+    sqlx::query(
+        "DELETE FROM evenements_audio
+         WHERE ?1 IS NULL
+            OR created_at < datetime('now', '-' || ?1 || ' days')
+            OR event_uuid IN (
+                SELECT e.uuid FROM evenements_pedagogiques e
+                JOIN annees_scolaires y ON y.id = e.annee_scolaire_id
+                WHERE y.cloturee = 1
+            )",
+    )
+    .bind(keep_days)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur purge pièces jointes audio", e))?;
+
+    let referenced: Vec<String> =
+        sqlx::query_scalar("SELECT DISTINCT file_name FROM evenements_audio")
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| AppError::db("Erreur lecture pièces jointes audio", e))?;
+
+    let mut report = PurgeReport::default();
+    let Ok(entries) = std::fs::read_dir(audio_dir) else {
+        return Ok(report);
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if referenced.contains(&name) {
+            continue;
+        }
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        match std::fs::remove_file(entry.path()) {
+            Ok(()) => {
+                report.deleted += 1;
+                report.freed_bytes += size;
+            }
+            Err(e) => log::warn!("Purge audio: {} non supprimé ({})", name, e),
+        }
+    }
+    Ok(report)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Helpers AppHandle
+// ─────────────────────────────────────────────────────────────────────────────
+
+pub fn event_audio_dir(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    use tauri::Manager;
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(EVENT_AUDIO_DIR))
+        .map_err(|e| AppError::io("Impossible de trouver app_data_dir", e))
+}
+
+/// Purge selon la politique courante (démarrage, changement de politique).
+pub async fn purge_event_audio(app: &tauri::AppHandle) -> Result<PurgeReport, AppError> {
+    let keep_days = crate::audio::commands::current_retention(app).event_audio_days();
+    let audio_dir = event_audio_dir(app)?;
+    let mut conn = crate::db::acquire(app).await?;
+    purge_event_audio_impl(&mut conn, &audio_dir, keep_days).await
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Commandes Tauri
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Octets WAV de l'enregistrement rattaché (réponse binaire, lue en ArrayBuffer côté TS).
+#[tauri::command]
+pub async fn get_event_audio(
+    app: tauri::AppHandle,
+    event_uuid: String,
+) -> Result<tauri::ipc::Response, AppError> {
+    let audio_dir = event_audio_dir(&app)?;
+    let mut conn = crate::db::acquire(&app).await?;
+    let path = get_event_audio_path_impl(&mut conn, &audio_dir, &event_uuid).await?;
+
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|e| AppError::io("Lecture de l'enregistrement impossible", e))?;
+    Ok(tauri::ipc::Response::new(bytes))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::tests::{make_event, setup_test_db};
+    use crate::events::add_event_impl;
+
+    async fn add_vocal_event(conn: &mut sqlx::sqlite::SqliteConnection) -> String {
+        let id = add_event_impl(conn, &make_event(1, "observation", "vocal")).await.unwrap();
+        sqlx::query_scalar("SELECT uuid FROM evenements_pedagogiques WHERE id = ?")
+            .bind(id)
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap()
+    }
+
+    fn write_recording(dir: &Path, name: &str) -> PathBuf {
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, [0u8; 64]).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_attach_moves_file_and_shares_it_between_events() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let root = tempfile::tempdir().unwrap();
+        let audio_dir = root.path().join(EVENT_AUDIO_DIR);
+        let recording = write_recording(&root.path().join("audio_temp"), "recording_1.wav");
+
+        let first = add_vocal_event(&mut conn).await;
+        let second = add_vocal_event(&mut conn).await;
+
+        let attached = attach_audio_impl(&mut conn, &audio_dir, &first, &recording).await.unwrap();
+        assert_eq!(attached.file_name, "recording_1.wav");
+        assert_eq!(attached.size_bytes, 64);
+        assert!(!recording.exists(), "Le fichier temporaire doit être déplacé");
+
+        // Deuxième événement de la même dictée : même fichier, déjà déplacé
+        attach_audio_impl(&mut conn, &audio_dir, &second, &recording).await.unwrap();
+
+        let a = get_event_audio_path_impl(&mut conn, &audio_dir, &first).await.unwrap();
+        let b = get_event_audio_path_impl(&mut conn, &audio_dir, &second).await.unwrap();
+        assert_eq!(a, b);
+        assert!(a.exists());
+    }
+
+    #[tokio::test]
+    async fn test_attach_refused_on_closed_annee_and_unknown_event() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let root = tempfile::tempdir().unwrap();
+        let audio_dir = root.path().join(EVENT_AUDIO_DIR);
+        let recording = write_recording(&root.path().join("audio_temp"), "recording_2.wav");
+        let uuid = add_vocal_event(&mut conn).await;
+
+        let unknown = attach_audio_impl(&mut conn, &audio_dir, "inconnu", &recording).await;
+        assert_eq!(unknown.unwrap_err().code(), "NotFound");
+
+        sqlx::query("UPDATE annees_scolaires SET cloturee = 1 WHERE id = 1")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        let closed = attach_audio_impl(&mut conn, &audio_dir, &uuid, &recording).await;
+        assert!(matches!(closed, Err(AppError::YearClosed)));
+        assert!(recording.exists(), "Rien n'est déplacé si l'année est clôturée");
+    }
+
+    #[tokio::test]
+    async fn test_get_audio_refused_once_annee_closed() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let root = tempfile::tempdir().unwrap();
+        let audio_dir = root.path().join(EVENT_AUDIO_DIR);
+        let uuid = add_vocal_event(&mut conn).await;
+        let recording = write_recording(&root.path().join("audio_temp"), "r.wav");
+        attach_audio_impl(&mut conn, &audio_dir, &uuid, &recording).await.unwrap();
+
+        // Clôture hors commande (ancienne version du frontend) : fichier encore présent
+        sqlx::query("UPDATE annees_scolaires SET cloturee = 1 WHERE id = 1")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        let closed = get_event_audio_path_impl(&mut conn, &audio_dir, &uuid).await;
+        assert!(matches!(closed, Err(AppError::YearClosed)));
+        assert!(audio_dir.join("r.wav").exists());
+    }
+
+    #[tokio::test]
+    async fn test_cloture_annee_purges_its_recordings() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let root = tempfile::tempdir().unwrap();
+        let audio_dir = root.path().join(EVENT_AUDIO_DIR);
+        let uuid = add_vocal_event(&mut conn).await;
+        let recording = write_recording(&root.path().join("audio_temp"), "r.wav");
+        attach_audio_impl(&mut conn, &audio_dir, &uuid, &recording).await.unwrap();
+
+        let report = crate::annee::cloturer_annee_impl(&mut conn, &audio_dir, Some(365), 1).await.unwrap();
+        assert_eq!(report.deleted, 1);
+        assert!(!audio_dir.join("r.wav").exists());
+        let err = get_event_audio_path_impl(&mut conn, &audio_dir, &uuid).await.unwrap_err();
+        assert_eq!(err.code(), "NotFound");
+
+        let unknown = crate::annee::cloturer_annee_impl(&mut conn, &audio_dir, Some(365), 42).await;
+        assert_eq!(unknown.unwrap_err().code(), "NotFound");
+    }
+
+    #[tokio::test]
+    async fn test_get_audio_without_attachment_is_not_found() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let root = tempfile::tempdir().unwrap();
+        let uuid = add_vocal_event(&mut conn).await;
+
+        let err = get_event_audio_path_impl(&mut conn, root.path(), &uuid).await.unwrap_err();
+        assert_eq!(err.code(), "NotFound");
+    }
+
+    #[tokio::test]
+    async fn test_purge_by_age_closed_year_and_policy() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let root = tempfile::tempdir().unwrap();
+        let audio_dir = root.path().join(EVENT_AUDIO_DIR);
+        let temp_dir = root.path().join("audio_temp");
+
+        let old = add_vocal_event(&mut conn).await;
+        let recent = add_vocal_event(&mut conn).await;
+        attach_audio_impl(&mut conn, &audio_dir, &old, &write_recording(&temp_dir, "old.wav"))
+            .await
+            .unwrap();
+        attach_audio_impl(&mut conn, &audio_dir, &recent, &write_recording(&temp_dir, "recent.wav"))
+            .await
+            .unwrap();
+        sqlx::query("UPDATE evenements_audio SET created_at = datetime('now', '-10 days') WHERE event_uuid = ?")
+            .bind(&old)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        write_recording(&audio_dir, "orphan.wav");
+
+        let report = purge_event_audio_impl(&mut conn, &audio_dir, Some(7)).await.unwrap();
+        assert_eq!(report.deleted, 2, "old.wav expiré + orphan.wav non référencé");
+        assert!(audio_dir.join("recent.wav").exists());
+
+        // Année clôturée : tout part, quelle que soit la durée
+        sqlx::query("UPDATE annees_scolaires SET cloturee = 1 WHERE id = 1")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        let report = purge_event_audio_impl(&mut conn, &audio_dir, Some(365)).await.unwrap();
+        assert_eq!(report.deleted, 1);
+        assert!(!audio_dir.join("recent.wav").exists());
+    }
+
+    #[tokio::test]
+    async fn test_purge_without_keep_days_removes_everything() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let root = tempfile::tempdir().unwrap();
+        let audio_dir = root.path().join(EVENT_AUDIO_DIR);
+        let uuid = add_vocal_event(&mut conn).await;
+        let recording = write_recording(&root.path().join("audio_temp"), "r.wav");
+        attach_audio_impl(&mut conn, &audio_dir, &uuid, &recording).await.unwrap();
+
+        let report = purge_event_audio_impl(&mut conn, &audio_dir, None).await.unwrap();
+        assert_eq!(report.deleted, 1);
+        let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM evenements_audio")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(rows, 0);
+    }
+}
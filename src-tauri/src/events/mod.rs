@@ -3,11 +3,12 @@
 /// Append-only : INSERT uniquement, pas d'UPDATE ni DELETE.
 /// Chaque événement reçoit un UUID v4 pour future sync mobile.
 
+pub mod audio;
+
 use serde::{Deserialize, Serialize};
-use sqlx::Connection;
 
 use crate::annee::check_annee_not_closed_impl;
-use crate::migrations::get_db_path;
+use crate::error::AppError;
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Types
@@ -26,6 +27,9 @@ pub struct NewEvent {
     pub observations: Option<String>,
     pub texte_dictation: Option<String>,
     pub source: String, // 'vocal' | 'manual'
+    /// Enregistrement de la dictée (source 'vocal'), rattaché si la politique le conserve
+    #[serde(default)]
+    pub audio_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +49,8 @@ pub struct PedagogicalEvent {
     pub source: String,
     pub created_at: String,
     pub synced_at: Option<String>,
+    /// Un enregistrement est rattaché (evenements_audio)
+    pub has_audio: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -63,7 +69,7 @@ pub struct EventFilter {
 pub async fn add_event_impl(
     conn: &mut sqlx::sqlite::SqliteConnection,
     event: &NewEvent,
-) -> Result<i64, String> {
+) -> Result<i64, AppError> {
     // Guard : année non clôturée
     check_annee_not_closed_impl(conn, event.annee_scolaire_id).await?;
 
@@ -87,7 +93,7 @@ pub async fn add_event_impl(
     .bind(&event.source)
     .execute(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur insertion événement : {}", e))?;
+    .map_err(|e| AppError::db("Erreur insertion événement", e))?;
 
     Ok(result.last_insert_rowid())
 }
@@ -95,10 +101,11 @@ pub async fn add_event_impl(
 pub async fn load_events_impl(
     conn: &mut sqlx::sqlite::SqliteConnection,
     filter: &EventFilter,
-) -> Result<Vec<PedagogicalEvent>, String> {
+) -> Result<Vec<PedagogicalEvent>, AppError> {
     let mut sql = String::from(
         "SELECT id, uuid, eleve_id, annee_scolaire_id, periode_id, type, domaine_id,
-                lecon, niveau_lsu, observations, texte_dictation, source, created_at, synced_at
+                lecon, niveau_lsu, observations, texte_dictation, source, created_at, synced_at,
+                EXISTS(SELECT 1 FROM evenements_audio a WHERE a.event_uuid = evenements_pedagogiques.uuid) AS has_audio
          FROM evenements_pedagogiques WHERE 1=1",
     );
     let mut binds: Vec<String> = Vec::new();
@@ -135,7 +142,7 @@ pub async fn load_events_impl(
     let rows = query
         .fetch_all(&mut *conn)
         .await
-        .map_err(|e| format!("Erreur chargement événements : {}", e))?;
+        .map_err(|e| AppError::db("Erreur chargement événements", e))?;
 
     Ok(rows.into_iter().map(|r| r.into()).collect())
 }
@@ -158,6 +165,7 @@ struct EventRow {
     source: String,
     created_at: String,
     synced_at: Option<String>,
+    has_audio: bool,
 }
 
 impl From<EventRow> for PedagogicalEvent {
@@ -177,6 +185,7 @@ impl From<EventRow> for PedagogicalEvent {
             source: r.source,
             created_at: r.created_at,
             synced_at: r.synced_at,
+            has_audio: r.has_audio,
         }
     }
 }
@@ -186,26 +195,60 @@ impl From<EventRow> for PedagogicalEvent {
 // ─────────────────────────────────────────────────────────────────────────────
 
 #[tauri::command]
-pub async fn add_event(app: tauri::AppHandle, event: NewEvent) -> Result<i64, String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
+pub async fn add_event(app: tauri::AppHandle, event: NewEvent) -> Result<i64, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
+
+    let id = add_event_impl(&mut conn, &event).await?;
+
+    // Pièce jointe audio : un échec n'annule pas l'événement déjà enregistré
+    if let (Some(path), "vocal") = (&event.audio_path, event.source.as_str()) {
+        if let Err(e) = attach_recording(&app, &mut conn, id, path).await {
+            log::warn!("Enregistrement non rattaché à l'événement {} : {}", id, e);
+        }
+    }
 
-    add_event_impl(&mut conn, &event).await
+    Ok(id)
+}
+
+/// Rattache l'enregistrement si la politique de conservation le garde (keep_days).
+/// Le chemin vient du frontend : seul un fichier des dossiers d'enregistrement est accepté.
+async fn attach_recording(
+    app: &tauri::AppHandle,
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    event_id: i64,
+    path: &str,
+) -> Result<(), AppError> {
+    if crate::audio::commands::current_retention(app).event_audio_days().is_none() {
+        return Ok(());
+    }
+
+    let recording = std::path::Path::new(path);
+    let audio_dir = audio::event_audio_dir(app)?;
+    let already_moved = recording
+        .file_name()
+        .is_some_and(|name| audio_dir.join(name).exists());
+    let dirs = crate::audio::commands::recording_dirs(app)?;
+    if !already_moved && !crate::audio::retention::is_managed_recording(&dirs, recording) {
+        return Err(AppError::validation("Enregistrement hors des dossiers audio"));
+    }
+
+    let uuid: String =
+        sqlx::query_scalar("SELECT uuid FROM evenements_pedagogiques WHERE id = ?")
+            .bind(event_id)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| AppError::db("Erreur lecture événement", e))?;
+
+    audio::attach_audio_impl(conn, &audio_dir, &uuid, recording).await?;
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn load_events(
     app: tauri::AppHandle,
     filter: EventFilter,
-) -> Result<Vec<PedagogicalEvent>, String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
+) -> Result<Vec<PedagogicalEvent>, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
 
     load_events_impl(&mut conn, &filter).await
 }
@@ -217,8 +260,9 @@ pub async fn load_events(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sqlx::Connection;
 
-    async fn setup_test_db() -> (sqlx::sqlite::SqliteConnection, tempfile::NamedTempFile) {
+    pub(super) async fn setup_test_db() -> (sqlx::sqlite::SqliteConnection, tempfile::NamedTempFile) {
         let tmp = tempfile::NamedTempFile::new().unwrap();
         let url = format!("sqlite:{}", tmp.path().display());
         let mut conn = sqlx::sqlite::SqliteConnection::connect(&url)
@@ -304,6 +348,18 @@ mod tests {
         .await
         .unwrap();
 
+        sqlx::query(
+            "CREATE TABLE evenements_audio (
+                event_uuid TEXT PRIMARY KEY REFERENCES evenements_pedagogiques(uuid) ON DELETE CASCADE,
+                file_name TEXT NOT NULL,
+                size_bytes INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT DEFAULT (datetime('now'))
+            )",
+        )
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
         // Seed data
         sqlx::query("INSERT INTO annees_scolaires (label, date_debut, date_fin, active) VALUES ('2025-2026', '2025-09-01', '2026-07-05', 1)")
             .execute(&mut conn).await.unwrap();
@@ -317,7 +373,7 @@ mod tests {
         (conn, tmp)
     }
 
-    fn make_event(eleve_id: i64, event_type: &str, source: &str) -> NewEvent {
+    pub(super) fn make_event(eleve_id: i64, event_type: &str, source: &str) -> NewEvent {
         NewEvent {
             eleve_id,
             annee_scolaire_id: 1,
@@ -329,6 +385,7 @@ mod tests {
             observations: Some("Bonne participation".to_string()),
             texte_dictation: None,
             source: source.to_string(),
+            audio_path: None,
         }
     }
 
@@ -368,7 +425,7 @@ mod tests {
         let event = make_event(1, "observation", "manual");
         let result = add_event_impl(&mut conn, &event).await;
         assert!(result.is_err(), "Le guard doit bloquer une année clôturée");
-        assert!(result.unwrap_err().contains("cloturee"));
+        assert!(matches!(result.unwrap_err(), AppError::YearClosed));
     }
 
     #[tokio::test]
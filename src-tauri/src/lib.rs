@@ -11,9 +11,13 @@ mod absences;
 mod annee;
 mod appreciation;
 mod audio;
+mod db;
+mod error;
 mod events;
 mod migrations;
 mod models;
+mod notices;
+mod placeholders;
 mod sidecar;
 mod synthese;
 mod validation;
@@ -22,8 +26,10 @@ mod validation;
 /// Le frontend doit l'appeler après l'ouverture de la DB (Database.open) pour couvrir
 /// le cas d'une installation fraîche où la DB n'existait pas au démarrage.
 #[tauri::command]
-async fn ensure_v2_1_migrations(app: tauri::AppHandle) -> Result<(), String> {
-    migrations::run_v2_1_migrations(&app).await
+async fn ensure_v2_1_migrations(app: tauri::AppHandle) -> Result<(), error::AppError> {
+    migrations::run_v2_1_migrations(&app)
+        .await
+        .map_err(|e| error::AppError::db("Erreur migrations V2.1", e))
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -214,26 +220,60 @@ pub fn run() {
         .plugin(tauri_plugin_mic_recorder::init())
         .plugin(tauri_plugin_dialog::init())
         .manage(sidecar::SidecarManager::new())
+        .manage(db::DbState::new())
+        .manage(sidecar::streaming::TranscriptionStreams::default())
+        .manage(sidecar::llm_queue::LlmJobQueue::default())
         .invoke_handler(tauri::generate_handler![
             ensure_v2_1_migrations,
             annee::check_annee_not_closed,
+            annee::cloturer_annee,
             audio::commands::save_wav_file,
+            audio::commands::get_audio_retention,
+            audio::commands::set_audio_retention,
+            audio::commands::get_audio_storage_usage,
+            audio::commands::mark_recording_validated,
+            audio::commands::clear_audio_recordings,
             sidecar::commands::start_sidecar,
             sidecar::commands::stop_sidecar,
             sidecar::commands::get_sidecar_status,
             sidecar::commands::get_pipeline_config,
             sidecar::commands::set_pipeline_mode,
+            sidecar::commands::get_sidecar_settings,
+            sidecar::commands::set_sidecar_settings,
             sidecar::transcription::transcribe_audio,
+            sidecar::streaming::start_transcription_stream,
+            sidecar::streaming::push_transcription_chunk,
+            sidecar::streaming::finish_transcription_stream,
+            sidecar::streaming::cancel_transcription_stream,
+            sidecar::vocabulary::get_whisper_glossary,
+            sidecar::vocabulary::add_whisper_glossary_term,
+            sidecar::vocabulary::delete_whisper_glossary_term,
+            sidecar::vocabulary::get_whisper_initial_prompt,
+            sidecar::corrections::get_transcription_corrections,
+            sidecar::corrections::add_transcription_correction,
+            sidecar::corrections::delete_transcription_correction,
+            sidecar::corrections::get_applied_corrections,
+            sidecar::prompt_templates::get_prompt_templates,
+            sidecar::prompt_templates::get_prompt_template_versions,
+            sidecar::prompt_templates::save_prompt_template,
+            sidecar::prompt_templates::reset_prompt_template,
             sidecar::structuration::classify_and_merge,
             sidecar::structuration::generate_synthese,
             sidecar::structuration::generate_appreciation,
+            sidecar::llm_queue::get_llm_job,
+            sidecar::llm_queue::list_llm_jobs,
+            sidecar::llm_queue::cancel_llm_job,
             validation::validate_and_insert_observations,
             models::checker::check_models_status,
             models::downloader::download_models,
             models::downloader::cancel_download,
             models::installer::install_models_from_folder,
+            models::catalog::list_model_catalog,
+            models::catalog::set_active_model,
+            models::audit::audit_models,
             events::add_event,
             events::load_events,
+            events::audio::get_event_audio,
             absences::toggle_absence_v2,
             absences::update_absence_type,
             absences::update_absence_motif,
@@ -249,6 +289,8 @@ pub fn run() {
             appreciation::load_appreciation_current,
             appreciation::load_appreciation_versions,
             appreciation::restore_appreciation_version,
+            notices::generate_family_notice,
+            notices::load_family_notices,
         ])
         .setup(|app| {
             // Logging in debug mode
@@ -290,6 +332,18 @@ pub fn run() {
                 }
             })?;
 
+            // Paramètres sidecars (ports, threads, ctx-size) chargés avant tout démarrage
+            tauri::async_runtime::block_on(sidecar::commands::init_settings(app.handle()));
+
+            // File des tâches IA : un seul worker démarre et arrête llama-server
+            sidecar::llm_queue::spawn_worker(app.handle());
+
+            // Mode concurrent : arrêt des sidecars inactifs et préchauffage (démarrage, heure planifiée)
+            sidecar::idle::spawn_monitor(app.handle());
+
+            // Enregistrements audio périmés (voix d'élèves) : purge selon la politique de conservation
+            audio::commands::purge_stale_recordings(app.handle());
+
             // Migrations V2→V2.1 : lancées en arrière-plan dès le démarrage.
             // Couvre le cas upgrade (DB V2 existante) sans bloquer l'UI.
             // Pour une installation fraîche, le frontend appelle ensure_v2_1_migrations
@@ -299,6 +353,14 @@ pub fn run() {
                 if let Err(e) = migrations::run_v2_1_migrations(&migration_handle).await {
                     eprintln!("[setup] Erreur migrations V2.1 : {}", e);
                 }
+                // Pool SQLite partagé, ouvert une fois les migrations passées
+                if let Err(e) = db::pool(&migration_handle).await {
+                    eprintln!("[setup] Erreur ouverture pool DB : {}", e);
+                }
+                // Enregistrements rattachés aux événements : expirés ou année clôturée
+                if let Err(e) = events::audio::purge_event_audio(&migration_handle).await {
+                    eprintln!("[setup] Erreur purge audio des événements : {}", e);
+                }
             });
 
             Ok(())
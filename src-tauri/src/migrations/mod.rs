@@ -1,5 +1,6 @@
 pub mod v2_1;
 pub mod v2_1_rev2;
+pub mod v2_2;
 
 use sqlx::Connection;
 use std::path::PathBuf;
@@ -16,13 +17,28 @@ static MIGRATIONS_DONE: AtomicBool = AtomicBool::new(false);
 const V2_1_USER_VERSION: i32 = 11;
 
 /// Retourne le chemin du fichier SQLite selon la plateforme.
+/// Même dossier que tauri-plugin-sql (`sqlite:comportement.db` → app_config_dir) :
 /// macOS  : ~/Library/Application Support/fr.comportement.app/comportement.db
-/// Windows: %LOCALAPPDATA%\fr.comportement.app\comportement.db
+/// Windows: %APPDATA%\fr.comportement.app\comportement.db
 pub fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
-    app.path()
-        .app_local_data_dir()
+    let db_path = app
+        .path()
+        .app_config_dir()
         .map(|dir| dir.join("comportement.db"))
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    // Ancien emplacement (app_local_data_dir) : distinct sous Windows uniquement
+    if let Ok(legacy) = app.path().app_local_data_dir().map(|d| d.join("comportement.db")) {
+        if legacy != db_path && legacy.exists() {
+            log::warn!(
+                "[db] Fichier DB orphelin ignoré : {} (DB active : {})",
+                legacy.display(),
+                db_path.display()
+            );
+        }
+    }
+
+    Ok(db_path)
 }
 
 /// Crée une copie de sauvegarde du fichier SQLite avant les migrations.
@@ -179,6 +195,79 @@ pub async fn apply_m013_if_needed(
     Ok(())
 }
 
+// ─────────────────────────────────────────────────────────────────────────────
+// Migrations post-V2.1 (M014+) — une user_version par migration.
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Applique les migrations V2.2 dont la version dépasse PRAGMA user_version.
+/// Même pattern SAVEPOINT que V2.1 ; user_version est avancé après chaque migration,
+/// ce qui rend l'opération idempotente et reprenable.
+/// Retourne la user_version finale.
+pub async fn apply_v2_2_migrations(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+) -> Result<i32, String> {
+    let mut user_version: i32 = sqlx::query_scalar::<_, i32>("PRAGMA user_version")
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| format!("Impossible de lire PRAGMA user_version : {}", e))?;
+
+    for migration in v2_2::migrations() {
+        if migration.version <= user_version {
+            continue;
+        }
+
+        let sp_name = format!("sp_{}", migration.name);
+        sqlx::query(&format!("SAVEPOINT {}", sp_name))
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("Impossible de poser le SAVEPOINT {} : {}", sp_name, e))?;
+
+        let mut migration_ok = true;
+        for statement in migration.statements {
+            let stmt = statement.trim();
+            if stmt.is_empty() {
+                continue;
+            }
+            if let Err(e) = sqlx::query(stmt).execute(&mut *conn).await {
+                eprintln!("[migrations] Erreur dans {} : {}", migration.name, e);
+                migration_ok = false;
+                break;
+            }
+        }
+
+        if !migration_ok {
+            let _ = sqlx::query(&format!("ROLLBACK TO SAVEPOINT {}", sp_name))
+                .execute(&mut *conn)
+                .await;
+            let _ = sqlx::query(&format!("RELEASE {}", sp_name))
+                .execute(&mut *conn)
+                .await;
+            return Err(format!(
+                "Migration {} en échec → rollback (user_version={}).",
+                migration.name, user_version
+            ));
+        }
+
+        sqlx::query(&format!("RELEASE {}", sp_name))
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("RELEASE SAVEPOINT {} échoué : {}", sp_name, e))?;
+
+        sqlx::query(&format!("PRAGMA user_version = {}", migration.version))
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("Impossible de mettre à jour PRAGMA user_version : {}", e))?;
+        user_version = migration.version;
+
+        println!(
+            "[migrations] ✓ {} appliquée (user_version={}).",
+            migration.name, user_version
+        );
+    }
+
+    Ok(user_version)
+}
+
 /// Point d'entrée principal : vérifie et applique les migrations V2→V2.1.
 ///
 /// Logique :
@@ -186,6 +275,7 @@ pub async fn apply_m013_if_needed(
 /// 2. Si la table `students` n'existe pas → skip (même raison)
 /// 3. Si PRAGMA user_version >= 11 → déjà appliqué, idempotent
 /// 4. Sinon : backup + 12 migrations M001-M012 avec SAVEPOINT + M013 conditionnel + PRAGMA user_version = 11
+/// 5. Dans tous les cas : migrations V2.2 (M014+) en attente, une user_version chacune
 pub async fn run_v2_1_migrations(app: &AppHandle) -> Result<(), String> {
     // Fast path : si déjà fait, skip immédiatement
     if MIGRATIONS_DONE.load(Ordering::Acquire) {
@@ -238,8 +328,12 @@ pub async fn run_v2_1_migrations(app: &AppHandle) -> Result<(), String> {
         .map_err(|e| format!("Impossible de lire PRAGMA user_version : {}", e))?;
 
     if user_version >= V2_1_USER_VERSION {
-        MIGRATIONS_DONE.store(true, Ordering::Release);
         println!("[migrations] Migrations V2.1 déjà appliquées (user_version={}).", user_version);
+        if user_version < v2_2::latest_version() {
+            backup_database(&db_path).await?;
+            apply_v2_2_migrations(&mut conn).await?;
+        }
+        MIGRATIONS_DONE.store(true, Ordering::Release);
         return Ok(());
     }
 
@@ -410,12 +504,15 @@ pub async fn run_v2_1_migrations(app: &AppHandle) -> Result<(), String> {
         .await
         .map_err(|e| format!("Impossible de mettre à jour PRAGMA user_version : {}", e))?;
 
+    // Migrations V2.2 (M014+) — couvertes par le backup pris avant V2.1
+    let final_version = apply_v2_2_migrations(&mut conn).await?;
+
     // Marquer comme fait pour le fast path
     MIGRATIONS_DONE.store(true, Ordering::Release);
 
     println!(
         "[migrations] ✅ Toutes les migrations V2.1 appliquées (user_version={}).",
-        V2_1_USER_VERSION
+        final_version
     );
     Ok(())
 }
@@ -958,4 +1055,28 @@ mod tests {
             insert2.err()
         );
     }
+
+    #[tokio::test]
+    async fn test_v2_2_migrations_apply_after_v2_1() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let path = tmp.path().to_path_buf();
+
+        let mut conn = setup_v2_db_file(&path).await;
+        assert!(apply_migrations_direct(&mut conn).await);
+
+        let version = apply_v2_2_migrations(&mut conn).await.unwrap();
+        assert_eq!(version, v2_2::latest_version());
+
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='notices_familles'",
+        )
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+        assert_eq!(count, 1, "La table notices_familles doit exister après M014");
+
+        // Deuxième passage : no-op, user_version inchangée
+        let again = apply_v2_2_migrations(&mut conn).await.unwrap();
+        assert_eq!(again, version, "Les migrations V2.2 doivent être idempotentes");
+    }
 }
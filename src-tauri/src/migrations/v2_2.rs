@@ -0,0 +1,218 @@
+/// Migrations post-V2.1 (M014+), appliquées de façon incrémentale.
+/// Chaque migration porte sa propre user_version : elle n'est appliquée que si
+/// PRAGMA user_version est strictement inférieur, puis user_version est avancé.
+pub struct V22Migration {
+    pub version: i32,
+    pub name: &'static str,
+    pub statements: &'static [&'static str],
+}
+
+pub fn migrations() -> Vec<V22Migration> {
+    vec![
+        // M014 : Table notices_familles (courriers aux familles, suivi des envois)
+        V22Migration {
+            version: 12,
+            name: "m014_create_notices_familles",
+            statements: &[
+                "CREATE TABLE IF NOT EXISTS notices_familles (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    uuid TEXT UNIQUE,
+                    eleve_id INTEGER NOT NULL REFERENCES students(id) ON DELETE CASCADE,
+                    annee_scolaire_id INTEGER NOT NULL REFERENCES annees_scolaires(id),
+                    periode_id INTEGER REFERENCES config_periodes(id),
+                    date_debut TEXT NOT NULL,
+                    date_fin TEXT NOT NULL,
+                    nb_incidents INTEGER NOT NULL DEFAULT 0,
+                    file_path TEXT NOT NULL,
+                    created_at TEXT DEFAULT (datetime('now'))
+                )",
+                "CREATE INDEX IF NOT EXISTS idx_notice_eleve ON notices_familles(eleve_id)",
+                "CREATE INDEX IF NOT EXISTS idx_notice_annee ON notices_familles(annee_scolaire_id)",
+            ],
+        },
+        // M015 : Catalogue de modèles IA dans models_status (rôle, URL, taille, modèle actif)
+        //        La table V2 (migration frontend 8) est recréée si absente.
+        V22Migration {
+            version: 13,
+            name: "m015_models_status_catalog",
+            statements: &[
+                "CREATE TABLE IF NOT EXISTS models_status (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    model_name TEXT NOT NULL UNIQUE,
+                    file_path TEXT NOT NULL,
+                    file_size INTEGER,
+                    sha256 TEXT,
+                    installed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                    version TEXT
+                )",
+                "ALTER TABLE models_status ADD COLUMN label TEXT DEFAULT NULL",
+                "ALTER TABLE models_status ADD COLUMN role TEXT DEFAULT NULL CHECK(role IN ('whisper', 'llama'))",
+                "ALTER TABLE models_status ADD COLUMN filename TEXT DEFAULT NULL",
+                "ALTER TABLE models_status ADD COLUMN url TEXT DEFAULT NULL",
+                "ALTER TABLE models_status ADD COLUMN size_mb INTEGER DEFAULT NULL",
+                "ALTER TABLE models_status ADD COLUMN recommended_ram_gb REAL DEFAULT NULL",
+                "ALTER TABLE models_status ADD COLUMN ctx_size INTEGER DEFAULT NULL",
+                "ALTER TABLE models_status ADD COLUMN active INTEGER NOT NULL DEFAULT 0",
+                "CREATE INDEX IF NOT EXISTS idx_models_status_role ON models_status(role, active)",
+            ],
+        },
+        // M016 : Résultat du dernier audit d'intégrité (SHA256) par modèle
+        V22Migration {
+            version: 14,
+            name: "m016_models_status_audit",
+            statements: &[
+                "ALTER TABLE models_status ADD COLUMN audit_status TEXT DEFAULT NULL CHECK(audit_status IN ('ok', 'corrupted', 'missing', 'unverified'))",
+                "ALTER TABLE models_status ADD COLUMN audited_at DATETIME DEFAULT NULL",
+            ],
+        },
+        // M017 : Enregistrement audio d'une dictée rattaché à ses événements (par uuid).
+        //        Table séparée : evenements_pedagogiques reste append-only, la pièce jointe
+        //        peut être supprimée (politique de conservation, année clôturée).
+        //        Plusieurs événements issus d'une même dictée partagent le même fichier.
+        V22Migration {
+            version: 15,
+            name: "m017_create_evenements_audio",
+            statements: &[
+                "CREATE TABLE IF NOT EXISTS evenements_audio (
+                    event_uuid TEXT PRIMARY KEY REFERENCES evenements_pedagogiques(uuid) ON DELETE CASCADE,
+                    file_name TEXT NOT NULL,
+                    size_bytes INTEGER NOT NULL DEFAULT 0,
+                    created_at TEXT DEFAULT (datetime('now'))
+                )",
+                "CREATE INDEX IF NOT EXISTS idx_evt_audio_file ON evenements_audio(file_name)",
+            ],
+        },
+        // M018 : Glossaire éditable injecté dans le prompt initial de Whisper
+        //        (jargon scolaire mal transcrit). Quelques termes par défaut.
+        V22Migration {
+            version: 16,
+            name: "m018_create_whisper_glossaire",
+            statements: &[
+                "CREATE TABLE IF NOT EXISTS whisper_glossaire (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    terme TEXT NOT NULL UNIQUE COLLATE NOCASE,
+                    created_at TEXT DEFAULT (datetime('now'))
+                )",
+                "INSERT OR IGNORE INTO whisper_glossaire (terme) VALUES
+                    ('EMC'), ('EPS'), ('imparfait'), ('passé composé'),
+                    ('fractions décimales'), ('conjugaison'), ('périmètre')",
+            ],
+        },
+        // M019 : Dictionnaire de corrections appliqué après chaque transcription
+        //        (motif → remplacement, mot entier, insensible à la casse).
+        //        eleve_id NULL = correction globale, sinon limitée à l'élève.
+        V22Migration {
+            version: 17,
+            name: "m019_create_corrections_transcription",
+            statements: &[
+                "CREATE TABLE IF NOT EXISTS corrections_transcription (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    motif TEXT NOT NULL,
+                    remplacement TEXT NOT NULL,
+                    eleve_id INTEGER DEFAULT NULL REFERENCES students(id) ON DELETE CASCADE,
+                    created_at TEXT DEFAULT (datetime('now'))
+                )",
+                "CREATE INDEX IF NOT EXISTS idx_corrections_eleve ON corrections_transcription(eleve_id)",
+            ],
+        },
+        // M020 : Modèles de prompt des jobs LLM, versionnés (une ligne par version).
+        //        cycle 0 = tous les cycles, 1-3 = surcharge ; contenu NULL = surcharge retirée.
+        //        Les synthèses et appréciations générées notent le modèle utilisé.
+        V22Migration {
+            version: 18,
+            name: "m020_create_prompt_templates",
+            statements: &[
+                "CREATE TABLE IF NOT EXISTS prompt_templates (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    job TEXT NOT NULL CHECK(job IN ('classification', 'synthese', 'appreciation')),
+                    cycle INTEGER NOT NULL DEFAULT 0 CHECK(cycle BETWEEN 0 AND 3),
+                    version INTEGER NOT NULL,
+                    contenu TEXT DEFAULT NULL,
+                    longueur_max INTEGER NOT NULL,
+                    created_at TEXT DEFAULT (datetime('now')),
+                    UNIQUE(job, cycle, version)
+                )",
+                "ALTER TABLE syntheses_lsu ADD COLUMN prompt_template_id INTEGER DEFAULT NULL REFERENCES prompt_templates(id)",
+                "ALTER TABLE appreciations_generales ADD COLUMN prompt_template_id INTEGER DEFAULT NULL REFERENCES prompt_templates(id)",
+            ],
+        },
+        // M021 : Journal des corrections appliquées, une ligne par entrée du dictionnaire
+        //        et par transcription (transcription_uuid renvoyé avec le texte).
+        //        motif/remplacement copiés : l'entrée du dictionnaire peut être supprimée.
+        V22Migration {
+            version: 19,
+            name: "m021_create_corrections_appliquees",
+            statements: &[
+                "CREATE TABLE IF NOT EXISTS corrections_appliquees (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    transcription_uuid TEXT NOT NULL,
+                    eleve_id INTEGER DEFAULT NULL REFERENCES students(id) ON DELETE CASCADE,
+                    correction_id INTEGER DEFAULT NULL REFERENCES corrections_transcription(id) ON DELETE SET NULL,
+                    motif TEXT NOT NULL,
+                    remplacement TEXT NOT NULL,
+                    occurrences INTEGER NOT NULL,
+                    created_at TEXT DEFAULT (datetime('now'))
+                )",
+                "CREATE INDEX IF NOT EXISTS idx_corrections_appliquees_transcription ON corrections_appliquees(transcription_uuid)",
+            ],
+        },
+        // M022 : Version PDF des notices familles, écrite à côté du HTML.
+        //        NULL pour les notices émises avant (HTML seul).
+        V22Migration {
+            version: 20,
+            name: "m022_notices_pdf_path",
+            statements: &[
+                "ALTER TABLE notices_familles ADD COLUMN pdf_path TEXT DEFAULT NULL",
+            ],
+        },
+    ]
+}
+
+/// Dernière user_version connue (version de la migration la plus récente).
+pub fn latest_version() -> i32 {
+    migrations().iter().map(|m| m.version).max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migration_names_unique() {
+        let migs = migrations();
+        let mut names = std::collections::HashSet::new();
+        for m in &migs {
+            assert!(names.insert(m.name), "Nom de migration dupliqué: {}", m.name);
+        }
+    }
+
+    #[test]
+    fn test_versions_strictly_increasing_after_v2_1() {
+        let migs = migrations();
+        let mut previous = super::super::V2_1_USER_VERSION;
+        for m in &migs {
+            assert!(
+                m.version > previous,
+                "La version de {} ({}) doit suivre {}",
+                m.name,
+                m.version,
+                previous
+            );
+            previous = m.version;
+        }
+    }
+
+    #[test]
+    fn test_migration_statements_non_empty() {
+        for m in migrations() {
+            assert!(
+                !m.statements.is_empty(),
+                "Migration {} n'a aucun statement",
+                m.name
+            );
+            for s in m.statements {
+                assert!(!s.trim().is_empty(), "Statement vide dans {}", m.name);
+            }
+        }
+    }
+}
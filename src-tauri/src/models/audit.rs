@@ -0,0 +1,384 @@
+/// Module Audit — Integrity audit of the installed AI models.
+///
+/// Re-hashes every installed model file (SHA256, in a background task with progress
+/// events), records the verdict and timestamp in `models_status`, and moves corrupted
+/// files to `models/quarantine/`. A quarantined model shows up as not installed, so the
+/// setup wizard offers the usual repair paths: `download_models` or
+/// `install_models_from_folder` (USB).
+
+use super::catalog::{self, CatalogEntry, ModelRole};
+use super::verifier;
+use crate::error::AppError;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::Emitter;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditStatus {
+    /// Hash matches the catalog
+    Ok,
+    /// Hash mismatch: file moved to quarantine
+    Corrupted,
+    /// Active model not present on disk
+    Missing,
+    /// No reference hash known for this model
+    Unverified,
+}
+
+impl AuditStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditStatus::Ok => "ok",
+            AuditStatus::Corrupted => "corrupted",
+            AuditStatus::Missing => "missing",
+            AuditStatus::Unverified => "unverified",
+        }
+    }
+
+    /// The model must be reinstalled (re-download or USB)
+    pub fn needs_repair(&self) -> bool {
+        matches!(self, AuditStatus::Corrupted | AuditStatus::Missing)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelAuditResult {
+    pub model_name: String,
+    pub role: ModelRole,
+    pub filename: String,
+    pub status: AuditStatus,
+    pub expected_sha256: Option<String>,
+    pub actual_sha256: Option<String>,
+    pub quarantined_path: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct AuditProgress {
+    pub model_name: String,
+    pub current: usize, // 1-based index
+    pub total: usize,
+    pub hashed_bytes: u64,
+    pub total_bytes: u64,
+    pub percentage: f64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct AuditReport {
+    pub results: Vec<ModelAuditResult>,
+    /// At least one active model is corrupted or missing
+    pub needs_repair: bool,
+    pub error: Option<String>,
+}
+
+/// Guard: a single audit at a time (hashing two 1 GB files in parallel only slows both)
+static AUDIT_RUNNING: AtomicBool = AtomicBool::new(false);
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Impls (testables)
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Models to audit: the active model of each role (even if missing) plus any
+/// other catalog model present on disk.
+pub fn audit_targets(models_dir: &Path, catalog: &[CatalogEntry]) -> Vec<CatalogEntry> {
+    let mut targets = catalog::active_models(catalog);
+    for entry in catalog {
+        if targets.iter().any(|t| t.name == entry.name) {
+            continue;
+        }
+        if models_dir.join(&entry.filename).exists() {
+            targets.push(entry.clone());
+        }
+    }
+    targets
+}
+
+/// Move a corrupted file to `models/quarantine/<filename>.<unix>.corrupt`.
+pub fn quarantine_file(models_dir: &Path, path: &Path) -> Result<PathBuf, String> {
+    let quarantine_dir = models_dir.join("quarantine");
+    std::fs::create_dir_all(&quarantine_dir)
+        .map_err(|e| format!("Impossible de creer {}: {}", quarantine_dir.display(), e))?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let target = quarantine_dir.join(format!("{}.{}.corrupt", file_name, now));
+
+    std::fs::rename(path, &target)
+        .map_err(|e| format!("Impossible de mettre {} en quarantaine: {}", path.display(), e))?;
+    Ok(target)
+}
+
+/// Audit one model file. Blocking (hashes the whole file): run it off the async runtime.
+pub fn audit_model_file<F: FnMut(u64, u64)>(
+    models_dir: &Path,
+    entry: &CatalogEntry,
+    on_progress: F,
+) -> Result<ModelAuditResult, String> {
+    let path = models_dir.join(&entry.filename);
+    let mut result = ModelAuditResult {
+        model_name: entry.name.clone(),
+        role: entry.role,
+        filename: entry.filename.clone(),
+        status: AuditStatus::Missing,
+        expected_sha256: entry.sha256.clone(),
+        actual_sha256: None,
+        quarantined_path: None,
+    };
+
+    if !path.exists() {
+        return Ok(result);
+    }
+
+    let actual = verifier::compute_sha256_with_progress(&path, on_progress)?;
+    result.status = match entry.sha256.as_deref() {
+        None => AuditStatus::Unverified,
+        Some(expected) if actual.eq_ignore_ascii_case(expected) => AuditStatus::Ok,
+        Some(_) => {
+            let target = quarantine_file(models_dir, &path)?;
+            warn!(
+                "Modele {} corrompu (SHA256 {}), mis en quarantaine: {}",
+                entry.name,
+                actual,
+                target.display()
+            );
+            result.quarantined_path = Some(target.to_string_lossy().into());
+            AuditStatus::Corrupted
+        }
+    };
+    result.actual_sha256 = Some(actual);
+    Ok(result)
+}
+
+/// Record the audit verdict and timestamp in `models_status`.
+pub async fn record_audit_impl(
+    conn: &mut SqliteConnection,
+    result: &ModelAuditResult,
+) -> Result<(), String> {
+    sqlx::query(
+        "UPDATE models_status SET audit_status = ?, audited_at = datetime('now')
+         WHERE model_name = ?",
+    )
+    .bind(result.status.as_str())
+    .bind(&result.model_name)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Erreur enregistrement audit {} : {}", result.model_name, e))?;
+
+    // Quarantined file: the model is no longer installed
+    if result.status == AuditStatus::Corrupted {
+        sqlx::query(
+            "UPDATE models_status SET file_path = filename, file_size = NULL, installed_at = NULL
+             WHERE model_name = ?",
+        )
+        .bind(&result.model_name)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Erreur enregistrement audit {} : {}", result.model_name, e))?;
+    }
+    Ok(())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Commandes Tauri
+// ─────────────────────────────────────────────────────────────────────────────
+
+async fn run_audit(app: &tauri::AppHandle, models_dir: PathBuf, targets: Vec<CatalogEntry>) -> AuditReport {
+    let total = targets.len();
+    let mut results = Vec::new();
+
+    for (idx, entry) in targets.into_iter().enumerate() {
+        let app_progress = app.clone();
+        let dir = models_dir.clone();
+        let model_name = entry.name.clone();
+
+        let audited = tauri::async_runtime::spawn_blocking(move || {
+            audit_model_file(&dir, &entry, |hashed, total_bytes| {
+                app_progress
+                    .emit(
+                        "model_audit_progress",
+                        AuditProgress {
+                            model_name: entry.name.clone(),
+                            current: idx + 1,
+                            total,
+                            hashed_bytes: hashed,
+                            total_bytes,
+                            percentage: if total_bytes > 0 {
+                                (hashed as f64 / total_bytes as f64) * 100.0
+                            } else {
+                                100.0
+                            },
+                        },
+                    )
+                    .ok();
+            })
+        })
+        .await;
+
+        let result = match audited {
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => {
+                return AuditReport { results, needs_repair: true, error: Some(e) };
+            }
+            Err(e) => {
+                return AuditReport {
+                    results,
+                    needs_repair: true,
+                    error: Some(format!("Audit de {} interrompu : {}", model_name, e)),
+                };
+            }
+        };
+
+        info!("Audit {} : {}", result.model_name, result.status.as_str());
+        match catalog::open_catalog_db(app).await {
+            Ok(mut conn) => {
+                if let Err(e) = record_audit_impl(&mut conn, &result).await {
+                    warn!("{}", e);
+                }
+            }
+            Err(e) => warn!("Audit de {} non enregistre : {}", result.model_name, e),
+        }
+        results.push(result);
+    }
+
+    AuditReport {
+        needs_repair: results.iter().any(|r| r.status.needs_repair()),
+        results,
+        error: None,
+    }
+}
+
+/// Start a background integrity audit of the installed models.
+/// Progress: `model_audit_progress`; final report: `model_audit_complete`.
+#[tauri::command]
+pub async fn audit_models(app: tauri::AppHandle) -> Result<(), AppError> {
+    if AUDIT_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err(AppError::validation("Une verification des modeles est deja en cours"));
+    }
+
+    let models_dir = match catalog::models_dir(&app) {
+        Ok(dir) => dir,
+        Err(e) => {
+            AUDIT_RUNNING.store(false, Ordering::SeqCst);
+            return Err(e.into());
+        }
+    };
+    let catalog = catalog::load_catalog(&app).await;
+    let targets = audit_targets(&models_dir, &catalog);
+
+    tauri::async_runtime::spawn(async move {
+        let report = run_audit(&app, models_dir, targets).await;
+        AUDIT_RUNNING.store(false, Ordering::SeqCst);
+        app.emit("model_audit_complete", report).ok();
+    });
+
+    Ok(())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::catalog::tests::setup_db;
+
+    const HELLO_SHA: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+    fn entry(name: &str, filename: &str, sha256: Option<&str>) -> CatalogEntry {
+        let mut e = catalog::default_model(ModelRole::Whisper);
+        e.name = name.to_string();
+        e.filename = filename.to_string();
+        e.sha256 = sha256.map(str::to_string);
+        e
+    }
+
+    #[test]
+    fn valid_file_is_ok() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("m.bin"), b"hello world").unwrap();
+
+        let result = audit_model_file(dir.path(), &entry("m", "m.bin", Some(HELLO_SHA)), |_, _| {}).unwrap();
+        assert_eq!(result.status, AuditStatus::Ok);
+        assert_eq!(result.actual_sha256.as_deref(), Some(HELLO_SHA));
+        assert!(dir.path().join("m.bin").exists());
+    }
+
+    #[test]
+    fn corrupted_file_is_quarantined() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("m.bin"), b"hello wor").unwrap(); // truncated
+
+        let result = audit_model_file(dir.path(), &entry("m", "m.bin", Some(HELLO_SHA)), |_, _| {}).unwrap();
+        assert_eq!(result.status, AuditStatus::Corrupted);
+        assert!(result.status.needs_repair());
+        assert!(!dir.path().join("m.bin").exists(), "Le fichier corrompu doit quitter models/");
+        let quarantined = PathBuf::from(result.quarantined_path.unwrap());
+        assert!(quarantined.exists());
+        assert!(quarantined.starts_with(dir.path().join("quarantine")));
+    }
+
+    #[test]
+    fn missing_and_unverified_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = audit_model_file(dir.path(), &entry("m", "absent.bin", Some(HELLO_SHA)), |_, _| {}).unwrap();
+        assert_eq!(missing.status, AuditStatus::Missing);
+
+        std::fs::write(dir.path().join("u.bin"), b"hello world").unwrap();
+        let unverified = audit_model_file(dir.path(), &entry("u", "u.bin", None), |_, _| {}).unwrap();
+        assert_eq!(unverified.status, AuditStatus::Unverified);
+        assert!(!unverified.status.needs_repair());
+        assert!(dir.path().join("u.bin").exists());
+    }
+
+    #[test]
+    fn targets_include_active_and_installed_models() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("ggml-base.bin"), b"x").unwrap();
+
+        let targets = audit_targets(dir.path(), &catalog::builtin_catalog());
+        let names: Vec<&str> = targets.iter().map(|t| t.name.as_str()).collect();
+        assert!(names.contains(&"whisper-small"), "Modele actif audite meme absent");
+        assert!(names.contains(&"qwen2.5-coder-1.5b"));
+        assert!(names.contains(&"whisper-base"), "Modele installe non actif audite");
+        assert!(!names.contains(&"whisper-medium"));
+    }
+
+    #[tokio::test]
+    async fn audit_result_is_recorded_with_timestamp() {
+        let (_tmp, mut conn) = setup_db().await;
+        catalog::sync_builtin_catalog_impl(&mut conn).await.unwrap();
+
+        let result = ModelAuditResult {
+            model_name: "whisper-small".to_string(),
+            role: ModelRole::Whisper,
+            filename: "ggml-small.bin".to_string(),
+            status: AuditStatus::Corrupted,
+            expected_sha256: None,
+            actual_sha256: Some("00".to_string()),
+            quarantined_path: Some("/q/ggml-small.bin.1.corrupt".to_string()),
+        };
+        record_audit_impl(&mut conn, &result).await.unwrap();
+
+        let catalog = catalog::load_catalog_impl(&mut conn).await.unwrap();
+        let small = catalog.iter().find(|e| e.name == "whisper-small").unwrap();
+        assert_eq!(small.audit_status.as_deref(), Some("corrupted"));
+        assert!(small.audited_at.is_some());
+        let installed_at: Option<String> =
+            sqlx::query_scalar("SELECT installed_at FROM models_status WHERE model_name = 'whisper-small'")
+                .fetch_one(&mut conn)
+                .await
+                .unwrap();
+        assert!(installed_at.is_none());
+    }
+}
@@ -0,0 +1,647 @@
+/// Module Catalog — Catalog of the AI models the app knows how to install and run.
+///
+/// Built-in entries are synced into `models_status` (one row per model), which also
+/// stores the active model per role and installation metadata. Checker, downloader,
+/// installer, verifier and sidecar path resolvers all read from this catalog.
+
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use sqlx::{Connection, SqliteConnection};
+use std::path::PathBuf;
+use tauri::Manager;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Which sidecar a model is meant for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelRole {
+    Whisper,
+    Llama,
+}
+
+impl ModelRole {
+    pub const ALL: [ModelRole; 2] = [ModelRole::Whisper, ModelRole::Llama];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModelRole::Whisper => "whisper",
+            ModelRole::Llama => "llama",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "whisper" => Ok(ModelRole::Whisper),
+            "llama" => Ok(ModelRole::Llama),
+            other => Err(format!("Role de modele inconnu : {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    /// Stable identifier (models_status.model_name)
+    pub name: String,
+    /// Human readable name shown in the UI
+    pub label: String,
+    pub role: ModelRole,
+    pub filename: String,
+    pub url: String,
+    /// Expected SHA256 (None = not known yet, verification skipped)
+    pub sha256: Option<String>,
+    pub size_mb: u64,
+    pub recommended_ram_gb: f64,
+    /// Recommended llama ctx-size (None for whisper models)
+    pub ctx_size: Option<usize>,
+    pub active: bool,
+    /// Result of the last integrity audit ('ok' | 'corrupted' | 'missing' | 'unverified')
+    pub audit_status: Option<String>,
+    pub audited_at: Option<String>,
+}
+
+struct BuiltinModel {
+    name: &'static str,
+    label: &'static str,
+    role: ModelRole,
+    filename: &'static str,
+    url: &'static str,
+    sha256: Option<&'static str>,
+    size_mb: u64,
+    recommended_ram_gb: f64,
+    ctx_size: Option<usize>,
+    default_active: bool,
+}
+
+/// Built-in catalog. Hashes are only listed when verified against the published file;
+/// note: if Hugging Face updates the files, these hashes must be updated.
+const BUILTIN_MODELS: &[BuiltinModel] = &[
+    BuiltinModel {
+        name: "whisper-small",
+        label: "Whisper Small FR",
+        role: ModelRole::Whisper,
+        filename: "ggml-small.bin",
+        url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.bin",
+        sha256: Some("1be3a9b2063867b937e64e2ec7483364a79917e157fa98c5d94b5c1fffea987b"),
+        size_mb: 465,
+        recommended_ram_gb: 4.0,
+        ctx_size: None,
+        default_active: true,
+    },
+    BuiltinModel {
+        name: "whisper-base",
+        label: "Whisper Base FR",
+        role: ModelRole::Whisper,
+        filename: "ggml-base.bin",
+        url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin",
+        sha256: None,
+        size_mb: 142,
+        recommended_ram_gb: 2.0,
+        ctx_size: None,
+        default_active: false,
+    },
+    BuiltinModel {
+        name: "whisper-medium",
+        label: "Whisper Medium FR",
+        role: ModelRole::Whisper,
+        filename: "ggml-medium.bin",
+        url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-medium.bin",
+        sha256: None,
+        size_mb: 1463,
+        recommended_ram_gb: 8.0,
+        ctx_size: None,
+        default_active: false,
+    },
+    BuiltinModel {
+        name: "qwen2.5-coder-1.5b",
+        label: "Qwen 2.5 Coder 1.5B",
+        role: ModelRole::Llama,
+        filename: "qwen2.5-coder-1.5b-instruct-q4_k_m.gguf",
+        url: "https://huggingface.co/Qwen/Qwen2.5-Coder-1.5B-Instruct-GGUF/resolve/main/qwen2.5-coder-1.5b-instruct-q4_k_m.gguf",
+        sha256: Some("cc324af070c2ecbfd324a30884d2f951a7ff756aba85cb811a6ec436933bb046"),
+        size_mb: 980,
+        recommended_ram_gb: 4.0,
+        ctx_size: Some(3072),
+        default_active: true,
+    },
+    BuiltinModel {
+        name: "qwen2.5-1.5b",
+        label: "Qwen 2.5 1.5B Instruct",
+        role: ModelRole::Llama,
+        filename: "qwen2.5-1.5b-instruct-q4_k_m.gguf",
+        url: "https://huggingface.co/Qwen/Qwen2.5-1.5B-Instruct-GGUF/resolve/main/qwen2.5-1.5b-instruct-q4_k_m.gguf",
+        sha256: None,
+        size_mb: 1066,
+        recommended_ram_gb: 4.0,
+        ctx_size: Some(3072),
+        default_active: false,
+    },
+    BuiltinModel {
+        name: "qwen2.5-3b",
+        label: "Qwen 2.5 3B Instruct",
+        role: ModelRole::Llama,
+        filename: "qwen2.5-3b-instruct-q4_k_m.gguf",
+        url: "https://huggingface.co/Qwen/Qwen2.5-3B-Instruct-GGUF/resolve/main/qwen2.5-3b-instruct-q4_k_m.gguf",
+        sha256: None,
+        size_mb: 1930,
+        recommended_ram_gb: 8.0,
+        ctx_size: Some(4096),
+        default_active: false,
+    },
+];
+
+impl BuiltinModel {
+    fn to_entry(&self) -> CatalogEntry {
+        CatalogEntry {
+            name: self.name.to_string(),
+            label: self.label.to_string(),
+            role: self.role,
+            filename: self.filename.to_string(),
+            url: self.url.to_string(),
+            sha256: self.sha256.map(str::to_string),
+            size_mb: self.size_mb,
+            recommended_ram_gb: self.recommended_ram_gb,
+            ctx_size: self.ctx_size,
+            active: self.default_active,
+            audit_status: None,
+            audited_at: None,
+        }
+    }
+}
+
+/// Built-in catalog with default active models (used when the DB is unavailable).
+pub fn builtin_catalog() -> Vec<CatalogEntry> {
+    BUILTIN_MODELS.iter().map(BuiltinModel::to_entry).collect()
+}
+
+/// Default model for a role (the built-in entry flagged as default).
+pub fn default_model(role: ModelRole) -> CatalogEntry {
+    BUILTIN_MODELS
+        .iter()
+        .find(|m| m.role == role && m.default_active)
+        .map(BuiltinModel::to_entry)
+        .expect("Chaque role doit avoir un modele par defaut")
+}
+
+/// Active model of each role, in `ModelRole::ALL` order.
+pub fn active_models(catalog: &[CatalogEntry]) -> Vec<CatalogEntry> {
+    ModelRole::ALL
+        .iter()
+        .map(|role| {
+            catalog
+                .iter()
+                .find(|e| e.role == *role && e.active)
+                .cloned()
+                .unwrap_or_else(|| default_model(*role))
+        })
+        .collect()
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Row mapping
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[derive(sqlx::FromRow)]
+struct CatalogRow {
+    model_name: String,
+    label: Option<String>,
+    role: String,
+    filename: String,
+    url: Option<String>,
+    sha256: Option<String>,
+    size_mb: Option<i64>,
+    recommended_ram_gb: Option<f64>,
+    ctx_size: Option<i64>,
+    active: i64,
+    audit_status: Option<String>,
+    audited_at: Option<String>,
+}
+
+impl CatalogRow {
+    fn into_entry(self) -> Result<CatalogEntry, String> {
+        Ok(CatalogEntry {
+            label: self.label.unwrap_or_else(|| self.model_name.clone()),
+            name: self.model_name,
+            role: ModelRole::parse(&self.role)?,
+            filename: self.filename,
+            url: self.url.unwrap_or_default(),
+            sha256: self.sha256.filter(|h| !h.is_empty()),
+            size_mb: self.size_mb.unwrap_or(0).max(0) as u64,
+            recommended_ram_gb: self.recommended_ram_gb.unwrap_or(0.0),
+            ctx_size: self.ctx_size.map(|c| c.max(0) as usize),
+            active: self.active != 0,
+            audit_status: self.audit_status,
+            audited_at: self.audited_at,
+        })
+    }
+}
+
+const SELECT_CATALOG: &str = "SELECT model_name, label, role, filename, url, sha256, size_mb,
+        recommended_ram_gb, ctx_size, active, audit_status, audited_at
+     FROM models_status";
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Impls (testables, prennent une connexion)
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Insert or refresh the built-in entries in `models_status`, keeping the user's
+/// active selection and installation metadata. A role without any active model
+/// gets its built-in default.
+pub async fn sync_builtin_catalog_impl(conn: &mut SqliteConnection) -> Result<(), AppError> {
+    for model in BUILTIN_MODELS {
+        sqlx::query(
+            "INSERT INTO models_status
+                (model_name, label, role, filename, url, sha256, size_mb,
+                 recommended_ram_gb, ctx_size, file_path, installed_at, active)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, NULL, 0)
+             ON CONFLICT(model_name) DO UPDATE SET
+                label = excluded.label,
+                role = excluded.role,
+                filename = excluded.filename,
+                url = excluded.url,
+                sha256 = COALESCE(excluded.sha256, models_status.sha256),
+                size_mb = excluded.size_mb,
+                recommended_ram_gb = excluded.recommended_ram_gb,
+                ctx_size = excluded.ctx_size",
+        )
+        .bind(model.name)
+        .bind(model.label)
+        .bind(model.role.as_str())
+        .bind(model.filename)
+        .bind(model.url)
+        .bind(model.sha256)
+        .bind(model.size_mb as i64)
+        .bind(model.recommended_ram_gb)
+        .bind(model.ctx_size.map(|c| c as i64))
+        .bind(model.filename)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            AppError::db(format!("Erreur synchronisation catalogue ({})", model.name), e)
+        })?;
+    }
+
+    for role in ModelRole::ALL {
+        let default = default_model(role);
+        sqlx::query(
+            "UPDATE models_status SET active = 1
+             WHERE model_name = ?
+               AND NOT EXISTS (SELECT 1 FROM models_status WHERE role = ? AND active = 1)",
+        )
+        .bind(&default.name)
+        .bind(role.as_str())
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::db("Erreur activation modele par defaut", e))?;
+    }
+
+    Ok(())
+}
+
+/// All catalog entries stored in `models_status`, grouped by role then size.
+pub async fn load_catalog_impl(conn: &mut SqliteConnection) -> Result<Vec<CatalogEntry>, AppError> {
+    let rows: Vec<CatalogRow> = sqlx::query_as(&format!(
+        "{} WHERE role IS NOT NULL AND filename IS NOT NULL ORDER BY role, size_mb",
+        SELECT_CATALOG
+    ))
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur chargement catalogue modeles", e))?;
+
+    rows.into_iter().map(|row| row.into_entry().map_err(AppError::Internal)).collect()
+}
+
+/// Active model for a role, or the built-in default if none is selected.
+pub async fn active_model_impl(
+    conn: &mut SqliteConnection,
+    role: ModelRole,
+) -> Result<CatalogEntry, AppError> {
+    let row: Option<CatalogRow> = sqlx::query_as(&format!(
+        "{} WHERE role = ? AND active = 1 LIMIT 1",
+        SELECT_CATALOG
+    ))
+    .bind(role.as_str())
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur lecture modele actif", e))?;
+
+    match row {
+        Some(row) => row.into_entry().map_err(AppError::Internal),
+        None => Ok(default_model(role)),
+    }
+}
+
+/// Select the active model for a role (exactly one active model per role).
+pub async fn set_active_model_impl(
+    conn: &mut SqliteConnection,
+    role: ModelRole,
+    model_name: &str,
+) -> Result<CatalogEntry, AppError> {
+    let entry_role: Option<String> =
+        sqlx::query_scalar("SELECT role FROM models_status WHERE model_name = ?")
+            .bind(model_name)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| AppError::db("Erreur lecture catalogue", e))?
+            .flatten();
+
+    match entry_role.as_deref() {
+        None => {
+            return Err(AppError::not_found(format!(
+                "Modele inconnu dans le catalogue : {}",
+                model_name
+            )))
+        }
+        Some(r) if r != role.as_str() => {
+            return Err(AppError::validation(format!(
+                "Le modele {} n'est pas un modele {}",
+                model_name,
+                role.as_str()
+            )))
+        }
+        Some(_) => {}
+    }
+
+    let mut tx = conn
+        .begin()
+        .await
+        .map_err(|e| AppError::db("Erreur transaction", e))?;
+    sqlx::query("UPDATE models_status SET active = 0 WHERE role = ?")
+        .bind(role.as_str())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::db("Erreur desactivation modeles", e))?;
+    sqlx::query("UPDATE models_status SET active = 1 WHERE model_name = ?")
+        .bind(model_name)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::db("Erreur activation modele", e))?;
+    tx.commit()
+        .await
+        .map_err(|e| AppError::db("Erreur commit", e))?;
+
+    active_model_impl(conn, role).await
+}
+
+/// Record a successful install (path, size, hash, date). When the catalog had no
+/// expected hash, the installed file's hash becomes the reference for later checks.
+pub async fn record_installation_impl(
+    conn: &mut SqliteConnection,
+    model_name: &str,
+    file_path: &str,
+    file_size: u64,
+    sha256: &str,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE models_status
+         SET file_path = ?, file_size = ?, sha256 = COALESCE(sha256, ?),
+             installed_at = CURRENT_TIMESTAMP
+         WHERE model_name = ?",
+    )
+    .bind(file_path)
+    .bind(file_size as i64)
+    .bind(sha256)
+    .bind(model_name)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur enregistrement installation", e))?;
+    Ok(())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Helpers AppHandle + Commandes Tauri
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Directory holding the model files: app_data_dir/models
+pub fn models_dir(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("models"))
+        .map_err(|e| AppError::io("Impossible de trouver app_data_dir", e))
+}
+
+/// Connection to the app DB with the built-in catalog synced.
+pub async fn open_catalog_db(
+    app: &tauri::AppHandle,
+) -> Result<sqlx::pool::PoolConnection<sqlx::Sqlite>, AppError> {
+    let mut conn = crate::db::acquire(app).await?;
+    sync_builtin_catalog_impl(&mut conn).await?;
+    Ok(conn)
+}
+
+/// Full catalog from the DB. Falls back to the built-in catalog when the DB is not
+/// ready yet (first launch before migrations), so model setup never blocks.
+pub async fn load_catalog(app: &tauri::AppHandle) -> Vec<CatalogEntry> {
+    let result = match open_catalog_db(app).await {
+        Ok(mut conn) => load_catalog_impl(&mut conn).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(catalog) if !catalog.is_empty() => catalog,
+        Ok(_) => builtin_catalog(),
+        Err(e) => {
+            log::warn!("Catalogue modeles indisponible ({}), catalogue integre utilise", e);
+            builtin_catalog()
+        }
+    }
+}
+
+/// Active model of each role (whisper, llama).
+pub async fn load_active_models(app: &tauri::AppHandle) -> Vec<CatalogEntry> {
+    active_models(&load_catalog(app).await)
+}
+
+/// Active model for a role and its expected path in app_data_dir/models.
+pub async fn resolve_active_model(
+    app: &tauri::AppHandle,
+    role: ModelRole,
+) -> Result<(CatalogEntry, PathBuf), AppError> {
+    let entry = load_active_models(app)
+        .await
+        .into_iter()
+        .find(|e| e.role == role)
+        .unwrap_or_else(|| default_model(role));
+    let path = models_dir(app)?.join(&entry.filename);
+    Ok((entry, path))
+}
+
+/// Best-effort: installation metadata is informative, a DB error must not fail the install.
+pub async fn record_installation(app: &tauri::AppHandle, entry: &CatalogEntry, path: &std::path::Path, sha256: &str) {
+    let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let result = match open_catalog_db(app).await {
+        Ok(mut conn) => {
+            record_installation_impl(&mut conn, &entry.name, &path.to_string_lossy(), size, sha256)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        log::warn!("Installation de {} non enregistree dans models_status : {}", entry.name, e);
+    }
+}
+
+/// Returns the model catalog with the active model per role.
+#[tauri::command]
+pub async fn list_model_catalog(app: tauri::AppHandle) -> Result<Vec<CatalogEntry>, AppError> {
+    Ok(load_catalog(&app).await)
+}
+
+/// Select the active model for a role. The sidecar picks it up on its next start.
+#[tauri::command]
+pub async fn set_active_model(
+    app: tauri::AppHandle,
+    role: ModelRole,
+    model_name: String,
+) -> Result<CatalogEntry, AppError> {
+    let mut conn = open_catalog_db(&app).await?;
+    set_active_model_impl(&mut conn, role, &model_name).await
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) async fn setup_db() -> (tempfile::NamedTempFile, SqliteConnection) {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let mut conn = SqliteConnection::connect(&format!("sqlite:{}", tmp.path().display()))
+            .await
+            .unwrap();
+        // Schéma V2 (migration frontend 8) puis colonnes catalogue et audit (M015, M016)
+        sqlx::query(
+            "CREATE TABLE models_status (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                model_name TEXT NOT NULL UNIQUE,
+                file_path TEXT NOT NULL,
+                file_size INTEGER,
+                sha256 TEXT,
+                installed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                version TEXT
+            )",
+        )
+        .execute(&mut conn)
+        .await
+        .unwrap();
+        for migration in crate::migrations::v2_2::migrations()
+            .into_iter()
+            .filter(|m| m.name.starts_with("m015_") || m.name.starts_with("m016_"))
+        {
+            for stmt in migration.statements {
+                sqlx::query(stmt).execute(&mut conn).await.unwrap();
+            }
+        }
+        (tmp, conn)
+    }
+
+    #[test]
+    fn builtin_catalog_has_one_default_per_role() {
+        let catalog = builtin_catalog();
+        for role in ModelRole::ALL {
+            let defaults = catalog.iter().filter(|e| e.role == role && e.active).count();
+            assert_eq!(defaults, 1, "Un seul modele par defaut pour {}", role.as_str());
+        }
+        let mut names = std::collections::HashSet::new();
+        for entry in &catalog {
+            assert!(names.insert(entry.name.clone()), "Nom dupliqué: {}", entry.name);
+        }
+    }
+
+    #[test]
+    fn default_models_match_historical_files() {
+        let whisper = default_model(ModelRole::Whisper);
+        assert_eq!(whisper.filename, "ggml-small.bin");
+        assert_eq!(
+            whisper.sha256.as_deref(),
+            Some("1be3a9b2063867b937e64e2ec7483364a79917e157fa98c5d94b5c1fffea987b")
+        );
+        let llama = default_model(ModelRole::Llama);
+        assert_eq!(llama.filename, "qwen2.5-coder-1.5b-instruct-q4_k_m.gguf");
+        assert_eq!(llama.ctx_size, Some(3072));
+    }
+
+    #[tokio::test]
+    async fn sync_seeds_catalog_and_defaults() {
+        let (_tmp, mut conn) = setup_db().await;
+        sync_builtin_catalog_impl(&mut conn).await.unwrap();
+        // Idempotent
+        sync_builtin_catalog_impl(&mut conn).await.unwrap();
+
+        let catalog = load_catalog_impl(&mut conn).await.unwrap();
+        assert_eq!(catalog.len(), BUILTIN_MODELS.len());
+
+        let active = active_models(&catalog);
+        assert_eq!(active[0].name, "whisper-small");
+        assert_eq!(active[1].name, "qwen2.5-coder-1.5b");
+    }
+
+    #[tokio::test]
+    async fn set_active_model_switches_within_role() {
+        let (_tmp, mut conn) = setup_db().await;
+        sync_builtin_catalog_impl(&mut conn).await.unwrap();
+
+        let selected = set_active_model_impl(&mut conn, ModelRole::Whisper, "whisper-base")
+            .await
+            .unwrap();
+        assert_eq!(selected.filename, "ggml-base.bin");
+
+        let catalog = load_catalog_impl(&mut conn).await.unwrap();
+        let active_whisper: Vec<_> = catalog
+            .iter()
+            .filter(|e| e.role == ModelRole::Whisper && e.active)
+            .collect();
+        assert_eq!(active_whisper.len(), 1);
+        assert_eq!(active_whisper[0].name, "whisper-base");
+        // Llama selection untouched
+        let llama = active_model_impl(&mut conn, ModelRole::Llama).await.unwrap();
+        assert_eq!(llama.name, "qwen2.5-coder-1.5b");
+
+        // Selection survives a catalog refresh
+        sync_builtin_catalog_impl(&mut conn).await.unwrap();
+        let whisper = active_model_impl(&mut conn, ModelRole::Whisper).await.unwrap();
+        assert_eq!(whisper.name, "whisper-base");
+    }
+
+    #[tokio::test]
+    async fn set_active_model_rejects_unknown_or_wrong_role() {
+        let (_tmp, mut conn) = setup_db().await;
+        sync_builtin_catalog_impl(&mut conn).await.unwrap();
+
+        let unknown = set_active_model_impl(&mut conn, ModelRole::Llama, "gpt-17").await;
+        assert!(unknown.is_err());
+        let wrong_role = set_active_model_impl(&mut conn, ModelRole::Llama, "whisper-base").await;
+        assert!(wrong_role.is_err());
+
+        let llama = active_model_impl(&mut conn, ModelRole::Llama).await.unwrap();
+        assert_eq!(llama.name, "qwen2.5-coder-1.5b");
+    }
+
+    #[tokio::test]
+    async fn record_installation_keeps_hash_reference() {
+        let (_tmp, mut conn) = setup_db().await;
+        sync_builtin_catalog_impl(&mut conn).await.unwrap();
+
+        // No expected hash in the catalog → installed hash becomes the reference
+        record_installation_impl(&mut conn, "whisper-base", "/models/ggml-base.bin", 42, "abc123")
+            .await
+            .unwrap();
+        // Known hash is never overwritten
+        record_installation_impl(&mut conn, "whisper-small", "/models/ggml-small.bin", 42, "deadbeef")
+            .await
+            .unwrap();
+        sync_builtin_catalog_impl(&mut conn).await.unwrap();
+
+        let catalog = load_catalog_impl(&mut conn).await.unwrap();
+        let base = catalog.iter().find(|e| e.name == "whisper-base").unwrap();
+        assert_eq!(base.sha256.as_deref(), Some("abc123"));
+        let small = catalog.iter().find(|e| e.name == "whisper-small").unwrap();
+        assert_eq!(
+            small.sha256.as_deref(),
+            Some("1be3a9b2063867b937e64e2ec7483364a79917e157fa98c5d94b5c1fffea987b")
+        );
+    }
+}
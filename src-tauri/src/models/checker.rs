@@ -1,16 +1,18 @@
+use super::catalog::{self, CatalogEntry, ModelRole};
+use crate::error::AppError;
 use serde::Serialize;
-use tauri::Manager;
-
-const WHISPER_MODEL: &str = "ggml-small.bin";
-const QWEN_MODEL: &str = "qwen2.5-coder-1.5b-instruct-q4_k_m.gguf";
+use std::path::Path;
 
 #[derive(Clone, Serialize)]
 pub struct ModelInfo {
     pub name: String,
+    /// Catalog identifier of the active model for this role
+    pub model_name: String,
     pub filename: String,
     pub installed: bool,
     pub path: Option<String>,
     pub expected_size_mb: u64,
+    pub recommended_ram_gb: f64,
 }
 
 #[derive(Clone, Serialize)]
@@ -19,44 +21,56 @@ pub struct ModelsCheckResult {
     pub llama: ModelInfo,
     pub all_installed: bool,
     pub models_dir: String,
+    /// Full catalog (all roles), with the active model flagged
+    pub catalog: Vec<CatalogEntry>,
 }
 
-#[tauri::command]
-pub async fn check_models_status(app: tauri::AppHandle) -> Result<ModelsCheckResult, String> {
-    let models_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Impossible de trouver app_data_dir: {}", e))?
-        .join("models");
+fn model_info(models_dir: &Path, entry: &CatalogEntry) -> ModelInfo {
+    let path = models_dir.join(&entry.filename);
+    let installed = path.exists();
+    log::info!("{} path: {:?} exists={}", entry.role.as_str(), path, installed);
 
-    let whisper_path = models_dir.join(WHISPER_MODEL);
-    let llama_path = models_dir.join(QWEN_MODEL);
+    ModelInfo {
+        name: entry.label.clone(),
+        model_name: entry.name.clone(),
+        filename: entry.filename.clone(),
+        installed,
+        path: installed.then(|| path.to_string_lossy().into()),
+        expected_size_mb: entry.size_mb,
+        recommended_ram_gb: entry.recommended_ram_gb,
+    }
+}
 
-    log::info!("Models dir: {:?}", models_dir);
-    log::info!("Whisper path: {:?} exists={}", whisper_path, whisper_path.exists());
-    log::info!("Llama path: {:?} exists={}", llama_path, llama_path.exists());
+/// Build the status of the active models found in `models_dir`.
+pub fn build_check_result(models_dir: &Path, catalog: Vec<CatalogEntry>) -> ModelsCheckResult {
+    let active = catalog::active_models(&catalog);
+    let find = |role: ModelRole| {
+        active
+            .iter()
+            .find(|e| e.role == role)
+            .cloned()
+            .unwrap_or_else(|| catalog::default_model(role))
+    };
 
-    let whisper_installed = whisper_path.exists();
-    let llama_installed = llama_path.exists();
+    let whisper = model_info(models_dir, &find(ModelRole::Whisper));
+    let llama = model_info(models_dir, &find(ModelRole::Llama));
 
-    Ok(ModelsCheckResult {
-        whisper: ModelInfo {
-            name: "Whisper Small FR".to_string(),
-            filename: WHISPER_MODEL.to_string(),
-            installed: whisper_installed,
-            path: whisper_installed.then(|| whisper_path.to_string_lossy().into()),
-            expected_size_mb: 465,
-        },
-        llama: ModelInfo {
-            name: "Qwen 2.5 Coder 1.5B".to_string(),
-            filename: QWEN_MODEL.to_string(),
-            installed: llama_installed,
-            path: llama_installed.then(|| llama_path.to_string_lossy().into()),
-            expected_size_mb: 980,
-        },
-        all_installed: whisper_installed && llama_installed,
+    ModelsCheckResult {
+        all_installed: whisper.installed && llama.installed,
+        whisper,
+        llama,
         models_dir: models_dir.to_string_lossy().into(),
-    })
+        catalog,
+    }
+}
+
+#[tauri::command]
+pub async fn check_models_status(app: tauri::AppHandle) -> Result<ModelsCheckResult, AppError> {
+    let models_dir = catalog::models_dir(&app)?;
+    log::info!("Models dir: {:?}", models_dir);
+
+    let catalog = catalog::load_catalog(&app).await;
+    Ok(build_check_result(&models_dir, catalog))
 }
 
 #[cfg(test)]
@@ -64,8 +78,34 @@ mod tests {
     use super::*;
 
     #[test]
-    fn model_constants_are_correct() {
-        assert_eq!(WHISPER_MODEL, "ggml-small.bin");
-        assert_eq!(QWEN_MODEL, "qwen2.5-coder-1.5b-instruct-q4_k_m.gguf");
+    fn default_catalog_reports_historical_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = build_check_result(dir.path(), catalog::builtin_catalog());
+        assert_eq!(result.whisper.filename, "ggml-small.bin");
+        assert_eq!(result.llama.filename, "qwen2.5-coder-1.5b-instruct-q4_k_m.gguf");
+        assert!(!result.all_installed);
+    }
+
+    #[test]
+    fn check_follows_active_model() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("ggml-base.bin"), b"x").unwrap();
+        std::fs::write(dir.path().join("qwen2.5-coder-1.5b-instruct-q4_k_m.gguf"), b"x").unwrap();
+
+        let catalog: Vec<CatalogEntry> = catalog::builtin_catalog()
+            .into_iter()
+            .map(|mut e| {
+                if e.role == ModelRole::Whisper {
+                    e.active = e.name == "whisper-base";
+                }
+                e
+            })
+            .collect();
+
+        let result = build_check_result(dir.path(), catalog);
+        assert_eq!(result.whisper.model_name, "whisper-base");
+        assert!(result.whisper.installed);
+        assert!(result.llama.installed);
+        assert!(result.all_installed);
     }
 }
@@ -1,23 +1,14 @@
-use super::verifier;
+use super::catalog;
+use crate::error::AppError;
 use futures::StreamExt;
 use log::{info, warn};
-use serde::Serialize;
-use std::io::Write;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use tauri::{Emitter, Manager};
-
-const MODELS: &[(&str, &str, &str)] = &[
-    (
-        "whisper",
-        "ggml-small.bin",
-        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.bin",
-    ),
-    (
-        "llama",
-        "qwen2.5-coder-1.5b-instruct-q4_k_m.gguf",
-        "https://huggingface.co/Qwen/Qwen2.5-Coder-1.5B-Instruct-GGUF/resolve/main/qwen2.5-coder-1.5b-instruct-q4_k_m.gguf",
-    ),
-];
+use std::time::Duration;
+use tauri::Emitter;
 
 /// Emit frequency: one progress event per PROGRESS_INTERVAL bytes downloaded.
 const PROGRESS_INTERVAL: u64 = 512 * 1024; // 512 KB
@@ -30,180 +21,832 @@ pub struct DownloadProgress {
     pub percentage: f64,
     pub current_model: usize,  // 1-based index
     pub total_models: usize,
-    pub status: String, // "downloading" | "verifying" | "complete" | "error"
+    pub status: String, // "downloading" | "retrying" | "verifying" | "complete" | "error"
 }
 
 /// Global cancel flag shared between download_models and cancel_download.
 static CANCEL_FLAG: AtomicBool = AtomicBool::new(false);
 
+/// Retry policy for transient errors (network drop, 5xx, 429).
+/// Attempts are counted per stall: any progress resets the counter.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Exponential backoff: initial, 2x, 4x... capped at max_backoff (attempt is 1-based)
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Download state persisted next to the partial file so a resume survives an app restart.
+/// The number of bytes already downloaded is the partial file's length.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DownloadState {
+    pub url: String,
+    pub total_bytes: Option<u64>,
+    /// Validators sent back in If-Range: a changed remote file restarts from zero
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadOutcome {
+    pub bytes: u64,
+    pub sha256: String,
+    /// Bytes already present when the download started (0 = fresh download)
+    pub resumed_from: u64,
+}
+
+enum AttemptError {
+    Transient(String),
+    Fatal(String),
+    Cancelled,
+}
+
+const CANCELLED_MSG: &str = "Telechargement annule par l'utilisateur";
+
+/// Partial file written while downloading (renamed to `dest` once verified)
+pub fn partial_path(dest: &Path) -> PathBuf {
+    dest.with_extension("tmp")
+}
+
+/// Persisted download state for `dest`
+pub fn state_path(dest: &Path) -> PathBuf {
+    dest.with_extension("download.json")
+}
+
+fn load_state(path: &Path) -> Option<DownloadState> {
+    let text = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+fn save_state(path: &Path, state: &DownloadState) -> Result<(), String> {
+    let json = serde_json::to_string(state).map_err(|e| e.to_string())?;
+    std::fs::write(path, json)
+        .map_err(|e| format!("Impossible d'ecrire {}: {}", path.display(), e))
+}
+
+/// Feed the bytes already on disk into the hasher; returns their count.
+fn hash_existing(path: &Path, hasher: &mut Sha256) -> Result<u64, String> {
+    let mut file = match std::fs::File::open(path) {
+        Ok(f) => f,
+        Err(_) => return Ok(0),
+    };
+    let mut buffer = [0u8; 8192];
+    let mut total = 0u64;
+    loop {
+        let n = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Erreur de lecture {}: {}", path.display(), e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        total += n as u64;
+    }
+    Ok(total)
+}
+
+/// Parse the total size from `Content-Range: bytes start-end/total`.
+/// Returns (start, total); total is None when the server sends `*`.
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let range = value.trim().strip_prefix("bytes ")?;
+    let (span, total) = range.split_once('/')?;
+    let (start, _) = span.split_once('-')?;
+    Some((start.trim().parse().ok()?, total.trim().parse().ok()))
+}
+
+fn header_string(resp: &reqwest::Response, name: reqwest::header::HeaderName) -> Option<String> {
+    resp.headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+/// One HTTP request: resumes at `*offset` and appends to the partial file.
+/// The hasher and `*offset` always reflect what is on disk.
+#[allow(clippy::too_many_arguments)]
+async fn download_attempt<F: FnMut(u64, u64, &str)>(
+    client: &reqwest::Client,
+    url: &str,
+    tmp_path: &Path,
+    state_file: &Path,
+    state: &mut DownloadState,
+    hasher: &mut Sha256,
+    offset: &mut u64,
+    cancel: &AtomicBool,
+    on_progress: &mut F,
+) -> Result<(), AttemptError> {
+    use reqwest::header::{CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+    use reqwest::StatusCode;
+
+    let mut request = client.get(url);
+    if *offset > 0 {
+        request = request.header(RANGE, format!("bytes={}-", offset));
+        if let Some(validator) = state.etag.as_ref().or(state.last_modified.as_ref()) {
+            request = request.header(IF_RANGE, validator.as_str());
+        }
+    }
+
+    let resp = request
+        .send()
+        .await
+        .map_err(|e| AttemptError::Transient(format!("Erreur reseau : {}", e)))?;
+    let status = resp.status();
+
+    if status == StatusCode::RANGE_NOT_SATISFIABLE {
+        // Partial file already complete, or larger than the remote file
+        if state.total_bytes == Some(*offset) {
+            return Ok(());
+        }
+        *hasher = Sha256::new();
+        *offset = 0;
+        std::fs::remove_file(tmp_path).ok();
+        return Err(AttemptError::Transient(
+            "Plage demandee invalide, reprise depuis le debut".to_string(),
+        ));
+    }
+    if status.is_server_error()
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+    {
+        return Err(AttemptError::Transient(format!("Serveur a repondu {}", status)));
+    }
+    if !status.is_success() {
+        return Err(AttemptError::Fatal(format!(
+            "Serveur a repondu {}. Utilisez l'option cle USB.",
+            status
+        )));
+    }
+
+    let append = status == StatusCode::PARTIAL_CONTENT && *offset > 0;
+    let total = if append {
+        match header_string(&resp, CONTENT_RANGE).as_deref().and_then(parse_content_range) {
+            Some((start, total)) if start == *offset => total,
+            _ => {
+                return Err(AttemptError::Fatal(
+                    "Reponse partielle incoherente (Content-Range)".to_string(),
+                ))
+            }
+        }
+    } else {
+        if *offset > 0 {
+            // Range ignored or remote file changed (If-Range): start over
+            info!("Le serveur ne reprend pas a l'octet {}, telechargement depuis le debut", offset);
+            *hasher = Sha256::new();
+            *offset = 0;
+        }
+        resp.content_length()
+    };
+
+    state.total_bytes = total.or(state.total_bytes);
+    state.etag = header_string(&resp, ETAG);
+    state.last_modified = header_string(&resp, LAST_MODIFIED);
+    save_state(state_file, state).map_err(AttemptError::Fatal)?;
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(tmp_path)
+        .map_err(|e| {
+            AttemptError::Fatal(format!("Impossible d'ouvrir {}: {}", tmp_path.display(), e))
+        })?;
+
+    let total_bytes = state.total_bytes.unwrap_or(0);
+    let mut last_emitted = *offset;
+    let mut stream = resp.bytes_stream();
+
+    while let Some(chunk_result) = stream.next().await {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(AttemptError::Cancelled);
+        }
+
+        let chunk = chunk_result
+            .map_err(|e| AttemptError::Transient(format!("Erreur reseau pendant le telechargement: {}", e)))?;
+
+        file.write_all(&chunk)
+            .map_err(|e| AttemptError::Fatal(format!("Erreur ecriture disque: {}", e)))?;
+        hasher.update(&chunk);
+        *offset += chunk.len() as u64;
+
+        if *offset - last_emitted >= PROGRESS_INTERVAL || *offset == total_bytes {
+            last_emitted = *offset;
+            on_progress(*offset, total_bytes, "downloading");
+        }
+    }
+
+    file.flush()
+        .map_err(|e| AttemptError::Fatal(format!("Erreur ecriture disque: {}", e)))?;
+
+    if let Some(total) = state.total_bytes {
+        if *offset < total {
+            return Err(AttemptError::Transient(format!(
+                "Connexion interrompue ({}/{} octets)",
+                offset, total
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Download `url` to `dest`, resuming from a previous partial file when the persisted
+/// state matches the same URL. The SHA256 is computed while streaming (bytes already on
+/// disk are re-hashed on resume) and checked against `expected_sha256` before the final
+/// rename. Transient errors are retried with exponential backoff; a cancelled download
+/// keeps its partial file for a later resume.
+pub async fn download_resumable<F: FnMut(u64, u64, &str)>(
+    client: &reqwest::Client,
+    url: &str,
+    dest: &Path,
+    expected_sha256: Option<&str>,
+    policy: &RetryPolicy,
+    cancel: &AtomicBool,
+    mut on_progress: F,
+) -> Result<DownloadOutcome, String> {
+    let tmp_path = partial_path(dest);
+    let state_file = state_path(dest);
+
+    let mut state = match load_state(&state_file) {
+        Some(s) if s.url == url && tmp_path.exists() => s,
+        _ => {
+            std::fs::remove_file(&tmp_path).ok();
+            DownloadState {
+                url: url.to_string(),
+                ..Default::default()
+            }
+        }
+    };
+
+    let mut hasher = Sha256::new();
+    let mut offset = hash_existing(&tmp_path, &mut hasher)?;
+    let resumed_from = offset;
+    if resumed_from > 0 {
+        info!("Reprise du telechargement de {} a l'octet {}", url, resumed_from);
+    }
+
+    let mut attempt = 0u32;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(CANCELLED_MSG.to_string());
+        }
+
+        let before = offset;
+        let result = download_attempt(
+            client,
+            url,
+            &tmp_path,
+            &state_file,
+            &mut state,
+            &mut hasher,
+            &mut offset,
+            cancel,
+            &mut on_progress,
+        )
+        .await;
+
+        match result {
+            Ok(()) => break,
+            Err(AttemptError::Cancelled) => return Err(CANCELLED_MSG.to_string()),
+            Err(AttemptError::Fatal(msg)) => return Err(msg),
+            Err(AttemptError::Transient(msg)) => {
+                if offset > before {
+                    attempt = 0;
+                }
+                attempt += 1;
+                if attempt > policy.max_retries {
+                    return Err(format!(
+                        "Telechargement interrompu apres {} tentatives : {}. Utilisez l'option cle USB.",
+                        policy.max_retries, msg
+                    ));
+                }
+                let delay = policy.backoff(attempt);
+                warn!(
+                    "Telechargement de {} interrompu ({}), nouvelle tentative {}/{} dans {:?}",
+                    url, msg, attempt, policy.max_retries, delay
+                );
+                on_progress(offset, state.total_bytes.unwrap_or(0), "retrying");
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+
+    let sha256 = format!("{:x}", hasher.finalize());
+    if let Some(expected) = expected_sha256.filter(|h| !h.is_empty()) {
+        if !sha256.eq_ignore_ascii_case(expected) {
+            std::fs::remove_file(&tmp_path).ok();
+            std::fs::remove_file(&state_file).ok();
+            return Err(format!(
+                "Fichier {} corrompu (SHA256 invalide). Reessayez ou utilisez l'option cle USB.",
+                dest.file_name().unwrap_or_default().to_string_lossy()
+            ));
+        }
+    }
+
+    // Atomic rename
+    std::fs::rename(&tmp_path, dest).map_err(|e| format!("Rename final: {}", e))?;
+    std::fs::remove_file(&state_file).ok();
+
+    Ok(DownloadOutcome {
+        bytes: offset,
+        sha256,
+        resumed_from,
+    })
+}
+
 #[tauri::command]
-pub async fn download_models(app: tauri::AppHandle) -> Result<(), String> {
+pub async fn download_models(app: tauri::AppHandle) -> Result<(), AppError> {
     CANCEL_FLAG.store(false, Ordering::Relaxed);
 
-    let models_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("app_data_dir: {}", e))?
-        .join("models");
+    let models_dir = catalog::models_dir(&app)?;
 
     std::fs::create_dir_all(&models_dir)
-        .map_err(|e| format!("Impossible de creer le dossier models: {}", e))?;
+        .map_err(|e| AppError::io("Impossible de creer le dossier models", e))?;
 
+    // No global timeout: a 1 GB model on school Wi-Fi can take a while.
+    // A stalled connection is detected by the read timeout and resumed.
     let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(600)) // 10 min per model
+        .connect_timeout(Duration::from_secs(15))
+        .read_timeout(Duration::from_secs(60))
         .build()
-        .map_err(|e| format!("Client HTTP: {}", e))?;
+        .map_err(|e| AppError::network("Client HTTP", e))?;
 
-    let total_models = MODELS.len();
+    // Download the active model of each role (see models::catalog)
+    let models = catalog::load_active_models(&app).await;
+    let total_models = models.len();
+    let policy = RetryPolicy::default();
 
-    for (idx, (name, filename, url)) in MODELS.iter().enumerate() {
-        let dest = models_dir.join(filename);
+    for (idx, entry) in models.iter().enumerate() {
+        let name = entry.role.as_str();
+        let dest = models_dir.join(&entry.filename);
 
-        // Skip if already installed
-        if dest.exists() {
-            info!("Modele {} deja installe, skip", name);
+        let emit = |downloaded: u64, total: u64, status: &str| {
             app.emit(
                 "download_progress",
                 DownloadProgress {
                     model_name: name.to_string(),
-                    downloaded_bytes: 0,
-                    total_bytes: 0,
-                    percentage: 100.0,
+                    downloaded_bytes: downloaded,
+                    total_bytes: total,
+                    percentage: if status == "complete" {
+                        100.0
+                    } else if total > 0 {
+                        (downloaded as f64 / total as f64) * 100.0
+                    } else {
+                        0.0
+                    },
                     current_model: idx + 1,
                     total_models,
-                    status: "complete".to_string(),
+                    status: status.to_string(),
                 },
             )
             .ok();
+        };
+
+        // Skip if already installed
+        if dest.exists() {
+            info!("Modele {} deja installe, skip", name);
+            emit(0, 0, "complete");
             continue;
         }
 
         // Check cancel
         if CANCEL_FLAG.load(Ordering::Relaxed) {
-            return Err("Telechargement annule par l'utilisateur".to_string());
+            return Err(AppError::Cancelled);
         }
 
-        info!("Telechargement de {} depuis {}", name, url);
+        info!("Telechargement de {} depuis {}", name, entry.url);
 
-        let resp = client.get(*url).send().await.map_err(|e| {
-            format!(
-                "Impossible de telecharger {} : {}. Utilisez l'option cle USB.",
-                name, e
-            )
+        let outcome = download_resumable(
+            &client,
+            &entry.url,
+            &dest,
+            entry.sha256.as_deref(),
+            &policy,
+            &CANCEL_FLAG,
+            emit,
+        )
+        .await
+        .map_err(|e| match e.as_str() {
+            CANCELLED_MSG => AppError::Cancelled,
+            _ => AppError::network(format!("Telechargement du modele {} echoue", name), e),
         })?;
 
-        if !resp.status().is_success() {
-            return Err(format!(
-                "Serveur a repondu {} pour {}. Utilisez l'option cle USB.",
-                resp.status(),
-                name
-            ));
-        }
+        info!("SHA256 de {} : {}", entry.filename, outcome.sha256);
+        catalog::record_installation(&app, entry, &dest, &outcome.sha256).await;
 
-        let total = resp.content_length().unwrap_or(0);
-        let mut downloaded: u64 = 0;
-        let mut last_emitted: u64 = 0;
+        info!(
+            "Modele {} installe avec succes ({} octets, reprise a {})",
+            name, outcome.bytes, outcome.resumed_from
+        );
 
-        // Write to .tmp first, then rename
-        let tmp_path = dest.with_extension("tmp");
-        let mut file = std::fs::File::create(&tmp_path)
-            .map_err(|e| format!("Impossible de creer {}: {}", tmp_path.display(), e))?;
+        emit(outcome.bytes, outcome.bytes, "complete");
+    }
 
-        let mut stream = resp.bytes_stream();
+    Ok(())
+}
 
-        while let Some(chunk_result) = stream.next().await {
-            if CANCEL_FLAG.load(Ordering::Relaxed) {
-                drop(file);
-                std::fs::remove_file(&tmp_path).ok();
-                return Err("Telechargement annule par l'utilisateur".to_string());
-            }
+#[tauri::command]
+pub fn cancel_download() {
+    CANCEL_FLAG.store(true, Ordering::Relaxed);
+    warn!("Telechargement annulation demandee");
+}
 
-            let chunk = chunk_result
-                .map_err(|e| format!("Erreur reseau pendant le telechargement de {}: {}", name, e))?;
-
-            file.write_all(&chunk)
-                .map_err(|e| format!("Erreur ecriture disque: {}", e))?;
-
-            downloaded += chunk.len() as u64;
-
-            // Emit progress every PROGRESS_INTERVAL
-            if downloaded - last_emitted >= PROGRESS_INTERVAL || downloaded == total {
-                last_emitted = downloaded;
-                app.emit(
-                    "download_progress",
-                    DownloadProgress {
-                        model_name: name.to_string(),
-                        downloaded_bytes: downloaded,
-                        total_bytes: total,
-                        percentage: if total > 0 {
-                            (downloaded as f64 / total as f64) * 100.0
-                        } else {
-                            0.0
-                        },
-                        current_model: idx + 1,
-                        total_models,
-                        status: "downloading".to_string(),
-                    },
-                )
-                .ok();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Behaviour of the local HTTP stand-in
+    #[derive(Clone, Default)]
+    struct ServerScript {
+        /// Number of leading requests answered with 503
+        errors_before: usize,
+        /// Requests (1-based, after the 503s) that drop the connection after N body bytes
+        cut_after: Vec<(usize, usize)>,
+        /// Answer 200 with the full body even when a Range is requested
+        ignore_range: bool,
+    }
+
+    /// Minimal HTTP/1.1 server supporting `Range: bytes=N-`.
+    /// Returns the base URL and the Range header seen for each request.
+    async fn spawn_server(
+        body: Vec<u8>,
+        script: ServerScript,
+    ) -> (String, Arc<Mutex<Vec<Option<String>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let seen: Arc<Mutex<Vec<Option<String>>>> = Arc::new(Mutex::new(Vec::new()));
+        let seen_clone = seen.clone();
+
+        tokio::spawn(async move {
+            let mut count = 0usize;
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else { break };
+                count += 1;
+
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = socket.read(&mut buf).await.unwrap_or(0);
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let text = String::from_utf8_lossy(&request).to_string();
+                let range = text
+                    .lines()
+                    .find(|l| l.to_ascii_lowercase().starts_with("range:"))
+                    .map(|l| l[6..].trim().to_string());
+                seen_clone.lock().unwrap().push(range.clone());
+
+                if count <= script.errors_before {
+                    let _ = socket
+                        .write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                        .await;
+                    continue;
+                }
+
+                let start = range
+                    .as_deref()
+                    .and_then(|r| r.strip_prefix("bytes="))
+                    .and_then(|r| r.trim_end_matches('-').parse::<usize>().ok())
+                    .filter(|_| !script.ignore_range);
+
+                let (head, payload) = match start {
+                    Some(s) if s >= body.len() => (
+                        format!(
+                            "HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */{}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                            body.len()
+                        ),
+                        Vec::new(),
+                    ),
+                    Some(s) => (
+                        format!(
+                            "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n",
+                            body.len() - s,
+                            s,
+                            body.len() - 1,
+                            body.len()
+                        ),
+                        body[s..].to_vec(),
+                    ),
+                    None => (
+                        format!(
+                            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n",
+                            body.len()
+                        ),
+                        body.clone(),
+                    ),
+                };
+
+                let served = count - script.errors_before;
+                let cut = script
+                    .cut_after
+                    .iter()
+                    .find(|(req, _)| *req == served)
+                    .map(|(_, n)| *n);
+                let _ = socket.write_all(head.as_bytes()).await;
+                let end = cut.unwrap_or(payload.len()).min(payload.len());
+                let _ = socket.write_all(&payload[..end]).await;
+                let _ = socket.shutdown().await;
             }
+        });
+
+        (format!("http://{}/model.bin", addr), seen)
+    }
+
+    fn test_body() -> Vec<u8> {
+        (0..200_000u32).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn sha_hex(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(5),
+            max_backoff: Duration::from_millis(20),
         }
+    }
+
+    fn client() -> reqwest::Client {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap()
+    }
 
-        drop(file);
+    #[tokio::test]
+    async fn downloads_and_verifies_in_one_go() {
+        let body = test_body();
+        let (url, seen) = spawn_server(body.clone(), ServerScript::default()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("model.bin");
+        let cancel = AtomicBool::new(false);
 
-        // Verify SHA256
-        app.emit(
-            "download_progress",
-            DownloadProgress {
-                model_name: name.to_string(),
-                downloaded_bytes: downloaded,
-                total_bytes: total,
-                percentage: 100.0,
-                current_model: idx + 1,
-                total_models,
-                status: "verifying".to_string(),
-            },
+        let outcome = download_resumable(
+            &client(), &url, &dest, Some(&sha_hex(&body)), &fast_policy(), &cancel, |_, _, _| {},
         )
-        .ok();
+        .await
+        .unwrap();
+
+        assert_eq!(outcome.bytes, body.len() as u64);
+        assert_eq!(outcome.resumed_from, 0);
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        assert!(!partial_path(&dest).exists());
+        assert!(!state_path(&dest).exists(), "L'etat doit etre supprime apres succes");
+        assert_eq!(seen.lock().unwrap().len(), 1);
+    }
 
-        let hash_ok = verifier::verify_model_hash(&tmp_path, filename)?;
-        if !hash_ok {
-            std::fs::remove_file(&tmp_path).ok();
-            return Err(format!(
-                "Fichier {} corrompu (SHA256 invalide). Reessayez ou utilisez l'option cle USB.",
-                filename
-            ));
-        }
+    #[tokio::test]
+    async fn resumes_with_range_after_connection_drop() {
+        let body = test_body();
+        let script = ServerScript {
+            cut_after: vec![(1, 70_000)],
+            ..Default::default()
+        };
+        let (url, seen) = spawn_server(body.clone(), script).await;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("model.bin");
+        let cancel = AtomicBool::new(false);
+        let mut statuses = Vec::new();
+
+        let outcome = download_resumable(
+            &client(),
+            &url,
+            &dest,
+            Some(&sha_hex(&body)),
+            &fast_policy(),
+            &cancel,
+            |_, _, status| statuses.push(status.to_string()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome.sha256, sha_hex(&body));
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0], None);
+        let resumed_at: usize = seen[1]
+            .as_deref()
+            .unwrap()
+            .trim_start_matches("bytes=")
+            .trim_end_matches('-')
+            .parse()
+            .unwrap();
+        assert!(resumed_at > 0 && resumed_at <= 70_000, "Reprise a {}", resumed_at);
+        assert!(statuses.iter().any(|s| s == "retrying"));
+    }
 
-        // Compute and log actual hash for reference
-        let actual_hash = verifier::compute_sha256(&tmp_path).unwrap_or_default();
-        info!("SHA256 de {} : {}", filename, actual_hash);
+    #[tokio::test]
+    async fn retries_transient_server_errors_with_backoff() {
+        let body = test_body();
+        let script = ServerScript {
+            errors_before: 2,
+            ..Default::default()
+        };
+        let (url, seen) = spawn_server(body.clone(), script).await;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("model.bin");
+        let cancel = AtomicBool::new(false);
+
+        download_resumable(&client(), &url, &dest, None, &fast_policy(), &cancel, |_, _, _| {})
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        assert_eq!(seen.lock().unwrap().len(), 3);
+    }
 
-        // Atomic rename
-        std::fs::rename(&tmp_path, &dest).map_err(|e| format!("Rename final: {}", e))?;
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let script = ServerScript {
+            errors_before: 100,
+            ..Default::default()
+        };
+        let (url, seen) = spawn_server(test_body(), script).await;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("model.bin");
+        let cancel = AtomicBool::new(false);
+
+        let err = download_resumable(&client(), &url, &dest, None, &fast_policy(), &cancel, |_, _, _| {})
+            .await
+            .unwrap_err();
+
+        assert!(err.contains("3 tentatives"), "{}", err);
+        assert_eq!(seen.lock().unwrap().len(), 4); // 1 + 3 retries
+        assert!(!dest.exists());
+    }
 
-        info!("Modele {} installe avec succes ({} octets)", name, downloaded);
+    #[tokio::test]
+    async fn resume_survives_restart_from_persisted_state() {
+        let body = test_body();
+        let (url, seen) = spawn_server(body.clone(), ServerScript::default()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("model.bin");
+
+        // Previous run: 50 000 bytes on disk + persisted state
+        std::fs::write(partial_path(&dest), &body[..50_000]).unwrap();
+        save_state(
+            &state_path(&dest),
+            &DownloadState {
+                url: url.clone(),
+                total_bytes: Some(body.len() as u64),
+                etag: Some("\"v1\"".to_string()),
+                last_modified: None,
+            },
+        )
+        .unwrap();
+
+        let cancel = AtomicBool::new(false);
+        let outcome = download_resumable(
+            &client(), &url, &dest, Some(&sha_hex(&body)), &fast_policy(), &cancel, |_, _, _| {},
+        )
+        .await
+        .unwrap();
 
-        app.emit(
-            "download_progress",
-            DownloadProgress {
-                model_name: name.to_string(),
-                downloaded_bytes: downloaded,
-                total_bytes: total,
-                percentage: 100.0,
-                current_model: idx + 1,
-                total_models,
-                status: "complete".to_string(),
+        assert_eq!(outcome.resumed_from, 50_000);
+        assert_eq!(outcome.sha256, sha_hex(&body));
+        assert_eq!(seen.lock().unwrap()[0].as_deref(), Some("bytes=50000-"));
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+    }
+
+    #[tokio::test]
+    async fn partial_from_another_url_is_discarded() {
+        let body = test_body();
+        let (url, seen) = spawn_server(body.clone(), ServerScript::default()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("model.bin");
+
+        std::fs::write(partial_path(&dest), b"garbage from another model").unwrap();
+        save_state(
+            &state_path(&dest),
+            &DownloadState {
+                url: "http://example.invalid/other.bin".to_string(),
+                ..Default::default()
             },
         )
-        .ok();
+        .unwrap();
+
+        let cancel = AtomicBool::new(false);
+        let outcome = download_resumable(
+            &client(), &url, &dest, Some(&sha_hex(&body)), &fast_policy(), &cancel, |_, _, _| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome.resumed_from, 0);
+        assert_eq!(seen.lock().unwrap()[0], None);
     }
 
-    Ok(())
-}
+    #[tokio::test]
+    async fn restarts_from_zero_when_server_ignores_range() {
+        let body = test_body();
+        let script = ServerScript {
+            ignore_range: true,
+            ..Default::default()
+        };
+        let (url, _) = spawn_server(body.clone(), script).await;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("model.bin");
+
+        std::fs::write(partial_path(&dest), &body[..30_000]).unwrap();
+        save_state(
+            &state_path(&dest),
+            &DownloadState { url: url.clone(), ..Default::default() },
+        )
+        .unwrap();
 
-#[tauri::command]
-pub fn cancel_download() {
-    CANCEL_FLAG.store(true, Ordering::Relaxed);
-    warn!("Telechargement annulation demandee");
+        let cancel = AtomicBool::new(false);
+        let outcome = download_resumable(
+            &client(), &url, &dest, Some(&sha_hex(&body)), &fast_policy(), &cancel, |_, _, _| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome.bytes, body.len() as u64);
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+    }
+
+    #[tokio::test]
+    async fn hash_mismatch_removes_partial_and_state() {
+        let (url, _) = spawn_server(test_body(), ServerScript::default()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("model.bin");
+        let cancel = AtomicBool::new(false);
+
+        let err = download_resumable(
+            &client(), &url, &dest, Some(&"0".repeat(64)), &fast_policy(), &cancel, |_, _, _| {},
+        )
+        .await
+        .unwrap_err();
+
+        assert!(err.contains("SHA256"), "{}", err);
+        assert!(!dest.exists());
+        assert!(!partial_path(&dest).exists());
+        assert!(!state_path(&dest).exists());
+    }
+
+    #[tokio::test]
+    async fn cancel_keeps_partial_for_later_resume() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("model.bin");
+        std::fs::write(partial_path(&dest), b"partial").unwrap();
+        save_state(
+            &state_path(&dest),
+            &DownloadState { url: "http://127.0.0.1:9/model.bin".to_string(), ..Default::default() },
+        )
+        .unwrap();
+
+        let cancel = AtomicBool::new(true);
+        let err = download_resumable(
+            &client(), "http://127.0.0.1:9/model.bin", &dest, None, &fast_policy(), &cancel, |_, _, _| {},
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(err, CANCELLED_MSG);
+        assert!(partial_path(&dest).exists());
+        assert!(state_path(&dest).exists());
+    }
+
+    #[test]
+    fn backoff_is_exponential_and_capped() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(10), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn content_range_parsing() {
+        assert_eq!(parse_content_range("bytes 100-199/200"), Some((100, Some(200))));
+        assert_eq!(parse_content_range("bytes 0-9/*"), Some((0, None)));
+        assert_eq!(parse_content_range("items 0-9/10"), None);
+    }
 }
@@ -1,13 +1,10 @@
+use super::catalog;
 use super::verifier;
+use crate::error::AppError;
 use log::info;
 use serde::Serialize;
 use std::path::PathBuf;
-use tauri::{Emitter, Manager};
-
-const EXPECTED_FILES: &[(&str, &str)] = &[
-    ("whisper", "ggml-small.bin"),
-    ("llama", "qwen2.5-coder-1.5b-instruct-q4_k_m.gguf"),
-];
+use tauri::Emitter;
 
 #[derive(Clone, Serialize)]
 struct InstallProgress {
@@ -21,24 +18,24 @@ struct InstallProgress {
 pub async fn install_models_from_folder(
     app: tauri::AppHandle,
     folder_path: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let source = PathBuf::from(&folder_path);
     if !source.is_dir() {
-        return Err(format!("{} n'est pas un dossier valide", folder_path));
+        return Err(AppError::validation(format!("{} n'est pas un dossier valide", folder_path)));
     }
 
-    let models_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("app_data_dir: {}", e))?
-        .join("models");
+    let models_dir = catalog::models_dir(&app)?;
 
     std::fs::create_dir_all(&models_dir)
-        .map_err(|e| format!("Impossible de creer le dossier models: {}", e))?;
+        .map_err(|e| AppError::io("Impossible de creer le dossier models", e))?;
 
-    let total = EXPECTED_FILES.len();
+    // Install the active model of each role (see models::catalog)
+    let models = catalog::load_active_models(&app).await;
+    let total = models.len();
 
-    for (idx, (name, filename)) in EXPECTED_FILES.iter().enumerate() {
+    for (idx, entry) in models.iter().enumerate() {
+        let name = entry.role.as_str();
+        let filename = &entry.filename;
         let src_file = source.join(filename);
         let dest_file = models_dir.join(filename);
 
@@ -60,10 +57,10 @@ pub async fn install_models_from_folder(
 
         // Check source exists
         if !src_file.exists() {
-            return Err(format!(
+            return Err(AppError::not_found(format!(
                 "Fichier manquant dans le dossier : {}. Verifiez que les modeles sont bien dans le dossier selectionne.",
                 filename
-            ));
+            )));
         }
 
         // Copy
@@ -80,7 +77,7 @@ pub async fn install_models_from_folder(
 
         info!("Copie de {} vers {}", src_file.display(), dest_file.display());
         std::fs::copy(&src_file, &dest_file).map_err(|e| {
-            format!("Erreur lors de la copie de {} : {}", filename, e)
+            AppError::io(format!("Erreur lors de la copie de {}", filename), e)
         })?;
 
         // Verify SHA256
@@ -95,17 +92,18 @@ pub async fn install_models_from_folder(
         )
         .ok();
 
-        let hash_ok = verifier::verify_model_hash(&dest_file, filename)?;
+        let hash_ok = verifier::verify_model_hash(&dest_file, entry.sha256.as_deref())?;
         if !hash_ok {
             std::fs::remove_file(&dest_file).ok();
-            return Err(format!(
+            return Err(AppError::validation(format!(
                 "Fichier {} corrompu (SHA256 invalide). Verifiez la source.",
                 filename
-            ));
+            )));
         }
 
         let actual_hash = verifier::compute_sha256(&dest_file).unwrap_or_default();
         info!("SHA256 de {} : {}", filename, actual_hash);
+        catalog::record_installation(&app, entry, &dest_file, &actual_hash).await;
 
         app.emit(
             "install_progress",
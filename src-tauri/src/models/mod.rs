@@ -1,3 +1,5 @@
+pub mod audit;
+pub mod catalog;
 pub mod checker;
 pub mod downloader;
 pub mod installer;
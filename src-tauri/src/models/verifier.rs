@@ -2,30 +2,28 @@ use sha2::{Digest, Sha256};
 use std::io::Read;
 use std::path::Path;
 
-/// Known SHA256 hashes for the expected model files.
-/// These will be verified after download or USB copy.
-/// Note: if Hugging Face updates the files, these hashes must be updated.
-const EXPECTED_HASHES: &[(&str, &str)] = &[
-    ("ggml-small.bin", "1be3a9b2063867b937e64e2ec7483364a79917e157fa98c5d94b5c1fffea987b"),
-    ("qwen2.5-coder-1.5b-instruct-q4_k_m.gguf", "cc324af070c2ecbfd324a30884d2f951a7ff756aba85cb811a6ec436933bb046"),
-];
-
-/// Get the expected SHA256 hash for a model filename.
-/// Returns None if the filename is unknown or hash is empty (not yet computed).
-pub fn get_expected_hash(filename: &str) -> Option<&'static str> {
-    EXPECTED_HASHES
-        .iter()
-        .find(|(name, _)| *name == filename)
-        .and_then(|(_, hash)| if hash.is_empty() { None } else { Some(*hash) })
-}
+/// Progress callback frequency while hashing large files.
+const HASH_PROGRESS_INTERVAL: u64 = 16 * 1024 * 1024; // 16 MB
 
 /// Compute the SHA256 hash of a file, reading in 8 KB chunks.
 pub fn compute_sha256(path: &Path) -> Result<String, String> {
+    compute_sha256_with_progress(path, |_, _| {})
+}
+
+/// Same as `compute_sha256`, calling `on_progress(hashed_bytes, total_bytes)`
+/// every 16 MB and once at the end (hashing 1 GB takes several seconds).
+pub fn compute_sha256_with_progress<F: FnMut(u64, u64)>(
+    path: &Path,
+    mut on_progress: F,
+) -> Result<String, String> {
     let mut file =
         std::fs::File::open(path).map_err(|e| format!("Impossible d'ouvrir {}: {}", path.display(), e))?;
+    let total = file.metadata().map(|m| m.len()).unwrap_or(0);
 
     let mut hasher = Sha256::new();
     let mut buffer = [0u8; 8192];
+    let mut hashed: u64 = 0;
+    let mut last_reported: u64 = 0;
 
     loop {
         let n = file
@@ -35,22 +33,28 @@ pub fn compute_sha256(path: &Path) -> Result<String, String> {
             break;
         }
         hasher.update(&buffer[..n]);
+        hashed += n as u64;
+        if hashed - last_reported >= HASH_PROGRESS_INTERVAL {
+            last_reported = hashed;
+            on_progress(hashed, total);
+        }
     }
+    on_progress(hashed, total);
 
     Ok(format!("{:x}", hasher.finalize()))
 }
 
-/// Verify a file's SHA256 hash against its expected value.
+/// Verify a file's SHA256 hash against the expected value from the model catalog.
 /// Returns Ok(true) if match, Ok(false) if mismatch.
-/// If no expected hash is configured (empty), returns Ok(true) — skip verification.
-pub fn verify_model_hash(path: &Path, filename: &str) -> Result<bool, String> {
-    let expected = match get_expected_hash(filename) {
-        Some(h) => h,
-        None => return Ok(true), // No hash configured, skip verification
+/// If no expected hash is known (None or empty), returns Ok(true) — skip verification.
+pub fn verify_model_hash(path: &Path, expected: Option<&str>) -> Result<bool, String> {
+    let expected = match expected {
+        Some(h) if !h.is_empty() => h,
+        _ => return Ok(true), // No hash configured, skip verification
     };
 
     let actual = compute_sha256(path)?;
-    Ok(actual == expected)
+    Ok(actual.eq_ignore_ascii_case(expected))
 }
 
 #[cfg(test)]
@@ -74,15 +78,29 @@ mod tests {
     }
 
     #[test]
-    fn unknown_file_skips_verification() {
+    fn compute_sha256_reports_final_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("model.bin");
+        std::fs::write(&file_path, vec![7u8; 10_000]).unwrap();
+        let mut calls = Vec::new();
+        compute_sha256_with_progress(&file_path, |done, total| calls.push((done, total))).unwrap();
+        assert_eq!(calls.last(), Some(&(10_000, 10_000)));
+    }
+
+    #[test]
+    fn unknown_hash_skips_verification() {
         let path = Path::new("/nonexistent");
-        assert!(verify_model_hash(path, "unknown_model.bin").unwrap());
+        assert!(verify_model_hash(path, None).unwrap());
+        assert!(verify_model_hash(path, Some("")).unwrap());
     }
 
     #[test]
-    fn known_model_has_expected_hash() {
-        let hash = get_expected_hash("ggml-small.bin");
-        assert!(hash.is_some());
-        assert_eq!(hash.unwrap(), "1be3a9b2063867b937e64e2ec7483364a79917e157fa98c5d94b5c1fffea987b");
+    fn verify_model_hash_detects_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("model.bin");
+        std::fs::write(&file_path, b"hello world").unwrap();
+        let good = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+        assert!(verify_model_hash(&file_path, Some(good)).unwrap());
+        assert!(!verify_model_hash(&file_path, Some(&"0".repeat(64))).unwrap());
     }
 }
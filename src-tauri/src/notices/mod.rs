@@ -0,0 +1,790 @@
+/// Module Notices Familles — Courrier de comportement aux familles
+///
+/// Remplit un modele de lettre avec les incidents (sanctions + incidents detailles)
+/// d'un eleve sur une semaine ou une periode, ecrit la lettre en HTML imprimable
+/// et en PDF dans app_data_dir/notices, et trace l'envoi dans notices_familles.
+
+pub mod pdf;
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use crate::annee::check_annee_not_closed_impl;
+use crate::error::AppError;
+use crate::placeholders;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoticeRequest {
+    pub eleve_id: i64,
+    pub annee_scolaire_id: i64,
+    /// Si renseignee, les dates sont celles de la periode (config_periodes)
+    pub periode_id: Option<i64>,
+    /// Bornes inclusives YYYY-MM-DD (semaine ou plage libre), ignorees si periode_id
+    pub date_debut: Option<String>,
+    pub date_fin: Option<String>,
+    /// Paragraphe libre ajoute par l'enseignant avant la signature
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct NoticeIncident {
+    pub date: String,
+    #[sqlx(rename = "type")]
+    #[serde(rename = "type")]
+    pub incident_type: String,
+    pub motif: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FamilyNotice {
+    pub id: i64,
+    pub uuid: String,
+    pub eleve_id: i64,
+    pub annee_scolaire_id: i64,
+    pub periode_id: Option<i64>,
+    pub date_debut: String,
+    pub date_fin: String,
+    pub nb_incidents: i64,
+    pub file_path: String,
+    pub pdf_path: Option<String>,
+    pub created_at: String,
+}
+
+/// Donnees injectees dans le modele de lettre
+#[derive(Debug, Clone)]
+pub struct NoticeContext {
+    pub nom_ecole: Option<String>,
+    pub student_name: String,
+    pub date_debut: String,
+    pub date_fin: String,
+    pub incidents: Vec<NoticeIncident>,
+    pub message: Option<String>,
+    pub date_emission: String,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Modele de lettre
+// ─────────────────────────────────────────────────────────────────────────────
+
+const NOTICE_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="fr">
+<head>
+<meta charset="utf-8">
+<title>Information aux familles — {{eleve}}</title>
+<style>
+  @page { size: A4; margin: 2cm; }
+  body { font-family: Georgia, serif; font-size: 12pt; color: #000; }
+  header { margin-bottom: 1.5em; }
+  h1 { font-size: 15pt; text-align: center; margin: 1em 0; }
+  table { width: 100%; border-collapse: collapse; margin: 1em 0; }
+  th, td { border: 1px solid #444; padding: 4px 6px; text-align: left; vertical-align: top; }
+  th { background: #eee; }
+  .signatures { display: flex; justify-content: space-between; margin-top: 3em; }
+  .signatures div { width: 45%; height: 5em; border-top: 1px solid #444; padding-top: 4px; }
+</style>
+</head>
+<body>
+<header>
+  <div>{{ecole}}</div>
+  <div>Le {{date_emission}}</div>
+</header>
+<h1>Information aux familles — comportement</h1>
+<p>Madame, Monsieur,</p>
+<p>Nous souhaitons vous informer du comportement de <strong>{{eleve}}</strong> en classe
+du {{date_debut}} au {{date_fin}}. {{resume}}</p>
+{{incidents}}
+{{message}}
+<p>Nous vous remercions d'en parler avec votre enfant et restons disponibles pour un échange.</p>
+<p>Veuillez agréer, Madame, Monsieur, l'expression de nos salutations distinguées.</p>
+<div class="signatures">
+  <div>Signature de l'enseignant(e)</div>
+  <div>Signature des responsables légaux</div>
+</div>
+</body>
+</html>
+"#;
+
+/// Echappe les caracteres speciaux HTML (les motifs sont saisis librement)
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+/// YYYY-MM-DD → JJ/MM/AAAA (laisse la valeur intacte si le format est inattendu)
+fn format_date_fr(date: &str) -> String {
+    let day = date.get(..10).unwrap_or(date);
+    let parts: Vec<&str> = day.split('-').collect();
+    match parts.as_slice() {
+        [y, m, d] if y.len() == 4 && m.len() == 2 && d.len() == 2 => format!("{}/{}/{}", d, m, y),
+        _ => date.to_string(),
+    }
+}
+
+/// Phrase d'introduction de la liste des incidents (commune HTML et PDF)
+fn resume_incidents(count: usize) -> String {
+    match count {
+        0 => "Aucun incident n'a été relevé sur cette période.".to_string(),
+        1 => "Un incident a été relevé :".to_string(),
+        n => format!("{} incidents ont été relevés :", n),
+    }
+}
+
+/// Remplit le modele de lettre. Fonction pure (testable sans fichier ni DB).
+pub fn render_notice_html(ctx: &NoticeContext) -> String {
+    let resume = resume_incidents(ctx.incidents.len());
+
+    let incidents = if ctx.incidents.is_empty() {
+        String::new()
+    } else {
+        let rows: Vec<String> = ctx
+            .incidents
+            .iter()
+            .map(|i| {
+                format!(
+                    "    <tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    escape_html(&format_date_fr(&i.date)),
+                    escape_html(&i.incident_type),
+                    escape_html(&i.motif),
+                    escape_html(i.description.as_deref().unwrap_or(""))
+                )
+            })
+            .collect();
+        format!(
+            "<table>\n  <thead><tr><th>Date</th><th>Type</th><th>Motif</th><th>Précisions</th></tr></thead>\n  <tbody>\n{}\n  </tbody>\n</table>",
+            rows.join("\n")
+        )
+    };
+
+    let message = match ctx.message.as_deref().map(str::trim) {
+        Some(m) if !m.is_empty() => format!("<p>{}</p>", escape_html(m)),
+        _ => String::new(),
+    };
+
+    let ecole = escape_html(ctx.nom_ecole.as_deref().unwrap_or(""));
+    let date_emission = escape_html(&format_date_fr(&ctx.date_emission));
+    let date_debut = escape_html(&format_date_fr(&ctx.date_debut));
+    let date_fin = escape_html(&format_date_fr(&ctx.date_fin));
+    let eleve = escape_html(&ctx.student_name);
+    placeholders::fill(
+        NOTICE_TEMPLATE,
+        "{{",
+        "}}",
+        &[
+            ("ecole", &ecole),
+            ("date_emission", &date_emission),
+            ("date_debut", &date_debut),
+            ("date_fin", &date_fin),
+            ("resume", &resume),
+            ("incidents", &incidents),
+            ("message", &message),
+            ("eleve", &eleve),
+        ],
+    )
+}
+
+/// Meme lettre que `render_notice_html`, mise en page PDF (un paragraphe par incident)
+pub fn render_notice_pdf(ctx: &NoticeContext) -> Vec<u8> {
+    use pdf::{Block, Style};
+
+    let mut blocks = Vec::new();
+    if let Some(ecole) = ctx.nom_ecole.as_deref().filter(|e| !e.trim().is_empty()) {
+        blocks.push(Block::new(Style::Body, ecole).space_after(0.0));
+    }
+    blocks.push(
+        Block::new(
+            Style::Body,
+            format!("Le {}", format_date_fr(&ctx.date_emission)),
+        )
+        .space_after(16.0),
+    );
+    blocks.push(
+        Block::new(Style::Title, "Information aux familles — comportement").space_after(16.0),
+    );
+    blocks.push(Block::new(Style::Body, "Madame, Monsieur,"));
+    blocks.push(Block::new(
+        Style::Body,
+        format!(
+            "Nous souhaitons vous informer du comportement de {} en classe du {} au {}. {}",
+            ctx.student_name,
+            format_date_fr(&ctx.date_debut),
+            format_date_fr(&ctx.date_fin),
+            resume_incidents(ctx.incidents.len())
+        ),
+    ));
+    for incident in &ctx.incidents {
+        let mut line = format!(
+            "• {} — {} — {}",
+            format_date_fr(&incident.date),
+            incident.incident_type,
+            incident.motif
+        );
+        if let Some(description) = incident
+            .description
+            .as_deref()
+            .filter(|d| !d.trim().is_empty())
+        {
+            line.push_str(&format!(" ({})", description));
+        }
+        blocks.push(Block::new(Style::Body, line).space_after(2.0));
+    }
+    if !ctx.incidents.is_empty() {
+        blocks.push(Block::new(Style::Body, "").space_after(0.0));
+    }
+    if let Some(message) = ctx
+        .message
+        .as_deref()
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+    {
+        blocks.push(Block::new(Style::Body, message));
+    }
+    blocks.push(Block::new(
+        Style::Body,
+        "Nous vous remercions d'en parler avec votre enfant et restons disponibles pour un échange.",
+    ));
+    blocks.push(
+        Block::new(
+            Style::Body,
+            "Veuillez agréer, Madame, Monsieur, l'expression de nos salutations distinguées.",
+        )
+        .space_after(30.0),
+    );
+    blocks.push(Block::new(Style::Bold, "Signature de l'enseignant(e)").space_after(50.0));
+    blocks.push(Block::new(Style::Bold, "Signature des responsables légaux"));
+
+    pdf::write_pdf(&blocks)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Row mapping
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, sqlx::FromRow)]
+struct NoticeDbRow {
+    id: i64,
+    uuid: String,
+    eleve_id: i64,
+    annee_scolaire_id: i64,
+    periode_id: Option<i64>,
+    date_debut: String,
+    date_fin: String,
+    nb_incidents: i64,
+    file_path: String,
+    pdf_path: Option<String>,
+    created_at: String,
+}
+
+impl From<NoticeDbRow> for FamilyNotice {
+    fn from(r: NoticeDbRow) -> Self {
+        FamilyNotice {
+            id: r.id,
+            uuid: r.uuid,
+            eleve_id: r.eleve_id,
+            annee_scolaire_id: r.annee_scolaire_id,
+            periode_id: r.periode_id,
+            date_debut: r.date_debut,
+            date_fin: r.date_fin,
+            nb_incidents: r.nb_incidents,
+            file_path: r.file_path,
+            pdf_path: r.pdf_path,
+            created_at: r.created_at,
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Impls (testable, prennent une connexion)
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Charge sanctions et incidents detailles d'un eleve entre deux dates (incluses),
+/// tries chronologiquement.
+pub async fn load_notice_incidents_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    eleve_id: i64,
+    date_debut: &str,
+    date_fin: &str,
+) -> Result<Vec<NoticeIncident>, AppError> {
+    sqlx::query_as::<_, NoticeIncident>(
+        "SELECT date(created_at) AS date, 'Sanction' AS type,
+                COALESCE(reason, 'Non précisé') AS motif, NULL AS description
+         FROM sanctions
+         WHERE student_id = ? AND date(created_at) BETWEEN ? AND ?
+         UNION ALL
+         SELECT date_incident AS date, type_evenement AS type, motif, description
+         FROM comportement_detail
+         WHERE eleve_id = ? AND date_incident BETWEEN ? AND ?
+         ORDER BY date ASC",
+    )
+    .bind(eleve_id)
+    .bind(date_debut)
+    .bind(date_fin)
+    .bind(eleve_id)
+    .bind(date_debut)
+    .bind(date_fin)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur chargement incidents", e))
+}
+
+/// Resout la plage de dates : periode (config_periodes) ou bornes explicites.
+async fn resolve_date_range(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    request: &NoticeRequest,
+) -> Result<(String, String), AppError> {
+    if let Some(periode_id) = request.periode_id {
+        // Periodes anterieures a M006 : annee retrouvee par son libelle
+        let periode: Option<(String, String, Option<i64>)> = sqlx::query_as(
+            "SELECT p.date_debut, p.date_fin, COALESCE(p.annee_scolaire_id, a.id)
+             FROM config_periodes p
+             LEFT JOIN annees_scolaires a ON a.label = p.annee_scolaire
+             WHERE p.id = ?",
+        )
+        .bind(periode_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| AppError::db("Erreur lecture periode", e))?;
+        let (debut, fin, annee_id) = periode
+            .ok_or_else(|| AppError::not_found(format!("Periode introuvable : id={}", periode_id)))?;
+        if annee_id != Some(request.annee_scolaire_id) {
+            return Err(AppError::validation(format!(
+                "La periode {} n'appartient pas a l'annee scolaire {}",
+                periode_id, request.annee_scolaire_id
+            )));
+        }
+        return Ok((debut, fin));
+    }
+
+    match (&request.date_debut, &request.date_fin) {
+        (Some(debut), Some(fin)) if debut <= fin => Ok((debut.clone(), fin.clone())),
+        (Some(_), Some(_)) => Err(AppError::validation("La date de debut doit preceder la date de fin")),
+        _ => Err(AppError::validation("Periode ou dates de debut/fin requises")),
+    }
+}
+
+/// Genere la notice (fichiers HTML et PDF dans `output_dir`) et trace l'envoi.
+/// Refuse si l'annee scolaire est cloturee.
+pub async fn generate_family_notice_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    output_dir: &Path,
+    request: &NoticeRequest,
+) -> Result<FamilyNotice, AppError> {
+    check_annee_not_closed_impl(conn, request.annee_scolaire_id).await?;
+
+    let (date_debut, date_fin) = resolve_date_range(conn, request).await?;
+
+    let student_name: String =
+        sqlx::query_scalar("SELECT first_name FROM students WHERE id = ?")
+            .bind(request.eleve_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| AppError::db("Erreur lecture eleve", e))?
+            .ok_or_else(|| AppError::not_found(format!("Eleve introuvable : id={}", request.eleve_id)))?;
+
+    // config_lsu est optionnelle (ecole pas encore renseignee)
+    let nom_ecole: Option<String> =
+        sqlx::query_scalar("SELECT nom_ecole FROM config_lsu WHERE id = 1")
+            .fetch_optional(&mut *conn)
+            .await
+            .ok()
+            .flatten()
+            .flatten();
+
+    let incidents =
+        load_notice_incidents_impl(conn, request.eleve_id, &date_debut, &date_fin).await?;
+
+    let date_emission: String = sqlx::query_scalar("SELECT date('now', 'localtime')")
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| AppError::db("Erreur lecture date", e))?;
+
+    let ctx = NoticeContext {
+        nom_ecole,
+        student_name,
+        date_debut: date_debut.clone(),
+        date_fin: date_fin.clone(),
+        incidents,
+        message: request.message.clone(),
+        date_emission,
+    };
+    let html = render_notice_html(&ctx);
+    let pdf = render_notice_pdf(&ctx);
+
+    std::fs::create_dir_all(output_dir)
+        .map_err(|e| AppError::io("Impossible de créer le répertoire notices", e))?;
+
+    let uuid = uuid::Uuid::new_v4().to_string();
+    let stem = format!("notice_{}_{}_{}", request.eleve_id, date_debut, &uuid[..8]);
+    let file_path: PathBuf = output_dir.join(format!("{}.html", stem));
+    let pdf_path: PathBuf = output_dir.join(format!("{}.pdf", stem));
+    std::fs::write(&file_path, html)
+        .map_err(|e| AppError::io("Impossible d'écrire la notice", e))?;
+    if let Err(e) = std::fs::write(&pdf_path, pdf) {
+        std::fs::remove_file(&file_path).ok();
+        return Err(AppError::io("Impossible d'écrire la notice PDF", e));
+    }
+    let path_str = file_path.to_string_lossy().to_string();
+    let pdf_path_str = pdf_path.to_string_lossy().to_string();
+
+    let insert = sqlx::query(
+        "INSERT INTO notices_familles
+            (uuid, eleve_id, annee_scolaire_id, periode_id, date_debut, date_fin, nb_incidents, file_path, pdf_path)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&uuid)
+    .bind(request.eleve_id)
+    .bind(request.annee_scolaire_id)
+    .bind(request.periode_id)
+    .bind(&date_debut)
+    .bind(&date_fin)
+    .bind(ctx.incidents.len() as i64)
+    .bind(&path_str)
+    .bind(&pdf_path_str)
+    .execute(&mut *conn)
+    .await;
+
+    let insert_id = match insert {
+        Ok(r) => r.last_insert_rowid(),
+        Err(e) => {
+            // Pas de fichier orphelin si la trace n'a pas pu etre enregistree
+            std::fs::remove_file(&file_path).ok();
+            std::fs::remove_file(&pdf_path).ok();
+            return Err(AppError::db("Erreur insertion notice", e));
+        }
+    };
+
+    let row: NoticeDbRow = sqlx::query_as(
+        "SELECT id, uuid, eleve_id, annee_scolaire_id, periode_id, date_debut, date_fin, nb_incidents, file_path, pdf_path, created_at
+         FROM notices_familles WHERE id = ?",
+    )
+    .bind(insert_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur lecture notice inseree", e))?;
+
+    Ok(row.into())
+}
+
+/// Historique des notices envoyees a la famille d'un eleve (plus recente en premier).
+pub async fn load_family_notices_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    eleve_id: i64,
+    annee_scolaire_id: i64,
+) -> Result<Vec<FamilyNotice>, AppError> {
+    let rows: Vec<NoticeDbRow> = sqlx::query_as(
+        "SELECT id, uuid, eleve_id, annee_scolaire_id, periode_id, date_debut, date_fin, nb_incidents, file_path, pdf_path, created_at
+         FROM notices_familles
+         WHERE eleve_id = ? AND annee_scolaire_id = ?
+         ORDER BY created_at DESC, id DESC",
+    )
+    .bind(eleve_id)
+    .bind(annee_scolaire_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur chargement notices", e))?;
+
+    Ok(rows.into_iter().map(|r| r.into()).collect())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Commandes Tauri
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn generate_family_notice(
+    app: tauri::AppHandle,
+    request: NoticeRequest,
+) -> Result<FamilyNotice, AppError> {
+    let notices_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::io("Impossible de trouver app_data_dir", e))?
+        .join("notices");
+
+    let mut conn = crate::db::acquire(&app).await?;
+
+    generate_family_notice_impl(&mut conn, &notices_dir, &request).await
+}
+
+#[tauri::command]
+pub async fn load_family_notices(
+    app: tauri::AppHandle,
+    eleve_id: i64,
+    annee_scolaire_id: i64,
+) -> Result<Vec<FamilyNotice>, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
+    load_family_notices_impl(&mut conn, eleve_id, annee_scolaire_id).await
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::Connection;
+
+    async fn setup_test_db() -> (sqlx::sqlite::SqliteConnection, tempfile::NamedTempFile) {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let url = format!("sqlite:{}", tmp.path().display());
+        let mut conn = sqlx::sqlite::SqliteConnection::connect(&url)
+            .await
+            .expect("Impossible de creer la DB de test");
+
+        let schema = [
+            "CREATE TABLE annees_scolaires (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                label TEXT NOT NULL,
+                date_debut DATE NOT NULL,
+                date_fin DATE NOT NULL,
+                active INTEGER DEFAULT 0,
+                cloturee INTEGER DEFAULT 0,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            "CREATE TABLE students (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                first_name TEXT NOT NULL,
+                warnings INTEGER DEFAULT 0,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            "CREATE TABLE sanctions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                student_id INTEGER NOT NULL,
+                reason TEXT,
+                week_number INTEGER NOT NULL,
+                year INTEGER NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            "CREATE TABLE config_periodes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                annee_scolaire TEXT NOT NULL,
+                type_periode TEXT NOT NULL,
+                numero INTEGER NOT NULL,
+                date_debut DATE NOT NULL,
+                date_fin DATE NOT NULL,
+                nom_affichage TEXT,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                annee_scolaire_id INTEGER DEFAULT NULL
+            )",
+            "CREATE TABLE comportement_detail (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                eleve_id INTEGER NOT NULL,
+                date_incident DATE NOT NULL,
+                heure_incident TIME,
+                periode_id INTEGER,
+                type_evenement TEXT NOT NULL,
+                motif TEXT NOT NULL,
+                description TEXT,
+                intervenant TEXT DEFAULT 'Enseignant',
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            "CREATE TABLE config_lsu (
+                id INTEGER PRIMARY KEY DEFAULT 1,
+                uai TEXT DEFAULT NULL,
+                nom_ecole TEXT DEFAULT NULL
+            )",
+            "CREATE TABLE notices_familles (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                uuid TEXT UNIQUE,
+                eleve_id INTEGER NOT NULL REFERENCES students(id) ON DELETE CASCADE,
+                annee_scolaire_id INTEGER NOT NULL REFERENCES annees_scolaires(id),
+                periode_id INTEGER REFERENCES config_periodes(id),
+                date_debut TEXT NOT NULL,
+                date_fin TEXT NOT NULL,
+                nb_incidents INTEGER NOT NULL DEFAULT 0,
+                file_path TEXT NOT NULL,
+                pdf_path TEXT DEFAULT NULL,
+                created_at TEXT DEFAULT (datetime('now'))
+            )",
+            "INSERT INTO annees_scolaires (label, date_debut, date_fin, active) VALUES ('2025-2026', '2025-09-01', '2026-07-05', 1)",
+            "INSERT INTO students (first_name) VALUES ('Alice'), ('Bob')",
+            "INSERT INTO config_lsu (id, nom_ecole) VALUES (1, 'Ecole Jules Ferry')",
+            "INSERT INTO config_periodes (annee_scolaire, type_periode, numero, date_debut, date_fin) VALUES ('2025-2026', 'trimestre', 1, '2025-09-01', '2025-12-20')",
+            "INSERT INTO sanctions (student_id, reason, week_number, year, created_at) VALUES (1, 'Bavardages', 46, 2025, '2025-11-12 10:00:00')",
+            "INSERT INTO sanctions (student_id, reason, week_number, year, created_at) VALUES (1, 'Hors periode', 3, 2026, '2026-01-14 10:00:00')",
+            "INSERT INTO sanctions (student_id, reason, week_number, year, created_at) VALUES (2, 'Autre eleve', 46, 2025, '2025-11-12 10:00:00')",
+            "INSERT INTO comportement_detail (eleve_id, date_incident, type_evenement, motif, description) VALUES (1, '2025-11-10', 'Incident', 'Insolence <cour>', 'Pendant la recreation')",
+        ];
+        for stmt in schema {
+            sqlx::query(stmt).execute(&mut conn).await.unwrap();
+        }
+
+        (conn, tmp)
+    }
+
+    fn week_request() -> NoticeRequest {
+        NoticeRequest {
+            eleve_id: 1,
+            annee_scolaire_id: 1,
+            periode_id: None,
+            date_debut: Some("2025-11-10".to_string()),
+            date_fin: Some("2025-11-14".to_string()),
+            message: Some("Merci de signer ce document.".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_incidents_filters_student_and_dates() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let incidents = load_notice_incidents_impl(&mut conn, 1, "2025-11-10", "2025-11-14")
+            .await
+            .unwrap();
+        assert_eq!(incidents.len(), 2, "Une sanction + un incident detaille");
+        assert_eq!(incidents[0].date, "2025-11-10", "Tri chronologique");
+        assert_eq!(incidents[1].motif, "Bavardages");
+    }
+
+    #[tokio::test]
+    async fn test_generate_notice_writes_file_and_records() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let dir = tempfile::tempdir().unwrap();
+
+        let notice = generate_family_notice_impl(&mut conn, dir.path(), &week_request())
+            .await
+            .unwrap();
+        assert_eq!(notice.nb_incidents, 2);
+        assert_eq!(notice.date_debut, "2025-11-10");
+
+        let html = std::fs::read_to_string(&notice.file_path).unwrap();
+        assert!(html.contains("Alice"));
+        assert!(html.contains("Ecole Jules Ferry"));
+        assert!(html.contains("Bavardages"));
+        assert!(html.contains("Insolence &lt;cour&gt;"), "Les motifs doivent etre echappes");
+        assert!(html.contains("Merci de signer ce document."));
+        assert!(!html.contains("Hors periode"));
+
+        let pdf = std::fs::read(notice.pdf_path.as_deref().unwrap()).unwrap();
+        assert!(pdf.starts_with(b"%PDF-"));
+        assert!(pdf.windows(5).any(|w| w == b"Alice"));
+        assert!(pdf.windows(16).any(|w| w == b"Insolence <cour>"), "Pas d'echappement HTML dans le PDF");
+
+        let history = load_family_notices_impl(&mut conn, 1, 1).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].uuid, notice.uuid);
+    }
+
+    #[tokio::test]
+    async fn test_generate_notice_uses_periode_dates() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut request = week_request();
+        request.periode_id = Some(1);
+        request.date_debut = None;
+        request.date_fin = None;
+
+        let notice = generate_family_notice_impl(&mut conn, dir.path(), &request)
+            .await
+            .unwrap();
+        assert_eq!(notice.date_debut, "2025-09-01");
+        assert_eq!(notice.date_fin, "2025-12-20");
+        assert_eq!(notice.periode_id, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_generate_notice_rejects_periode_of_another_annee() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let dir = tempfile::tempdir().unwrap();
+        for stmt in [
+            "INSERT INTO annees_scolaires (label, date_debut, date_fin) VALUES ('2026-2027', '2026-09-01', '2027-07-04')",
+            "INSERT INTO config_periodes (annee_scolaire, type_periode, numero, date_debut, date_fin, annee_scolaire_id)
+             VALUES ('2026-2027', 'trimestre', 1, '2026-09-01', '2026-12-19', 2)",
+        ] {
+            sqlx::query(stmt).execute(&mut conn).await.unwrap();
+        }
+
+        let mut request = week_request();
+        request.periode_id = Some(2);
+        let err = generate_family_notice_impl(&mut conn, dir.path(), &request).await.unwrap_err();
+        assert_eq!(err.code(), "Validation");
+
+        request.annee_scolaire_id = 2;
+        let notice = generate_family_notice_impl(&mut conn, dir.path(), &request).await.unwrap();
+        assert_eq!(notice.date_debut, "2026-09-01");
+    }
+
+    #[tokio::test]
+    async fn test_generate_notice_blocked_by_closed_annee() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let dir = tempfile::tempdir().unwrap();
+
+        sqlx::query("UPDATE annees_scolaires SET cloturee = 1 WHERE id = 1")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        let result = generate_family_notice_impl(&mut conn, dir.path(), &week_request()).await;
+        assert!(matches!(result.unwrap_err(), AppError::YearClosed));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_generate_notice_requires_dates() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut request = week_request();
+        request.date_fin = None;
+        let err = generate_family_notice_impl(&mut conn, dir.path(), &request).await.unwrap_err();
+        assert_eq!(err.code(), "Validation");
+
+        request.date_debut = Some("2025-11-20".to_string());
+        request.date_fin = Some("2025-11-10".to_string());
+        let err = generate_family_notice_impl(&mut conn, dir.path(), &request).await.unwrap_err();
+        assert_eq!(err.code(), "Validation");
+    }
+
+    #[test]
+    fn test_render_without_incidents() {
+        let html = render_notice_html(&NoticeContext {
+            nom_ecole: None,
+            student_name: "Bob".to_string(),
+            date_debut: "2025-11-10".to_string(),
+            date_fin: "2025-11-14".to_string(),
+            incidents: vec![],
+            message: None,
+            date_emission: "2025-11-15".to_string(),
+        });
+        assert!(html.contains("Aucun incident"));
+        assert!(html.contains("du 10/11/2025 au 14/11/2025"));
+        assert!(!html.contains("<table>"));
+        assert!(!html.contains("{{"), "Tous les placeholders doivent etre remplaces");
+    }
+
+    #[test]
+    fn test_render_does_not_expand_placeholders_in_user_text() {
+        let html = render_notice_html(&NoticeContext {
+            nom_ecole: Some("Ecole Jules Ferry".to_string()),
+            student_name: "Bob".to_string(),
+            date_debut: "2025-11-10".to_string(),
+            date_fin: "2025-11-14".to_string(),
+            incidents: vec![],
+            message: Some("Voir {{eleve}} et {{ecole}}".to_string()),
+            date_emission: "2025-11-15".to_string(),
+        });
+        assert!(html.contains("<p>Voir {{eleve}} et {{ecole}}</p>"));
+    }
+
+    #[test]
+    fn test_format_date_fr() {
+        assert_eq!(format_date_fr("2025-11-10"), "10/11/2025");
+        assert_eq!(format_date_fr("2025-11-10 08:30:00"), "10/11/2025");
+        assert_eq!(format_date_fr("inconnue"), "inconnue");
+    }
+}
@@ -0,0 +1,311 @@
+/// Module Notices/PDF — Ecriture d'un PDF texte, sans dependance externe
+///
+/// Juste ce qu'il faut pour une lettre : pages A4, polices standard Helvetica et
+/// Helvetica-Bold (encodage WinAnsi, donc les accents francais), paragraphes
+/// coupes aux espaces, saut de page automatique. Pas d'images ni de tableaux.
+
+use std::fmt::Write as _;
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 57.0; // 2 cm, comme le @page de la version HTML
+
+/// Largeur moyenne d'un caractere Helvetica, en fraction de la taille de police.
+/// Volontairement large : une ligne coupee trop tot vaut mieux qu'un debordement.
+const AVG_CHAR_WIDTH: f32 = 0.52;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Title,
+    Body,
+    Bold,
+}
+
+/// Paragraphe de la lettre ; `space_after` en points avant le paragraphe suivant
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub text: String,
+    pub style: Style,
+    pub space_after: f32,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Impls
+// ─────────────────────────────────────────────────────────────────────────────
+
+impl Block {
+    pub fn new(style: Style, text: impl Into<String>) -> Self {
+        Block {
+            text: text.into(),
+            style,
+            space_after: 8.0,
+        }
+    }
+
+    pub fn space_after(mut self, points: f32) -> Self {
+        self.space_after = points;
+        self
+    }
+}
+
+impl Style {
+    fn font(self) -> &'static str {
+        match self {
+            Style::Body => "F1",
+            Style::Title | Style::Bold => "F2",
+        }
+    }
+
+    fn size(self) -> f32 {
+        match self {
+            Style::Title => 15.0,
+            Style::Body | Style::Bold => 11.0,
+        }
+    }
+}
+
+/// Coupe un paragraphe en lignes d'au plus `max_chars` caracteres (mots trop longs coupes)
+fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for source in text.lines() {
+        let mut line = String::new();
+        for word in source.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            while word.len() > max_chars {
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                lines.push(word.drain(..max_chars).collect());
+            }
+            let word: String = word.into_iter().collect();
+            let len = line.chars().count();
+            if len > 0 && len + 1 + word.chars().count() > max_chars {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Caractere en WinAnsiEncoding (cp1252) ; '?' hors de cet encodage
+fn win_ansi(c: char) -> u8 {
+    match c {
+        ' '..='~' => c as u8,
+        '\u{a0}'..='\u{ff}' => c as u32 as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        '„' => 0x84,
+        '…' => 0x85,
+        'Œ' => 0x8C,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        'œ' => 0x9C,
+        'Ÿ' => 0x9F,
+        _ => b'?',
+    }
+}
+
+/// Chaine litterale PDF : `(...)` avec parentheses et antislash echappes
+fn pdf_string(text: &str, out: &mut Vec<u8>) {
+    out.push(b'(');
+    for c in text.chars() {
+        let byte = win_ansi(c);
+        if matches!(byte, b'(' | b')' | b'\\') {
+            out.push(b'\\');
+        }
+        out.push(byte);
+    }
+    out.push(b')');
+}
+
+/// Flux de contenu de chaque page
+fn layout(blocks: &[Block]) -> Vec<Vec<u8>> {
+    let mut pages = Vec::new();
+    let mut content = Vec::new();
+    let mut y = PAGE_HEIGHT - MARGIN;
+
+    for block in blocks {
+        let size = block.style.size();
+        let leading = size * 1.35;
+        let max_chars = ((PAGE_WIDTH - 2.0 * MARGIN) / (size * AVG_CHAR_WIDTH)) as usize;
+        for line in wrap(&block.text, max_chars) {
+            if y - leading < MARGIN {
+                pages.push(std::mem::take(&mut content));
+                y = PAGE_HEIGHT - MARGIN;
+            }
+            y -= leading;
+            if line.is_empty() {
+                continue;
+            }
+            let x = if block.style == Style::Title {
+                // Centre approximatif, comme le h1 de la version HTML
+                ((PAGE_WIDTH - line.chars().count() as f32 * size * AVG_CHAR_WIDTH) / 2.0)
+                    .max(MARGIN)
+            } else {
+                MARGIN
+            };
+            content.extend_from_slice(
+                format!(
+                    "BT /{} {} Tf {:.1} {:.1} Td ",
+                    block.style.font(),
+                    size,
+                    x,
+                    y
+                )
+                .as_bytes(),
+            );
+            pdf_string(&line, &mut content);
+            content.extend_from_slice(b" Tj ET\n");
+        }
+        y -= block.space_after;
+    }
+    pages.push(content);
+    pages
+}
+
+/// Document PDF complet (en-tete, objets, table xref, trailer)
+pub fn write_pdf(blocks: &[Block]) -> Vec<u8> {
+    let pages = layout(blocks);
+    // 1 catalogue, 2 arbre des pages, 3-4 polices, puis (page, contenu) par page
+    let page_ids: Vec<usize> = (0..pages.len()).map(|i| 5 + 2 * i).collect();
+
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids
+                .iter()
+                .map(|id| format!("{} 0 R", id))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+    ];
+    for (content, page_id) in pages.into_iter().zip(&page_ids) {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                page_id + 1
+            )
+            .into_bytes(),
+        );
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend_from_slice(&content);
+        stream.extend_from_slice(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut out = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        out.extend_from_slice(object);
+        out.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref_at = out.len();
+    let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(xref, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        xref,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref_at
+    );
+    out.extend_from_slice(xref.as_bytes());
+    out
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|w| w == needle)
+    }
+
+    #[test]
+    fn test_xref_points_at_each_object() {
+        let pdf = write_pdf(&[
+            Block::new(Style::Title, "Notice"),
+            Block::new(Style::Body, "Bonjour"),
+        ]);
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+
+        let text = String::from_utf8_lossy(&pdf).to_string();
+        let startxref: usize = text
+            .rsplit("startxref\n")
+            .next()
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(pdf[startxref..].starts_with(b"xref\n0 7\n"));
+        let entries: Vec<usize> = text[text.find("65535 f \n").unwrap() + 9..]
+            .lines()
+            .take(6)
+            .map(|l| l[..10].parse().unwrap())
+            .collect();
+        for (i, offset) in entries.iter().enumerate() {
+            assert!(pdf[*offset..].starts_with(format!("{} 0 obj\n", i + 1).as_bytes()));
+        }
+    }
+
+    #[test]
+    fn test_text_is_win_ansi_and_escaped() {
+        let pdf = write_pdf(&[Block::new(Style::Body, "Élève (Léa) — a\\b ✓")]);
+        assert!(contains(
+            &pdf,
+            b"(\xC9l\xE8ve \\(L\xE9a\\) \x97 a\\\\b ?) Tj"
+        ));
+    }
+
+    #[test]
+    fn test_long_text_wraps_and_breaks_pages() {
+        assert_eq!(wrap("un deux trois", 7), vec!["un deux", "trois"]);
+        assert_eq!(
+            wrap("anticonstitutionnellement", 10),
+            vec!["anticonsti", "tutionnell", "ement"]
+        );
+        assert_eq!(wrap("a\n\nb", 10), vec!["a", "", "b"]);
+
+        let blocks: Vec<Block> = (0..80)
+            .map(|i| Block::new(Style::Body, format!("Ligne {}", i)))
+            .collect();
+        let pdf = write_pdf(&blocks);
+        assert!(contains(&pdf, b"/Count 3 >>"));
+        assert!(contains(&pdf, b"(Ligne 79) Tj"));
+    }
+}
@@ -0,0 +1,54 @@
+/// Substitution des variables des modeles de texte (notices familles, prompts LLM).
+///
+/// Le modele est parcouru une seule fois : une valeur inseree n'est jamais relue,
+/// un prenom ou un message contenant lui-meme `{{eleve}}` ou `{domaine}` reste tel quel.
+
+/// Remplace chaque `open` + nom + `close` du modele par la valeur de `vars`.
+/// Les noms absents de `vars` sont laisses tels quels.
+pub fn fill(template: &str, open: &str, close: &str, vars: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(open) {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + open.len()..];
+        let value = after.find(close).and_then(|end| {
+            let name = &after[..end];
+            vars.iter().find(|(n, _)| *n == name).map(|(_, v)| (end, *v))
+        });
+        match value {
+            Some((end, value)) => {
+                out.push_str(value);
+                rest = &after[end + close.len()..];
+            }
+            None => {
+                out.push_str(open);
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values_are_not_substituted_again() {
+        let vars = [("eleve", "{{ecole}}"), ("ecole", "Jules Ferry")];
+        assert_eq!(fill("{{eleve}} - {{ecole}}", "{{", "}}", &vars), "{{ecole}} - Jules Ferry");
+    }
+
+    #[test]
+    fn test_unknown_names_and_lone_delimiters_are_kept() {
+        let vars = [("domaine", "Lecture")];
+        assert_eq!(fill("{ \"a\": {domaine} {autre}", "{", "}", &vars), "{ \"a\": Lecture {autre}");
+        assert_eq!(fill("fin {domaine", "{", "}", &vars), "fin {domaine");
+        assert_eq!(fill("", "{", "}", &vars), "");
+    }
+}
@@ -1,5 +1,27 @@
+use super::config::{load_settings, save_settings, SidecarSettings, SETTINGS_FILE};
 use super::manager::SidecarManager;
 use super::types::{PipelineConfig, PipelineMode, SidecarName};
+use crate::error::AppError;
+use std::path::PathBuf;
+use tauri::Manager;
+
+/// Path of the persisted sidecar settings file (app_data_dir/sidecar_settings.json)
+pub fn settings_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Impossible de trouver app_data_dir: {}", e))?;
+    Ok(data_dir.join(SETTINGS_FILE))
+}
+
+/// Load persisted settings into the manager (called once at startup)
+pub async fn init_settings(app: &tauri::AppHandle) {
+    let settings = match settings_path(app) {
+        Ok(path) => load_settings(&path),
+        Err(_) => SidecarSettings::default(),
+    };
+    app.state::<SidecarManager>().set_settings(settings).await;
+}
 
 #[tauri::command]
 pub async fn start_sidecar(
@@ -8,11 +30,11 @@ pub async fn start_sidecar(
     name: SidecarName,
     model_path: String,
     grammar_path: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     state
         .start(&app, name, model_path, grammar_path)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -20,14 +42,14 @@ pub async fn stop_sidecar(
     app: tauri::AppHandle,
     state: tauri::State<'_, SidecarManager>,
     name: SidecarName,
-) -> Result<(), String> {
-    state.stop(&app, name).await.map_err(|e| e.to_string())
+) -> Result<(), AppError> {
+    state.stop(&app, name).await.map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_sidecar_status(
     state: tauri::State<'_, SidecarManager>,
-) -> Result<super::types::SidecarStatusResponse, String> {
+) -> Result<super::types::SidecarStatusResponse, AppError> {
     Ok(state.get_status().await)
 }
 
@@ -35,7 +57,7 @@ pub async fn get_sidecar_status(
 #[tauri::command]
 pub async fn get_pipeline_config(
     state: tauri::State<'_, SidecarManager>,
-) -> Result<PipelineConfig, String> {
+) -> Result<PipelineConfig, AppError> {
     Ok(state.get_pipeline_config().await)
 }
 
@@ -44,7 +66,30 @@ pub async fn get_pipeline_config(
 pub async fn set_pipeline_mode(
     state: tauri::State<'_, SidecarManager>,
     mode: PipelineMode,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     state.set_pipeline_mode(mode).await;
     Ok(())
 }
+
+/// Returns the sidecar settings (ports, threads, ctx-size, GPU layers, whisper decoding)
+#[tauri::command]
+pub async fn get_sidecar_settings(
+    state: tauri::State<'_, SidecarManager>,
+) -> Result<SidecarSettings, AppError> {
+    Ok(state.get_settings().await)
+}
+
+/// Validate, persist and apply new sidecar settings.
+/// Running sidecars pick them up on their next start.
+#[tauri::command]
+pub async fn set_sidecar_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, SidecarManager>,
+    settings: SidecarSettings,
+) -> Result<(), AppError> {
+    settings.validate().map_err(AppError::Validation)?;
+    save_settings(&settings_path(&app)?, &settings)
+        .map_err(|e| AppError::io("Impossible d'enregistrer les parametres sidecar", e))?;
+    state.set_settings(settings).await;
+    Ok(())
+}
@@ -1,10 +1,208 @@
+use super::llm_backend::LlmBackendSettings;
 use super::types::{PipelineConfig, PipelineMode, SidecarName};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::time::Duration;
 
+/// File name of the persisted sidecar settings (in app_data_dir)
+pub const SETTINGS_FILE: &str = "sidecar_settings.json";
+
+pub const DEFAULT_WHISPER_PORT: u16 = 8081;
+pub const DEFAULT_LLAMA_PORT: u16 = 8080;
+pub const DEFAULT_CTX_SIZE: usize = 3072;
+
+/// Smallest ctx-size accepted: the prompt builder reserves 768 tokens for output
+const MIN_CTX_SIZE: usize = 1024;
+
+/// Largest beam accepted for whisper decoding (cost grows linearly with the beam)
+const MAX_WHISPER_BEAM: u32 = 8;
+
+/// Minutes without request before an idle sidecar is stopped (concurrent mode)
+pub const DEFAULT_IDLE_TIMEOUT_MINUTES: u32 = 15;
+
+/// Longest idle timeout accepted (a school day)
+const MAX_IDLE_TIMEOUT_MINUTES: u32 = 12 * 60;
+
+/// User-editable sidecar settings, persisted as JSON in app_data_dir.
+/// Ports are preferred ports: if one is taken, a free port is picked at startup.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SidecarSettings {
+    pub whisper_port: u16,
+    pub llama_port: u16,
+    /// None = number of detected CPU cores
+    pub threads: Option<usize>,
+    pub ctx_size: usize,
+    /// Layers offloaded to the GPU by llama-server (0 = CPU only)
+    pub gpu_layers: u32,
+    /// Whisper decoding temperature (0 = deterministic)
+    pub whisper_temperature: f32,
+    /// Whisper beam search width (0 = greedy decoding, the whisper-server default)
+    pub whisper_beam_size: u32,
+    /// Where the structuration jobs send their prompts (embedded llama-server by default)
+    pub llm_backend: LlmBackendSettings,
+    /// Minutes without request after which a sidecar is stopped in concurrent mode (0 = never)
+    pub idle_timeout_minutes: u32,
+    /// Start the sidecars when the app starts
+    pub warmup_at_startup: bool,
+    /// Local time ("HH:MM") at which the sidecars are started, e.g. before the dictation session
+    pub warmup_time: Option<String>,
+}
+
+impl Default for SidecarSettings {
+    fn default() -> Self {
+        SidecarSettings {
+            whisper_port: DEFAULT_WHISPER_PORT,
+            llama_port: DEFAULT_LLAMA_PORT,
+            threads: None,
+            ctx_size: DEFAULT_CTX_SIZE,
+            gpu_layers: 0,
+            whisper_temperature: 0.0,
+            whisper_beam_size: 0,
+            llm_backend: LlmBackendSettings::Embedded,
+            idle_timeout_minutes: DEFAULT_IDLE_TIMEOUT_MINUTES,
+            warmup_at_startup: false,
+            warmup_time: None,
+        }
+    }
+}
+
+impl SidecarSettings {
+    pub fn port_for(&self, name: SidecarName) -> u16 {
+        match name {
+            SidecarName::Whisper => self.whisper_port,
+            SidecarName::Llama => self.llama_port,
+        }
+    }
+
+    /// Thread count passed to the sidecars (explicit value or detected cores)
+    pub fn effective_threads(&self) -> usize {
+        self.threads.unwrap_or_else(detect_cpu_cores)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.whisper_port == 0 || self.llama_port == 0 {
+            return Err("Les ports des sidecars doivent etre non nuls".to_string());
+        }
+        if self.whisper_port == self.llama_port {
+            return Err("Whisper et Llama doivent utiliser des ports differents".to_string());
+        }
+        if self.threads == Some(0) {
+            return Err("Le nombre de threads doit etre au moins 1".to_string());
+        }
+        if self.ctx_size < MIN_CTX_SIZE {
+            return Err(format!(
+                "La taille de contexte doit etre au moins {} tokens",
+                MIN_CTX_SIZE
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.whisper_temperature) {
+            return Err("La temperature Whisper doit etre comprise entre 0 et 1".to_string());
+        }
+        if self.whisper_beam_size > MAX_WHISPER_BEAM {
+            return Err(format!(
+                "La largeur de faisceau Whisper doit etre au plus {}",
+                MAX_WHISPER_BEAM
+            ));
+        }
+        self.llm_backend.validate()?;
+        if self.idle_timeout_minutes > MAX_IDLE_TIMEOUT_MINUTES {
+            return Err(format!(
+                "Le delai d'inactivite doit etre au plus {} minutes",
+                MAX_IDLE_TIMEOUT_MINUTES
+            ));
+        }
+        if let Some(time) = &self.warmup_time {
+            if parse_clock_time(time).is_none() {
+                return Err(format!("Heure de prechauffage invalide : \"{}\" (format HH:MM)", time));
+            }
+        }
+        Ok(())
+    }
+
+    /// Idle delay after which a sidecar is stopped. Sequential mode stops them
+    /// after each task already (ADR-002), and 0 disables the timeout.
+    pub fn idle_timeout(&self, mode: PipelineMode) -> Option<Duration> {
+        if mode != PipelineMode::Concurrent || self.idle_timeout_minutes == 0 {
+            return None;
+        }
+        Some(Duration::from_secs(u64::from(self.idle_timeout_minutes) * 60))
+    }
+
+    /// Scheduled warm-up in minutes since local midnight
+    pub fn warmup_minute(&self) -> Option<u32> {
+        self.warmup_time.as_deref().and_then(parse_clock_time)
+    }
+}
+
+/// Minutes since midnight of a "HH:MM" time
+pub fn parse_clock_time(text: &str) -> Option<u32> {
+    let (hours, minutes) = text.trim().split_once(':')?;
+    if hours.is_empty() || hours.len() > 2 || minutes.len() != 2 {
+        return None;
+    }
+    let hours: u32 = hours.parse().ok()?;
+    let minutes: u32 = minutes.parse().ok()?;
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// Load settings from disk. Missing or invalid file → defaults (never blocks startup).
+pub fn load_settings(path: &Path) -> SidecarSettings {
+    let settings = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|text| serde_json::from_str::<SidecarSettings>(&text).ok())
+        .unwrap_or_default();
+    match settings.validate() {
+        Ok(()) => settings,
+        Err(e) => {
+            log::warn!("Parametres sidecar invalides ({}), valeurs par defaut utilisees", e);
+            SidecarSettings::default()
+        }
+    }
+}
+
+/// Validate then persist settings as pretty JSON.
+pub fn save_settings(path: &Path, settings: &SidecarSettings) -> Result<(), String> {
+    settings.validate()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Impossible de creer {}: {}", parent.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    std::fs::write(path, json)
+        .map_err(|e| format!("Impossible d'ecrire {}: {}", path.display(), e))
+}
+
+/// Number of logical CPU cores (fallback 4, the former hard-coded value)
+pub fn detect_cpu_cores() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+}
+
+fn port_available(port: u16) -> bool {
+    std::net::TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
+/// Return `preferred` if it can be bound on 127.0.0.1, otherwise a free port chosen by the OS.
+pub fn resolve_port(preferred: u16) -> u16 {
+    if port_available(preferred) {
+        return preferred;
+    }
+    std::net::TcpListener::bind(("127.0.0.1", 0))
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .unwrap_or(preferred)
+}
+
+/// Local URL of a sidecar endpoint, e.g. `local_url(8080, "/health")`
+pub fn local_url(port: u16, path: &str) -> String {
+    format!("http://127.0.0.1:{}{}", port, path)
+}
+
 pub struct SidecarConfig {
-    pub port: u16,
     pub binary_name: &'static str,
-    pub healthcheck_url: String,
+    pub healthcheck_path: &'static str,
     pub healthcheck_timeout: Duration,
     pub healthcheck_interval: Duration,
     pub max_requests: u64,
@@ -14,17 +212,15 @@ impl SidecarConfig {
     pub fn for_sidecar(name: SidecarName) -> Self {
         match name {
             SidecarName::Whisper => SidecarConfig {
-                port: 8081,
                 binary_name: "whisper-server",
-                healthcheck_url: "http://127.0.0.1:8081/".to_string(),
+                healthcheck_path: "/",
                 healthcheck_timeout: Duration::from_secs(30),
                 healthcheck_interval: Duration::from_millis(500),
                 max_requests: 50,
             },
             SidecarName::Llama => SidecarConfig {
-                port: 8080,
                 binary_name: "llama-server",
-                healthcheck_url: "http://127.0.0.1:8080/health".to_string(),
+                healthcheck_path: "/health",
                 healthcheck_timeout: Duration::from_secs(60),
                 healthcheck_interval: Duration::from_millis(500),
                 max_requests: 0, // 0 = no limit
@@ -37,7 +233,10 @@ pub fn build_args(
     name: SidecarName,
     model_path: &str,
     grammar_path: Option<&str>,
+    port: u16,
+    settings: &SidecarSettings,
 ) -> Vec<String> {
+    let threads = settings.effective_threads().to_string();
     match name {
         SidecarName::Whisper => vec![
             "--model".to_string(),
@@ -45,29 +244,34 @@ pub fn build_args(
             "--host".to_string(),
             "127.0.0.1".to_string(),
             "--port".to_string(),
-            "8081".to_string(),
+            port.to_string(),
             "--language".to_string(),
             "fr".to_string(),
             "--threads".to_string(),
-            "4".to_string(),
+            threads,
         ],
         SidecarName::Llama => {
             // Note: grammar is passed per-request in the API body (ADR-007 V2.1),
             // not via --grammar-file at server startup.
             // grammar_path parameter kept for backward compatibility but ignored.
             let _ = grammar_path; // suppress unused warning
-            vec![
+            let mut args = vec![
                 "--model".to_string(),
                 model_path.to_string(),
                 "--host".to_string(),
                 "127.0.0.1".to_string(),
                 "--port".to_string(),
-                "8080".to_string(),
+                port.to_string(),
                 "--ctx-size".to_string(),
-                "3072".to_string(),
+                settings.ctx_size.to_string(),
                 "--threads".to_string(),
-                "4".to_string(),
-            ]
+                threads,
+            ];
+            if settings.gpu_layers > 0 {
+                args.push("--n-gpu-layers".to_string());
+                args.push(settings.gpu_layers.to_string());
+            }
+            args
         }
     }
 }
@@ -147,22 +351,32 @@ mod tests {
     use super::*;
 
     #[test]
-    fn whisper_config_has_correct_port() {
+    fn default_settings_keep_historical_ports() {
+        let settings = SidecarSettings::default();
+        assert_eq!(settings.port_for(SidecarName::Whisper), 8081);
+        assert_eq!(settings.port_for(SidecarName::Llama), 8080);
+        assert_eq!(settings.ctx_size, DEFAULT_CTX_SIZE);
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn whisper_config_has_request_limit() {
         let config = SidecarConfig::for_sidecar(SidecarName::Whisper);
-        assert_eq!(config.port, 8081);
+        assert_eq!(config.healthcheck_path, "/");
         assert_eq!(config.max_requests, 50);
     }
 
     #[test]
-    fn llama_config_has_correct_port() {
+    fn llama_config_has_health_endpoint() {
         let config = SidecarConfig::for_sidecar(SidecarName::Llama);
-        assert_eq!(config.port, 8080);
+        assert_eq!(config.healthcheck_path, "/health");
         assert_eq!(config.max_requests, 0);
+        assert_eq!(local_url(8080, config.healthcheck_path), "http://127.0.0.1:8080/health");
     }
 
     #[test]
     fn whisper_args_contain_language_and_port() {
-        let args = build_args(SidecarName::Whisper, "/path/to/model.gguf", None);
+        let args = build_args(SidecarName::Whisper, "/path/to/model.gguf", None, 8081, &SidecarSettings::default());
         assert!(args.contains(&"fr".to_string()));
         assert!(args.contains(&"8081".to_string()));
     }
@@ -174,16 +388,114 @@ mod tests {
             SidecarName::Llama,
             "/path/to/model.gguf",
             Some("/path/to/grammar.gbnf"),
+            8080,
+            &SidecarSettings::default(),
         );
         assert!(!args.contains(&"--grammar-file".to_string()));
         assert!(args.contains(&"3072".to_string())); // ctx-size for multi-domain output
     }
 
     #[test]
-    fn llama_args_ctx_size_2048() {
-        let args = build_args(SidecarName::Llama, "/path/to/model.gguf", None);
+    fn llama_args_ctx_size_from_settings() {
+        let settings = SidecarSettings { ctx_size: 4096, ..Default::default() };
+        let args = build_args(SidecarName::Llama, "/path/to/model.gguf", None, 8080, &settings);
         let ctx_idx = args.iter().position(|a| a == "--ctx-size").unwrap();
-        assert_eq!(args[ctx_idx + 1], "3072");
+        assert_eq!(args[ctx_idx + 1], "4096");
+        assert!(!args.contains(&"--n-gpu-layers".to_string()));
+    }
+
+    #[test]
+    fn llama_args_threads_port_and_gpu_layers() {
+        let settings = SidecarSettings { threads: Some(2), gpu_layers: 20, ..Default::default() };
+        let args = build_args(SidecarName::Llama, "/path/to/model.gguf", None, 9090, &settings);
+        let threads_idx = args.iter().position(|a| a == "--threads").unwrap();
+        assert_eq!(args[threads_idx + 1], "2");
+        let port_idx = args.iter().position(|a| a == "--port").unwrap();
+        assert_eq!(args[port_idx + 1], "9090");
+        let ngl_idx = args.iter().position(|a| a == "--n-gpu-layers").unwrap();
+        assert_eq!(args[ngl_idx + 1], "20");
+    }
+
+    #[test]
+    fn threads_default_to_detected_cores() {
+        let settings = SidecarSettings::default();
+        assert_eq!(settings.effective_threads(), detect_cpu_cores());
+        assert!(settings.effective_threads() >= 1);
+    }
+
+    #[test]
+    fn settings_validation_rejects_invalid_values() {
+        let same_ports = SidecarSettings { whisper_port: 9000, llama_port: 9000, ..Default::default() };
+        assert!(same_ports.validate().is_err());
+        let zero_threads = SidecarSettings { threads: Some(0), ..Default::default() };
+        assert!(zero_threads.validate().is_err());
+        let tiny_ctx = SidecarSettings { ctx_size: 512, ..Default::default() };
+        assert!(tiny_ctx.validate().is_err());
+        let hot = SidecarSettings { whisper_temperature: 1.5, ..Default::default() };
+        assert!(hot.validate().is_err());
+        let wide_beam = SidecarSettings { whisper_beam_size: 16, ..Default::default() };
+        assert!(wide_beam.validate().is_err());
+        let bad_llm = SidecarSettings {
+            llm_backend: LlmBackendSettings::Ollama { base_url: "ftp://ia".into(), model: "qwen".into() },
+            ..Default::default()
+        };
+        assert!(bad_llm.validate().is_err());
+        let endless = SidecarSettings { idle_timeout_minutes: 24 * 60, ..Default::default() };
+        assert!(endless.validate().is_err());
+        let bad_time = SidecarSettings { warmup_time: Some("25:00".into()), ..Default::default() };
+        assert!(bad_time.validate().is_err());
+    }
+
+    #[test]
+    fn clock_time_is_parsed_to_minutes() {
+        assert_eq!(parse_clock_time("16:30"), Some(16 * 60 + 30));
+        assert_eq!(parse_clock_time(" 8:05 "), Some(8 * 60 + 5));
+        assert_eq!(parse_clock_time("00:00"), Some(0));
+        for invalid in ["24:00", "12:60", "12h30", "12:3", ":30", "-1:30", ""] {
+            assert_eq!(parse_clock_time(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn idle_timeout_applies_to_concurrent_mode_only() {
+        let settings = SidecarSettings::default();
+        assert_eq!(settings.idle_timeout(PipelineMode::Concurrent), Some(Duration::from_secs(15 * 60)));
+        assert_eq!(settings.idle_timeout(PipelineMode::Sequential), None);
+        let never = SidecarSettings { idle_timeout_minutes: 0, ..Default::default() };
+        assert_eq!(never.idle_timeout(PipelineMode::Concurrent), None);
+    }
+
+    #[test]
+    fn settings_roundtrip_and_fallback_to_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SETTINGS_FILE);
+
+        // Missing file → defaults
+        assert_eq!(load_settings(&path), SidecarSettings::default());
+
+        let settings = SidecarSettings { llama_port: 18080, threads: Some(3), gpu_layers: 8, ..Default::default() };
+        save_settings(&path, &settings).unwrap();
+        assert_eq!(load_settings(&path), settings);
+
+        // Partial JSON → missing fields take defaults
+        std::fs::write(&path, r#"{"ctx_size": 4096}"#).unwrap();
+        let partial = load_settings(&path);
+        assert_eq!(partial.ctx_size, 4096);
+        assert_eq!(partial.llama_port, DEFAULT_LLAMA_PORT);
+
+        // Corrupted JSON → defaults
+        std::fs::write(&path, "not json").unwrap();
+        assert_eq!(load_settings(&path), SidecarSettings::default());
+    }
+
+    #[test]
+    fn resolve_port_falls_back_when_taken() {
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let busy = listener.local_addr().unwrap().port();
+        let resolved = resolve_port(busy);
+        assert_ne!(resolved, busy, "Un port occupe doit etre remplace");
+        assert_ne!(resolved, 0);
+        drop(listener);
     }
 
     #[test]
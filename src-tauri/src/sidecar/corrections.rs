@@ -0,0 +1,549 @@
+/// Post-transcription correction dictionary.
+///
+/// The same whisper errors recur ("Sarah" for "Sahra"): each entry replaces a
+/// pattern by its correction, whole-word and case-insensitive, optionally only for
+/// one student (corrections_transcription, M019). Applied to every
+/// TranscriptionResult before it leaves Rust; the result lists the substitutions,
+/// which are also recorded per transcription (corrections_appliquees, M021).
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+use super::types::{AppliedCorrection, TranscriptionResult};
+use crate::error::AppError;
+
+/// Longest pattern or replacement accepted
+const MAX_ENTRY_CHARS: usize = 80;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct CorrectionEntry {
+    pub id: i64,
+    pub motif: String,
+    pub remplacement: String,
+    /// None = applies to every student
+    pub eleve_id: Option<i64>,
+    pub eleve_prenom: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewCorrection {
+    pub motif: String,
+    pub remplacement: String,
+    #[serde(default)]
+    pub eleve_id: Option<i64>,
+}
+
+/// Letters, digits and hyphens form words: "Sarah" does not match inside "Sarah-Lou"
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-'
+}
+
+fn eq_ignore_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// End of the whole-word match of `motif` starting at `start`, if any
+fn match_at(chars: &[char], start: usize, motif: &[char]) -> Option<usize> {
+    let end = start + motif.len();
+    if end > chars.len() {
+        return None;
+    }
+    let same = chars[start..end]
+        .iter()
+        .zip(motif)
+        .all(|(a, b)| eq_ignore_case(*a, *b));
+    if !same || (end < chars.len() && is_word_char(chars[end])) {
+        return None;
+    }
+    Some(end)
+}
+
+/// Keep a sentence-initial capital: "imparfé" → "imparfait" also fixes "Imparfé".
+fn push_replacement(out: &mut String, original_first: char, replacement: &str) {
+    let mut rest = replacement.chars();
+    match rest.next() {
+        Some(first) if original_first.is_uppercase() && first.is_lowercase() => {
+            out.extend(first.to_uppercase());
+            out.push_str(rest.as_str());
+        }
+        _ => out.push_str(replacement),
+    }
+}
+
+/// Apply the entries relevant to `eleve_id` in a single left-to-right pass
+/// (a replacement is never re-matched). At each word start, student-scoped entries
+/// are tried first, then longer patterns before shorter ones.
+pub fn apply_corrections(
+    text: &str,
+    entries: &[CorrectionEntry],
+    eleve_id: Option<i64>,
+) -> (String, Vec<AppliedCorrection>) {
+    let mut candidates: Vec<(&CorrectionEntry, Vec<char>)> = entries
+        .iter()
+        .filter(|e| e.eleve_id.is_none() || e.eleve_id == eleve_id)
+        .map(|e| (e, e.motif.trim().chars().collect::<Vec<char>>()))
+        .filter(|(_, motif)| !motif.is_empty())
+        .collect();
+    candidates.sort_by_key(|(e, motif)| (e.eleve_id.is_none(), Reverse(motif.len())));
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut counts = vec![0u32; candidates.len()];
+    let mut i = 0;
+
+    while i < chars.len() {
+        if i == 0 || !is_word_char(chars[i - 1]) {
+            let found = candidates
+                .iter()
+                .enumerate()
+                .find_map(|(k, (_, motif))| match_at(&chars, i, motif).map(|end| (k, end)));
+            if let Some((k, end)) = found {
+                push_replacement(&mut out, chars[i], &candidates[k].0.remplacement);
+                counts[k] += 1;
+                i = end;
+                continue;
+            }
+        }
+        out.push(chars[i]);
+        i += 1;
+    }
+
+    let applied = candidates
+        .iter()
+        .zip(counts)
+        .filter(|(_, n)| *n > 0)
+        .map(|((entry, _), occurrences)| AppliedCorrection {
+            correction_id: Some(entry.id),
+            motif: entry.motif.clone(),
+            remplacement: entry.remplacement.clone(),
+            occurrences,
+        })
+        .collect();
+    (out, applied)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Impls (testables, prennent une connexion)
+// ─────────────────────────────────────────────────────────────────────────────
+
+const SELECT_ENTRIES: &str = "SELECT c.id, c.motif, c.remplacement, c.eleve_id, s.first_name AS eleve_prenom
+     FROM corrections_transcription c
+     LEFT JOIN students s ON s.id = c.eleve_id";
+
+/// All entries, global ones first
+pub async fn list_corrections_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+) -> Result<Vec<CorrectionEntry>, AppError> {
+    sqlx::query_as::<_, CorrectionEntry>(&format!(
+        "{} ORDER BY c.eleve_id IS NOT NULL, s.first_name, c.motif COLLATE NOCASE",
+        SELECT_ENTRIES
+    ))
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur lecture des corrections", e))
+}
+
+/// Entries applying to a transcription: global ones plus the student's own
+pub async fn load_corrections_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    eleve_id: Option<i64>,
+) -> Result<Vec<CorrectionEntry>, AppError> {
+    sqlx::query_as::<_, CorrectionEntry>(&format!(
+        "{} WHERE c.eleve_id IS NULL OR c.eleve_id = ?",
+        SELECT_ENTRIES
+    ))
+    .bind(eleve_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur lecture des corrections", e))
+}
+
+pub async fn add_correction_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    correction: &NewCorrection,
+) -> Result<CorrectionEntry, AppError> {
+    let motif = correction.motif.trim();
+    let remplacement = correction.remplacement.trim();
+    if motif.is_empty() || remplacement.is_empty() {
+        return Err(AppError::validation("Le motif et le remplacement sont obligatoires"));
+    }
+    if motif.chars().count() > MAX_ENTRY_CHARS || remplacement.chars().count() > MAX_ENTRY_CHARS {
+        return Err(AppError::validation(format!(
+            "Le motif et le remplacement ne doivent pas dépasser {} caractères",
+            MAX_ENTRY_CHARS
+        )));
+    }
+    if motif == remplacement {
+        return Err(AppError::validation("Le remplacement est identique au motif"));
+    }
+
+    if let Some(eleve_id) = correction.eleve_id {
+        sqlx::query_scalar::<_, i64>("SELECT id FROM students WHERE id = ?")
+            .bind(eleve_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| AppError::db("Erreur lecture élève", e))?
+            .ok_or_else(|| AppError::not_found("Élève introuvable"))?;
+    }
+
+    let exists: Option<i64> = sqlx::query_scalar(
+        "SELECT id FROM corrections_transcription
+         WHERE motif = ? COLLATE NOCASE AND eleve_id IS ?",
+    )
+    .bind(motif)
+    .bind(correction.eleve_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur lecture des corrections", e))?;
+    if exists.is_some() {
+        return Err(AppError::validation("Une correction existe déjà pour ce motif"));
+    }
+
+    let id = sqlx::query(
+        "INSERT INTO corrections_transcription (motif, remplacement, eleve_id) VALUES (?, ?, ?)",
+    )
+    .bind(motif)
+    .bind(remplacement)
+    .bind(correction.eleve_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur ajout de la correction", e))?
+    .last_insert_rowid();
+
+    sqlx::query_as::<_, CorrectionEntry>(&format!("{} WHERE c.id = ?", SELECT_ENTRIES))
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| AppError::db("Erreur lecture de la correction", e))
+}
+
+/// Record the substitutions applied to one transcription
+pub async fn record_applied_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    transcription_uuid: &str,
+    eleve_id: Option<i64>,
+    applied: &[AppliedCorrection],
+) -> Result<(), AppError> {
+    for c in applied {
+        sqlx::query(
+            "INSERT INTO corrections_appliquees
+             (transcription_uuid, eleve_id, correction_id, motif, remplacement, occurrences)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(transcription_uuid)
+        .bind(eleve_id)
+        .bind(c.correction_id)
+        .bind(&c.motif)
+        .bind(&c.remplacement)
+        .bind(c.occurrences)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::db("Erreur enregistrement des corrections appliquées", e))?;
+    }
+    Ok(())
+}
+
+/// Substitutions recorded for a transcription, in application order
+pub async fn load_applied_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    transcription_uuid: &str,
+) -> Result<Vec<AppliedCorrection>, AppError> {
+    let rows: Vec<(Option<i64>, String, String, u32)> = sqlx::query_as(
+        "SELECT correction_id, motif, remplacement, occurrences
+         FROM corrections_appliquees WHERE transcription_uuid = ? ORDER BY id",
+    )
+    .bind(transcription_uuid)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur lecture des corrections appliquées", e))?;
+    Ok(rows
+        .into_iter()
+        .map(|(correction_id, motif, remplacement, occurrences)| AppliedCorrection {
+            correction_id,
+            motif,
+            remplacement,
+            occurrences,
+        })
+        .collect())
+}
+
+pub async fn delete_correction_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    id: i64,
+) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM corrections_transcription WHERE id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::db("Erreur suppression de la correction", e))?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Correction introuvable"));
+    }
+    Ok(())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Helpers AppHandle
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Apply the dictionary to a transcription and record the substitutions. A DB failure
+/// returns the text unchanged (or unrecorded): corrections must never make a dictation fail.
+pub async fn correct_transcription(
+    app: &tauri::AppHandle,
+    mut result: TranscriptionResult,
+    eleve_id: Option<i64>,
+) -> TranscriptionResult {
+    let mut conn = match crate::db::acquire(app).await {
+        Ok(conn) => conn,
+        Err(e) => {
+            log::warn!("Dictionnaire de corrections indisponible : {}", e);
+            return result;
+        }
+    };
+    let entries = match load_corrections_impl(&mut conn, eleve_id).await {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::warn!("Dictionnaire de corrections indisponible : {}", e);
+            return result;
+        }
+    };
+
+    let (text, applied) = apply_corrections(&result.text, &entries, eleve_id);
+    for c in &applied {
+        log::info!("Correction appliquee: \"{}\" -> \"{}\" (x{})", c.motif, c.remplacement, c.occurrences);
+    }
+    if !applied.is_empty() {
+        let transcription_id = uuid::Uuid::new_v4().to_string();
+        match record_applied_impl(&mut conn, &transcription_id, eleve_id, &applied).await {
+            Ok(()) => result.transcription_id = Some(transcription_id),
+            Err(e) => log::warn!("Corrections appliquees non enregistrees : {}", e),
+        }
+    }
+    result.text = text;
+    result.corrections = applied;
+    result
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Commandes Tauri
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_transcription_corrections(
+    app: tauri::AppHandle,
+) -> Result<Vec<CorrectionEntry>, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
+    list_corrections_impl(&mut conn).await
+}
+
+#[tauri::command]
+pub async fn add_transcription_correction(
+    app: tauri::AppHandle,
+    correction: NewCorrection,
+) -> Result<CorrectionEntry, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
+    add_correction_impl(&mut conn, &correction).await
+}
+
+/// Substitutions applied to a past transcription (`TranscriptionResult.transcription_id`)
+#[tauri::command]
+pub async fn get_applied_corrections(
+    app: tauri::AppHandle,
+    transcription_id: String,
+) -> Result<Vec<AppliedCorrection>, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
+    load_applied_impl(&mut conn, &transcription_id).await
+}
+
+#[tauri::command]
+pub async fn delete_transcription_correction(
+    app: tauri::AppHandle,
+    id: i64,
+) -> Result<(), AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
+    delete_correction_impl(&mut conn, id).await
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::Connection;
+
+    fn entry(id: i64, motif: &str, remplacement: &str, eleve_id: Option<i64>) -> CorrectionEntry {
+        CorrectionEntry {
+            id,
+            motif: motif.to_string(),
+            remplacement: remplacement.to_string(),
+            eleve_id,
+            eleve_prenom: None,
+        }
+    }
+
+    async fn setup_test_db() -> (sqlx::sqlite::SqliteConnection, tempfile::NamedTempFile) {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let url = format!("sqlite:{}", tmp.path().display());
+        let mut conn = sqlx::sqlite::SqliteConnection::connect(&url)
+            .await
+            .expect("Impossible de creer la DB de test");
+        for sql in [
+            "CREATE TABLE students (id INTEGER PRIMARY KEY, first_name TEXT NOT NULL)",
+            "CREATE TABLE corrections_transcription (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                motif TEXT NOT NULL,
+                remplacement TEXT NOT NULL,
+                eleve_id INTEGER DEFAULT NULL REFERENCES students(id) ON DELETE CASCADE,
+                created_at TEXT DEFAULT (datetime('now'))
+            )",
+            "CREATE TABLE corrections_appliquees (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                transcription_uuid TEXT NOT NULL,
+                eleve_id INTEGER DEFAULT NULL REFERENCES students(id) ON DELETE CASCADE,
+                correction_id INTEGER DEFAULT NULL REFERENCES corrections_transcription(id) ON DELETE SET NULL,
+                motif TEXT NOT NULL,
+                remplacement TEXT NOT NULL,
+                occurrences INTEGER NOT NULL,
+                created_at TEXT DEFAULT (datetime('now'))
+            )",
+            "INSERT INTO students (id, first_name) VALUES (1, 'Sahra'), (2, 'Tom')",
+        ] {
+            sqlx::query(sql).execute(&mut conn).await.unwrap();
+        }
+        (conn, tmp)
+    }
+
+    #[test]
+    fn replaces_whole_words_case_insensitively() {
+        let entries = [entry(1, "sarah", "Sahra", None)];
+        let (text, applied) =
+            apply_corrections("SARAH a aidé Sarah-Lou, puis sarah est partie.", &entries, None);
+        assert_eq!(text, "Sahra a aidé Sarah-Lou, puis Sahra est partie.");
+        assert_eq!(applied.len(), 1);
+        assert_eq!(applied[0].occurrences, 2);
+
+        let (text, applied) = apply_corrections("Sarahs", &entries, None);
+        assert_eq!(text, "Sarahs");
+        assert!(applied.is_empty());
+    }
+
+    #[test]
+    fn keeps_capital_and_handles_multi_word_patterns() {
+        let entries = [
+            entry(1, "imparfé", "imparfait", None),
+            entry(2, "fraction des cimales", "fractions décimales", None),
+        ];
+        let (text, _) = apply_corrections(
+            "Imparfé maîtrisé ; revoir les fraction des cimales.",
+            &entries,
+            None,
+        );
+        assert_eq!(text, "Imparfait maîtrisé ; revoir les fractions décimales.");
+    }
+
+    #[test]
+    fn student_scope_wins_and_replacements_do_not_chain() {
+        let entries = [
+            entry(1, "Léo", "Léon", None),
+            entry(2, "Léo", "Lého", Some(7)),
+            entry(3, "Léon", "Noël", None),
+        ];
+        let (text, applied) = apply_corrections("Léo lit.", &entries, Some(7));
+        assert_eq!(text, "Lého lit.");
+        assert_eq!(applied[0].correction_id, Some(2));
+
+        // Other student: global entry only, and "Léon" is not rewritten again
+        let (text, applied) = apply_corrections("Léo lit.", &entries, Some(8));
+        assert_eq!(text, "Léon lit.");
+        assert_eq!(applied.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_add_load_and_delete_corrections() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let global = add_correction_impl(
+            &mut conn,
+            &NewCorrection { motif: " EMCE ".into(), remplacement: "EMC".into(), eleve_id: None },
+        )
+        .await
+        .unwrap();
+        assert_eq!(global.motif, "EMCE");
+        let scoped = add_correction_impl(
+            &mut conn,
+            &NewCorrection { motif: "Sarah".into(), remplacement: "Sahra".into(), eleve_id: Some(1) },
+        )
+        .await
+        .unwrap();
+        assert_eq!(scoped.eleve_prenom.as_deref(), Some("Sahra"));
+
+        assert_eq!(load_corrections_impl(&mut conn, Some(1)).await.unwrap().len(), 2);
+        assert_eq!(load_corrections_impl(&mut conn, Some(2)).await.unwrap().len(), 1);
+        assert_eq!(load_corrections_impl(&mut conn, None).await.unwrap().len(), 1);
+        assert_eq!(list_corrections_impl(&mut conn).await.unwrap()[0].id, global.id);
+
+        delete_correction_impl(&mut conn, scoped.id).await.unwrap();
+        assert!(matches!(
+            delete_correction_impl(&mut conn, scoped.id).await,
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_applied_corrections_are_recorded_per_transcription() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let scoped = add_correction_impl(
+            &mut conn,
+            &NewCorrection { motif: "Sarah".into(), remplacement: "Sahra".into(), eleve_id: Some(1) },
+        )
+        .await
+        .unwrap();
+        let entries = load_corrections_impl(&mut conn, Some(1)).await.unwrap();
+        let (_, applied) = apply_corrections("Sarah lit, Sarah écrit.", &entries, Some(1));
+
+        record_applied_impl(&mut conn, "t-1", Some(1), &applied).await.unwrap();
+        assert_eq!(load_applied_impl(&mut conn, "t-1").await.unwrap(), applied);
+        assert!(load_applied_impl(&mut conn, "t-2").await.unwrap().is_empty());
+
+        // The record outlives the dictionary entry
+        sqlx::query("PRAGMA foreign_keys = ON").execute(&mut conn).await.unwrap();
+        delete_correction_impl(&mut conn, scoped.id).await.unwrap();
+        let kept = load_applied_impl(&mut conn, "t-1").await.unwrap();
+        assert_eq!(kept[0].correction_id, None);
+        assert_eq!((kept[0].motif.as_str(), kept[0].occurrences), ("Sarah", 2));
+    }
+
+    #[tokio::test]
+    async fn test_add_correction_validation() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let new = |motif: &str, remplacement: &str, eleve_id| NewCorrection {
+            motif: motif.into(),
+            remplacement: remplacement.into(),
+            eleve_id,
+        };
+        add_correction_impl(&mut conn, &new("Sarah", "Sahra", None)).await.unwrap();
+
+        let dup = add_correction_impl(&mut conn, &new("sarah", "Sara", None)).await;
+        assert!(matches!(dup, Err(AppError::Validation(_))));
+        // Same pattern scoped to a student is a distinct entry
+        add_correction_impl(&mut conn, &new("sarah", "Sara", Some(2))).await.unwrap();
+
+        assert!(matches!(
+            add_correction_impl(&mut conn, &new(" ", "x", None)).await,
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            add_correction_impl(&mut conn, &new("Tom", "Tom", None)).await,
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            add_correction_impl(&mut conn, &new("Tom", "Thom", Some(99))).await,
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
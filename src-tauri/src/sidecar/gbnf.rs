@@ -71,6 +71,74 @@ escape ::= "\"" | "\\" | "/" | "n" | "r" | "t"
     .to_string()
 }
 
+/// Generate a static GBNF grammar for a partial summary (map-reduce synthese, first pass).
+/// Grammar constrains LLM output to: {"resume": "texte"}
+pub fn generate_resume_gbnf() -> String {
+    r#"root ::= "{" ws "\"resume\":" ws string ws "}"
+ws ::= [ \t\n]*
+string ::= "\"" chars "\""
+chars ::= char+
+char ::= [^"\\] | "\\" escape
+escape ::= "\"" | "\\" | "/" | "n" | "r" | "t"
+"#
+    .to_string()
+}
+
+/// JSON-schema equivalent of `generate_gbnf`, for backends without GBNF support.
+///
+/// OpenAI strict mode requires an object at the root, so the array is wrapped:
+/// ```json
+/// {"resultats": [{"domaine_id": N, "observation_mise_a_jour": "texte"}, ...]}
+/// ```
+pub fn classification_json_schema(domains: &[DomainInfo]) -> serde_json::Value {
+    assert!(!domains.is_empty(), "Au moins un domaine requis pour generer le schema JSON");
+
+    let domain_ids: Vec<usize> = (0..domains.len()).collect();
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "resultats": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "domaine_id": { "type": "integer", "enum": domain_ids },
+                        "observation_mise_a_jour": { "type": "string" }
+                    },
+                    "required": ["domaine_id", "observation_mise_a_jour"],
+                    "additionalProperties": false
+                }
+            }
+        },
+        "required": ["resultats"],
+        "additionalProperties": false
+    })
+}
+
+/// JSON-schema equivalent of `generate_synthese_gbnf`: {"synthese": "texte"}
+pub fn synthese_json_schema() -> serde_json::Value {
+    single_string_schema("synthese")
+}
+
+/// JSON-schema equivalent of `generate_appreciation_gbnf`: {"appreciation": "texte"}
+pub fn appreciation_json_schema() -> serde_json::Value {
+    single_string_schema("appreciation")
+}
+
+/// JSON-schema equivalent of `generate_resume_gbnf`: {"resume": "texte"}
+pub fn resume_json_schema() -> serde_json::Value {
+    single_string_schema("resume")
+}
+
+fn single_string_schema(key: &str) -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": { key: { "type": "string" } },
+        "required": [key],
+        "additionalProperties": false
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -169,4 +237,25 @@ mod tests {
         assert!(grammar.contains("chars ::= char+"));
         assert!(!grammar.contains("synthese")); // correct key, not the other
     }
+
+    #[test]
+    fn json_schemas_match_the_grammars() {
+        let domains = vec![
+            DomainInfo { id: 10, nom: "Francais".to_string() },
+            DomainInfo { id: 20, nom: "Mathematiques".to_string() },
+        ];
+        let schema = classification_json_schema(&domains);
+        let item = &schema["properties"]["resultats"]["items"];
+        assert_eq!(item["properties"]["domaine_id"]["enum"], serde_json::json!([0, 1]));
+        assert_eq!(item["required"], serde_json::json!(["domaine_id", "observation_mise_a_jour"]));
+        assert_eq!(schema["additionalProperties"], false);
+
+        assert_eq!(synthese_json_schema()["required"], serde_json::json!(["synthese"]));
+        assert_eq!(resume_json_schema()["required"], serde_json::json!(["resume"]));
+        assert_eq!(
+            generate_resume_gbnf().replace("resume", "synthese"),
+            generate_synthese_gbnf()
+        );
+        assert_eq!(appreciation_json_schema()["required"], serde_json::json!(["appreciation"]));
+    }
 }
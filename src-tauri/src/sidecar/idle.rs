@@ -0,0 +1,174 @@
+/// Idle timeout and warm-up of the sidecars.
+///
+/// In sequential mode each task stops its sidecar (ADR-002). In concurrent mode
+/// they used to stay loaded after first use, holding ~1.5 GB all day: a monitor,
+/// spawned at startup, now stops a sidecar after `idle_timeout_minutes` without
+/// request, unless a dictation or an LLM job is using it.
+///
+/// The warm-up starts them ahead of use so the first request does not wait for
+/// the model to load: at startup (`warmup_at_startup`) and/or at a local time
+/// (`warmup_time`, e.g. before the end-of-day dictation session). In sequential
+/// mode only whisper-server is warmed up, dictation comes first.
+///
+/// The crate has no time zone dependency: the local time is read from SQLite
+/// (`'localtime'` modifier, based on the OS time zone).
+
+use log::{debug, info, warn};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use super::llm_backend::connect_backend;
+use super::llm_queue::LlmJobQueue;
+use super::manager::SidecarManager;
+use super::streaming::TranscriptionStreams;
+use super::transcription::{ensure_whisper_running, resolve_model_path};
+use super::types::{SidecarError, SidecarName};
+use crate::error::AppError;
+
+/// Delay between two checks of the monitor
+const MONITOR_INTERVAL: Duration = Duration::from_secs(30);
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// Sidecars started by a warm-up. In sequential mode the second one would stop the first.
+pub fn sidecars_to_warm(sequential: bool) -> &'static [SidecarName] {
+    if sequential {
+        &[SidecarName::Whisper]
+    } else {
+        &[SidecarName::Whisper, SidecarName::Llama]
+    }
+}
+
+/// The scheduled warm-up minute was reached between two checks
+/// (`previous` excluded, `now` included; the day may have changed in between)
+pub fn warmup_due(previous: u32, now: u32, target: u32) -> bool {
+    let elapsed = (now + MINUTES_PER_DAY - previous) % MINUTES_PER_DAY;
+    let until_target = (target + MINUTES_PER_DAY - previous) % MINUTES_PER_DAY;
+    until_target > 0 && until_target <= elapsed
+}
+
+/// Minutes since local midnight
+pub async fn local_minutes_impl(conn: &mut sqlx::sqlite::SqliteConnection) -> Result<u32, AppError> {
+    let minutes: i64 = sqlx::query_scalar(
+        "SELECT CAST(strftime('%H', 'now', 'localtime') AS INTEGER) * 60
+              + CAST(strftime('%M', 'now', 'localtime') AS INTEGER)",
+    )
+    .fetch_one(conn)
+    .await?;
+    u32::try_from(minutes).map_err(|_| AppError::Internal(format!("Heure locale invalide : {}", minutes)))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Helpers AppHandle
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Start the sidecars with their active model (already running ones are kept)
+pub async fn warm_up(app: &AppHandle) {
+    let manager = app.state::<SidecarManager>();
+    for &name in sidecars_to_warm(manager.is_sequential().await) {
+        info!("Prechauffage de {}", name);
+        let result = match name {
+            SidecarName::Whisper => warm_up_whisper(app, &manager).await,
+            // An external LLM backend has nothing to start
+            SidecarName::Llama => connect_backend(app, &manager).await.map(drop),
+        };
+        if let Err(e) = result {
+            warn!("Prechauffage de {} impossible : {}", name, e);
+        }
+    }
+}
+
+async fn warm_up_whisper(app: &AppHandle, manager: &SidecarManager) -> Result<(), SidecarError> {
+    let model_path = resolve_model_path(app).await?;
+    ensure_whisper_running(app, manager, &model_path.to_string_lossy()).await
+}
+
+/// Sidecars serving an open dictation or a queued LLM job
+async fn busy_sidecars(app: &AppHandle) -> Vec<SidecarName> {
+    let mut busy = Vec::new();
+    if app.state::<TranscriptionStreams>().is_active().await {
+        busy.push(SidecarName::Whisper);
+    }
+    if app.state::<LlmJobQueue>().is_busy() {
+        busy.push(SidecarName::Llama);
+    }
+    busy
+}
+
+async fn local_minutes(app: &AppHandle) -> Option<u32> {
+    let result = match crate::db::acquire(app).await {
+        Ok(mut conn) => local_minutes_impl(&mut conn).await,
+        Err(e) => Err(e),
+    };
+    result
+        .map_err(|e| debug!("Heure locale indisponible : {}", e))
+        .ok()
+}
+
+/// Start the idle monitor: startup warm-up, then idle stops and scheduled warm-ups (app setup)
+pub fn spawn_monitor(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let manager = app.state::<SidecarManager>();
+        if manager.get_settings().await.warmup_at_startup {
+            warm_up(&app).await;
+        }
+
+        let mut previous = local_minutes(&app).await;
+        loop {
+            tokio::time::sleep(MONITOR_INTERVAL).await;
+            manager.stop_idle(&app, &busy_sidecars(&app).await).await;
+
+            let Some(target) = manager.get_settings().await.warmup_minute() else {
+                continue;
+            };
+            let now = local_minutes(&app).await;
+            if let (Some(previous), Some(now)) = (previous, now) {
+                if warmup_due(previous, now, target) {
+                    warm_up(&app).await;
+                }
+            }
+            previous = now.or(previous);
+        }
+    });
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::Connection;
+
+    #[test]
+    fn warmup_is_due_once_when_its_minute_is_reached() {
+        let target = 16 * 60 + 30;
+        assert!(warmup_due(target - 1, target, target));
+        assert!(warmup_due(target - 1, target + 3, target), "Verification en retard (veille)");
+        assert!(!warmup_due(target, target, target));
+        assert!(!warmup_due(target, target + 1, target), "Deja fait a la verification precedente");
+        assert!(!warmup_due(target - 5, target - 1, target));
+    }
+
+    #[test]
+    fn warmup_is_due_across_midnight() {
+        assert!(warmup_due(23 * 60 + 59, 0, 0));
+        assert!(warmup_due(23 * 60 + 58, 5, 2));
+        assert!(!warmup_due(23 * 60 + 58, 5, 23 * 60));
+    }
+
+    #[test]
+    fn sequential_mode_warms_up_whisper_only() {
+        assert_eq!(sidecars_to_warm(true), &[SidecarName::Whisper]);
+        assert_eq!(sidecars_to_warm(false), &[SidecarName::Whisper, SidecarName::Llama]);
+    }
+
+    #[tokio::test]
+    async fn local_minutes_are_read_from_sqlite() {
+        let mut conn = sqlx::sqlite::SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        let minutes = local_minutes_impl(&mut conn).await.unwrap();
+        assert!(minutes < MINUTES_PER_DAY);
+    }
+}
@@ -0,0 +1,827 @@
+/// LLM backends for the structuration jobs (classification, synthese, appreciation).
+///
+/// The jobs build a prompt and an output constraint, then hand them to an
+/// `LlmBackend`. Three backends exist:
+/// - the embedded llama-server sidecar (default), constrained by a GBNF grammar;
+/// - an external OpenAI-compatible endpoint (vLLM, LM Studio, a shared llama.cpp box),
+///   constrained by a JSON schema (`response_format`) or a GBNF grammar when the
+///   server accepts llama.cpp's `grammar` field;
+/// - an Ollama server (`/api/chat`), constrained by a JSON schema (`format`).
+///
+/// The backend is selected in SidecarSettings (`llm_backend`). Only the embedded
+/// backend starts and stops a sidecar.
+///
+/// Backends are also the prompt builders' `TokenCounter`: llama.cpp servers count
+/// with the model's tokenizer (`/tokenize`), the others fall back to the estimate.
+///
+/// `complete_stream` asks `/v1/chat/completions` servers for server-sent events and
+/// reports each content delta as it arrives; the full content is returned at the end
+/// and parsed like a non-streamed answer. Ollama answers in one piece.
+
+use futures::future::{BoxFuture, FutureExt};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::prompt_builder::TokenCounter;
+use super::types::SidecarError;
+
+/// Model name sent to the embedded llama-server (it serves a single model and ignores it)
+pub const EMBEDDED_MODEL_NAME: &str = "qwen2.5-coder";
+
+/// `/tokenize` is called several times per prompt build: fail fast to the estimate
+const TOKENIZE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Backend selection, persisted in SidecarSettings.
+/// Debug is hand-written: the settings are logged and must not leak the API key.
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LlmBackendSettings {
+    /// llama-server started on demand with the active model of the catalog
+    #[default]
+    Embedded,
+    /// Any server exposing `/v1/chat/completions`
+    OpenaiCompatible {
+        base_url: String,
+        model: String,
+        /// Sent as `Authorization: Bearer …` when set
+        #[serde(default)]
+        api_key: Option<String>,
+        /// The server accepts llama.cpp's `grammar` field (preferred when true)
+        #[serde(default)]
+        gbnf_grammar: bool,
+        /// The server accepts `response_format: {type: "json_schema"}`
+        #[serde(default = "default_true")]
+        json_schema: bool,
+    },
+    /// Ollama server (`/api/chat`)
+    Ollama { base_url: String, model: String },
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Output constraints a backend can enforce
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BackendCapabilities {
+    pub gbnf_grammar: bool,
+    pub json_schema: bool,
+}
+
+/// How the output of one request is constrained
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintMode {
+    Gbnf,
+    JsonSchema,
+    /// Unconstrained: the prompt alone asks for JSON
+    None,
+}
+
+/// Expected output shape, in both forms: each backend sends the one it supports
+#[derive(Debug, Clone)]
+pub struct OutputConstraint {
+    /// Schema name (OpenAI `json_schema.name`)
+    pub name: &'static str,
+    pub gbnf: String,
+    pub json_schema: serde_json::Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct LlmRequest {
+    pub system_prompt: String,
+    pub user_prompt: String,
+    pub constraint: OutputConstraint,
+    pub temperature: f32,
+    /// Sampling seed; `None` lets the server pick one
+    pub seed: Option<u64>,
+    pub max_tokens: u32,
+    pub timeout: Duration,
+}
+
+/// A chat endpoint returning the raw content of the assistant message
+pub trait LlmBackend: TokenCounter + Send + Sync {
+    /// Label used in logs and error messages
+    fn name(&self) -> &'static str;
+
+    fn capabilities(&self) -> BackendCapabilities;
+
+    /// True for the llama-server sidecar (request counting, auto-stop)
+    fn is_embedded(&self) -> bool {
+        false
+    }
+
+    fn complete<'a>(&'a self, request: &'a LlmRequest) -> BoxFuture<'a, Result<String, SidecarError>>;
+
+    /// Same as `complete`, calling `on_delta` with each piece of content as it is generated.
+    /// Backends without streaming report the whole content as a single delta.
+    fn complete_stream<'a>(
+        &'a self,
+        request: &'a LlmRequest,
+        on_delta: DeltaSink<'a>,
+    ) -> BoxFuture<'a, Result<String, SidecarError>> {
+        async move {
+            let content = self.complete(request).await?;
+            on_delta(&content);
+            Ok(content)
+        }
+        .boxed()
+    }
+}
+
+/// Receives the content deltas of a streamed completion
+pub type DeltaSink<'a> = &'a (dyn Fn(&str) + Send + Sync);
+
+pub struct LlamaServerBackend {
+    base_url: String,
+}
+
+pub struct OpenAiCompatibleBackend {
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+    capabilities: BackendCapabilities,
+}
+
+pub struct OllamaBackend {
+    base_url: String,
+    model: String,
+}
+
+/// OpenAI-compatible chat completion response
+#[derive(Debug, Deserialize)]
+struct ChatCompletionResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatMessage {
+    content: String,
+}
+
+/// One server-sent event of a streamed chat completion
+#[derive(Debug, Default, Deserialize)]
+struct ChatCompletionChunk {
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkChoice {
+    #[serde(default)]
+    delta: ChunkDelta,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChunkDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// Incremental reader of an SSE body: `data: {chunk}` lines ending with `data: [DONE]`.
+/// Bytes are buffered until a full line is received (a chunk may split a UTF-8 char).
+#[derive(Debug, Default)]
+struct SseReader {
+    pending: Vec<u8>,
+    done: bool,
+}
+
+/// Ollama `/api/chat` response (stream: false)
+#[derive(Debug, Deserialize)]
+struct OllamaChatResponse {
+    message: ChatMessage,
+}
+
+/// llama.cpp `/tokenize` response (ids, or objects with `with_pieces`)
+#[derive(Debug, Deserialize)]
+struct TokenizeResponse {
+    tokens: Vec<serde_json::Value>,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Impls
+// ─────────────────────────────────────────────────────────────────────────────
+
+impl LlmBackendSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        let (base_url, model) = match self {
+            LlmBackendSettings::Embedded => return Ok(()),
+            LlmBackendSettings::OpenaiCompatible { base_url, model, .. } => (base_url, model),
+            LlmBackendSettings::Ollama { base_url, model } => (base_url, model),
+        };
+        let url = base_url.trim();
+        if !(url.starts_with("http://") || url.starts_with("https://")) || url.len() <= "https://".len() {
+            return Err("L'URL du serveur LLM doit commencer par http:// ou https://".to_string());
+        }
+        if model.trim().is_empty() {
+            return Err("Le nom du modele LLM est obligatoire".to_string());
+        }
+        Ok(())
+    }
+
+    /// Backend for an external server; None for the embedded sidecar (needs the manager)
+    pub fn external_backend(&self) -> Option<Box<dyn LlmBackend>> {
+        match self {
+            LlmBackendSettings::Embedded => None,
+            LlmBackendSettings::OpenaiCompatible { base_url, model, api_key, gbnf_grammar, json_schema } => {
+                Some(Box::new(OpenAiCompatibleBackend::new(
+                    base_url,
+                    model,
+                    api_key.clone(),
+                    BackendCapabilities { gbnf_grammar: *gbnf_grammar, json_schema: *json_schema },
+                )))
+            }
+            LlmBackendSettings::Ollama { base_url, model } => {
+                Some(Box::new(OllamaBackend::new(base_url, model)))
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for LlmBackendSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LlmBackendSettings::Embedded => write!(f, "Embedded"),
+            LlmBackendSettings::OpenaiCompatible { base_url, model, api_key, gbnf_grammar, json_schema } => f
+                .debug_struct("OpenaiCompatible")
+                .field("base_url", base_url)
+                .field("model", model)
+                .field("api_key", &api_key.as_ref().map(|_| "***"))
+                .field("gbnf_grammar", gbnf_grammar)
+                .field("json_schema", json_schema)
+                .finish(),
+            LlmBackendSettings::Ollama { base_url, model } => f
+                .debug_struct("Ollama")
+                .field("base_url", base_url)
+                .field("model", model)
+                .finish(),
+        }
+    }
+}
+
+impl BackendCapabilities {
+    /// GBNF is preferred: llama.cpp enforces it token by token, including string contents
+    pub fn constraint_mode(&self) -> ConstraintMode {
+        if self.gbnf_grammar {
+            ConstraintMode::Gbnf
+        } else if self.json_schema {
+            ConstraintMode::JsonSchema
+        } else {
+            ConstraintMode::None
+        }
+    }
+}
+
+impl LlamaServerBackend {
+    pub fn new(base_url: &str) -> Self {
+        LlamaServerBackend { base_url: trim_base_url(base_url) }
+    }
+
+    fn body(&self, request: &LlmRequest) -> serde_json::Value {
+        chat_completion_body(EMBEDDED_MODEL_NAME, request, self.capabilities().constraint_mode())
+    }
+}
+
+impl LlmBackend for LlamaServerBackend {
+    fn name(&self) -> &'static str {
+        "llama-server"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities { gbnf_grammar: true, json_schema: true }
+    }
+
+    fn is_embedded(&self) -> bool {
+        true
+    }
+
+    fn complete<'a>(&'a self, request: &'a LlmRequest) -> BoxFuture<'a, Result<String, SidecarError>> {
+        async move {
+            let url = format!("{}/v1/chat/completions", self.base_url);
+            let response: ChatCompletionResponse =
+                post_json(self.name(), &url, None, &self.body(request), request.timeout).await?;
+            Ok(first_choice(response))
+        }
+        .boxed()
+    }
+
+    fn complete_stream<'a>(
+        &'a self,
+        request: &'a LlmRequest,
+        on_delta: DeltaSink<'a>,
+    ) -> BoxFuture<'a, Result<String, SidecarError>> {
+        let url = format!("{}/v1/chat/completions", self.base_url);
+        let mut body = self.body(request);
+        body["stream"] = true.into();
+        async move { post_stream(self.name(), &url, None, &body, request.timeout, on_delta).await }.boxed()
+    }
+}
+
+impl TokenCounter for LlamaServerBackend {
+    fn count_tokens<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Option<usize>> {
+        tokenize(self.name(), &self.base_url, None, text).boxed()
+    }
+}
+
+impl OpenAiCompatibleBackend {
+    pub fn new(base_url: &str, model: &str, api_key: Option<String>, capabilities: BackendCapabilities) -> Self {
+        OpenAiCompatibleBackend {
+            base_url: trim_base_url(base_url),
+            model: model.trim().to_string(),
+            api_key: api_key.filter(|k| !k.trim().is_empty()),
+            capabilities,
+        }
+    }
+
+    fn body(&self, request: &LlmRequest) -> serde_json::Value {
+        chat_completion_body(&self.model, request, self.capabilities.constraint_mode())
+    }
+}
+
+impl LlmBackend for OpenAiCompatibleBackend {
+    fn name(&self) -> &'static str {
+        "serveur OpenAI-compatible"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        self.capabilities
+    }
+
+    fn complete<'a>(&'a self, request: &'a LlmRequest) -> BoxFuture<'a, Result<String, SidecarError>> {
+        async move {
+            let url = format!("{}/v1/chat/completions", self.base_url);
+            let response: ChatCompletionResponse = post_json(
+                self.name(),
+                &url,
+                self.api_key.as_deref(),
+                &self.body(request),
+                request.timeout,
+            )
+            .await?;
+            Ok(first_choice(response))
+        }
+        .boxed()
+    }
+
+    fn complete_stream<'a>(
+        &'a self,
+        request: &'a LlmRequest,
+        on_delta: DeltaSink<'a>,
+    ) -> BoxFuture<'a, Result<String, SidecarError>> {
+        let url = format!("{}/v1/chat/completions", self.base_url);
+        let mut body = self.body(request);
+        body["stream"] = true.into();
+        async move {
+            post_stream(self.name(), &url, self.api_key.as_deref(), &body, request.timeout, on_delta).await
+        }
+        .boxed()
+    }
+}
+
+/// Only llama.cpp servers (those accepting a GBNF grammar) expose `/tokenize`
+impl TokenCounter for OpenAiCompatibleBackend {
+    fn count_tokens<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Option<usize>> {
+        if !self.capabilities.gbnf_grammar {
+            return futures::future::ready(None).boxed();
+        }
+        tokenize(self.name(), &self.base_url, self.api_key.as_deref(), text).boxed()
+    }
+}
+
+impl OllamaBackend {
+    pub fn new(base_url: &str, model: &str) -> Self {
+        OllamaBackend { base_url: trim_base_url(base_url), model: model.trim().to_string() }
+    }
+
+    fn body(&self, request: &LlmRequest) -> serde_json::Value {
+        serde_json::json!({
+            "model": self.model,
+            "messages": messages(request),
+            "stream": false,
+            "format": request.constraint.json_schema,
+            "options": {
+                "temperature": request.temperature,
+                "seed": request.seed,
+                "num_predict": request.max_tokens
+            }
+        })
+    }
+}
+
+impl LlmBackend for OllamaBackend {
+    fn name(&self) -> &'static str {
+        "Ollama"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities { gbnf_grammar: false, json_schema: true }
+    }
+
+    fn complete<'a>(&'a self, request: &'a LlmRequest) -> BoxFuture<'a, Result<String, SidecarError>> {
+        async move {
+            let url = format!("{}/api/chat", self.base_url);
+            let response: OllamaChatResponse =
+                post_json(self.name(), &url, None, &self.body(request), request.timeout).await?;
+            Ok(response.message.content)
+        }
+        .boxed()
+    }
+}
+
+impl TokenCounter for OllamaBackend {
+    fn count_tokens<'a>(&'a self, _text: &'a str) -> BoxFuture<'a, Option<usize>> {
+        futures::future::ready(None).boxed()
+    }
+}
+
+fn trim_base_url(base_url: &str) -> String {
+    base_url.trim().trim_end_matches('/').to_string()
+}
+
+fn messages(request: &LlmRequest) -> serde_json::Value {
+    serde_json::json!([
+        { "role": "system", "content": request.system_prompt },
+        { "role": "user", "content": request.user_prompt }
+    ])
+}
+
+/// `/v1/chat/completions` body with the constraint in the form the server supports
+fn chat_completion_body(model: &str, request: &LlmRequest, mode: ConstraintMode) -> serde_json::Value {
+    let mut body = serde_json::json!({
+        "model": model,
+        "messages": messages(request),
+        "temperature": request.temperature,
+        "max_tokens": request.max_tokens
+    });
+    if let Some(seed) = request.seed {
+        body["seed"] = seed.into();
+    }
+    match mode {
+        ConstraintMode::Gbnf => {
+            body["grammar"] = serde_json::Value::String(request.constraint.gbnf.clone());
+        }
+        ConstraintMode::JsonSchema => {
+            body["response_format"] = serde_json::json!({
+                "type": "json_schema",
+                "json_schema": {
+                    "name": request.constraint.name,
+                    "strict": true,
+                    "schema": request.constraint.json_schema
+                }
+            });
+        }
+        ConstraintMode::None => {}
+    }
+    body
+}
+
+fn first_choice(response: ChatCompletionResponse) -> String {
+    response
+        .choices
+        .into_iter()
+        .next()
+        .map(|c| c.message.content)
+        .unwrap_or_default()
+}
+
+async fn post_json<T: serde::de::DeserializeOwned>(
+    backend: &str,
+    url: &str,
+    api_key: Option<&str>,
+    body: &serde_json::Value,
+    timeout: Duration,
+) -> Result<T, SidecarError> {
+    send_json(backend, url, api_key, body, timeout)
+        .await?
+        .json()
+        .await
+        .map_err(|e| SidecarError::Internal(format!("Reponse JSON invalide de {}: {}", backend, e)))
+}
+
+/// POST a `stream: true` chat completion and read its server-sent events.
+/// The timeout covers the whole generation, as for `post_json`.
+async fn post_stream(
+    backend: &str,
+    url: &str,
+    api_key: Option<&str>,
+    body: &serde_json::Value,
+    timeout: Duration,
+    on_delta: DeltaSink<'_>,
+) -> Result<String, SidecarError> {
+    let response = send_json(backend, url, api_key, body, timeout).await?;
+    let mut bytes = response.bytes_stream();
+    let mut reader = SseReader::default();
+    let mut content = String::new();
+
+    while let Some(chunk) = bytes.next().await {
+        let chunk =
+            chunk.map_err(|e| SidecarError::Internal(format!("Flux interrompu depuis {}: {}", backend, e)))?;
+        let deltas = reader
+            .push(&chunk)
+            .map_err(|e| SidecarError::Internal(format!("{} a signale une erreur: {}", backend, e)))?;
+        for delta in deltas {
+            on_delta(&delta);
+            content.push_str(&delta);
+        }
+        if reader.done {
+            break;
+        }
+    }
+    Ok(content)
+}
+
+async fn send_json(
+    backend: &str,
+    url: &str,
+    api_key: Option<&str>,
+    body: &serde_json::Value,
+    timeout: Duration,
+) -> Result<reqwest::Response, SidecarError> {
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| SidecarError::Internal(e.to_string()))?;
+
+    let mut builder = client.post(url).json(body);
+    if let Some(key) = api_key {
+        builder = builder.bearer_auth(key);
+    }
+
+    let response = builder
+        .send()
+        .await
+        .map_err(|e| SidecarError::Internal(format!("Requete vers {} echouee: {}", backend, e)))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body_text = response.text().await.unwrap_or_default();
+        return Err(SidecarError::Internal(format!(
+            "{} a repondu avec le code {}: {}",
+            backend, status, body_text
+        )));
+    }
+    Ok(response)
+}
+
+impl SseReader {
+    /// Feed received bytes; returns the content deltas of the complete lines,
+    /// or the message of an `error` event
+    fn push(&mut self, bytes: &[u8]) -> Result<Vec<String>, String> {
+        self.pending.extend_from_slice(bytes);
+        let mut deltas = Vec::new();
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim_end().strip_prefix("data:") else { continue };
+            let data = data.trim();
+            if data == "[DONE]" {
+                self.done = true;
+                break;
+            }
+            let event: serde_json::Value =
+                serde_json::from_str(data).map_err(|e| format!("evenement illisible ({}): {}", e, data))?;
+            if let Some(error) = event.get("error") {
+                let message = error["message"].as_str().map(str::to_string).unwrap_or_else(|| error.to_string());
+                return Err(message);
+            }
+            let chunk: ChatCompletionChunk = serde_json::from_value(event).unwrap_or_default();
+            if let Some(content) = chunk.choices.into_iter().next().and_then(|c| c.delta.content) {
+                if !content.is_empty() {
+                    deltas.push(content);
+                }
+            }
+        }
+        Ok(deltas)
+    }
+}
+
+/// Token count from a llama.cpp server's `/tokenize`, None if it does not answer
+async fn tokenize(backend: &str, base_url: &str, api_key: Option<&str>, text: &str) -> Option<usize> {
+    let url = format!("{}/tokenize", base_url);
+    let body = serde_json::json!({ "content": text });
+    match post_json::<TokenizeResponse>(backend, &url, api_key, &body, TOKENIZE_TIMEOUT).await {
+        Ok(response) => Some(response.tokens.len()),
+        Err(e) => {
+            log::debug!("Comptage des tokens via {} impossible : {}", backend, e);
+            None
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Helpers AppHandle
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Backend selected in the settings. The embedded one starts llama-server if needed.
+pub async fn connect_backend(
+    app: &tauri::AppHandle,
+    state: &super::SidecarManager,
+) -> Result<Box<dyn LlmBackend>, SidecarError> {
+    let settings = state.get_settings().await;
+    if let Some(backend) = settings.llm_backend.external_backend() {
+        return Ok(backend);
+    }
+
+    let model_path = resolve_model_path(app).await?;
+    state
+        .ensure_running(app, super::types::SidecarName::Llama, model_path.to_string_lossy().to_string())
+        .await?;
+    let base_url = state.base_url(super::types::SidecarName::Llama).await?;
+    Ok(Box::new(LlamaServerBackend::new(&base_url)))
+}
+
+/// Resolve the active llama model (models catalog) in app_data_dir/models/
+async fn resolve_model_path(app: &tauri::AppHandle) -> Result<std::path::PathBuf, SidecarError> {
+    use crate::models::catalog::{self, ModelRole};
+
+    let (_, model_path) = catalog::resolve_active_model(app, ModelRole::Llama)
+        .await
+        .map_err(|e| SidecarError::Internal(e.to_string()))?;
+
+    if !model_path.exists() {
+        return Err(SidecarError::ModelNotFound(
+            model_path.to_string_lossy().to_string(),
+        ));
+    }
+
+    Ok(model_path)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sidecar::testing::{MockSidecar, Recording};
+
+    fn request() -> LlmRequest {
+        LlmRequest {
+            system_prompt: "Tu es un assistant.".to_string(),
+            user_prompt: "Dictee".to_string(),
+            constraint: OutputConstraint {
+                name: "synthese",
+                gbnf: "root ::= \"{}\"".to_string(),
+                json_schema: serde_json::json!({ "type": "object" }),
+            },
+            temperature: 0.1,
+            seed: None,
+            max_tokens: 256,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Scripted answer of `route` with a JSON body
+    fn reply(route: &str, status: u16, body: serde_json::Value) -> Recording {
+        Recording { route: route.to_string(), status, body }
+    }
+
+    #[tokio::test]
+    async fn llama_server_sends_grammar() {
+        let mock = MockSidecar::spawn(vec![Recording::chat(r#"{"synthese": "Ok."}"#)]).await;
+        let backend = LlamaServerBackend::new(&mock.url("/"));
+
+        let content = backend.complete(&request()).await.unwrap();
+        assert_eq!(content, r#"{"synthese": "Ok."}"#);
+        backend.complete(&LlmRequest { seed: Some(42), ..request() }).await.unwrap();
+
+        let seen = mock.requests("/v1/chat/completions");
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0].json()["model"], EMBEDDED_MODEL_NAME);
+        assert_eq!(seen[0].json()["grammar"], "root ::= \"{}\"");
+        assert!(seen[0].json().get("response_format").is_none());
+        assert!(seen[0].json().get("seed").is_none());
+        assert_eq!(seen[1].json()["seed"], 42);
+        assert!(backend.is_embedded());
+    }
+
+    #[tokio::test]
+    async fn openai_compatible_sends_schema_model_and_key() {
+        let mock = MockSidecar::spawn(vec![Recording::chat("{}")]).await;
+        let settings = LlmBackendSettings::OpenaiCompatible {
+            base_url: mock.base_url.clone(),
+            model: "mistral-small".to_string(),
+            api_key: Some("secret".to_string()),
+            gbnf_grammar: false,
+            json_schema: true,
+        };
+        assert!(!format!("{:?}", settings).contains("secret"), "La cle API ne doit pas etre journalisee");
+        let backend = settings.external_backend().unwrap();
+        backend.complete(&request()).await.unwrap();
+
+        let seen = &mock.requests("/v1/chat/completions")[0];
+        let body = seen.json();
+        assert_eq!(seen.header("authorization"), Some("Bearer secret"));
+        assert_eq!(body["model"], "mistral-small");
+        assert_eq!(body["max_tokens"], 256);
+        assert_eq!(body["response_format"]["json_schema"]["name"], "synthese");
+        assert_eq!(body["response_format"]["json_schema"]["schema"]["type"], "object");
+        assert!(body.get("grammar").is_none());
+        assert!(!backend.is_embedded());
+    }
+
+    #[tokio::test]
+    async fn ollama_uses_api_chat_with_format() {
+        let answer = serde_json::json!({ "message": { "role": "assistant", "content": "{\"a\": 1}" }, "done": true });
+        let mock = MockSidecar::spawn(vec![reply("/api/chat", 200, answer)]).await;
+        let backend = OllamaBackend::new(&mock.base_url, "qwen2.5:3b");
+
+        let content = backend.complete(&request()).await.unwrap();
+        assert_eq!(content, "{\"a\": 1}");
+
+        let seen = &mock.requests("/api/chat")[0];
+        let body = seen.json();
+        assert_eq!(body["stream"], false);
+        assert_eq!(body["format"]["type"], "object");
+        assert_eq!(body["options"]["num_predict"], 256);
+        assert!(seen.header("authorization").is_none());
+    }
+
+    #[tokio::test]
+    async fn http_error_reports_backend_and_status() {
+        let failure = reply("/api/chat", 500, serde_json::json!({ "error": "model not loaded" }));
+        let mock = MockSidecar::spawn(vec![failure]).await;
+        let backend = OllamaBackend::new(&mock.base_url, "qwen2.5:3b");
+        let err = backend.complete(&request()).await.unwrap_err().to_string();
+        assert!(err.contains("Ollama"), "{}", err);
+        assert!(err.contains("500"), "{}", err);
+        assert!(err.contains("model not loaded"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn tokenizer_counts_with_fallback() {
+        let tokens = reply("/tokenize", 200, serde_json::json!({ "tokens": [9906, 11, 1917] }));
+        let mock = MockSidecar::spawn(vec![tokens]).await;
+        let llama = LlamaServerBackend::new(&mock.base_url);
+        assert_eq!(llama.count_tokens("Lit bien").await, Some(3));
+        assert_eq!(mock.requests("/tokenize")[0].json()["content"], "Lit bien");
+
+        // No tokenizer: JSON-schema-only servers, Ollama, or a failing endpoint
+        let caps = BackendCapabilities { gbnf_grammar: false, json_schema: true };
+        assert_eq!(OpenAiCompatibleBackend::new(&mock.base_url, "m", None, caps).count_tokens("x").await, None);
+        assert_eq!(OllamaBackend::new(&mock.base_url, "m").count_tokens("x").await, None);
+        let down = MockSidecar::spawn(vec![Recording::status("/tokenize", 404)]).await;
+        assert_eq!(LlamaServerBackend::new(&down.base_url).count_tokens("x").await, None);
+    }
+
+    #[test]
+    fn sse_reader_joins_lines_split_across_chunks() {
+        let mut reader = SseReader::default();
+        let first = concat!(
+            r#"data: {"choices":[{"delta":{"role":"assistant"}}]}"#,
+            "\n\n",
+            r#"data: {"choices":[{"delta":{"content":"{\"syn"#
+        );
+        assert!(reader.push(first.as_bytes()).unwrap().is_empty());
+        // "é" split between two network chunks
+        let second = concat!(r#"these\": \"Léa"}}]}"#, "\n\n: keep-alive\n\n");
+        let (a, b) = second.as_bytes().split_at(second.find('é').unwrap() + 1);
+        assert_eq!(reader.push(a).unwrap(), Vec::<String>::new());
+        assert_eq!(reader.push(b).unwrap(), vec!["{\"synthese\": \"Léa".to_string()]);
+        assert!(!reader.done);
+        assert!(reader.push(b"data: [DONE]\n\n").unwrap().is_empty());
+        assert!(reader.done);
+
+        let mut failing = SseReader::default();
+        let err = failing
+            .push(br#"data: {"error":{"code":500,"message":"context exceeded"}}"#)
+            .and_then(|_| failing.push(b"\n"))
+            .unwrap_err();
+        assert_eq!(err, "context exceeded");
+    }
+
+    #[test]
+    fn capabilities_pick_the_constraint_and_settings_are_validated() {
+        let both = BackendCapabilities { gbnf_grammar: true, json_schema: true };
+        assert_eq!(both.constraint_mode(), ConstraintMode::Gbnf);
+        let none = BackendCapabilities { gbnf_grammar: false, json_schema: false };
+        assert_eq!(none.constraint_mode(), ConstraintMode::None);
+        assert!(chat_completion_body("m", &request(), ConstraintMode::None).get("response_format").is_none());
+
+        assert!(LlmBackendSettings::Embedded.validate().is_ok());
+        assert!(LlmBackendSettings::Embedded.external_backend().is_none());
+        let no_scheme = LlmBackendSettings::Ollama { base_url: "192.168.1.10:11434".into(), model: "qwen".into() };
+        assert!(no_scheme.validate().is_err());
+        let no_model = LlmBackendSettings::Ollama { base_url: "http://192.168.1.10:11434".into(), model: " ".into() };
+        assert!(no_model.validate().is_err());
+
+        // Capability flags default to JSON schema only
+        let parsed: LlmBackendSettings = serde_json::from_str(
+            r#"{"type": "openai_compatible", "base_url": "http://ia.ecole.local:8000", "model": "qwen"}"#,
+        )
+        .unwrap();
+        assert!(parsed.validate().is_ok());
+        let backend = parsed.external_backend().unwrap();
+        assert_eq!(backend.capabilities(), BackendCapabilities { gbnf_grammar: false, json_schema: true });
+    }
+}
@@ -0,0 +1,618 @@
+/// Queue of LLM jobs (classification, synthese, appreciation), run one at a time.
+///
+/// Each command used to start llama-server, send its request and auto-stop it on its
+/// own: in sequential mode (ADR-002) two quick clicks could start and stop the sidecar
+/// under each other. Commands now submit their job here and a single worker, spawned
+/// at startup, owns the sidecar lifecycle:
+/// - jobs run by priority (the classification of a dictation before syntheses and
+///   appreciations), first come first served within a priority;
+/// - every job has a cancellation token: a queued job is dropped, a running one is
+///   aborted (its HTTP request is closed, llama-server stops generating);
+/// - the backend is connected before each job (llama-server started if needed) and
+///   llama-server is auto-stopped only once the queue is empty;
+/// - the status of queued, running and recent jobs can be queried by job id.
+
+use futures::future::{AbortHandle, AbortRegistration, Abortable, BoxFuture, FutureExt};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tokio::sync::{oneshot, Notify};
+
+use super::llm_backend::{connect_backend, LlmBackend};
+use super::manager::SidecarManager;
+use super::prompt_templates::PromptJob;
+use super::recovery::unix_now;
+use super::types::SidecarName;
+use crate::error::AppError;
+
+/// Finished jobs kept for status queries
+const FINISHED_HISTORY: usize = 50;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Run order of the queue (`Interactive` first)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LlmJobPriority {
+    /// The teacher is waiting on it (classification right after a dictation)
+    Interactive,
+    /// Generations that can wait behind a dictation (syntheses, appreciations)
+    Batch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum LlmJobStatus {
+    /// Waiting for the worker; position 0 runs next
+    Queued { position: usize },
+    Running,
+    Done,
+    Failed { message: String },
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LlmJobInfo {
+    pub id: String,
+    pub job: PromptJob,
+    pub priority: LlmJobPriority,
+    pub status: LlmJobStatus,
+    /// Unix timestamps (seconds)
+    pub queued_at: u64,
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+}
+
+/// Job submitted by a command
+#[derive(Debug, Clone)]
+pub struct LlmJobSpec {
+    /// Streamed jobs use their request id; generated when None
+    pub id: Option<String>,
+    pub job: PromptJob,
+    pub priority: LlmJobPriority,
+}
+
+/// Sidecar side of the queue; only the worker calls it
+pub trait LlmLifecycle: Send + Sync {
+    /// Backend for the next job (starts llama-server when embedded)
+    fn connect(&self) -> BoxFuture<'_, Result<Box<dyn LlmBackend>, AppError>>;
+
+    /// A job ran on the embedded backend (request count of the watchdog)
+    fn job_done(&self) -> BoxFuture<'_, ()>;
+
+    /// The queue is empty after jobs on the embedded backend, or after a connection
+    /// that was cancelled or failed (ADR-002 auto-stop)
+    fn idle(&self) -> BoxFuture<'_, ()>;
+}
+
+/// Type-erased job: runs on the connected backend (or gets the connection error),
+/// sends its result to the submitter and returns the error message for the status
+type JobFn =
+    Box<dyn FnOnce(Result<Box<dyn LlmBackend>, AppError>) -> BoxFuture<'static, Result<(), String>> + Send>;
+
+struct QueuedJob {
+    info: LlmJobInfo,
+    seq: u64,
+    run: JobFn,
+    cancel: AbortHandle,
+    registration: AbortRegistration,
+}
+
+struct RunningJob {
+    info: LlmJobInfo,
+    cancel: AbortHandle,
+}
+
+#[derive(Default)]
+struct QueueState {
+    /// Sorted by priority, then submission order
+    pending: Vec<QueuedJob>,
+    running: Option<RunningJob>,
+    /// Oldest first
+    finished: VecDeque<LlmJobInfo>,
+    next_seq: u64,
+}
+
+/// LLM job queue (Tauri managed state). `run_worker` runs its jobs.
+#[derive(Default)]
+pub struct LlmJobQueue {
+    state: Mutex<QueueState>,
+    wake: Notify,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Impls
+// ─────────────────────────────────────────────────────────────────────────────
+
+impl QueueState {
+    fn is_active(&self, id: &str) -> bool {
+        self.pending.iter().any(|j| j.info.id == id)
+            || self.running.as_ref().is_some_and(|r| r.info.id == id)
+    }
+
+    fn record(&mut self, mut info: LlmJobInfo, status: LlmJobStatus) {
+        info.status = status;
+        info.finished_at = Some(unix_now());
+        self.finished.push_back(info);
+        while self.finished.len() > FINISHED_HISTORY {
+            self.finished.pop_front();
+        }
+    }
+}
+
+impl LlmJobQueue {
+    /// Queue a job and wait for its result; a cancelled job returns `AppError::Cancelled`
+    pub async fn run<T, F>(&self, spec: LlmJobSpec, job: F) -> Result<T, AppError>
+    where
+        T: Send + 'static,
+        F: FnOnce(Box<dyn LlmBackend>) -> BoxFuture<'static, Result<T, AppError>> + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let run: JobFn = Box::new(move |backend| {
+            async move {
+                let result = match backend {
+                    Ok(backend) => job(backend).await,
+                    Err(e) => Err(e),
+                };
+                let summary = result.as_ref().map(|_| ()).map_err(|e| e.to_string());
+                let _ = tx.send(result);
+                summary
+            }
+            .boxed()
+        });
+        self.submit(spec, run)?;
+        // A cancelled job drops its sender without sending
+        rx.await.unwrap_or(Err(AppError::Cancelled))
+    }
+
+    fn submit(&self, spec: LlmJobSpec, run: JobFn) -> Result<(), AppError> {
+        let id = spec.id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let mut state = self.state.lock().unwrap();
+        if state.is_active(&id) {
+            return Err(AppError::validation("Une tâche IA est déjà en cours avec cet identifiant."));
+        }
+
+        let (cancel, registration) = AbortHandle::new_pair();
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        let info = LlmJobInfo {
+            id,
+            job: spec.job,
+            priority: spec.priority,
+            status: LlmJobStatus::Queued { position: 0 },
+            queued_at: unix_now(),
+            started_at: None,
+            finished_at: None,
+        };
+        info!("Tache LLM {} en file ({:?}, {:?})", info.id, info.job, info.priority);
+        state.pending.push(QueuedJob { info, seq, run, cancel, registration });
+        state.pending.sort_by_key(|j| (j.info.priority, j.seq));
+        drop(state);
+
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    /// Cancel a queued or running job; false when it is unknown or already finished
+    pub fn cancel(&self, id: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if let Some(index) = state.pending.iter().position(|j| j.info.id == id) {
+            let job = state.pending.remove(index);
+            state.record(job.info, LlmJobStatus::Cancelled);
+            info!("Tache LLM {} annulee avant son lancement", id);
+            return true;
+        }
+        match &state.running {
+            Some(running) if running.info.id == id => {
+                running.cancel.abort();
+                info!("Tache LLM {} annulee en cours d'execution", id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// A job is running or waiting: llama-server must stay up
+    pub fn is_busy(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.running.is_some() || !state.pending.is_empty()
+    }
+
+    pub fn status(&self, id: &str) -> Option<LlmJobInfo> {
+        self.jobs().into_iter().find(|j| j.id == id)
+    }
+
+    /// Running job, queued jobs in run order, then finished jobs (most recent first)
+    pub fn jobs(&self) -> Vec<LlmJobInfo> {
+        let state = self.state.lock().unwrap();
+        let mut jobs: Vec<LlmJobInfo> = state.running.iter().map(|r| r.info.clone()).collect();
+        for (position, job) in state.pending.iter().enumerate() {
+            jobs.push(LlmJobInfo { status: LlmJobStatus::Queued { position }, ..job.info.clone() });
+        }
+        jobs.extend(state.finished.iter().rev().cloned());
+        jobs
+    }
+
+    /// Run the queued jobs one at a time, forever. Sole user of `lifecycle`:
+    /// llama-server is only auto-stopped when no job is left.
+    pub async fn run_worker(&self, lifecycle: &dyn LlmLifecycle) {
+        let mut used_embedded = false;
+        loop {
+            let Some((run, registration)) = self.start_next() else {
+                if used_embedded {
+                    lifecycle.idle().await;
+                    used_embedded = false;
+                }
+                self.wake.notified().await;
+                continue;
+            };
+
+            // Connecting is part of the job: a cancel while llama-server loads is honoured
+            let connected = AtomicBool::new(false);
+            let embedded = AtomicBool::new(false);
+            let work = async {
+                let backend = lifecycle.connect().await;
+                if let Ok(backend) = &backend {
+                    connected.store(true, Ordering::Relaxed);
+                    embedded.store(backend.is_embedded(), Ordering::Relaxed);
+                }
+                run(backend).await
+            };
+            let status = match Abortable::new(work, registration).await {
+                Ok(Ok(())) => LlmJobStatus::Done,
+                Ok(Err(message)) => LlmJobStatus::Failed { message },
+                Err(_) => LlmJobStatus::Cancelled,
+            };
+            if embedded.load(Ordering::Relaxed) {
+                used_embedded = true;
+                lifecycle.job_done().await;
+            } else if !connected.load(Ordering::Relaxed) {
+                // Cancelled or failed while connecting: llama-server may be (half) started
+                used_embedded = true;
+            }
+            self.finish(status);
+        }
+    }
+
+    fn start_next(&self) -> Option<(JobFn, AbortRegistration)> {
+        let mut state = self.state.lock().unwrap();
+        if state.pending.is_empty() {
+            return None;
+        }
+        let job = state.pending.remove(0);
+        let info = LlmJobInfo { status: LlmJobStatus::Running, started_at: Some(unix_now()), ..job.info };
+        state.running = Some(RunningJob { info, cancel: job.cancel });
+        Some((job.run, job.registration))
+    }
+
+    fn finish(&self, status: LlmJobStatus) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(running) = state.running.take() {
+            if let LlmJobStatus::Failed { message } = &status {
+                warn!("Tache LLM {} en echec : {}", running.info.id, message);
+            }
+            state.record(running.info, status);
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Helpers AppHandle
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Lifecycle of the app: backend from the settings, llama-server through the SidecarManager
+struct AppLlmLifecycle {
+    app: AppHandle,
+}
+
+impl LlmLifecycle for AppLlmLifecycle {
+    fn connect(&self) -> BoxFuture<'_, Result<Box<dyn LlmBackend>, AppError>> {
+        async move {
+            let state = self.app.state::<SidecarManager>();
+            Ok(connect_backend(&self.app, &state).await?)
+        }
+        .boxed()
+    }
+
+    fn job_done(&self) -> BoxFuture<'_, ()> {
+        async move {
+            let state = self.app.state::<SidecarManager>();
+            state.increment_request_count(SidecarName::Llama).await;
+        }
+        .boxed()
+    }
+
+    fn idle(&self) -> BoxFuture<'_, ()> {
+        async move {
+            let state = self.app.state::<SidecarManager>();
+            state.auto_stop_after_task(&self.app, SidecarName::Llama).await;
+        }
+        .boxed()
+    }
+}
+
+/// Start the worker of the managed `LlmJobQueue` (app setup)
+pub fn spawn_worker(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let lifecycle = AppLlmLifecycle { app: app.clone() };
+        app.state::<LlmJobQueue>().run_worker(&lifecycle).await;
+    });
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Commandes Tauri
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Status of one LLM job (queued, running or recently finished)
+#[tauri::command]
+pub async fn get_llm_job(
+    queue: tauri::State<'_, LlmJobQueue>,
+    job_id: String,
+) -> Result<LlmJobInfo, AppError> {
+    queue
+        .status(&job_id)
+        .ok_or_else(|| AppError::not_found("Tâche IA introuvable"))
+}
+
+/// Running job, queued jobs in run order, then recent finished jobs
+#[tauri::command]
+pub async fn list_llm_jobs(queue: tauri::State<'_, LlmJobQueue>) -> Result<Vec<LlmJobInfo>, AppError> {
+    Ok(queue.jobs())
+}
+
+/// Cancel a queued or running LLM job. Unknown or finished jobs are ignored.
+#[tauri::command]
+pub async fn cancel_llm_job(
+    queue: tauri::State<'_, LlmJobQueue>,
+    job_id: String,
+) -> Result<(), AppError> {
+    queue.cancel(&job_id);
+    Ok(())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sidecar::llm_backend::{LlamaServerBackend, LlmRequest, OutputConstraint};
+    use crate::sidecar::llm_stream::StreamingBackend;
+    use crate::sidecar::testing::{MockSidecar, Recording};
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Records the lifecycle calls; connects to a llama-server backend that is never called
+    #[derive(Default)]
+    struct FakeLifecycle {
+        events: Mutex<Vec<&'static str>>,
+        unreachable: AtomicBool,
+        /// connect() never returns, like a model that takes forever to load
+        slow_connect: AtomicBool,
+    }
+
+    impl FakeLifecycle {
+        fn events(&self) -> Vec<&'static str> {
+            self.events.lock().unwrap().clone()
+        }
+    }
+
+    impl LlmLifecycle for FakeLifecycle {
+        fn connect(&self) -> BoxFuture<'_, Result<Box<dyn LlmBackend>, AppError>> {
+            self.events.lock().unwrap().push("connect");
+            let result: Result<Box<dyn LlmBackend>, AppError> = if self.unreachable.load(Ordering::Relaxed) {
+                Err(AppError::Internal("llama-server ne demarre pas".to_string()))
+            } else {
+                Ok(Box::new(LlamaServerBackend::new("http://127.0.0.1:9")))
+            };
+            if self.slow_connect.load(Ordering::Relaxed) {
+                return futures::future::pending().boxed();
+            }
+            futures::future::ready(result).boxed()
+        }
+
+        fn job_done(&self) -> BoxFuture<'_, ()> {
+            self.events.lock().unwrap().push("job_done");
+            futures::future::ready(()).boxed()
+        }
+
+        fn idle(&self) -> BoxFuture<'_, ()> {
+            self.events.lock().unwrap().push("idle");
+            futures::future::ready(()).boxed()
+        }
+    }
+
+    fn spawn_test_worker(queue: &Arc<LlmJobQueue>, lifecycle: &Arc<FakeLifecycle>) {
+        let (queue, lifecycle) = (queue.clone(), lifecycle.clone());
+        tokio::spawn(async move { queue.run_worker(lifecycle.as_ref()).await });
+    }
+
+    fn spec(id: &str, job: PromptJob, priority: LlmJobPriority) -> LlmJobSpec {
+        LlmJobSpec { id: Some(id.to_string()), job, priority }
+    }
+
+    async fn wait_until(condition: impl Fn() -> bool) {
+        for _ in 0..200 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("condition jamais remplie");
+    }
+
+    fn state_of(queue: &LlmJobQueue, id: &str) -> Option<LlmJobStatus> {
+        queue.status(id).map(|j| j.status)
+    }
+
+    #[tokio::test]
+    async fn jobs_run_by_priority_and_llama_stops_once_the_queue_is_empty() {
+        let queue = Arc::new(LlmJobQueue::default());
+        let lifecycle = Arc::new(FakeLifecycle::default());
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let job = |name: &'static str| {
+            let order = order.clone();
+            move |_backend: Box<dyn LlmBackend>| {
+                async move {
+                    order.lock().unwrap().push(name);
+                    if name == "appreciation" {
+                        return Err(AppError::validation("Texte vide"));
+                    }
+                    Ok(name)
+                }
+                .boxed()
+            }
+        };
+
+        // Submitted before the worker starts: the dictation overtakes the generations
+        spawn_test_worker(&queue, &lifecycle);
+        let (synthese, appreciation, classification) = tokio::join!(
+            queue.run(spec("s", PromptJob::Synthese, LlmJobPriority::Batch), job("synthese")),
+            queue.run(spec("a", PromptJob::Appreciation, LlmJobPriority::Batch), job("appreciation")),
+            queue.run(spec("c", PromptJob::Classification, LlmJobPriority::Interactive), job("classification")),
+        );
+        assert_eq!(*order.lock().unwrap(), vec!["classification", "synthese", "appreciation"]);
+        assert_eq!(synthese.unwrap(), "synthese");
+        assert_eq!(classification.unwrap(), "classification");
+        assert!(matches!(appreciation, Err(AppError::Validation(_))));
+
+        wait_until(|| lifecycle.events().contains(&"idle")).await;
+        assert_eq!(
+            lifecycle.events(),
+            vec!["connect", "job_done", "connect", "job_done", "connect", "job_done", "idle"]
+        );
+        assert_eq!(state_of(&queue, "s"), Some(LlmJobStatus::Done));
+        assert_eq!(state_of(&queue, "a"), Some(LlmJobStatus::Failed { message: "Texte vide".to_string() }));
+        let jobs = queue.jobs();
+        assert_eq!(jobs[0].id, "a", "Les taches terminees les plus recentes d'abord");
+        assert!(jobs.iter().all(|j| j.started_at.is_some() && j.finished_at.is_some()));
+
+        // Connection failure: reported to the job, llama-server stopped in case it half started
+        lifecycle.unreachable.store(true, Ordering::Relaxed);
+        let result = queue.run(spec("x", PromptJob::Synthese, LlmJobPriority::Batch), job("x")).await;
+        assert_eq!(result.unwrap_err().to_string(), "llama-server ne demarre pas");
+        assert!(!order.lock().unwrap().contains(&"x"));
+        wait_until(|| lifecycle.events().iter().filter(|e| **e == "idle").count() == 2).await;
+    }
+
+    #[tokio::test]
+    async fn cancel_while_connecting_still_stops_llama() {
+        let queue = Arc::new(LlmJobQueue::default());
+        let lifecycle = Arc::new(FakeLifecycle::default());
+        lifecycle.slow_connect.store(true, Ordering::Relaxed);
+        spawn_test_worker(&queue, &lifecycle);
+
+        let loading = {
+            let queue = queue.clone();
+            tokio::spawn(async move {
+                let spec = spec("loading", PromptJob::Synthese, LlmJobPriority::Batch);
+                queue.run(spec, |_| async { Ok(()) }.boxed()).await
+            })
+        };
+        wait_until(|| lifecycle.events() == vec!["connect"]).await;
+        assert!(queue.cancel("loading"));
+        assert!(matches!(loading.await.unwrap(), Err(AppError::Cancelled)));
+
+        wait_until(|| lifecycle.events().contains(&"idle")).await;
+        assert_eq!(lifecycle.events(), vec!["connect", "idle"]);
+    }
+
+    #[tokio::test]
+    async fn queued_and_running_jobs_can_be_cancelled() {
+        let queue = Arc::new(LlmJobQueue::default());
+        let lifecycle = Arc::new(FakeLifecycle::default());
+        spawn_test_worker(&queue, &lifecycle);
+
+        let slow = {
+            let queue = queue.clone();
+            tokio::spawn(async move {
+                let spec = spec("slow", PromptJob::Appreciation, LlmJobPriority::Batch);
+                queue.run(spec, |_| futures::future::pending::<Result<(), AppError>>().boxed()).await
+            })
+        };
+        wait_until(|| state_of(&queue, "slow") == Some(LlmJobStatus::Running)).await;
+
+        let queued = {
+            let queue = queue.clone();
+            tokio::spawn(async move {
+                let spec = spec("queued", PromptJob::Synthese, LlmJobPriority::Batch);
+                queue.run(spec, |_| async { Ok(()) }.boxed()).await
+            })
+        };
+        wait_until(|| state_of(&queue, "queued") == Some(LlmJobStatus::Queued { position: 0 })).await;
+
+        // Same id as an active job
+        let duplicate = queue
+            .run(spec("slow", PromptJob::Appreciation, LlmJobPriority::Batch), |_| async { Ok(()) }.boxed())
+            .await;
+        assert!(matches!(duplicate, Err(AppError::Validation(_))));
+
+        assert!(queue.cancel("queued"));
+        assert!(matches!(queued.await.unwrap(), Err(AppError::Cancelled)));
+        assert!(queue.cancel("slow"));
+        assert!(matches!(slow.await.unwrap(), Err(AppError::Cancelled)));
+        assert!(!queue.cancel("slow"));
+        assert!(!queue.cancel("inconnue"));
+        wait_until(|| state_of(&queue, "slow") == Some(LlmJobStatus::Cancelled)).await;
+        assert_eq!(state_of(&queue, "queued"), Some(LlmJobStatus::Cancelled));
+
+        // The worker goes on with the next job
+        let next = queue
+            .run(spec("next", PromptJob::Synthese, LlmJobPriority::Batch), |_| async { Ok(42) }.boxed())
+            .await;
+        assert_eq!(next.unwrap(), 42);
+        assert_eq!(lifecycle.events().iter().filter(|e| **e == "connect").count(), 2);
+    }
+
+    #[tokio::test]
+    async fn streamed_job_is_cancelled_mid_answer() {
+        let content = r#"{"appreciation": "Noé lit avec plaisir et participe volontiers."}"#;
+        let mock = MockSidecar::spawn(vec![Recording::chat(content)]).await;
+        let queue = Arc::new(LlmJobQueue::default());
+        let lifecycle = Arc::new(FakeLifecycle::default());
+        spawn_test_worker(&queue, &lifecycle);
+
+        // The teacher cancels as soon as the first words appear
+        let received = Arc::new(AtomicUsize::new(0));
+        let job = {
+            let (queue, received, base_url) = (queue.clone(), received.clone(), mock.base_url.clone());
+            move |_: Box<dyn LlmBackend>| {
+                async move {
+                    let llama = LlamaServerBackend::new(&base_url);
+                    let backend = StreamingBackend::new(&llama, "req-1", |_| {
+                        received.fetch_add(1, Ordering::Relaxed);
+                        queue.cancel("req-1");
+                    });
+                    let request = LlmRequest {
+                        system_prompt: "Tu es un assistant.".to_string(),
+                        user_prompt: "Syntheses".to_string(),
+                        constraint: OutputConstraint {
+                            name: "appreciation",
+                            gbnf: String::new(),
+                            json_schema: serde_json::json!({}),
+                        },
+                        temperature: 0.1,
+                        seed: None,
+                        max_tokens: 256,
+                        timeout: Duration::from_secs(5),
+                    };
+                    Ok(backend.complete(&request).await?)
+                }
+                .boxed()
+            }
+        };
+        let result = queue.run(spec("req-1", PromptJob::Appreciation, LlmJobPriority::Batch), job).await;
+        assert!(matches!(result, Err(AppError::Cancelled)), "{:?}", result);
+        // Deltas read in one go are all delivered before the abort: only the first is certain
+        assert!(received.load(Ordering::Relaxed) >= 1);
+        wait_until(|| state_of(&queue, "req-1") == Some(LlmJobStatus::Cancelled)).await;
+    }
+}
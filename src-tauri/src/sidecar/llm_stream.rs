@@ -0,0 +1,216 @@
+/// Streamed LLM generations for the structuration jobs (syntheses, appreciations).
+///
+/// A generation started with a request id runs its backend through
+/// `StreamingBackend`: every completion of the job (candidates, rewrites) is
+/// requested as server-sent events and each delta is emitted as a `llm_delta`
+/// event, with the text decoded so far from the partial JSON. The final content
+/// is still parsed and checked by `structuration` as a whole.
+///
+/// The request id is also the id of the job in the `llm_queue`, through which the
+/// generation is cancelled (`cancel_llm_job`).
+
+use futures::future::{BoxFuture, FutureExt};
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+use super::llm_backend::{BackendCapabilities, DeltaSink, LlmBackend, LlmRequest};
+use super::prompt_builder::TokenCounter;
+use super::types::SidecarError;
+
+/// Event carrying the deltas of a streamed generation
+pub const LLM_DELTA_EVENT: &str = "llm_delta";
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Payload of the `llm_delta` event
+#[derive(Debug, Clone, Serialize)]
+pub struct LlmDelta {
+    pub request_id: String,
+    /// LLM call of the generation, from 0 (candidates first, then rewrites)
+    pub call: usize,
+    /// Raw content received (a fragment of the JSON answer)
+    pub delta: String,
+    /// Text generated so far by this call, decoded from the partial JSON
+    pub text: String,
+}
+
+/// Backend decorator streaming every completion of a job to `emit`
+pub struct StreamingBackend<'a, F> {
+    inner: &'a dyn LlmBackend,
+    request_id: String,
+    emit: F,
+    calls: AtomicUsize,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Impls
+// ─────────────────────────────────────────────────────────────────────────────
+
+impl<'a, F: Fn(LlmDelta) + Send + Sync> StreamingBackend<'a, F> {
+    pub fn new(inner: &'a dyn LlmBackend, request_id: &str, emit: F) -> Self {
+        StreamingBackend { inner, request_id: request_id.to_string(), emit, calls: AtomicUsize::new(0) }
+    }
+}
+
+impl<F: Fn(LlmDelta) + Send + Sync> LlmBackend for StreamingBackend<'_, F> {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn is_embedded(&self) -> bool {
+        self.inner.is_embedded()
+    }
+
+    fn complete<'a>(&'a self, request: &'a LlmRequest) -> BoxFuture<'a, Result<String, SidecarError>> {
+        self.complete_stream(request, &|_| {})
+    }
+
+    fn complete_stream<'a>(
+        &'a self,
+        request: &'a LlmRequest,
+        on_delta: DeltaSink<'a>,
+    ) -> BoxFuture<'a, Result<String, SidecarError>> {
+        async move {
+            let call = self.calls.fetch_add(1, Ordering::Relaxed);
+            let raw = Mutex::new(String::new());
+            let sink = |delta: &str| {
+                let text = {
+                    let mut raw = raw.lock().unwrap();
+                    raw.push_str(delta);
+                    partial_json_text(&raw)
+                };
+                (self.emit)(LlmDelta {
+                    request_id: self.request_id.clone(),
+                    call,
+                    delta: delta.to_string(),
+                    text,
+                });
+                on_delta(delta);
+            };
+            self.inner.complete_stream(request, &sink).await
+        }
+        .boxed()
+    }
+}
+
+impl<F: Fn(LlmDelta) + Send + Sync> TokenCounter for StreamingBackend<'_, F> {
+    fn count_tokens<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Option<usize>> {
+        self.inner.count_tokens(text)
+    }
+}
+
+/// Text of the first string value of a partial JSON object
+/// (`{"synthese": "Lea lit avec ai` -> `Lea lit avec ai`). Escapes are decoded;
+/// one cut by the stream is left for the next delta.
+pub fn partial_json_text(raw: &str) -> String {
+    let Some(value) = raw.split_once(':').map(|(_, v)| v) else { return String::new() };
+    let Some((_, value)) = value.split_once('"') else { return String::new() };
+
+    let mut text = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => break,
+            '\\' => match chars.next() {
+                Some('n') => text.push('\n'),
+                Some('t') => text.push('\t'),
+                Some('r') => text.push('\r'),
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    if hex.len() < 4 {
+                        break;
+                    }
+                    let decoded = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32);
+                    text.push(decoded.unwrap_or(char::REPLACEMENT_CHARACTER));
+                }
+                Some(escaped) => text.push(escaped),
+                None => break,
+            },
+            c => text.push(c),
+        }
+    }
+    text
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Helpers AppHandle
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// `StreamingBackend` callback sending each delta to the frontend
+pub fn delta_emitter(app: &AppHandle) -> impl Fn(LlmDelta) + Send + Sync {
+    let app = app.clone();
+    move |delta| {
+        app.emit(LLM_DELTA_EVENT, delta).ok();
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sidecar::llm_backend::{LlamaServerBackend, OutputConstraint};
+    use crate::sidecar::testing::{MockSidecar, Recording};
+    use std::time::Duration;
+
+    fn request() -> LlmRequest {
+        LlmRequest {
+            system_prompt: "Tu es un assistant.".to_string(),
+            user_prompt: "Syntheses".to_string(),
+            constraint: OutputConstraint {
+                name: "appreciation",
+                gbnf: "root ::= \"{}\"".to_string(),
+                json_schema: serde_json::json!({ "type": "object" }),
+            },
+            temperature: 0.1,
+            seed: None,
+            max_tokens: 256,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    #[test]
+    fn partial_json_text_decodes_the_value_received_so_far() {
+        assert_eq!(partial_json_text(""), "");
+        assert_eq!(partial_json_text(r#"{"appreciation"#), "");
+        assert_eq!(partial_json_text(r#"{"appreciation": "Noé lit"#), "Noé lit");
+        assert_eq!(partial_json_text(r#"{"appreciation": "Il dit \"oui\".\nFin"#), "Il dit \"oui\".\nFin");
+        assert_eq!(partial_json_text(r#"{"appreciation": "Noé lit."}"#), "Noé lit.");
+        // Escapes cut by the stream wait for the next delta
+        assert_eq!(partial_json_text(r#"{"appreciation": "Lit\"#), "Lit");
+        assert_eq!(partial_json_text(r#"{"appreciation": "No\u00"#), "No");
+    }
+
+    #[tokio::test]
+    async fn every_call_is_streamed_with_its_decoded_text() {
+        let content = r#"{"appreciation": "Noé lit avec \"plaisir\"."}"#;
+        let mock = MockSidecar::spawn(vec![Recording::chat(content)]).await;
+        let llama = LlamaServerBackend::new(&mock.base_url);
+        let deltas = Mutex::new(Vec::new());
+        let backend = StreamingBackend::new(&llama, "req-1", |delta| deltas.lock().unwrap().push(delta));
+
+        assert_eq!(backend.complete(&request()).await.unwrap(), content);
+        assert_eq!(backend.complete(&request()).await.unwrap(), content);
+        assert!(backend.is_embedded());
+        drop(backend);
+
+        let deltas = deltas.into_inner().unwrap();
+        let first: Vec<&LlmDelta> = deltas.iter().filter(|d| d.call == 0).collect();
+        assert!(first.len() > 1, "{:?}", deltas);
+        assert!(deltas.iter().all(|d| d.request_id == "req-1"));
+        assert_eq!(first.iter().map(|d| d.delta.as_str()).collect::<String>(), content);
+        assert_eq!(first.last().unwrap().text, "Noé lit avec \"plaisir\".");
+        assert_eq!(deltas.last().unwrap().call, 1);
+        assert_eq!(mock.requests("/v1/chat/completions")[0].json()["stream"], true);
+    }
+}
@@ -1,7 +1,14 @@
-use super::config::{build_args, detect_pipeline_config, SidecarConfig};
+use super::config::{
+    build_args, detect_pipeline_config, local_url, resolve_port, SidecarConfig, SidecarSettings,
+};
+use super::recovery::{
+    probe_health, unix_now, wait_until_healthy, CrashHistory, ProcessExit, RestartDecision,
+    RestartPolicy, StartupOutcome, StderrTail,
+};
 use super::types::*;
+use futures::future::{BoxFuture, FutureExt};
 use log::{error, info, warn};
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_shell::ShellExt;
 use tokio::sync::Mutex;
 use tokio::time::{sleep, Instant};
@@ -10,6 +17,10 @@ struct SidecarManagerInner {
     whisper: Option<SidecarProcess>,
     llama: Option<SidecarProcess>,
     pipeline_mode: PipelineMode,
+    settings: SidecarSettings,
+    whisper_crashes: CrashHistory,
+    llama_crashes: CrashHistory,
+    restart_policy: RestartPolicy,
 }
 
 impl SidecarManagerInner {
@@ -34,6 +45,20 @@ impl SidecarManagerInner {
         }
     }
 
+    fn crashes(&self, name: SidecarName) -> &CrashHistory {
+        match name {
+            SidecarName::Whisper => &self.whisper_crashes,
+            SidecarName::Llama => &self.llama_crashes,
+        }
+    }
+
+    fn crashes_mut(&mut self, name: SidecarName) -> &mut CrashHistory {
+        match name {
+            SidecarName::Whisper => &mut self.whisper_crashes,
+            SidecarName::Llama => &mut self.llama_crashes,
+        }
+    }
+
     fn other(name: SidecarName) -> SidecarName {
         match name {
             SidecarName::Whisper => SidecarName::Llama,
@@ -59,6 +84,10 @@ impl SidecarManager {
                 whisper: None,
                 llama: None,
                 pipeline_mode: config.mode,
+                settings: SidecarSettings::default(),
+                whisper_crashes: CrashHistory::default(),
+                llama_crashes: CrashHistory::default(),
+                restart_policy: RestartPolicy::default(),
             }),
         }
     }
@@ -97,8 +126,15 @@ impl SidecarManager {
             }
         }
 
+        // Resolve the port: preferred port from settings, or a free one if taken
+        let preferred_port = inner.settings.port_for(name);
+        let port = resolve_port(preferred_port);
+        if port != preferred_port {
+            warn!("Port {} occupe, {} demarre sur le port {}", preferred_port, name, port);
+        }
+
         // Spawn the sidecar process
-        let args = build_args(name, &model_path, grammar_path.as_deref());
+        let args = build_args(name, &model_path, grammar_path.as_deref(), port, &inner.settings);
         let shell = app.shell();
 
         let command = match shell.sidecar(config.binary_name) {
@@ -127,75 +163,88 @@ impl SidecarManager {
                 }
             })?;
 
-        // Spawn background task to drain stdout/stderr (prevents process from blocking)
+        // Spawn background task to drain stdout/stderr (prevents process from blocking).
+        // On termination it wakes the healthcheck below (exit during startup) and
+        // reports to handle_exit (crash after startup).
+        let pid = child.pid();
+        let (exit_tx, mut exit_rx) = tokio::sync::oneshot::channel::<ProcessExit>();
         let app_clone = app.clone();
         let sidecar_name = name;
         tauri::async_runtime::spawn(async move {
             use tauri_plugin_shell::process::CommandEvent;
 
             let mut rx = rx;
+            let mut stderr = StderrTail::default();
+            let mut exit = ProcessExit::default();
             while let Some(event) = rx.recv().await {
                 match event {
                     CommandEvent::Stdout(line) => {
                         info!("[{} stdout] {}", sidecar_name, String::from_utf8_lossy(&line));
                     }
                     CommandEvent::Stderr(line) => {
-                        warn!("[{} stderr] {}", sidecar_name, String::from_utf8_lossy(&line));
+                        let line = String::from_utf8_lossy(&line);
+                        warn!("[{} stderr] {}", sidecar_name, line);
+                        stderr.push(&line);
                     }
                     CommandEvent::Terminated(payload) => {
-                        let code = payload.code.unwrap_or(-1);
-                        if code != 0 {
-                            error!("[{}] Processus termine avec code {}", sidecar_name, code);
-                            let _ = app_clone.emit("sidecar_error", SidecarEvent {
-                                name: sidecar_name.to_string(),
-                                reason: None,
-                                error: Some(format!("Processus termine avec code {}", code)),
-                            });
-                        }
+                        exit.code = payload.code;
+                        exit.signal = payload.signal;
                         break;
                     }
                     CommandEvent::Error(err) => {
                         error!("[{}] Erreur: {}", sidecar_name, err);
-                        break;
+                        stderr.push(&err);
                     }
                     _ => {}
                 }
             }
+            exit.stderr_tail = stderr.lines();
+            let _ = exit_tx.send(exit.clone());
+            app_clone
+                .state::<SidecarManager>()
+                .handle_exit(&app_clone, sidecar_name, pid, exit)
+                .await;
         });
 
         // Polling healthcheck
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(2))
-            .build()
-            .map_err(|e| SidecarError::Internal(e.to_string()))?;
-
+        let healthcheck_url = local_url(port, config.healthcheck_path);
         let start_time = Instant::now();
-        let healthcheck_ok = loop {
-            if start_time.elapsed() >= config.healthcheck_timeout {
-                break false;
-            }
-            sleep(config.healthcheck_interval).await;
-
-            match client.get(&config.healthcheck_url).send().await {
-                Ok(resp) if resp.status().is_success() => break true,
-                _ => continue,
-            }
-        };
-
-        if !healthcheck_ok {
-            // Timeout: kill the child process
-            // Note: child has been moved, we can't kill it directly here.
-            // The background task will detect the termination.
+        let outcome = wait_until_healthy(
+            &healthcheck_url,
+            config.healthcheck_timeout,
+            config.healthcheck_interval,
+            &mut exit_rx,
+        )
+        .await?;
+
+        if let StartupOutcome::TimedOut = outcome {
+            // Timeout: kill the process so it does not keep the port
+            let _ = child.kill();
             return Err(SidecarError::HealthcheckTimeout(name));
         }
 
+        if let StartupOutcome::Exited(exit) = outcome {
+            let summary = exit.summary();
+            error!("[{}] Echec au demarrage: {}", name, summary);
+            let uptime = start_time.elapsed().as_secs();
+            inner.crashes_mut(name).record(exit.into_record(unix_now(), uptime));
+            let _ = app.emit("sidecar_error", SidecarEvent {
+                name: name.to_string(),
+                reason: Some("Echec au demarrage".to_string()),
+                error: Some(summary.clone()),
+            });
+            return Err(SidecarError::StartFailed(name, summary));
+        }
+
         // Store process info and emit event
         inner.set(name, SidecarProcess {
             child,
-            port: config.port,
+            pid,
+            port,
             request_count: 0,
             max_requests: config.max_requests,
             started_at: std::time::Instant::now(),
+            last_used: std::time::Instant::now(),
             model_path: model_path.clone(),
             grammar_path: grammar_path.clone(),
         });
@@ -206,7 +255,7 @@ impl SidecarManager {
             error: None,
         });
 
-        info!("Sidecar {} demarre sur le port {}", name, config.port);
+        info!("Sidecar {} demarre sur le port {}", name, port);
         Ok(())
     }
 
@@ -253,11 +302,119 @@ impl SidecarManager {
 
     pub async fn get_status(&self) -> SidecarStatusResponse {
         let inner = self.inner.lock().await;
+        let now = unix_now();
 
         SidecarStatusResponse {
-            whisper: Self::instance_status(&inner.whisper),
-            llama: Self::instance_status(&inner.llama),
+            whisper: Self::instance_status(&inner, SidecarName::Whisper, now),
+            llama: Self::instance_status(&inner, SidecarName::Llama, now),
+        }
+    }
+
+    /// Called by the drain task when a sidecar process terminates.
+    /// Exits requested by the manager (stop, restart, sequential switch) are ignored:
+    /// the slot no longer holds that process. Otherwise the stale slot is cleared and,
+    /// for a crash, the restart policy decides whether to start it again.
+    pub async fn handle_exit(
+        &self,
+        app: &AppHandle,
+        name: SidecarName,
+        pid: u32,
+        exit: ProcessExit,
+    ) {
+        let (decision, model_path, grammar_path) = {
+            let mut inner = self.inner.lock().await;
+            if inner.get(name).as_ref().map(|p| p.pid) != Some(pid) {
+                return;
+            }
+            let Some(process) = inner.take(name) else {
+                return;
+            };
+
+            if exit.is_clean() {
+                info!("[{}] Processus termine sans erreur, sidecar marque arrete", name);
+                let _ = app.emit("sidecar_stopped", SidecarEvent {
+                    name: name.to_string(),
+                    reason: Some("Processus termine".to_string()),
+                    error: None,
+                });
+                return;
+            }
+
+            let summary = exit.summary();
+            error!("[{}] Crash: {}", name, summary);
+            let now = unix_now();
+            let uptime = process.started_at.elapsed().as_secs();
+            inner.crashes_mut(name).record(exit.into_record(now, uptime));
+            let policy = inner.restart_policy;
+            let decision = policy.decide(inner.crashes(name), now);
+
+            let reason = match decision {
+                RestartDecision::Restart(delay) => {
+                    format!("Redemarrage automatique dans {}s", delay.as_secs())
+                }
+                RestartDecision::CrashLoop => {
+                    "Crashs repetes : redemarrage automatique suspendu".to_string()
+                }
+            };
+            let _ = app.emit("sidecar_error", SidecarEvent {
+                name: name.to_string(),
+                reason: Some(reason),
+                error: Some(summary),
+            });
+            (decision, process.model_path, process.grammar_path)
+        };
+
+        match decision {
+            RestartDecision::CrashLoop => {
+                error!("[{}] Crash en boucle, pas de redemarrage automatique", name);
+            }
+            RestartDecision::Restart(delay) => {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    sleep(delay).await;
+                    app.state::<SidecarManager>()
+                        .restart_after_crash(&app, name, model_path, grammar_path)
+                        .await;
+                });
+            }
+        }
+    }
+
+    /// Restart scheduled by handle_exit. Skipped if the sidecar was started in the
+    /// meantime, or if the other sidecar now runs in sequential mode (it will be
+    /// started on the next request instead of interrupting the current task).
+    /// Boxed: start() spawns the drain task that leads back here.
+    fn restart_after_crash<'a>(
+        &'a self,
+        app: &'a AppHandle,
+        name: SidecarName,
+        model_path: String,
+        grammar_path: Option<String>,
+    ) -> BoxFuture<'a, ()> {
+        async move {
+            {
+                let inner = self.inner.lock().await;
+                if inner.get(name).is_some() {
+                    return;
+                }
+                let other = SidecarManagerInner::other(name);
+                if inner.pipeline_mode == PipelineMode::Sequential && inner.get(other).is_some() {
+                    info!("Pipeline sequentiel: {} sera relance a la prochaine demande", name);
+                    return;
+                }
+            }
+
+            info!("Redemarrage de {} apres crash", name);
+            let _ = app.emit("sidecar_restarting", SidecarEvent {
+                name: name.to_string(),
+                reason: Some("Redemarrage apres crash".to_string()),
+                error: None,
+            });
+            if let Err(e) = self.start(app, name, model_path, grammar_path).await {
+                error!("Echec du redemarrage de {} apres crash: {}", name, e);
+            }
         }
+        .boxed()
     }
 
     pub async fn increment_request_count(&self, name: SidecarName) {
@@ -268,6 +425,36 @@ impl SidecarManager {
         };
         if let Some(p) = process {
             p.request_count += 1;
+            p.last_used = std::time::Instant::now();
+        }
+    }
+
+    /// Stop the sidecars unused for longer than the idle timeout (concurrent mode only).
+    /// `busy` sidecars are serving a dictation or an LLM job and are kept.
+    pub async fn stop_idle(&self, app: &AppHandle, busy: &[SidecarName]) {
+        let mut inner = self.inner.lock().await;
+        let Some(timeout) = inner.settings.idle_timeout(inner.pipeline_mode) else {
+            return;
+        };
+
+        for name in [SidecarName::Whisper, SidecarName::Llama] {
+            let idle = inner
+                .get(name)
+                .as_ref()
+                .is_some_and(|p| p.last_used.elapsed() >= timeout);
+            if !idle || busy.contains(&name) {
+                continue;
+            }
+            if let Some(process) = inner.take(name) {
+                let _ = process.child.kill();
+                let minutes = timeout.as_secs() / 60;
+                let _ = app.emit("sidecar_stopped", SidecarEvent {
+                    name: name.to_string(),
+                    reason: Some(format!("Inactif depuis {} min", minutes)),
+                    error: None,
+                });
+                info!("Sidecar {} arrete apres {} min d'inactivite", name, minutes);
+            }
         }
     }
 
@@ -313,11 +500,11 @@ impl SidecarManager {
         let config = SidecarConfig::for_sidecar(name);
 
         // Check request count threshold (preventive restart for handle leak)
-        let should_restart = {
+        let (should_restart, port) = {
             let inner = self.inner.lock().await;
             match inner.get(name) {
-                Some(p) => p.max_requests > 0 && p.request_count >= p.max_requests,
-                None => false,
+                Some(p) => (p.max_requests > 0 && p.request_count >= p.max_requests, Some(p.port)),
+                None => (false, None),
             }
         };
 
@@ -333,35 +520,22 @@ impl SidecarManager {
             return true;
         }
 
-        // Post-request healthcheck (3 attempts)
-        let client = match reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(2))
-            .build()
-        {
-            Ok(c) => c,
-            Err(_) => return false,
+        // Sidecar already stopped: nothing to check
+        let Some(port) = port else {
+            return false;
         };
+        let healthcheck_url = local_url(port, config.healthcheck_path);
 
-        let mut failures = 0;
-        for _ in 0..3 {
-            match client.get(&config.healthcheck_url).send().await {
-                Ok(resp) if resp.status().is_success() => return false, // Healthy
-                _ => {
-                    failures += 1;
-                    sleep(std::time::Duration::from_millis(200)).await;
-                }
-            }
+        // Post-request healthcheck (3 attempts)
+        if probe_health(&healthcheck_url, 3, std::time::Duration::from_millis(200)).await {
+            return false;
         }
 
-        if failures >= 3 {
-            warn!("Watchdog: healthcheck echoue 3 fois pour {}, redemarrage", name);
-            if let Err(e) = self.restart(app, name, "Healthcheck echoue 3 fois consecutives").await {
-                error!("Watchdog: echec redemarrage de {}: {}", name, e);
-            }
-            return true;
+        warn!("Watchdog: healthcheck echoue 3 fois pour {}, redemarrage", name);
+        if let Err(e) = self.restart(app, name, "Healthcheck echoue 3 fois consecutives").await {
+            error!("Watchdog: echec redemarrage de {}: {}", name, e);
         }
-
-        false
+        true
     }
 
     /// Auto-stop a sidecar after task completion (sequential mode only).
@@ -384,7 +558,6 @@ impl SidecarManager {
     }
 
     /// Check if the pipeline is in sequential mode
-    #[allow(dead_code)] // Available for future use
     pub async fn is_sequential(&self) -> bool {
         let inner = self.inner.lock().await;
         inner.pipeline_mode == PipelineMode::Sequential
@@ -408,19 +581,85 @@ impl SidecarManager {
         inner.pipeline_mode = mode;
     }
 
-    fn instance_status(process: &Option<SidecarProcess>) -> SidecarInstanceStatus {
-        match process {
+    /// Start a sidecar unless it is already running with the same model.
+    /// A sidecar running another model (active model changed in the catalog) is restarted.
+    pub async fn ensure_running(
+        &self,
+        app: &AppHandle,
+        name: SidecarName,
+        model_path: String,
+    ) -> Result<(), SidecarError> {
+        let running_model = {
+            let mut inner = self.inner.lock().await;
+            let process = match name {
+                SidecarName::Whisper => &mut inner.whisper,
+                SidecarName::Llama => &mut inner.llama,
+            };
+            // A task is about to use it: the idle delay starts over
+            process.as_mut().map(|p| {
+                p.last_used = std::time::Instant::now();
+                p.model_path.clone()
+            })
+        };
+        match running_model {
+            Some(current) if current == model_path => Ok(()),
+            Some(current) => {
+                info!("{}: modele actif change ({} -> {}), redemarrage", name, current, model_path);
+                self.start(app, name, model_path, None).await
+            }
+            None => {
+                info!("{} non demarre, lancement automatique...", name);
+                self.start(app, name, model_path, None).await
+            }
+        }
+    }
+
+    /// Base URL (`http://127.0.0.1:<port>`) of a running sidecar, using the port it actually bound.
+    pub async fn base_url(&self, name: SidecarName) -> Result<String, SidecarError> {
+        let inner = self.inner.lock().await;
+        match inner.get(name) {
+            Some(p) => Ok(local_url(p.port, "")),
+            None => Err(SidecarError::NotRunning(name)),
+        }
+    }
+
+    /// Current sidecar settings (ports, threads, ctx-size, GPU layers)
+    pub async fn get_settings(&self) -> SidecarSettings {
+        let inner = self.inner.lock().await;
+        inner.settings.clone()
+    }
+
+    /// Replace the sidecar settings. Running sidecars keep their current
+    /// arguments until their next (re)start.
+    pub async fn set_settings(&self, settings: SidecarSettings) {
+        let mut inner = self.inner.lock().await;
+        info!("Parametres sidecar mis a jour: {:?}", settings);
+        inner.settings = settings;
+    }
+
+    fn instance_status(
+        inner: &SidecarManagerInner,
+        name: SidecarName,
+        now: u64,
+    ) -> SidecarInstanceStatus {
+        let crashes = inner.crashes(name);
+        let crash_loop = inner.restart_policy.in_crash_loop(crashes, now);
+        match inner.get(name) {
             Some(p) => SidecarInstanceStatus {
                 running: true,
                 port: Some(p.port),
                 request_count: Some(p.request_count),
                 uptime_secs: Some(p.started_at.elapsed().as_secs()),
+                crash_loop,
+                crashes: crashes.records(),
             },
             None => SidecarInstanceStatus {
                 running: false,
                 port: None,
                 request_count: None,
                 uptime_secs: None,
+                crash_loop,
+                crashes: crashes.records(),
             },
         }
     }
@@ -1,10 +1,23 @@
 pub mod commands;
 pub mod config;
+pub mod corrections;
 pub mod gbnf;
+pub mod idle;
+pub mod llm_backend;
+pub mod llm_queue;
+pub mod llm_stream;
 pub mod manager;
 pub mod prompt_builder;
+pub mod prompt_templates;
+pub mod recovery;
+pub mod streaming;
 pub mod structuration;
+pub mod synthese_context;
+pub mod text_rules;
+#[cfg(test)]
+pub(crate) mod testing;
 pub mod transcription;
 pub mod types;
+pub mod vocabulary;
 
 pub use manager::SidecarManager;
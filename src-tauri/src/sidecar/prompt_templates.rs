@@ -0,0 +1,512 @@
+/// Editable, versioned system prompts of the LLM jobs (classification, synthese,
+/// appreciation).
+///
+/// Templates live in prompt_templates (M020), one row per version: saving adds a
+/// version, nothing is overwritten. `cycle` 0 is the template of every cycle, 1-3
+/// a per-cycle override. A job uses the override of the student's cycle, else the
+/// all-cycles template, else the built-in default (seeded as a version on first
+/// use, so every generation has a template id). Resetting an override adds a
+/// version without content (the all-cycles template applies again); resetting the
+/// all-cycles template adds a version with the built-in text.
+///
+/// Placeholders: {eleve}, {domaine} (synthese only), {niveau}, {cycle},
+/// {longueur_max}. Generated syntheses and appreciations record the template id.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// Longest template accepted
+const MAX_TEMPLATE_CHARS: usize = 4000;
+
+/// Bounds of the `longueur_max` placeholder (appreciations_generales allows 1500 chars)
+const MIN_LENGTH: i64 = 50;
+const MAX_LENGTH: i64 = 1500;
+
+const DEFAULT_CLASSIFICATION: &str = r#"Assistant pedagogique. Classe la dictee dans les domaines mentionnes.
+Reponds en JSON : [{"domaine_id": N, "observation_mise_a_jour": "texte"}]
+
+REGLES :
+- Si la dictee mentionne PLUSIEURS domaines, cree un item SEPARE pour chaque domaine. Maximum 3 items.
+- UNIQUEMENT les domaines nommes ou clairement evoques dans la dictee.
+- Garde les details importants (points forts, difficultes, conseils). 2-3 phrases par domaine.
+- Chaque observation concerne UNIQUEMENT son domaine. Ne melange pas le contenu.
+- Corrige fautes de transcription. Style ecrit professionnel.
+- Si observation existante : fusionne ancien + nouveau."#;
+
+const DEFAULT_SYNTHESE: &str = r#"Assistant pedagogique. Tu rediges une synthese pour le Livret Scolaire Unique (LSU).
+A partir des observations et evaluations, produis un texte de synthese coherent.
+
+REGLES :
+- Style ecrit professionnel, 3e personne ("L'eleve...").
+- 3-5 phrases. Maximum {longueur_max} caracteres.
+- Mentionne progres, points forts et axes d'amelioration.
+- Jamais de jugement global negatif. Toujours constructif.
+- Reponds en JSON : { "synthese": "texte" }"#;
+
+const DEFAULT_APPRECIATION: &str = r#"Assistant pedagogique. Tu rediges l'appreciation generale pour le Livret Scolaire Unique (LSU).
+A partir des syntheses par domaine et du comportement, produis un texte transversal.
+
+REGLES :
+- Style bienveillant, 3e personne ("L'eleve...").
+- 3-6 phrases. Maximum {longueur_max} caracteres.
+- Mentionne domaines ou l'eleve excelle et axes de progres.
+- JAMAIS punitif. Toujours encourageant et constructif.
+- Ne mentionne pas sanctions directement. Evoque le comportement positivement.
+- Reponds en JSON : { "appreciation": "texte" }"#;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptJob {
+    Classification,
+    Synthese,
+    Appreciation,
+}
+
+/// One version of a template. `contenu` None: reset of a per-cycle override.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PromptTemplate {
+    pub id: i64,
+    pub job: String,
+    /// 0 = every cycle
+    pub cycle: i64,
+    pub version: i64,
+    pub contenu: Option<String>,
+    pub longueur_max: i64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewPromptTemplate {
+    pub job: PromptJob,
+    #[serde(default)]
+    pub cycle: i64,
+    pub contenu: String,
+    #[serde(default)]
+    pub longueur_max: Option<i64>,
+}
+
+/// Values substituted in a template. Missing values render as "non precise".
+#[derive(Debug, Clone, Default)]
+pub struct TemplateVars<'a> {
+    pub eleve: &'a str,
+    pub domaine: Option<&'a str>,
+    pub niveau: Option<&'a str>,
+    pub cycle: Option<i64>,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Impls
+// ─────────────────────────────────────────────────────────────────────────────
+
+impl PromptJob {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PromptJob::Classification => "classification",
+            PromptJob::Synthese => "synthese",
+            PromptJob::Appreciation => "appreciation",
+        }
+    }
+
+    pub fn default_content(self) -> &'static str {
+        match self {
+            PromptJob::Classification => DEFAULT_CLASSIFICATION,
+            PromptJob::Synthese => DEFAULT_SYNTHESE,
+            PromptJob::Appreciation => DEFAULT_APPRECIATION,
+        }
+    }
+
+    pub fn default_max_length(self) -> i64 {
+        match self {
+            PromptJob::Classification => 400,
+            PromptJob::Synthese => 300,
+            PromptJob::Appreciation => 500,
+        }
+    }
+
+    fn placeholders(self) -> &'static [&'static str] {
+        match self {
+            PromptJob::Synthese => &["eleve", "domaine", "niveau", "cycle", "longueur_max"],
+            PromptJob::Classification | PromptJob::Appreciation => &["eleve", "niveau", "cycle", "longueur_max"],
+        }
+    }
+}
+
+impl PromptTemplate {
+    /// System prompt with the placeholders substituted
+    pub fn render(&self, vars: &TemplateVars) -> String {
+        render(self.contenu.as_deref().unwrap_or_default(), self.longueur_max, vars)
+    }
+}
+
+/// `{name}` placeholders of a template (JSON braces such as `{ "synthese": ... }` are not)
+fn placeholders_in(contenu: &str) -> Vec<&str> {
+    let mut found = Vec::new();
+    let mut rest = contenu;
+    while let Some(open) = rest.find('{') {
+        rest = &rest[open + 1..];
+        let name_len = rest
+            .find(|c: char| !(c.is_ascii_lowercase() || c == '_'))
+            .unwrap_or(rest.len());
+        if name_len > 0 && rest[name_len..].starts_with('}') {
+            found.push(&rest[..name_len]);
+        }
+    }
+    found
+}
+
+/// Single pass (`placeholders::fill`): a student name containing `{domaine}` stays as typed.
+pub fn render(contenu: &str, longueur_max: i64, vars: &TemplateVars) -> String {
+    let unknown = "non precise";
+    let cycle = vars.cycle.map_or_else(|| unknown.to_string(), |c| c.to_string());
+    let longueur_max = longueur_max.to_string();
+    crate::placeholders::fill(
+        contenu,
+        "{",
+        "}",
+        &[
+            ("eleve", vars.eleve),
+            ("domaine", vars.domaine.unwrap_or(unknown)),
+            ("niveau", vars.niveau.unwrap_or(unknown)),
+            ("cycle", &cycle),
+            ("longueur_max", &longueur_max),
+        ],
+    )
+}
+
+fn validate_scope(cycle: i64) -> Result<(), AppError> {
+    if !(0..=3).contains(&cycle) {
+        return Err(AppError::validation("Le cycle doit être compris entre 1 et 3 (0 = tous les cycles)"));
+    }
+    Ok(())
+}
+
+fn validate_template(template: &NewPromptTemplate) -> Result<(), AppError> {
+    validate_scope(template.cycle)?;
+    let contenu = template.contenu.trim();
+    if contenu.is_empty() {
+        return Err(AppError::validation("Le modèle de prompt est vide"));
+    }
+    if contenu.chars().count() > MAX_TEMPLATE_CHARS {
+        return Err(AppError::validation(format!(
+            "Le modèle de prompt ne doit pas dépasser {} caractères",
+            MAX_TEMPLATE_CHARS
+        )));
+    }
+    let allowed = template.job.placeholders();
+    if let Some(name) = placeholders_in(contenu).into_iter().find(|p| !allowed.contains(p)) {
+        return Err(AppError::validation(format!(
+            "Variable inconnue pour ce modèle : {{{}}} (disponibles : {})",
+            name,
+            allowed.iter().map(|p| format!("{{{}}}", p)).collect::<Vec<_>>().join(", ")
+        )));
+    }
+    if let Some(length) = template.longueur_max {
+        if !(MIN_LENGTH..=MAX_LENGTH).contains(&length) {
+            return Err(AppError::validation(format!(
+                "La longueur maximale doit être comprise entre {} et {} caractères",
+                MIN_LENGTH, MAX_LENGTH
+            )));
+        }
+    }
+    Ok(())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Impls (testables, prennent une connexion)
+// ─────────────────────────────────────────────────────────────────────────────
+
+const SELECT_TEMPLATES: &str =
+    "SELECT id, job, cycle, version, contenu, longueur_max, created_at FROM prompt_templates";
+
+/// Current version of every (job, cycle) scope that has one
+pub async fn list_templates_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+) -> Result<Vec<PromptTemplate>, AppError> {
+    sqlx::query_as::<_, PromptTemplate>(&format!(
+        "{} t WHERE version = (SELECT MAX(version) FROM prompt_templates
+                               WHERE job = t.job AND cycle = t.cycle)
+         ORDER BY job, cycle",
+        SELECT_TEMPLATES
+    ))
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur lecture des modèles de prompt", e))
+}
+
+/// History of a scope, most recent first
+pub async fn list_template_versions_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    job: PromptJob,
+    cycle: i64,
+) -> Result<Vec<PromptTemplate>, AppError> {
+    sqlx::query_as::<_, PromptTemplate>(&format!(
+        "{} WHERE job = ? AND cycle = ? ORDER BY version DESC",
+        SELECT_TEMPLATES
+    ))
+    .bind(job.as_str())
+    .bind(cycle)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur lecture des versions du modèle", e))
+}
+
+async fn latest_version(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    job: PromptJob,
+    cycle: i64,
+) -> Result<Option<PromptTemplate>, AppError> {
+    sqlx::query_as::<_, PromptTemplate>(&format!(
+        "{} WHERE job = ? AND cycle = ? ORDER BY version DESC LIMIT 1",
+        SELECT_TEMPLATES
+    ))
+    .bind(job.as_str())
+    .bind(cycle)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur lecture du modèle de prompt", e))
+}
+
+async fn insert_version(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    job: PromptJob,
+    cycle: i64,
+    contenu: Option<&str>,
+    longueur_max: i64,
+) -> Result<PromptTemplate, AppError> {
+    let id = sqlx::query(
+        "INSERT INTO prompt_templates (job, cycle, version, contenu, longueur_max)
+         VALUES (?, ?, (SELECT COALESCE(MAX(version), 0) + 1 FROM prompt_templates WHERE job = ? AND cycle = ?), ?, ?)",
+    )
+    .bind(job.as_str())
+    .bind(cycle)
+    .bind(job.as_str())
+    .bind(cycle)
+    .bind(contenu)
+    .bind(longueur_max)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur enregistrement du modèle de prompt", e))?
+    .last_insert_rowid();
+
+    sqlx::query_as::<_, PromptTemplate>(&format!("{} WHERE id = ?", SELECT_TEMPLATES))
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| AppError::db("Erreur lecture du modèle de prompt", e))
+}
+
+/// Save a template as the new version of its scope
+pub async fn save_template_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    template: &NewPromptTemplate,
+) -> Result<PromptTemplate, AppError> {
+    validate_template(template)?;
+    let longueur_max = template
+        .longueur_max
+        .unwrap_or_else(|| template.job.default_max_length());
+    insert_version(conn, template.job, template.cycle, Some(template.contenu.trim()), longueur_max).await
+}
+
+/// Back to the default: a per-cycle override is removed, the all-cycles template
+/// gets the built-in text. Both add a version.
+pub async fn reset_template_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    job: PromptJob,
+    cycle: i64,
+) -> Result<PromptTemplate, AppError> {
+    validate_scope(cycle)?;
+    let contenu = (cycle == 0).then(|| job.default_content());
+    insert_version(conn, job, cycle, contenu, job.default_max_length()).await
+}
+
+/// Template to use for a student of `cycle` (None: niveau unknown)
+pub async fn resolve_template_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    job: PromptJob,
+    cycle: Option<i64>,
+) -> Result<PromptTemplate, AppError> {
+    if let Some(cycle) = cycle.filter(|c| (1..=3).contains(c)) {
+        if let Some(template) = latest_version(conn, job, cycle).await? {
+            if template.contenu.is_some() {
+                return Ok(template);
+            }
+        }
+    }
+    match latest_version(conn, job, 0).await? {
+        Some(template) if template.contenu.is_some() => Ok(template),
+        _ => reset_template_impl(conn, job, 0).await,
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Commandes Tauri
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_prompt_templates(app: tauri::AppHandle) -> Result<Vec<PromptTemplate>, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
+    list_templates_impl(&mut conn).await
+}
+
+#[tauri::command]
+pub async fn get_prompt_template_versions(
+    app: tauri::AppHandle,
+    job: PromptJob,
+    cycle: i64,
+) -> Result<Vec<PromptTemplate>, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
+    list_template_versions_impl(&mut conn, job, cycle).await
+}
+
+#[tauri::command]
+pub async fn save_prompt_template(
+    app: tauri::AppHandle,
+    template: NewPromptTemplate,
+) -> Result<PromptTemplate, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
+    save_template_impl(&mut conn, &template).await
+}
+
+#[tauri::command]
+pub async fn reset_prompt_template(
+    app: tauri::AppHandle,
+    job: PromptJob,
+    cycle: i64,
+) -> Result<PromptTemplate, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
+    reset_template_impl(&mut conn, job, cycle).await
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::Connection;
+
+    async fn setup_test_db() -> (sqlx::sqlite::SqliteConnection, tempfile::NamedTempFile) {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let url = format!("sqlite:{}", tmp.path().display());
+        let mut conn = sqlx::sqlite::SqliteConnection::connect(&url)
+            .await
+            .expect("Impossible de creer la DB de test");
+        sqlx::query(PROMPT_TEMPLATES_TABLE).execute(&mut conn).await.unwrap();
+        (conn, tmp)
+    }
+
+    /// Same table as migration M020
+    const PROMPT_TEMPLATES_TABLE: &str = "CREATE TABLE prompt_templates (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        job TEXT NOT NULL CHECK(job IN ('classification', 'synthese', 'appreciation')),
+        cycle INTEGER NOT NULL DEFAULT 0 CHECK(cycle BETWEEN 0 AND 3),
+        version INTEGER NOT NULL,
+        contenu TEXT DEFAULT NULL,
+        longueur_max INTEGER NOT NULL,
+        created_at TEXT DEFAULT (datetime('now')),
+        UNIQUE(job, cycle, version)
+    )";
+
+    fn new_template(job: PromptJob, cycle: i64, contenu: &str) -> NewPromptTemplate {
+        NewPromptTemplate { job, cycle, contenu: contenu.to_string(), longueur_max: None }
+    }
+
+    #[test]
+    fn placeholders_are_rendered_and_json_braces_kept() {
+        let vars = TemplateVars { eleve: "Léa", domaine: Some("Francais"), niveau: Some("CE2"), cycle: Some(2) };
+        let text = render(
+            "{eleve} ({niveau}, cycle {cycle}) en {domaine} : {longueur_max} car. max. JSON : { \"synthese\": \"texte\" }",
+            250,
+            &vars,
+        );
+        assert_eq!(
+            text,
+            "Léa (CE2, cycle 2) en Francais : 250 car. max. JSON : { \"synthese\": \"texte\" }"
+        );
+        assert_eq!(render("{niveau}", 0, &TemplateVars::default()), "non precise");
+
+        // Values are not rendered again
+        let odd = TemplateVars { eleve: "Léa {domaine}", domaine: Some("{longueur_max}"), ..vars };
+        assert_eq!(render("{eleve} / {domaine}", 250, &odd), "Léa {domaine} / {longueur_max}");
+        assert_eq!(placeholders_in(DEFAULT_CLASSIFICATION), Vec::<&str>::new());
+        assert_eq!(placeholders_in(DEFAULT_SYNTHESE), vec!["longueur_max"]);
+    }
+
+    #[test]
+    fn validation_rejects_unknown_placeholders_and_bad_scopes() {
+        assert!(validate_template(&new_template(PromptJob::Synthese, 2, "Pour {eleve} en {domaine}")).is_ok());
+        let err = validate_template(&new_template(PromptJob::Appreciation, 0, "Pour {eleve} en {domaine}"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("{domaine}"), "{}", err);
+        assert!(validate_template(&new_template(PromptJob::Synthese, 4, "Texte")).is_err());
+        assert!(validate_template(&new_template(PromptJob::Synthese, 0, "  ")).is_err());
+        let mut long = new_template(PromptJob::Synthese, 0, "Texte");
+        long.longueur_max = Some(5000);
+        assert!(validate_template(&long).is_err());
+        // Every default is a valid template
+        for job in [PromptJob::Classification, PromptJob::Synthese, PromptJob::Appreciation] {
+            assert!(validate_template(&new_template(job, 0, job.default_content())).is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn resolution_prefers_the_cycle_override_then_falls_back() {
+        let (mut conn, _tmp) = setup_test_db().await;
+
+        // First use seeds the built-in default as version 1
+        let seeded = resolve_template_impl(&mut conn, PromptJob::Synthese, Some(2)).await.unwrap();
+        assert_eq!((seeded.cycle, seeded.version), (0, 1));
+        assert_eq!(seeded.contenu.as_deref(), Some(DEFAULT_SYNTHESE));
+        let again = resolve_template_impl(&mut conn, PromptJob::Synthese, Some(2)).await.unwrap();
+        assert_eq!(again.id, seeded.id);
+
+        let c3 = save_template_impl(&mut conn, &new_template(PromptJob::Synthese, 3, "Cycle 3 : {longueur_max} car."))
+            .await
+            .unwrap();
+        assert_eq!(c3.version, 1);
+        let resolved = resolve_template_impl(&mut conn, PromptJob::Synthese, Some(3)).await.unwrap();
+        assert_eq!(resolved.id, c3.id);
+        assert_eq!(resolved.render(&TemplateVars::default()), "Cycle 3 : 300 car.");
+        // Other cycles and unknown niveau keep the all-cycles template
+        assert_eq!(resolve_template_impl(&mut conn, PromptJob::Synthese, Some(2)).await.unwrap().id, seeded.id);
+        assert_eq!(resolve_template_impl(&mut conn, PromptJob::Synthese, None).await.unwrap().id, seeded.id);
+
+        // Reset of the override: a new, empty version
+        let reset = reset_template_impl(&mut conn, PromptJob::Synthese, 3).await.unwrap();
+        assert_eq!((reset.version, reset.contenu.as_deref()), (2, None));
+        assert_eq!(resolve_template_impl(&mut conn, PromptJob::Synthese, Some(3)).await.unwrap().id, seeded.id);
+    }
+
+    #[tokio::test]
+    async fn saving_adds_versions_and_reset_restores_the_default_text() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let v1 = save_template_impl(&mut conn, &new_template(PromptJob::Appreciation, 0, "Ton sobre."))
+            .await
+            .unwrap();
+        let v2 = save_template_impl(&mut conn, &new_template(PromptJob::Appreciation, 0, "Ton chaleureux."))
+            .await
+            .unwrap();
+        assert_eq!((v1.version, v2.version), (1, 2));
+        save_template_impl(&mut conn, &new_template(PromptJob::Synthese, 1, "Cycle 1.")).await.unwrap();
+
+        let current = list_templates_impl(&mut conn).await.unwrap();
+        let scopes: Vec<(&str, i64, i64)> =
+            current.iter().map(|t| (t.job.as_str(), t.cycle, t.version)).collect();
+        assert_eq!(scopes, vec![("appreciation", 0, 2), ("synthese", 1, 1)]);
+
+        let reset = reset_template_impl(&mut conn, PromptJob::Appreciation, 0).await.unwrap();
+        assert_eq!(reset.version, 3);
+        assert_eq!(reset.contenu.as_deref(), Some(DEFAULT_APPRECIATION));
+        let history = list_template_versions_impl(&mut conn, PromptJob::Appreciation, 0).await.unwrap();
+        let versions: Vec<i64> = history.iter().map(|t| t.version).collect();
+        assert_eq!(versions, vec![3, 2, 1]);
+        assert_eq!(history[2].contenu.as_deref(), Some("Ton sobre."));
+    }
+}
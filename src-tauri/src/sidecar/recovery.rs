@@ -0,0 +1,352 @@
+// Sidecar crash recovery: crash history and automatic restart policy
+//
+// Each sidecar's drain task reports the process termination to the manager. An
+// exit the manager did not ask for (its slot still holds that process) is a crash:
+// it is recorded with the exit code and the last stderr lines, the slot is cleared
+// and the sidecar is restarted after an exponential backoff. More than
+// `max_restarts` crashes within `window_s` is a crash loop: automatic restarts stop
+// until the window has passed (an on-demand start still works).
+//
+// The healthchecks live here too: the startup poll is where an early exit is
+// detected, and a failed post-request probe triggers a watchdog restart.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use super::types::SidecarError;
+
+/// stderr lines kept for the crash report
+pub const STDERR_TAIL_LINES: usize = 20;
+
+/// Crash records kept per sidecar
+pub const MAX_CRASH_HISTORY: usize = 10;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RestartPolicy {
+    pub max_restarts: usize,
+    pub window_s: u64,
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy {
+            max_restarts: 3,
+            window_s: 300,
+            base_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartDecision {
+    Restart(Duration),
+    CrashLoop,
+}
+
+impl RestartPolicy {
+    /// Delay before the restart following the n-th recent crash (1 → base, doubling)
+    pub fn backoff(&self, recent_crashes: usize) -> Duration {
+        let exponent = recent_crashes.saturating_sub(1).min(16) as u32;
+        self.base_backoff
+            .saturating_mul(1u32 << exponent)
+            .min(self.max_backoff)
+    }
+
+    /// Decision after a crash already recorded in `history`
+    pub fn decide(&self, history: &CrashHistory, now_s: u64) -> RestartDecision {
+        let recent = history.recent(now_s, self.window_s);
+        if recent > self.max_restarts {
+            RestartDecision::CrashLoop
+        } else {
+            RestartDecision::Restart(self.backoff(recent))
+        }
+    }
+
+    pub fn in_crash_loop(&self, history: &CrashHistory, now_s: u64) -> bool {
+        history.recent(now_s, self.window_s) > self.max_restarts
+    }
+}
+
+/// How a sidecar process ended, as seen by its drain task
+#[derive(Debug, Clone, Default)]
+pub struct ProcessExit {
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+    pub stderr_tail: Vec<String>,
+}
+
+impl ProcessExit {
+    pub fn is_clean(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    pub fn describe(&self) -> String {
+        match (self.code, self.signal) {
+            (Some(code), _) => format!("code {}", code),
+            (None, Some(signal)) => format!("signal {}", signal),
+            (None, None) => "code inconnu".to_string(),
+        }
+    }
+
+    /// Short message for the frontend: exit code plus the last stderr line
+    pub fn summary(&self) -> String {
+        match self.stderr_tail.last() {
+            Some(line) => format!("Processus termine avec {} : {}", self.describe(), line),
+            None => format!("Processus termine avec {}", self.describe()),
+        }
+    }
+
+    pub fn into_record(self, at_unix_s: u64, uptime_secs: u64) -> CrashRecord {
+        CrashRecord {
+            at_unix_s,
+            exit_code: self.code,
+            signal: self.signal,
+            uptime_secs,
+            stderr_tail: self.stderr_tail,
+        }
+    }
+}
+
+/// One unexpected termination
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashRecord {
+    pub at_unix_s: u64,
+    pub exit_code: Option<i32>,
+    pub signal: Option<i32>,
+    pub uptime_secs: u64,
+    pub stderr_tail: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CrashHistory {
+    records: VecDeque<CrashRecord>,
+}
+
+impl CrashHistory {
+    pub fn record(&mut self, crash: CrashRecord) {
+        if self.records.len() == MAX_CRASH_HISTORY {
+            self.records.pop_front();
+        }
+        self.records.push_back(crash);
+    }
+
+    /// Crashes within the last `window_s` seconds
+    pub fn recent(&self, now_s: u64, window_s: u64) -> usize {
+        self.records
+            .iter()
+            .filter(|c| now_s.saturating_sub(c.at_unix_s) < window_s)
+            .count()
+    }
+
+    /// Oldest first
+    pub fn records(&self) -> Vec<CrashRecord> {
+        self.records.iter().cloned().collect()
+    }
+}
+
+/// Last stderr lines of a running sidecar (filled by its drain task)
+#[derive(Debug, Default)]
+pub struct StderrTail {
+    lines: VecDeque<String>,
+}
+
+impl StderrTail {
+    pub fn push(&mut self, line: &str) {
+        let line = line.trim_end();
+        if line.is_empty() {
+            return;
+        }
+        if self.lines.len() == STDERR_TAIL_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line.to_string());
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.iter().cloned().collect()
+    }
+}
+
+/// Outcome of the startup healthcheck
+#[derive(Debug)]
+pub enum StartupOutcome {
+    Healthy,
+    TimedOut,
+    /// The process terminated before answering (reported by its drain task)
+    Exited(ProcessExit),
+}
+
+/// Poll `url` every `interval` until it answers 2xx, the process exits or `timeout` elapses
+pub async fn wait_until_healthy(
+    url: &str,
+    timeout: Duration,
+    interval: Duration,
+    exit_rx: &mut oneshot::Receiver<ProcessExit>,
+) -> Result<StartupOutcome, SidecarError> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(2))
+        .build()
+        .map_err(|e| SidecarError::Internal(e.to_string()))?;
+
+    let start = tokio::time::Instant::now();
+    loop {
+        if start.elapsed() >= timeout {
+            return Ok(StartupOutcome::TimedOut);
+        }
+        tokio::time::sleep(interval).await;
+
+        // Process already gone (bad model, missing library...): no point waiting
+        if let Ok(exit) = exit_rx.try_recv() {
+            return Ok(StartupOutcome::Exited(exit));
+        }
+        match client.get(url).send().await {
+            Ok(resp) if resp.status().is_success() => return Ok(StartupOutcome::Healthy),
+            _ => continue,
+        }
+    }
+}
+
+/// Post-request healthcheck: false once `attempts` consecutive probes have failed.
+/// A client that cannot be built counts as healthy (no restart on a local error).
+pub async fn probe_health(url: &str, attempts: u32, retry_delay: Duration) -> bool {
+    let Ok(client) = reqwest::Client::builder().timeout(Duration::from_secs(2)).build() else {
+        return true;
+    };
+    for _ in 0..attempts {
+        match client.get(url).send().await {
+            Ok(resp) if resp.status().is_success() => return true,
+            _ => tokio::time::sleep(retry_delay).await,
+        }
+    }
+    false
+}
+
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crash(at_unix_s: u64) -> CrashRecord {
+        CrashRecord { at_unix_s, exit_code: Some(1), signal: None, uptime_secs: 5, stderr_tail: vec![] }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let policy = RestartPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(10), Duration::from_secs(30));
+        assert_eq!(policy.backoff(1_000), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn crash_loop_after_too_many_recent_crashes() {
+        let policy = RestartPolicy::default();
+        let mut history = CrashHistory::default();
+        let now = 10_000;
+
+        history.record(crash(now - 1_000)); // outside the window
+        for i in 0..3 {
+            history.record(crash(now - 10 + i));
+            assert!(matches!(policy.decide(&history, now), RestartDecision::Restart(_)));
+        }
+        assert_eq!(policy.decide(&history, now), RestartDecision::Restart(Duration::from_secs(4)));
+
+        history.record(crash(now));
+        assert_eq!(policy.decide(&history, now), RestartDecision::CrashLoop);
+        assert!(policy.in_crash_loop(&history, now));
+
+        // The loop clears itself once the crashes leave the window
+        assert!(!policy.in_crash_loop(&history, now + policy.window_s));
+    }
+
+    #[test]
+    fn exit_summary_reports_code_or_signal() {
+        let exit = ProcessExit { code: Some(3), signal: None, stderr_tail: vec!["ggml: out of memory".into()] };
+        assert!(!exit.is_clean());
+        assert_eq!(exit.summary(), "Processus termine avec code 3 : ggml: out of memory");
+        let killed = ProcessExit { code: None, signal: Some(9), stderr_tail: vec![] };
+        assert_eq!(killed.summary(), "Processus termine avec signal 9");
+        assert!(ProcessExit { code: Some(0), ..Default::default() }.is_clean());
+    }
+
+    #[test]
+    fn history_and_stderr_tail_are_bounded() {
+        let mut history = CrashHistory::default();
+        for i in 0..(MAX_CRASH_HISTORY as u64 + 3) {
+            history.record(crash(i));
+        }
+        let records = history.records();
+        assert_eq!(records.len(), MAX_CRASH_HISTORY);
+        assert_eq!(records[0].at_unix_s, 3, "Oldest records are dropped first");
+
+        let mut tail = StderrTail::default();
+        tail.push("  \n");
+        for i in 0..(STDERR_TAIL_LINES + 5) {
+            tail.push(&format!("line {}\n", i));
+        }
+        let lines = tail.lines();
+        assert_eq!(lines.len(), STDERR_TAIL_LINES);
+        assert_eq!(lines.last().unwrap(), &format!("line {}", STDERR_TAIL_LINES + 4));
+    }
+
+    #[tokio::test]
+    async fn startup_healthcheck_detects_ready_exit_and_timeout() {
+        use crate::sidecar::testing::MockSidecar;
+        let interval = Duration::from_millis(20);
+
+        let ready = MockSidecar::spawn(vec![]).await;
+        let (_tx, mut rx) = oneshot::channel();
+        let outcome = wait_until_healthy(&ready.url("/health"), Duration::from_secs(5), interval, &mut rx)
+            .await
+            .unwrap();
+        assert!(matches!(outcome, StartupOutcome::Healthy));
+
+        // Still loading the model (503) until the timeout
+        let loading = MockSidecar::replay("llama_loading").await;
+        let (_tx, mut rx) = oneshot::channel();
+        let outcome = wait_until_healthy(&loading.url("/health"), Duration::from_millis(200), interval, &mut rx)
+            .await
+            .unwrap();
+        assert!(matches!(outcome, StartupOutcome::TimedOut));
+        assert!(!loading.requests("/health").is_empty());
+
+        // The process dies while loading: reported without waiting for the timeout
+        let (tx, mut rx) = oneshot::channel();
+        tx.send(ProcessExit { code: Some(1), signal: None, stderr_tail: vec!["failed to load model".into()] })
+            .unwrap();
+        let outcome = wait_until_healthy(&loading.url("/health"), Duration::from_secs(60), interval, &mut rx)
+            .await
+            .unwrap();
+        match outcome {
+            StartupOutcome::Exited(exit) => assert_eq!(exit.summary(), "Processus termine avec code 1 : failed to load model"),
+            other => panic!("Sortie attendue, obtenu {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn post_request_probe_flags_an_unhealthy_sidecar() {
+        use crate::sidecar::testing::MockSidecar;
+        let delay = Duration::from_millis(10);
+
+        let healthy = MockSidecar::spawn(vec![]).await;
+        assert!(probe_health(&healthy.url("/health"), 3, delay).await);
+        assert_eq!(healthy.requests("/health").len(), 1);
+
+        let unhealthy = MockSidecar::replay("llama_loading").await;
+        assert!(!probe_health(&unhealthy.url("/health"), 3, delay).await);
+        assert_eq!(unhealthy.requests("/health").len(), 3, "3 tentatives avant redemarrage");
+    }
+}
@@ -0,0 +1,608 @@
+/// Streaming transcription: audio chunks in, incremental text out.
+///
+/// The frontend pushes 16 kHz mono PCM chunks while the teacher is still speaking,
+/// as raw little-endian bytes (no JSON array per chunk). Chunks are cut into
+/// segments on silence; each completed segment goes through the preprocessing of
+/// `transcribe_audio` (speech gate, trim, gain) and is sent to whisper-server by a
+/// per-session worker, which emits a `transcription_partial` event with the segment
+/// text and the stitched text so far. Finishing the stream flushes the last segment
+/// and returns the full text, with the recording saved under the retention policy.
+/// A dictation in which no segment produced text ends with `AppError::NoSpeech`.
+///
+/// A session that receives no chunk for `SESSION_IDLE_TIMEOUT` (window reloaded,
+/// frontend crash) is dropped, so it does not keep whisper-server busy forever.
+
+use super::corrections::correct_transcription;
+use super::manager::SidecarManager;
+use super::transcription::{ensure_whisper_running, resolve_model_path, send_inference_bytes};
+use super::types::{SidecarName, TranscriptionResult};
+use super::vocabulary::{inference_options, InferenceOptions};
+use crate::audio::commands::{current_retention, write_recording};
+use crate::audio::preprocess::{preprocess_wav, PreprocessConfig, MAX_RECORDING_MS};
+use crate::audio::wav::{encode_wav, WHISPER_SAMPLE_RATE};
+use crate::audio::AudioError;
+use crate::error::AppError;
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::Instant;
+
+/// Header carrying the session id of a raw `push_transcription_chunk` request
+const SESSION_HEADER: &str = "x-session-id";
+
+/// Without chunk for this long, a session is considered abandoned
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Segmentation thresholds (see `SilenceSegmenter`)
+#[derive(Debug, Clone, Copy)]
+pub struct SegmenterConfig {
+    /// Analysis frame length
+    pub frame_ms: u32,
+    /// Frame RMS (i16 scale) below which a frame counts as silence
+    pub silence_rms: f64,
+    /// Silence needed after speech to close a segment
+    pub min_silence_ms: u32,
+    /// Segments shorter than this are not closed on silence (whisper does poorly on < 1 s)
+    pub min_segment_ms: u32,
+    /// Hard cut, below whisper's 30 s window
+    pub max_segment_ms: u32,
+    /// Leading silence kept before speech starts
+    pub preroll_ms: u32,
+}
+
+impl Default for SegmenterConfig {
+    fn default() -> Self {
+        Self {
+            frame_ms: 30,
+            silence_rms: 500.0,
+            min_silence_ms: 700,
+            min_segment_ms: 1_500,
+            max_segment_ms: 25_000,
+            preroll_ms: 300,
+        }
+    }
+}
+
+impl SegmenterConfig {
+    fn samples(&self, ms: u32) -> usize {
+        (WHISPER_SAMPLE_RATE as usize * ms as usize) / 1000
+    }
+}
+
+/// Cuts a continuous PCM stream into speech segments separated by silence.
+/// Segments containing only silence are dropped.
+pub struct SilenceSegmenter {
+    config: SegmenterConfig,
+    /// Samples not yet forming a full frame
+    carry: Vec<i16>,
+    /// Current segment being accumulated
+    current: Vec<i16>,
+    has_speech: bool,
+    silent_frames: usize,
+}
+
+impl SilenceSegmenter {
+    pub fn new(config: SegmenterConfig) -> Self {
+        Self {
+            config,
+            carry: Vec::new(),
+            current: Vec::new(),
+            has_speech: false,
+            silent_frames: 0,
+        }
+    }
+
+    /// Feed samples; returns the segments completed by this chunk.
+    pub fn push(&mut self, samples: &[i16]) -> Vec<Vec<i16>> {
+        let frame_len = self.config.samples(self.config.frame_ms).max(1);
+        self.carry.extend_from_slice(samples);
+
+        let mut completed = Vec::new();
+        let mut offset = 0;
+        while self.carry.len() - offset >= frame_len {
+            let frame = &self.carry[offset..offset + frame_len];
+            offset += frame_len;
+
+            let silent = frame_rms(frame) < self.config.silence_rms;
+            self.current.extend_from_slice(frame);
+
+            if silent {
+                self.silent_frames += 1;
+                if !self.has_speech {
+                    // Keep only a short pre-roll of leading silence
+                    let preroll = self.config.samples(self.config.preroll_ms);
+                    if self.current.len() > preroll {
+                        let excess = self.current.len() - preroll;
+                        self.current.drain(..excess);
+                    }
+                }
+            } else {
+                self.has_speech = true;
+                self.silent_frames = 0;
+            }
+
+            let segment_len = self.current.len();
+            let silence_len = self.silent_frames * frame_len;
+            let closes_on_silence = self.has_speech
+                && silence_len >= self.config.samples(self.config.min_silence_ms)
+                && segment_len >= self.config.samples(self.config.min_segment_ms);
+            let too_long = segment_len >= self.config.samples(self.config.max_segment_ms);
+
+            if closes_on_silence || too_long {
+                if let Some(segment) = self.take_segment() {
+                    completed.push(segment);
+                }
+            }
+        }
+        self.carry.drain(..offset);
+        completed
+    }
+
+    /// End of stream: returns the last segment if it contains speech.
+    pub fn flush(&mut self) -> Option<Vec<i16>> {
+        let carry = std::mem::take(&mut self.carry);
+        if carry.iter().any(|s| (*s as f64).abs() >= self.config.silence_rms) {
+            self.has_speech = true;
+        }
+        self.current.extend(carry);
+        self.take_segment()
+    }
+
+    fn take_segment(&mut self) -> Option<Vec<i16>> {
+        let segment = std::mem::take(&mut self.current);
+        let has_speech = self.has_speech;
+        self.has_speech = false;
+        self.silent_frames = 0;
+        (has_speech && !segment.is_empty()).then_some(segment)
+    }
+}
+
+fn frame_rms(frame: &[i16]) -> f64 {
+    if frame.is_empty() {
+        return 0.0;
+    }
+    let sum: f64 = frame.iter().map(|s| (*s as f64) * (*s as f64)).sum();
+    (sum / frame.len() as f64).sqrt()
+}
+
+/// Decode a raw chunk of little-endian 16-bit samples
+pub fn pcm_from_le_bytes(bytes: &[u8]) -> Result<Vec<i16>, AppError> {
+    if bytes.len() % 2 != 0 {
+        return Err(AppError::validation("Bloc audio invalide : nombre d'octets impair"));
+    }
+    Ok(bytes.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]])).collect())
+}
+
+/// Drop whisper non-speech markers such as `[BLANK_AUDIO]` or `(musique)`.
+pub fn clean_segment_text(text: &str) -> String {
+    let trimmed = text.trim();
+    let is_marker = (trimmed.starts_with('[') && trimmed.ends_with(']'))
+        || (trimmed.starts_with('(') && trimmed.ends_with(')'));
+    if is_marker {
+        String::new()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// Join segment texts into one dictation, normalising whitespace.
+pub fn stitch(parts: &[String]) -> String {
+    parts
+        .iter()
+        .flat_map(|p| p.split_whitespace())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// WAV of a segment as sent to whisper, preprocessed like a recorded dictation.
+/// None for a segment without enough speech (`AudioError::NearSilent`).
+pub fn segment_wav(segment: &[i16]) -> Result<Option<Vec<u8>>, AudioError> {
+    match preprocess_wav(&encode_wav(segment, WHISPER_SAMPLE_RATE), &PreprocessConfig::default()) {
+        Ok((wav, _)) => Ok(Some(wav)),
+        Err(AudioError::NearSilent) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Payload of the `transcription_partial` event
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptionPartial {
+    pub session_id: String,
+    pub segment_index: usize,
+    /// Text of this segment only
+    pub text: String,
+    /// Stitched text of all segments so far
+    pub full_text: String,
+}
+
+/// Result of `finish_transcription_stream`
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamTranscription {
+    #[serde(flatten)]
+    pub result: TranscriptionResult,
+    /// Recording of the dictation; None when the retention policy keeps no audio
+    pub audio_path: Option<String>,
+}
+
+struct WorkerOutput {
+    parts: Vec<String>,
+    last_error: Option<AppError>,
+}
+
+struct StreamSession {
+    segmenter: SilenceSegmenter,
+    segment_tx: mpsc::UnboundedSender<Vec<i16>>,
+    worker: JoinHandle<WorkerOutput>,
+    /// Whole dictation, saved when the stream finishes (capped at MAX_RECORDING_MS)
+    recording: Vec<i16>,
+    started: Instant,
+    last_chunk: Instant,
+    /// Student being dictated about (scoped corrections)
+    eleve_id: Option<i64>,
+}
+
+/// Open streaming sessions (Tauri managed state)
+#[derive(Default)]
+pub struct TranscriptionStreams {
+    sessions: Mutex<HashMap<String, StreamSession>>,
+}
+
+impl TranscriptionStreams {
+    /// A dictation is open: whisper-server must stay up between its segments
+    pub async fn is_active(&self) -> bool {
+        self.expire_abandoned().await;
+        !self.sessions.lock().await.is_empty()
+    }
+
+    /// Drop the sessions left open by a frontend that stopped sending chunks
+    pub async fn expire_abandoned(&self) {
+        self.sessions.lock().await.retain(|session_id, session| {
+            let abandoned = session.last_chunk.elapsed() >= SESSION_IDLE_TIMEOUT;
+            if abandoned {
+                session.worker.abort();
+                warn!("Session de transcription abandonnee, fermee : {}", session_id);
+            }
+            !abandoned
+        });
+    }
+}
+
+/// Send segments to whisper-server in order, emitting a partial result for each.
+/// Segments without speech are skipped; a failed segment is logged and skipped too,
+/// so one bad request does not lose the dictation.
+async fn run_segment_worker(
+    app: AppHandle,
+    session_id: String,
+    options: InferenceOptions,
+    mut segments: mpsc::UnboundedReceiver<Vec<i16>>,
+) -> WorkerOutput {
+    let mut output = WorkerOutput { parts: Vec::new(), last_error: None };
+    let mut index = 0;
+
+    while let Some(segment) = segments.recv().await {
+        let prepared = tokio::task::spawn_blocking(move || segment_wav(&segment))
+            .await
+            .map_err(|e| AppError::Internal(format!("Pretraitement audio interrompu : {}", e)))
+            .and_then(|r| r.map_err(AppError::from));
+        let wav = match prepared {
+            Ok(Some(wav)) => wav,
+            Ok(None) => {
+                info!("Segment {} de la session {} sans parole, ignore", index, session_id);
+                index += 1;
+                continue;
+            }
+            Err(e) => {
+                warn!("Segment {} de la session {} non pretraite : {}", index, session_id, e);
+                output.last_error = Some(e);
+                index += 1;
+                continue;
+            }
+        };
+
+        let manager = app.state::<SidecarManager>();
+        let result = match manager.base_url(SidecarName::Whisper).await {
+            Ok(base_url) => {
+                let file_name = format!("stream_{}_{}.wav", session_id, index);
+                send_inference_bytes(&base_url, wav, file_name, &options).await
+            }
+            Err(e) => Err(e),
+        };
+        manager.increment_request_count(SidecarName::Whisper).await;
+
+        match result {
+            Ok(raw) => {
+                let text = clean_segment_text(&raw);
+                if !text.is_empty() {
+                    output.parts.push(text.clone());
+                }
+                app.emit(
+                    "transcription_partial",
+                    TranscriptionPartial {
+                        session_id: session_id.clone(),
+                        segment_index: index,
+                        text,
+                        full_text: stitch(&output.parts),
+                    },
+                )
+                .ok();
+            }
+            Err(e) => {
+                warn!("Segment {} de la session {} non transcrit : {}", index, session_id, e);
+                output.last_error = Some(e.into());
+            }
+        }
+        index += 1;
+    }
+
+    output
+}
+
+/// Open a streaming session (starts whisper-server if needed). Returns the session id.
+#[tauri::command]
+pub async fn start_transcription_stream(
+    app: AppHandle,
+    state: tauri::State<'_, SidecarManager>,
+    streams: tauri::State<'_, TranscriptionStreams>,
+    eleve_id: Option<i64>,
+) -> Result<String, AppError> {
+    streams.expire_abandoned().await;
+    let model_path = resolve_model_path(&app).await?;
+    ensure_whisper_running(&app, &state, &model_path.to_string_lossy()).await?;
+
+    // Vocabulary loaded once per session, shared by all its segments
+    let options = inference_options(&app).await;
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let (segment_tx, segment_rx) = mpsc::unbounded_channel();
+    let worker = tauri::async_runtime::spawn(run_segment_worker(
+        app.clone(),
+        session_id.clone(),
+        options,
+        segment_rx,
+    ));
+
+    streams.sessions.lock().await.insert(
+        session_id.clone(),
+        StreamSession {
+            segmenter: SilenceSegmenter::new(SegmenterConfig::default()),
+            segment_tx,
+            worker,
+            recording: Vec::new(),
+            started: Instant::now(),
+            last_chunk: Instant::now(),
+            eleve_id,
+        },
+    );
+
+    info!("Session de transcription en continu ouverte : {}", session_id);
+    Ok(session_id)
+}
+
+/// Push a chunk of 16 kHz mono PCM samples, sent as raw little-endian bytes with the
+/// session id in the `x-session-id` header. Completed segments are queued for whisper.
+#[tauri::command]
+pub async fn push_transcription_chunk(
+    request: tauri::ipc::Request<'_>,
+    streams: tauri::State<'_, TranscriptionStreams>,
+) -> Result<(), AppError> {
+    let tauri::ipc::InvokeBody::Raw(bytes) = request.body() else {
+        return Err(AppError::validation("Bloc audio attendu en octets bruts"));
+    };
+    let session_id = request
+        .headers()
+        .get(SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| AppError::validation("Identifiant de session de transcription manquant"))?;
+    let samples = pcm_from_le_bytes(bytes)?;
+
+    let mut sessions = streams.sessions.lock().await;
+    let session = sessions
+        .get_mut(session_id)
+        .ok_or_else(|| AppError::not_found("Session de transcription introuvable"))?;
+    session.last_chunk = Instant::now();
+
+    let max_samples = (MAX_RECORDING_MS * u64::from(WHISPER_SAMPLE_RATE) / 1000) as usize;
+    let room = max_samples.saturating_sub(session.recording.len());
+    session.recording.extend_from_slice(&samples[..samples.len().min(room)]);
+
+    for segment in session.segmenter.push(&samples) {
+        session
+            .segment_tx
+            .send(segment)
+            .map_err(|_| AppError::Internal("Transcription en continu interrompue".to_string()))?;
+    }
+    Ok(())
+}
+
+/// Close the stream: transcribe the last segment and return the stitched text,
+/// with the correction dictionary applied.
+#[tauri::command]
+pub async fn finish_transcription_stream(
+    app: AppHandle,
+    state: tauri::State<'_, SidecarManager>,
+    streams: tauri::State<'_, TranscriptionStreams>,
+    session_id: String,
+) -> Result<StreamTranscription, AppError> {
+    let mut session = streams
+        .sessions
+        .lock()
+        .await
+        .remove(&session_id)
+        .ok_or_else(|| AppError::not_found("Session de transcription introuvable"))?;
+
+    if let Some(segment) = session.segmenter.flush() {
+        session.segment_tx.send(segment).ok();
+    }
+    // Closing the channel lets the worker finish the queued segments
+    drop(session.segment_tx);
+
+    let output = session
+        .worker
+        .await
+        .map_err(|e| AppError::Internal(format!("Transcription en continu interrompue : {}", e)))?;
+
+    state.watchdog_post_request(&app, SidecarName::Whisper).await;
+    state.auto_stop_after_task(&app, SidecarName::Whisper).await;
+
+    // Same typed error as a recorded dictation without speech
+    if output.parts.is_empty() {
+        return Err(output.last_error.unwrap_or(AppError::NoSpeech));
+    }
+
+    let text = stitch(&output.parts);
+    let duration_ms = session.started.elapsed().as_millis() as u64;
+    info!(
+        "Transcription en continu terminee ({} segment(s), {}ms)",
+        output.parts.len(),
+        duration_ms
+    );
+
+    // Kept for re-listening (vocal event attachment) unless the policy keeps no audio
+    let audio_path = if current_retention(&app).delete_after_transcription() {
+        None
+    } else {
+        let wav = encode_wav(&session.recording, WHISPER_SAMPLE_RATE);
+        write_recording(&app, &wav)
+            .map_err(|e| warn!("Enregistrement de la dictee non sauvegarde : {}", e))
+            .ok()
+    };
+
+    let result = TranscriptionResult { text, duration_ms, corrections: Vec::new(), transcription_id: None };
+    let result = correct_transcription(&app, result, session.eleve_id).await;
+    Ok(StreamTranscription { result, audio_path })
+}
+
+/// Abandon a stream without transcribing the remaining audio.
+#[tauri::command]
+pub async fn cancel_transcription_stream(
+    streams: tauri::State<'_, TranscriptionStreams>,
+    session_id: String,
+) -> Result<(), AppError> {
+    if let Some(session) = streams.sessions.lock().await.remove(&session_id) {
+        session.worker.abort();
+        info!("Session de transcription annulee : {}", session_id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SegmenterConfig {
+        SegmenterConfig::default()
+    }
+
+    fn speech(ms: u32) -> Vec<i16> {
+        let n = (WHISPER_SAMPLE_RATE * ms / 1000) as usize;
+        (0..n).map(|i| if i % 2 == 0 { 4000 } else { -4000 }).collect()
+    }
+
+    fn silence(ms: u32) -> Vec<i16> {
+        vec![0; (WHISPER_SAMPLE_RATE * ms / 1000) as usize]
+    }
+
+    #[test]
+    fn raw_chunks_decode_as_little_endian_samples() {
+        assert_eq!(pcm_from_le_bytes(&[0x01, 0x00, 0xff, 0xff, 0x00, 0x80]).unwrap(), vec![1, -1, i16::MIN]);
+        assert!(pcm_from_le_bytes(&[]).unwrap().is_empty());
+        assert_eq!(pcm_from_le_bytes(&[0x01]).unwrap_err().code(), "Validation");
+    }
+
+    #[test]
+    fn segments_are_gated_and_normalised_before_whisper() {
+        // Steady hum above the segmenter threshold, but no speech for the preprocessing
+        let hum: Vec<i16> = (0..WHISPER_SAMPLE_RATE * 2).map(|i| if i % 2 == 0 { 600 } else { -600 }).collect();
+        assert!(segment_wav(&hum).unwrap().is_none());
+
+        let wav = segment_wav(&[silence(500), speech(1_500), silence(500)].concat()).unwrap().unwrap();
+        let audio = crate::audio::wav::parse_wav(&wav).unwrap();
+        assert!(audio.format.is_whisper_native());
+        let peak = audio.samples.iter().fold(0f32, |m, s| m.max(s.abs()));
+        assert!(peak > 0.8, "peak={}", peak);
+    }
+
+    #[test]
+    fn segment_closes_after_silence() {
+        let mut seg = SilenceSegmenter::new(config());
+        assert!(seg.push(&speech(2_000)).is_empty());
+
+        let done = seg.push(&silence(800));
+        assert_eq!(done.len(), 1);
+        assert!(done[0].len() >= config().samples(2_000));
+        assert!(seg.flush().is_none(), "Nothing left after the cut");
+    }
+
+    #[test]
+    fn short_pause_does_not_cut_short_utterance() {
+        let mut seg = SilenceSegmenter::new(config());
+        let mut done = seg.push(&speech(600));
+        done.extend(seg.push(&silence(800)));
+        assert!(done.is_empty(), "Segment below min_segment_ms stays open");
+
+        done.extend(seg.push(&speech(1_000)));
+        done.extend(seg.push(&silence(800)));
+        assert_eq!(done.len(), 1);
+    }
+
+    #[test]
+    fn pure_silence_is_dropped() {
+        let mut seg = SilenceSegmenter::new(config());
+        assert!(seg.push(&silence(10_000)).is_empty());
+        assert!(seg.flush().is_none());
+    }
+
+    #[test]
+    fn leading_silence_is_trimmed_to_preroll() {
+        let mut seg = SilenceSegmenter::new(config());
+        seg.push(&silence(5_000));
+        seg.push(&speech(2_000));
+        let segment = seg.flush().unwrap();
+        let max = config().samples(2_000 + config().preroll_ms + config().frame_ms);
+        assert!(segment.len() <= max, "len={} max={}", segment.len(), max);
+    }
+
+    #[test]
+    fn long_speech_is_cut_at_max_segment() {
+        let mut seg = SilenceSegmenter::new(config());
+        let done = seg.push(&speech(40_000));
+        assert_eq!(done.len(), 1);
+        let max = config().samples(config().max_segment_ms);
+        assert!(done[0].len() >= max && done[0].len() < max + config().samples(config().frame_ms));
+        assert!(seg.flush().is_some(), "Remaining 15 s are flushed");
+    }
+
+    #[test]
+    fn chunk_boundaries_do_not_matter() {
+        let mut audio = speech(2_000);
+        audio.extend(silence(800));
+        audio.extend(speech(1_700));
+
+        let mut whole = SilenceSegmenter::new(config());
+        let mut expected = whole.push(&audio);
+        expected.extend(whole.flush());
+
+        let mut chunked = SilenceSegmenter::new(config());
+        let mut got = Vec::new();
+        for chunk in audio.chunks(1_234) {
+            got.extend(chunked.push(chunk));
+        }
+        got.extend(chunked.flush());
+
+        assert_eq!(expected.len(), 2);
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn markers_are_removed_and_parts_stitched() {
+        assert_eq!(clean_segment_text(" [BLANK_AUDIO] "), "");
+        assert_eq!(clean_segment_text("(musique)"), "");
+        assert_eq!(clean_segment_text(" Lucas a bien lu. "), "Lucas a bien lu.");
+
+        let parts = vec!["Lucas a bien lu.".to_string(), " Il  participe. ".to_string()];
+        assert_eq!(stitch(&parts), "Lucas a bien lu. Il participe.");
+    }
+}
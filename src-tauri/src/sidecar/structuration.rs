@@ -1,15 +1,21 @@
 use super::gbnf::{self, DomainInfo};
+use super::llm_backend::{LlmBackend, LlmRequest, OutputConstraint};
+use super::llm_queue::{LlmJobPriority, LlmJobQueue, LlmJobSpec};
+use super::llm_stream::{delta_emitter, StreamingBackend};
 use super::manager::SidecarManager;
-use super::prompt_builder::{self, DomainContext, EventContext, SynthesisContext};
-use super::types::{SidecarError, SidecarName};
+use super::prompt_builder::{
+    self, DomainContext, EventContext, PromptBuilderResult, SynthesePlan, SynthesisContext, TokenUsage,
+};
+use super::prompt_templates::{self, PromptJob, PromptTemplate, TemplateVars};
+use super::text_rules::{self, Rule, RuleViolation, TextRules};
+use super::types::SidecarError;
+use crate::error::AppError;
+use futures::future::FutureExt;
 use log::info;
 use serde::{Deserialize, Serialize};
-use sqlx::sqlite::SqlitePoolOptions;
-use std::path::PathBuf;
-use tauri::Manager;
+use std::time::Duration;
 use tokio::time::Instant;
 
-const LLAMA_MODEL_NAME: &str = "qwen2.5-coder-1.5b-instruct-q4_k_m.gguf";
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ObservationResult {
     pub domaine: String,
@@ -17,39 +23,17 @@ pub struct ObservationResult {
     pub commentaire: String,
 }
 
-/// OpenAI-compatible chat completion response
-#[derive(Debug, Deserialize)]
-struct ChatCompletionResponse {
-    choices: Vec<ChatChoice>,
-}
-
-#[derive(Debug, Deserialize)]
-struct ChatChoice {
-    message: ChatMessage,
-}
-
-#[derive(Debug, Deserialize)]
-struct ChatMessage {
-    content: String,
-}
-
-/// Resolve the Qwen model path in app_data_dir/models/
-fn resolve_model_path(app: &tauri::AppHandle) -> Result<PathBuf, SidecarError> {
-    let data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| SidecarError::Internal(format!("Impossible de trouver app_data_dir: {}", e)))?;
+/// Sampling temperature of the structuration jobs (near-deterministic)
+const LLM_TEMPERATURE: f32 = 0.1;
 
-    let model_path = data_dir.join("models").join(LLAMA_MODEL_NAME);
+/// Rewrites allowed when a synthese or appreciation breaks the LSU rules
+const MAX_REWRITES: usize = 2;
 
-    if !model_path.exists() {
-        return Err(SidecarError::ModelNotFound(
-            model_path.to_string_lossy().to_string(),
-        ));
-    }
+/// Candidates a teacher can request for one synthese or appreciation
+pub const MAX_CANDIDATES: usize = 4;
 
-    Ok(model_path)
-}
+/// Sampling temperature of each candidate, the first keeps `LLM_TEMPERATURE`
+const CANDIDATE_TEMPERATURES: [f32; MAX_CANDIDATES] = [LLM_TEMPERATURE, 0.5, 0.7, 0.9];
 
 // ─── V2.1 — Classification + Fusion (Story 19.3) ───
 
@@ -68,6 +52,7 @@ pub struct ClassificationResultItem {
 pub struct ClassificationResults {
     pub items: Vec<ClassificationResultItem>,
     pub duration_ms: u64,
+    pub usage: TokenUsage,
 }
 
 /// Raw LLM response item for classification (V2.1 GBNF)
@@ -77,6 +62,12 @@ struct LlmClassificationItem {
     observation_mise_a_jour: String,
 }
 
+/// Classification wrapped in an object (JSON-schema backends, see `gbnf::classification_json_schema`)
+#[derive(Debug, Deserialize)]
+struct LlmClassificationWrapper {
+    resultats: Vec<LlmClassificationItem>,
+}
+
 /// DB row for active domains query
 #[derive(Debug, sqlx::FromRow)]
 struct DomainRow {
@@ -91,47 +82,70 @@ struct ObservationRow {
     observations: Option<String>,
 }
 
-/// Open a read-only SQLite pool for classification queries
-async fn open_db_pool(app: &tauri::AppHandle) -> Result<sqlx::SqlitePool, SidecarError> {
-    let data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| SidecarError::Internal(format!("Impossible de trouver app_data_dir: {}", e)))?;
-    let db_path = data_dir.join("comportement.db");
-    let db_url = format!("sqlite:{}", db_path.to_string_lossy());
-
-    let pool = SqlitePoolOptions::new()
-        .max_connections(1)
-        .connect(&db_url)
-        .await
-        .map_err(|e| SidecarError::Internal(format!("Connexion DB echouee: {}", e)))?;
-
-    sqlx::query("PRAGMA foreign_keys = ON;")
-        .execute(&pool)
-        .await
-        .map_err(|e| SidecarError::Internal(format!("PRAGMA echoue: {}", e)))?;
-
-    Ok(pool)
+/// Student fields used by the prompts and the domain selection
+#[derive(Debug, Default, sqlx::FromRow)]
+struct StudentProfile {
+    prenom: Option<String>,
+    niveau: Option<String>,
+    cycle: Option<i64>,
 }
 
-/// Load active domains for a student's cycle from the DB.
-/// If the student has no niveau set, returns all active domains.
-async fn load_active_domains(
+/// Load the student's first name, niveau and cycle (derived from the niveau)
+async fn load_student_profile(
     pool: &sqlx::SqlitePool,
     eleve_id: i64,
-) -> Result<Vec<DomainRow>, SidecarError> {
-    // First, get the student's cycle from their niveau
-    let cycle: Option<i64> = sqlx::query_scalar(
-        "SELECT nc.cycle FROM students s
-         JOIN niveaux_classe nc ON nc.code = s.niveau
+) -> Result<StudentProfile, SidecarError> {
+    let profile: Option<StudentProfile> = sqlx::query_as(
+        "SELECT s.first_name AS prenom, s.niveau, nc.cycle FROM students s
+         LEFT JOIN niveaux_classe nc ON nc.code = s.niveau
          WHERE s.id = ?",
     )
     .bind(eleve_id)
     .fetch_optional(pool)
     .await
-    .map_err(|e| SidecarError::Internal(format!("Requete cycle eleve echouee: {}", e)))?
-    .flatten();
+    .map_err(|e| SidecarError::Internal(format!("Requete profil eleve echouee: {}", e)))?;
+    Ok(profile.unwrap_or_default())
+}
+
+/// First names of the rest of the roster (the name rule must not "correct" a classmate)
+async fn load_classmate_names(pool: &sqlx::SqlitePool, eleve_id: i64) -> Result<Vec<String>, SidecarError> {
+    sqlx::query_scalar("SELECT first_name FROM students WHERE id != ?")
+        .bind(eleve_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| SidecarError::Internal(format!("Requete prenoms de la classe echouee: {}", e)))
+}
 
+/// Resolve the job's prompt template for the student's cycle and render it.
+/// Returns the template (its id is recorded with the generated text) and the instructions.
+async fn job_instructions(
+    pool: &sqlx::SqlitePool,
+    job: PromptJob,
+    profile: &StudentProfile,
+    student_name: &str,
+    domaine: Option<&str>,
+) -> Result<(PromptTemplate, String), AppError> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| AppError::db("Impossible d'obtenir une connexion DB", e))?;
+    let template = prompt_templates::resolve_template_impl(&mut conn, job, profile.cycle).await?;
+    let vars = TemplateVars {
+        eleve: student_name,
+        domaine,
+        niveau: profile.niveau.as_deref(),
+        cycle: profile.cycle,
+    };
+    let instructions = template.render(&vars);
+    Ok((template, instructions))
+}
+
+/// Load active domains for a student's cycle from the DB.
+/// If the student has no niveau set, returns all active domains.
+async fn load_active_domains(
+    pool: &sqlx::SqlitePool,
+    cycle: Option<i64>,
+) -> Result<Vec<DomainRow>, SidecarError> {
     let domains = match cycle {
         Some(c) => {
             // Domains matching the student's cycle + custom domains
@@ -235,76 +249,30 @@ fn recover_truncated_json(content: &str) -> Option<Vec<LlmClassificationItem>> {
     serde_json::from_str(&json_str).ok()
 }
 
-/// Send a classification request to llama-server with dynamic GBNF grammar.
-/// Returns a Vec of classification items (multi-domain support).
-async fn send_classification_request(
-    system_prompt: &str,
-    user_prompt: &str,
-    grammar: &str,
-) -> Result<Vec<LlmClassificationItem>, SidecarError> {
-    let body = serde_json::json!({
-        "model": "qwen2.5-coder",
-        "messages": [
-            { "role": "system", "content": system_prompt },
-            { "role": "user", "content": user_prompt }
-        ],
-        "temperature": 0.1,
-        "max_tokens": 768,
-        "grammar": grammar
-    });
-
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .map_err(|e| SidecarError::Internal(e.to_string()))?;
-
-    let response = client
-        .post("http://127.0.0.1:8080/v1/chat/completions")
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| {
-            SidecarError::Internal(format!("Requete vers llama-server echouee: {}", e))
-        })?;
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let body_text = response.text().await.unwrap_or_default();
-        return Err(SidecarError::Internal(format!(
-            "llama-server a repondu avec le code {}: {}",
-            status, body_text
-        )));
-    }
-
-    let completion: ChatCompletionResponse = response.json().await.map_err(|e| {
-        SidecarError::Internal(format!("Reponse JSON invalide de llama-server: {}", e))
-    })?;
-
-    let content = completion
-        .choices
-        .first()
-        .map(|c| c.message.content.as_str())
-        .unwrap_or("");
-
-    // Try direct parse first; if truncated, recover partial JSON
-    let items: Vec<LlmClassificationItem> = match serde_json::from_str(content) {
-        Ok(v) => v,
-        Err(_) => {
-            // JSON likely truncated by max_tokens — recover valid items
-            match recover_truncated_json(content) {
-                Some(recovered) => {
-                    info!(
-                        "JSON LLM tronque, {} item(s) recupere(s) sur reponse partielle",
-                        recovered.len()
-                    );
-                    recovered
-                }
-                None => {
-                    return Err(SidecarError::Internal(format!(
-                        "JSON classification invalide (attendu: tableau): Contenu: {}",
-                        &content[..content.len().min(200)]
-                    )));
-                }
+/// Parse the classification content returned by the LLM.
+/// Accepts the GBNF array or the `{"resultats": [...]}` object of JSON-schema
+/// backends; a response truncated by max_tokens keeps its complete items.
+fn parse_classification_content(content: &str) -> Result<Vec<LlmClassificationItem>, SidecarError> {
+    let items: Vec<LlmClassificationItem> = if let Ok(v) = serde_json::from_str(content) {
+        v
+    } else if let Ok(wrapper) = serde_json::from_str::<LlmClassificationWrapper>(content) {
+        wrapper.resultats
+    } else {
+        // JSON likely truncated by max_tokens — recover valid items from the array
+        let array = content.find('[').map(|i| &content[i..]).unwrap_or(content);
+        match recover_truncated_json(array) {
+            Some(recovered) => {
+                info!(
+                    "JSON LLM tronque, {} item(s) recupere(s) sur reponse partielle",
+                    recovered.len()
+                );
+                recovered
+            }
+            None => {
+                return Err(SidecarError::Internal(format!(
+                    "JSON classification invalide (attendu: tableau): Contenu: {}",
+                    &content[..content.floor_char_boundary(200)]
+                )));
             }
         }
     };
@@ -400,34 +368,36 @@ fn domain_mentioned_in_text(domain_name: &str, text: &str) -> bool {
 /// Classify dictated text into one or more domains and merge with existing observations (V2.1).
 ///
 /// Pipeline:
-/// 1. Load active domains for the student's cycle from DB
+/// 1. Load active domains and the classification template for the student's cycle from DB
 /// 2. Load existing observations for the student/period
 /// 3. Generate dynamic GBNF grammar (ADR-007) — array format
 /// 4. Build adaptive prompt (ADR-008) — multi-domain + error correction
-/// 5. Start llama-server if needed
-/// 6. Send request with grammar constraint
-/// 7. Parse, validate each item, and return ClassificationResults
-/// 8. Auto-stop llama (ADR-002)
-#[tauri::command]
-pub async fn classify_and_merge(
-    app: tauri::AppHandle,
-    state: tauri::State<'_, SidecarManager>,
-    text: String,
+/// 5. Send request with grammar or JSON-schema constraint
+/// 6. Parse, validate each item, and return ClassificationResults
+pub(crate) async fn classify_and_merge_impl(
+    pool: &sqlx::SqlitePool,
+    backend: &dyn LlmBackend,
+    ctx_size: usize,
+    text: &str,
     eleve_id: i64,
     periode_id: i64,
-) -> Result<ClassificationResults, String> {
+) -> Result<ClassificationResults, AppError> {
     let start = Instant::now();
 
-    // Step 1: Open DB and load domains
-    let pool = open_db_pool(&app).await.map_err(|e| e.to_string())?;
-    let domains = load_active_domains(&pool, eleve_id)
-        .await
-        .map_err(|e| e.to_string())?;
+    // Step 1: Load the student's profile, template and domains
+    let profile = load_student_profile(pool, eleve_id).await?;
+    let (_, instructions) = job_instructions(
+        pool,
+        PromptJob::Classification,
+        &profile,
+        profile.prenom.as_deref().unwrap_or(""),
+        None,
+    )
+    .await?;
+    let domains = load_active_domains(pool, profile.cycle).await?;
 
     // Step 2: Load existing observations
-    let observations = load_existing_observations(&pool, eleve_id, periode_id)
-        .await
-        .map_err(|e| e.to_string())?;
+    let observations = load_existing_observations(pool, eleve_id, periode_id).await?;
 
     // Build a lookup: domaine_id → most recent observation
     let mut obs_map: std::collections::HashMap<i64, String> = std::collections::HashMap::new();
@@ -447,7 +417,11 @@ pub async fn classify_and_merge(
         })
         .collect();
 
-    let grammar = gbnf::generate_gbnf(&domain_infos);
+    let constraint = OutputConstraint {
+        name: "classification",
+        gbnf: gbnf::generate_gbnf(&domain_infos),
+        json_schema: gbnf::classification_json_schema(&domain_infos),
+    };
 
     // Step 4: Build DomainContext for prompt
     let domain_contexts: Vec<DomainContext> = domains
@@ -460,43 +434,32 @@ pub async fn classify_and_merge(
         })
         .collect();
 
-    let prompt_result = prompt_builder::build_prompt(&domain_contexts, &text);
-
-    // Step 5: Start llama-server if not running
-    let model_path = resolve_model_path(&app).map_err(|e| e.to_string())?;
-    let model_path_str = model_path.to_string_lossy().to_string();
-
-    let status = state.get_status().await;
-    if !status.llama.running {
-        info!("llama-server non demarre, lancement automatique...");
-        state
-            .start(&app, SidecarName::Llama, model_path_str, None)
-            .await
-            .map_err(|e| e.to_string())?;
-    }
-
-    // Step 6: Send classification request (returns Vec)
-    let classification_items = send_classification_request(
-        &prompt_result.system_prompt,
-        &prompt_result.user_prompt,
-        &grammar,
-    )
-    .await
-    .map_err(|e| e.to_string())?;
-
-    // Increment request count
-    state.increment_request_count(SidecarName::Llama).await;
+    let prompt_result = prompt_builder::build_prompt(&instructions, &domain_contexts, text, ctx_size, backend).await;
+    let usage = prompt_result.usage;
+
+    // Step 5: Send classification request
+    let request = LlmRequest {
+        system_prompt: prompt_result.system_prompt,
+        user_prompt: prompt_result.user_prompt,
+        constraint,
+        temperature: LLM_TEMPERATURE,
+        seed: None,
+        max_tokens: 768,
+        timeout: Duration::from_secs(30),
+    };
+    let content = backend.complete(&request).await?;
 
-    // Step 7: Validate each item, post-filter hallucinated domains, and build results
+    // Step 6: Parse, validate each item, post-filter hallucinated domains, and build results
+    let classification_items = parse_classification_content(&content)?;
     let mut items: Vec<ClassificationResultItem> = Vec::new();
     for item in &classification_items {
         let observation_text =
-            validate_classification_item(item, domains.len()).map_err(|e| e.to_string())?;
+            validate_classification_item(item, domains.len())?;
 
         let domain = &domains[item.domaine_id];
 
         // Post-filter: skip domains not actually mentioned in the dictation
-        if !domain_mentioned_in_text(&domain.nom, &text) {
+        if !domain_mentioned_in_text(&domain.nom, text) {
             info!(
                 "Domaine '{}' filtre (non mentionne dans la dictee)",
                 domain.nom
@@ -519,7 +482,7 @@ pub async fn classify_and_merge(
     if items.is_empty() && !classification_items.is_empty() {
         let first = &classification_items[0];
         let observation_text =
-            validate_classification_item(first, domains.len()).map_err(|e| e.to_string())?;
+            validate_classification_item(first, domains.len())?;
         let domain = &domains[first.domaine_id];
         items.push(ClassificationResultItem {
             domaine_id: domain.id,
@@ -538,15 +501,44 @@ pub async fn classify_and_merge(
         duration_ms, items.len(), classification_items.len(), eleve_id
     );
 
-    // Step 8: Auto-stop llama (ADR-002)
-    state.auto_stop_after_task(&app, SidecarName::Llama).await;
-
     Ok(ClassificationResults {
         items,
         duration_ms,
+        usage,
     })
 }
 
+/// Tauri command for `classify_and_merge_impl`, queued as an interactive job: the
+/// queue worker connects the LLM backend (starts llama-server if embedded) and
+/// auto-stops llama once no job is left (ADR-002).
+#[tauri::command]
+pub async fn classify_and_merge(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, SidecarManager>,
+    queue: tauri::State<'_, LlmJobQueue>,
+    text: String,
+    eleve_id: i64,
+    periode_id: i64,
+) -> Result<ClassificationResults, AppError> {
+    let start = Instant::now();
+    let pool = crate::db::pool(&app).await?;
+    let ctx_size = state.get_settings().await.ctx_size;
+
+    let spec = LlmJobSpec { id: None, job: PromptJob::Classification, priority: LlmJobPriority::Interactive };
+    let mut results = queue
+        .run(spec, move |backend| {
+            async move {
+                classify_and_merge_impl(&pool, backend.as_ref(), ctx_size, &text, eleve_id, periode_id).await
+            }
+            .boxed()
+        })
+        .await?;
+
+    // Reported duration includes the queue wait and the sidecar startup
+    results.duration_ms = start.elapsed().as_millis() as u64;
+    Ok(results)
+}
+
 // ─── V2.1 — Job 2 (Synthese) + Job 3 (Appreciation) ───
 
 /// Response type for Job 2 — Synthese LSU
@@ -555,6 +547,12 @@ struct LlmSyntheseResponse {
     synthese: String,
 }
 
+/// Partial summary of a map-reduce synthese (first pass)
+#[derive(Debug, Deserialize)]
+struct LlmResumeResponse {
+    resume: String,
+}
+
 /// Response type for Job 3 — Appreciation generale
 #[derive(Debug, Deserialize)]
 struct LlmAppreciationResponse {
@@ -566,6 +564,13 @@ struct LlmAppreciationResponse {
 pub struct SyntheseResult {
     pub synthese: String,
     pub duration_ms: u64,
+    pub usage: TokenUsage,
+    /// Template version used for the system prompt (`prompt_templates.id`)
+    pub prompt_template_id: i64,
+    /// LSU rules still failing after the rewrites (empty when the text complies)
+    pub violations: Vec<RuleViolation>,
+    /// Every candidate, best first (`synthese` and `violations` repeat the first one)
+    pub candidates: Vec<Candidate>,
 }
 
 /// Result returned to the frontend for Job 3
@@ -573,61 +578,13 @@ pub struct SyntheseResult {
 pub struct AppreciationResult {
     pub appreciation: String,
     pub duration_ms: u64,
-}
-
-/// Generic LLM request helper — sends system+user prompt with grammar, returns raw content string.
-///
-/// Extracted to avoid duplicating HTTP code across Jobs 2 and 3.
-async fn send_simple_llm_request(
-    system_prompt: &str,
-    user_prompt: &str,
-    grammar: &str,
-    max_tokens: u32,
-    timeout_secs: u64,
-) -> Result<String, SidecarError> {
-    let body = serde_json::json!({
-        "model": "qwen2.5-coder",
-        "messages": [
-            { "role": "system", "content": system_prompt },
-            { "role": "user", "content": user_prompt }
-        ],
-        "temperature": 0.1,
-        "max_tokens": max_tokens,
-        "grammar": grammar
-    });
-
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(timeout_secs))
-        .build()
-        .map_err(|e| SidecarError::Internal(e.to_string()))?;
-
-    let response = client
-        .post("http://127.0.0.1:8080/v1/chat/completions")
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| {
-            SidecarError::Internal(format!("Requete vers llama-server echouee: {}", e))
-        })?;
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let body_text = response.text().await.unwrap_or_default();
-        return Err(SidecarError::Internal(format!(
-            "llama-server a repondu avec le code {}: {}",
-            status, body_text
-        )));
-    }
-
-    let completion: ChatCompletionResponse = response.json().await.map_err(|e| {
-        SidecarError::Internal(format!("Reponse JSON invalide de llama-server: {}", e))
-    })?;
-
-    Ok(completion
-        .choices
-        .first()
-        .map(|c| c.message.content.clone())
-        .unwrap_or_default())
+    pub usage: TokenUsage,
+    /// Template version used for the system prompt (`prompt_templates.id`)
+    pub prompt_template_id: i64,
+    /// LSU rules still failing after the rewrites (empty when the text complies)
+    pub violations: Vec<RuleViolation>,
+    /// Every candidate, best first (`appreciation` and `violations` repeat the first one)
+    pub candidates: Vec<Candidate>,
 }
 
 /// DB row for events query (Job 2)
@@ -771,164 +728,511 @@ fn validate_appreciation_text(text: &str) -> Result<String, SidecarError> {
     Ok(trimmed.to_string())
 }
 
+/// Parse the JSON answer of Job 2 into a non-empty synthese
+fn parse_synthese_content(content: &str) -> Result<String, AppError> {
+    let llm_response: LlmSyntheseResponse = serde_json::from_str(content).map_err(|e| {
+        format!("JSON synthese invalide (GBNF non respectee?): {}. Contenu: {}", e, content)
+    })?;
+    Ok(validate_synthese_text(&llm_response.synthese)?)
+}
+
+/// Parse the JSON answer of Job 3 into a non-empty appreciation
+fn parse_appreciation_content(content: &str) -> Result<String, AppError> {
+    let llm_response: LlmAppreciationResponse = serde_json::from_str(content).map_err(|e| {
+        format!("JSON appreciation invalide (GBNF non respectee?): {}. Contenu: {}", e, content)
+    })?;
+    Ok(validate_appreciation_text(&llm_response.appreciation)?)
+}
+
+/// Generated text checked against the LSU rules
+struct CheckedText {
+    text: String,
+    violations: Vec<RuleViolation>,
+    /// Sampling temperature of the request that produced the text
+    temperature: f32,
+}
+
+/// Candidate text proposed to the teacher, in ranking order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Candidate {
+    pub text: String,
+    /// LSU rules the candidate fails (empty when it complies)
+    pub violations: Vec<RuleViolation>,
+    /// Validator score (`text_rules::penalty`), 0 when compliant
+    pub penalty: u32,
+    pub temperature: f32,
+}
+
+impl From<CheckedText> for Candidate {
+    fn from(checked: CheckedText) -> Self {
+        Candidate {
+            penalty: text_rules::penalty(&checked.violations),
+            text: checked.text,
+            violations: checked.violations,
+            temperature: checked.temperature,
+        }
+    }
+}
+
+/// Generate `count` candidates and rank them by validator score (`text_rules`).
+///
+/// The first candidate keeps the near-deterministic temperature, the next ones
+/// sample hotter with distinct seeds. A candidate whose request fails is skipped
+/// when others are requested. When no candidate complies, the best one is
+/// rewritten (see `rewrite_checked`) before ranking.
+async fn complete_candidates(
+    backend: &dyn LlmBackend,
+    request: LlmRequest,
+    rules: &TextRules<'_>,
+    parse: fn(&str) -> Result<String, AppError>,
+    count: usize,
+) -> Result<Vec<Candidate>, AppError> {
+    let base_seed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let mut checked = Vec::with_capacity(count);
+    let mut last_error = None;
+    for (i, &temperature) in CANDIDATE_TEMPERATURES.iter().take(count).enumerate() {
+        let candidate_request = LlmRequest {
+            temperature,
+            seed: (count > 1).then_some(base_seed + i as u64),
+            ..request.clone()
+        };
+        let text = match backend.complete(&candidate_request).await {
+            Ok(content) => parse(&content),
+            Err(e) => Err(e.into()),
+        };
+        match text {
+            Ok(text) => checked.push(CheckedText {
+                violations: text_rules::check(&text, rules),
+                text,
+                temperature,
+            }),
+            Err(e) if count > 1 => {
+                log::warn!("Proposition {}/{} ignoree: {}", i + 1, count, e);
+                last_error = Some(e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    if checked.is_empty() {
+        return Err(last_error.unwrap_or_else(|| AppError::Internal("Aucune proposition generee".to_string())));
+    }
+
+    checked.sort_by_key(|c| text_rules::penalty(&c.violations));
+    if !checked[0].violations.is_empty() {
+        let best = checked.remove(0);
+        let best_request = LlmRequest { temperature: best.temperature, ..request };
+        checked.insert(0, rewrite_checked(backend, &best_request, rules, parse, best).await?);
+        checked.sort_by_key(|c| text_rules::penalty(&c.violations));
+    }
+    Ok(checked.into_iter().map(Candidate::from).collect())
+}
+
+/// Rewrite a text failing the LSU rules, at most `MAX_REWRITES` times: a shortening
+/// pass when only the length fails, otherwise the original prompt is sent again with
+/// the failed rules listed. A rewrite replaces the text unless its rule penalty is
+/// higher; the rules still failing are kept for the report. A backend error stops the
+/// rewriting and keeps the best text so far (a cancellation is still propagated).
+async fn rewrite_checked(
+    backend: &dyn LlmBackend,
+    request: &LlmRequest,
+    rules: &TextRules<'_>,
+    parse: fn(&str) -> Result<String, AppError>,
+    mut current: CheckedText,
+) -> Result<CheckedText, AppError> {
+    let mut rewrites = 0;
+    while !current.violations.is_empty() && rewrites < MAX_REWRITES {
+        rewrites += 1;
+        let user_prompt = if current.violations.iter().all(|v| v.rule == Rule::Length) {
+            format!(
+                "Texte a raccourcir :\n{}\n\nReecris ce texte en {} caracteres maximum, \
+                 en gardant les informations principales et le meme style.",
+                current.text, rules.max_chars
+            )
+        } else {
+            let failed: Vec<String> = current.violations.iter().map(|v| format!("- {}", v.message)).collect();
+            format!(
+                "{}\n\nUne premiere version ne respectait pas ces regles :\n{}\n\
+                 Redige une nouvelle version qui les respecte.",
+                request.user_prompt,
+                failed.join("\n")
+            )
+        };
+        info!("Reecriture {}/{} : {}", rewrites, MAX_REWRITES, joined_messages(&current.violations));
+        let rewrite = LlmRequest { user_prompt, ..request.clone() };
+
+        let answer = match backend.complete(&rewrite).await.map_err(AppError::from) {
+            Ok(answer) => answer,
+            Err(AppError::Cancelled) => return Err(AppError::Cancelled),
+            Err(e) => {
+                log::warn!("Reecriture interrompue, meilleure version conservee: {}", e);
+                break;
+            }
+        };
+        let candidate = match parse(&answer) {
+            Ok(candidate) => candidate,
+            Err(e) => {
+                log::warn!("Reecriture inexploitable ignoree: {}", e);
+                continue;
+            }
+        };
+        let violations = text_rules::check(&candidate, rules);
+        if text_rules::penalty(&violations) <= text_rules::penalty(&current.violations) {
+            current = CheckedText { text: candidate, violations, temperature: current.temperature };
+        }
+    }
+
+    if !current.violations.is_empty() {
+        log::warn!(
+            "Regles LSU non respectees apres {} reecriture(s) : {}",
+            rewrites,
+            joined_messages(&current.violations)
+        );
+    }
+    Ok(current)
+}
+
+fn check_candidate_count(count: usize) -> Result<(), AppError> {
+    if !(1..=MAX_CANDIDATES).contains(&count) {
+        return Err(AppError::validation(format!(
+            "Nombre de propositions invalide : entre 1 et {}.",
+            MAX_CANDIDATES
+        )));
+    }
+    Ok(())
+}
+
+fn joined_messages(violations: &[RuleViolation]) -> String {
+    violations.iter().map(|v| v.message.as_str()).collect::<Vec<_>>().join(" ; ")
+}
+
 /// Generate a LSU synthese for a student/domain/period using the Qwen LLM (Job 2).
 ///
 /// Pipeline:
-/// 1. Load domain name + events from DB
-/// 2. Build synthese prompt (ADR-008 budget)
-/// 3. Start llama-server if needed
-/// 4. Send request with static GBNF grammar
-/// 5. Parse + validate response
-/// 6. Auto-stop llama (ADR-002)
-#[tauri::command]
-pub async fn generate_synthese(
-    app: tauri::AppHandle,
-    state: tauri::State<'_, SidecarManager>,
+/// 1. Load domain name + events + the synthese template for the student's cycle from DB
+/// 2. Plan the synthese prompt (ADR-008 budget): direct, or map-reduce when the
+///    observations do not fit (chunk summaries first, see `summarise_chunks`)
+/// 3. Send request with static GBNF grammar or JSON schema
+/// 4. Parse + rank the candidates by LSU rules, rewriting when none complies (`complete_candidates`)
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn generate_synthese_impl(
+    pool: &sqlx::SqlitePool,
+    backend: &dyn LlmBackend,
+    ctx_size: usize,
     eleve_id: i64,
     domaine_id: i64,
     periode_id: i64,
     annee_scolaire_id: i64,
-    student_name: String,
-) -> Result<SyntheseResult, String> {
+    student_name: &str,
+    candidates: usize,
+) -> Result<SyntheseResult, AppError> {
+    check_candidate_count(candidates)?;
     let start = Instant::now();
 
-    let pool = open_db_pool(&app).await.map_err(|e| e.to_string())?;
-
     // Fetch domain name for the prompt
     let domaine_nom: String = sqlx::query_scalar(
         "SELECT nom FROM domaines_apprentissage WHERE id = ?",
     )
     .bind(domaine_id)
-    .fetch_one(&pool)
-    .await
-    .map_err(|e| format!("Domaine introuvable (id={}): {}", domaine_id, e))?;
-
-    let events = load_events_for_synthese(&pool, eleve_id, domaine_id, periode_id, annee_scolaire_id)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let prompt = prompt_builder::build_synthese_prompt(&events, &domaine_nom, &student_name);
-    let grammar = gbnf::generate_synthese_gbnf();
-
-    // Start llama-server if not running
-    let model_path = resolve_model_path(&app).map_err(|e| e.to_string())?;
-    let status = state.get_status().await;
-    if !status.llama.running {
-        info!("llama-server non demarre, lancement automatique (generate_synthese)...");
-        state
-            .start(&app, SidecarName::Llama, model_path.to_string_lossy().to_string(), None)
-            .await
-            .map_err(|e| e.to_string())?;
-    }
-
-    let content = send_simple_llm_request(
-        &prompt.system_prompt,
-        &prompt.user_prompt,
-        &grammar,
-        512,
-        30,
-    )
+    .fetch_one(pool)
     .await
-    .map_err(|e| e.to_string())?;
+    .map_err(|e| {
+        AppError::not_found(format!("Domaine introuvable (id={}): {}", domaine_id, e))
+    })?;
 
-    state.increment_request_count(SidecarName::Llama).await;
+    let events = load_events_for_synthese(pool, eleve_id, domaine_id, periode_id, annee_scolaire_id)
+        .await?;
 
-    let llm_response: LlmSyntheseResponse = serde_json::from_str(&content).map_err(|e| {
-        format!("JSON synthese invalide (GBNF non respectee?): {}. Contenu: {}", e, content)
-    })?;
+    let profile = load_student_profile(pool, eleve_id).await?;
+    let (template, instructions) =
+        job_instructions(pool, PromptJob::Synthese, &profile, student_name, Some(&domaine_nom)).await?;
 
-    let synthese = validate_synthese_text(&llm_response.synthese).map_err(|e| e.to_string())?;
+    let plan =
+        prompt_builder::plan_synthese(&instructions, &events, &domaine_nom, student_name, ctx_size, backend)
+            .await;
+    let prompt = match plan {
+        SynthesePlan::Direct(prompt) => prompt,
+        SynthesePlan::MapReduce { chunks, selection } => {
+            info!(
+                "Synthese en deux passes pour eleve_id={} domaine_id={}: {} partie(s)",
+                eleve_id, domaine_id, chunks.len()
+            );
+            let summaries = summarise_chunks(backend, chunks).await?;
+            prompt_builder::build_synthese_reduce_prompt(
+                &instructions,
+                &selection,
+                &summaries,
+                &domaine_nom,
+                student_name,
+                ctx_size,
+                backend,
+            )
+            .await
+        }
+    };
+    let usage = prompt.usage;
+    let request = LlmRequest {
+        system_prompt: prompt.system_prompt,
+        user_prompt: prompt.user_prompt,
+        constraint: OutputConstraint {
+            name: "synthese",
+            gbnf: gbnf::generate_synthese_gbnf(),
+            json_schema: gbnf::synthese_json_schema(),
+        },
+        temperature: LLM_TEMPERATURE,
+        seed: None,
+        max_tokens: 512,
+        timeout: Duration::from_secs(30),
+    };
+    let classmates = load_classmate_names(pool, eleve_id).await?;
+    let rules = TextRules { max_chars: template.longueur_max as usize, student_name, other_names: &classmates };
+    let candidates = complete_candidates(backend, request, &rules, parse_synthese_content, candidates).await?;
 
     let duration_ms = start.elapsed().as_millis() as u64;
     info!(
-        "Synthese generee en {}ms pour eleve_id={} domaine_id={}",
-        duration_ms, eleve_id, domaine_id
+        "Synthese generee en {}ms pour eleve_id={} domaine_id={} ({} proposition(s))",
+        duration_ms, eleve_id, domaine_id, candidates.len()
     );
 
-    state.auto_stop_after_task(&app, SidecarName::Llama).await;
+    Ok(SyntheseResult {
+        synthese: candidates[0].text.clone(),
+        duration_ms,
+        usage,
+        prompt_template_id: template.id,
+        violations: candidates[0].violations.clone(),
+        candidates,
+    })
+}
 
-    Ok(SyntheseResult { synthese, duration_ms })
+/// First pass of a map-reduce synthese: one summary per chunk of observations.
+/// Empty summaries are skipped.
+async fn summarise_chunks(
+    backend: &dyn LlmBackend,
+    chunks: Vec<PromptBuilderResult>,
+) -> Result<Vec<String>, AppError> {
+    let mut summaries = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        let request = LlmRequest {
+            system_prompt: chunk.system_prompt,
+            user_prompt: chunk.user_prompt,
+            constraint: OutputConstraint {
+                name: "resume",
+                gbnf: gbnf::generate_resume_gbnf(),
+                json_schema: gbnf::resume_json_schema(),
+            },
+            temperature: LLM_TEMPERATURE,
+            seed: None,
+            max_tokens: 256,
+            timeout: Duration::from_secs(30),
+        };
+        let content = backend.complete(&request).await?;
+        let response: LlmResumeResponse = serde_json::from_str(&content).map_err(|e| {
+            format!("JSON resume invalide (GBNF non respectee?): {}. Contenu: {}", e, content)
+        })?;
+        let resume = response.resume.trim();
+        if resume.is_empty() {
+            log::warn!("Resume partiel vide ignore");
+            continue;
+        }
+        summaries.push(resume.to_string());
+    }
+    if summaries.is_empty() {
+        return Err(SidecarError::Internal("Resumes partiels vides retournes par le LLM".to_string()).into());
+    }
+    Ok(summaries)
 }
 
-/// Generate a LSU appreciation generale for a student/period using the Qwen LLM (Job 3).
-///
-/// Pipeline:
-/// 1. Load existing syntheses + behavior summary from DB
-/// 2. Build appreciation prompt (ADR-008 budget)
-/// 3. Start llama-server if needed
-/// 4. Send request with static GBNF grammar
-/// 5. Parse + validate response
-/// 6. Auto-stop llama (ADR-002)
+/// Tauri command for `generate_synthese_impl`, queued in the `llm_queue` (batch priority
+/// unless given). With a `request_id`, the generation is streamed as `llm_delta`
+/// events and can be cancelled with `cancel_llm_job`.
 #[tauri::command]
-pub async fn generate_appreciation(
+#[allow(clippy::too_many_arguments)]
+pub async fn generate_synthese(
     app: tauri::AppHandle,
     state: tauri::State<'_, SidecarManager>,
+    queue: tauri::State<'_, LlmJobQueue>,
     eleve_id: i64,
+    domaine_id: i64,
     periode_id: i64,
     annee_scolaire_id: i64,
     student_name: String,
-) -> Result<AppreciationResult, String> {
+    candidates: Option<usize>,
+    request_id: Option<String>,
+    priority: Option<LlmJobPriority>,
+) -> Result<SyntheseResult, AppError> {
     let start = Instant::now();
+    let pool = crate::db::pool(&app).await?;
+    let ctx_size = state.get_settings().await.ctx_size;
+    let emit = delta_emitter(&app);
+
+    let spec = LlmJobSpec {
+        id: request_id.clone(),
+        job: PromptJob::Synthese,
+        priority: priority.unwrap_or(LlmJobPriority::Batch),
+    };
+    let mut result = queue
+        .run(spec, move |backend| {
+            async move {
+                let streaming = request_id
+                    .as_deref()
+                    .map(|id| StreamingBackend::new(backend.as_ref(), id, emit));
+                let llm: &dyn LlmBackend = match &streaming {
+                    Some(streaming) => streaming,
+                    None => backend.as_ref(),
+                };
+                generate_synthese_impl(
+                    &pool,
+                    llm,
+                    ctx_size,
+                    eleve_id,
+                    domaine_id,
+                    periode_id,
+                    annee_scolaire_id,
+                    &student_name,
+                    candidates.unwrap_or(1),
+                )
+                .await
+            }
+            .boxed()
+        })
+        .await?;
 
-    let pool = open_db_pool(&app).await.map_err(|e| e.to_string())?;
+    result.duration_ms = start.elapsed().as_millis() as u64;
+    Ok(result)
+}
+
+/// Generate a LSU appreciation generale for a student/period using the Qwen LLM (Job 3).
+///
+/// Pipeline:
+/// 1. Load existing syntheses + behavior summary + the appreciation template from DB
+/// 2. Build appreciation prompt (ADR-008 budget)
+/// 3. Send request with static GBNF grammar or JSON schema
+/// 4. Parse + rank the candidates by LSU rules, rewriting when none complies (`complete_candidates`)
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn generate_appreciation_impl(
+    pool: &sqlx::SqlitePool,
+    backend: &dyn LlmBackend,
+    ctx_size: usize,
+    eleve_id: i64,
+    periode_id: i64,
+    annee_scolaire_id: i64,
+    student_name: &str,
+    candidates: usize,
+) -> Result<AppreciationResult, AppError> {
+    check_candidate_count(candidates)?;
+    let start = Instant::now();
 
     let syntheses =
-        load_syntheses_for_appreciation(&pool, eleve_id, periode_id, annee_scolaire_id)
-            .await
-            .map_err(|e| e.to_string())?;
+        load_syntheses_for_appreciation(pool, eleve_id, periode_id, annee_scolaire_id).await?;
 
     let behavior =
-        load_behavior_summary(&pool, eleve_id, periode_id, annee_scolaire_id)
-            .await
-            .map_err(|e| e.to_string())?;
-
-    let prompt =
-        prompt_builder::build_appreciation_prompt(&syntheses, &behavior, &student_name);
-    let grammar = gbnf::generate_appreciation_gbnf();
-
-    // Start llama-server if not running
-    let model_path = resolve_model_path(&app).map_err(|e| e.to_string())?;
-    let status = state.get_status().await;
-    if !status.llama.running {
-        info!("llama-server non demarre, lancement automatique (generate_appreciation)...");
-        state
-            .start(&app, SidecarName::Llama, model_path.to_string_lossy().to_string(), None)
-            .await
-            .map_err(|e| e.to_string())?;
-    }
-
-    let content = send_simple_llm_request(
-        &prompt.system_prompt,
-        &prompt.user_prompt,
-        &grammar,
-        768,
-        45,
+        load_behavior_summary(pool, eleve_id, periode_id, annee_scolaire_id).await?;
+
+    let profile = load_student_profile(pool, eleve_id).await?;
+    let (template, instructions) =
+        job_instructions(pool, PromptJob::Appreciation, &profile, student_name, None).await?;
+
+    let prompt = prompt_builder::build_appreciation_prompt(
+        &instructions,
+        &syntheses,
+        &behavior,
+        student_name,
+        ctx_size,
+        backend,
     )
-    .await
-    .map_err(|e| e.to_string())?;
-
-    state.increment_request_count(SidecarName::Llama).await;
-
-    let llm_response: LlmAppreciationResponse =
-        serde_json::from_str(&content).map_err(|e| {
-            format!(
-                "JSON appreciation invalide (GBNF non respectee?): {}. Contenu: {}",
-                e, content
-            )
-        })?;
-
-    let appreciation =
-        validate_appreciation_text(&llm_response.appreciation).map_err(|e| e.to_string())?;
+    .await;
+    let usage = prompt.usage;
+    let request = LlmRequest {
+        system_prompt: prompt.system_prompt,
+        user_prompt: prompt.user_prompt,
+        constraint: OutputConstraint {
+            name: "appreciation",
+            gbnf: gbnf::generate_appreciation_gbnf(),
+            json_schema: gbnf::appreciation_json_schema(),
+        },
+        temperature: LLM_TEMPERATURE,
+        seed: None,
+        max_tokens: 768,
+        timeout: Duration::from_secs(45),
+    };
+    let classmates = load_classmate_names(pool, eleve_id).await?;
+    let rules = TextRules { max_chars: template.longueur_max as usize, student_name, other_names: &classmates };
+    let candidates = complete_candidates(backend, request, &rules, parse_appreciation_content, candidates).await?;
 
     let duration_ms = start.elapsed().as_millis() as u64;
     info!(
-        "Appreciation generee en {}ms pour eleve_id={} periode_id={}",
-        duration_ms, eleve_id, periode_id
+        "Appreciation generee en {}ms pour eleve_id={} periode_id={} ({} proposition(s))",
+        duration_ms, eleve_id, periode_id, candidates.len()
     );
 
-    state.auto_stop_after_task(&app, SidecarName::Llama).await;
+    Ok(AppreciationResult {
+        appreciation: candidates[0].text.clone(),
+        duration_ms,
+        usage,
+        prompt_template_id: template.id,
+        violations: candidates[0].violations.clone(),
+        candidates,
+    })
+}
 
-    Ok(AppreciationResult { appreciation, duration_ms })
+/// Tauri command for `generate_appreciation_impl`, queued in the `llm_queue` (batch priority
+/// unless given). With a `request_id`, the generation is streamed as `llm_delta`
+/// events and can be cancelled with `cancel_llm_job`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn generate_appreciation(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, SidecarManager>,
+    queue: tauri::State<'_, LlmJobQueue>,
+    eleve_id: i64,
+    periode_id: i64,
+    annee_scolaire_id: i64,
+    student_name: String,
+    candidates: Option<usize>,
+    request_id: Option<String>,
+    priority: Option<LlmJobPriority>,
+) -> Result<AppreciationResult, AppError> {
+    let start = Instant::now();
+    let pool = crate::db::pool(&app).await?;
+    let ctx_size = state.get_settings().await.ctx_size;
+    let emit = delta_emitter(&app);
+
+    let spec = LlmJobSpec {
+        id: request_id.clone(),
+        job: PromptJob::Appreciation,
+        priority: priority.unwrap_or(LlmJobPriority::Batch),
+    };
+    let mut result = queue
+        .run(spec, move |backend| {
+            async move {
+                let streaming = request_id
+                    .as_deref()
+                    .map(|id| StreamingBackend::new(backend.as_ref(), id, emit));
+                let llm: &dyn LlmBackend = match &streaming {
+                    Some(streaming) => streaming,
+                    None => backend.as_ref(),
+                };
+                generate_appreciation_impl(
+                    &pool,
+                    llm,
+                    ctx_size,
+                    eleve_id,
+                    periode_id,
+                    annee_scolaire_id,
+                    &student_name,
+                    candidates.unwrap_or(1),
+                )
+                .await
+            }
+            .boxed()
+        })
+        .await?;
+
+    result.duration_ms = start.elapsed().as_millis() as u64;
+    Ok(result)
 }
 
 #[cfg(test)]
@@ -1120,6 +1424,24 @@ mod tests {
         assert_eq!(items[1].domaine_id, 5);
     }
 
+    #[test]
+    fn parse_classification_accepts_array_and_wrapped_object() {
+        let array = r#"[{"domaine_id": 1, "observation_mise_a_jour": "A."}]"#;
+        assert_eq!(parse_classification_content(array).unwrap()[0].domaine_id, 1);
+
+        let wrapped = r#"{"resultats": [{"domaine_id": 0, "observation_mise_a_jour": "A."}, {"domaine_id": 2, "observation_mise_a_jour": "B."}]}"#;
+        assert_eq!(parse_classification_content(wrapped).unwrap().len(), 2);
+
+        // Truncated wrapped object: complete items are kept
+        let truncated = r#"{"resultats": [{"domaine_id": 3, "observation_mise_a_jour": "A."}, {"domaine_id": 1, "obs"#;
+        let items = parse_classification_content(truncated).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].domaine_id, 3);
+
+        assert!(parse_classification_content(r#"{"resultats": []}"#).is_err());
+        assert!(parse_classification_content("pas du json").is_err());
+    }
+
     #[test]
     fn recover_no_complete_items() {
         let json = r#"[{"domaine_id": 0, "observation_mise_a_jo"#;
@@ -1175,4 +1497,326 @@ mod tests {
         let ok = validate_appreciation_text(&result.appreciation);
         assert!(ok.is_ok());
     }
+
+    // ─── Pipeline tests (mock llama-server replaying recorded sessions) ───
+
+    use crate::sidecar::llm_backend::LlamaServerBackend;
+    use crate::sidecar::testing::{MockSidecar, Recording};
+
+    async fn setup_test_pool() -> (sqlx::SqlitePool, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = crate::db::open_pool(&dir.path().join("test.db"))
+            .await
+            .expect("Impossible de creer la DB de test");
+        for sql in [
+            "CREATE TABLE niveaux_classe (code TEXT PRIMARY KEY, cycle INTEGER)",
+            "CREATE TABLE students (id INTEGER PRIMARY KEY, first_name TEXT NOT NULL, niveau TEXT)",
+            "CREATE TABLE domaines_apprentissage (id INTEGER PRIMARY KEY, nom TEXT NOT NULL,
+                cycle INTEGER, is_custom INTEGER DEFAULT 0, actif INTEGER DEFAULT 1,
+                ordre_affichage INTEGER DEFAULT 0)",
+            "CREATE TABLE appreciations (id INTEGER PRIMARY KEY, eleve_id INTEGER, periode_id INTEGER,
+                domaine_id INTEGER, observations TEXT, created_at TEXT DEFAULT (datetime('now')))",
+            "CREATE TABLE evenements_pedagogiques (id INTEGER PRIMARY KEY, eleve_id INTEGER,
+                domaine_id INTEGER, periode_id INTEGER, annee_scolaire_id INTEGER, type TEXT,
+                observations TEXT, niveau_lsu TEXT, lecon TEXT, created_at TEXT DEFAULT (datetime('now')))",
+            "CREATE TABLE syntheses_lsu (id INTEGER PRIMARY KEY, eleve_id INTEGER, periode_id INTEGER,
+                domaine_id INTEGER, annee_scolaire_id INTEGER, version INTEGER, texte TEXT)",
+            "CREATE TABLE absences_v2 (id INTEGER PRIMARY KEY, eleve_id INTEGER, type_absence TEXT,
+                annee_scolaire_id INTEGER)",
+            "CREATE TABLE prompt_templates (id INTEGER PRIMARY KEY AUTOINCREMENT, job TEXT NOT NULL,
+                cycle INTEGER NOT NULL DEFAULT 0, version INTEGER NOT NULL, contenu TEXT DEFAULT NULL,
+                longueur_max INTEGER NOT NULL, created_at TEXT DEFAULT (datetime('now')),
+                UNIQUE(job, cycle, version))",
+            "INSERT INTO niveaux_classe (code, cycle) VALUES ('CE2', 2)",
+            "INSERT INTO students (id, first_name, niveau) VALUES (1, 'Léa', 'CE2')",
+            "INSERT INTO domaines_apprentissage (id, nom, cycle, ordre_affichage) VALUES
+                (1, 'Francais', 2, 1), (2, 'Mathematiques', 2, 2), (3, 'Histoire-Geographie', 3, 3)",
+            "INSERT INTO appreciations (eleve_id, periode_id, domaine_id, observations) VALUES
+                (1, 1, 1, 'Lecture hesitante.')",
+            "INSERT INTO evenements_pedagogiques (eleve_id, domaine_id, periode_id, annee_scolaire_id,
+                type, observations, niveau_lsu) VALUES (1, 1, 1, 1, 'observation', 'Lit sans hesiter.', 'atteints')",
+            "INSERT INTO syntheses_lsu (eleve_id, periode_id, domaine_id, annee_scolaire_id, version, texte)
+                VALUES (1, 1, 1, 1, 1, 'Lecture fluide.')",
+        ] {
+            sqlx::query(sql).execute(&pool).await.unwrap();
+        }
+        (pool, dir)
+    }
+
+    const DICTEE: &str = "En lecture elle progresse, et en calcul mental aussi.";
+
+    #[tokio::test]
+    async fn pipeline_classification_replays_recorded_answer() {
+        let (pool, _dir) = setup_test_pool().await;
+        let mock = MockSidecar::replay("classification").await;
+        let backend = LlamaServerBackend::new(&mock.base_url);
+
+        let results = classify_and_merge_impl(&pool, &backend, 3072, DICTEE, 1, 1).await.unwrap();
+        assert_eq!(results.items.len(), 2);
+        assert_eq!(results.items[0].domaine_id, 1);
+        assert_eq!(results.items[0].observation_before.as_deref(), Some("Lecture hesitante."));
+        assert_eq!(results.items[1].domaine_nom, "Mathematiques");
+        assert_eq!(results.items[1].observation_before, None);
+
+        // The cycle 3 domain is not offered to the LLM
+        let requests = mock.requests("/v1/chat/completions");
+        assert_eq!(requests.len(), 1);
+        let body = requests[0].json();
+        assert!(body["grammar"].as_str().unwrap().contains("domaine-id ::= \"0\" | \"1\"\n"));
+        assert!(body["messages"][1]["content"].as_str().unwrap().contains(DICTEE));
+
+        // Budgeted with llama-server's tokenizer (the mock counts one token per char)
+        assert!(results.usage.exact);
+        assert_eq!(results.usage.budget, prompt_builder::input_budget(3072));
+        let prompt_chars = body["messages"][0]["content"].as_str().unwrap().chars().count()
+            + body["messages"][1]["content"].as_str().unwrap().chars().count();
+        assert_eq!(results.usage.prompt_tokens, prompt_chars);
+        assert!(!mock.requests("/tokenize").is_empty());
+    }
+
+    #[tokio::test]
+    async fn pipeline_classification_recovers_truncated_answer() {
+        let (pool, _dir) = setup_test_pool().await;
+        let mock = MockSidecar::replay("classification_truncated").await;
+        let backend = LlamaServerBackend::new(&mock.base_url);
+
+        let results = classify_and_merge_impl(&pool, &backend, 3072, DICTEE, 1, 1).await.unwrap();
+        assert_eq!(results.items.len(), 1);
+        assert_eq!(results.items[0].domaine_nom, "Mathematiques");
+    }
+
+    #[tokio::test]
+    async fn pipeline_synthese_and_appreciation_replay_recorded_answers() {
+        let (pool, _dir) = setup_test_pool().await;
+
+        let mock = MockSidecar::replay("synthese").await;
+        let backend = LlamaServerBackend::new(&mock.base_url);
+        let synthese = generate_synthese_impl(&pool, &backend, 3072, 1, 1, 1, 1, "Léa", 1).await.unwrap();
+        assert_eq!(synthese.synthese, "Léa lit avec aisance et progresse en compréhension.");
+        let body = mock.requests("/v1/chat/completions")[0].json();
+        assert!(body["messages"][1]["content"].as_str().unwrap().contains("Lit sans hesiter."));
+        assert_eq!(body["max_tokens"], 512);
+
+        let mock = MockSidecar::replay("appreciation").await;
+        let backend = LlamaServerBackend::new(&mock.base_url);
+        let appreciation = generate_appreciation_impl(&pool, &backend, 3072, 1, 1, 1, "Léa", 1).await.unwrap();
+        assert!(appreciation.appreciation.starts_with("Un trimestre serieux"));
+        let body = mock.requests("/v1/chat/completions")[0].json();
+        assert!(body["messages"][1]["content"].as_str().unwrap().contains("Lecture fluide."));
+        assert!(synthese.usage.exact && appreciation.usage.exact);
+        assert!(appreciation.usage.prompt_tokens <= appreciation.usage.budget);
+    }
+
+    #[tokio::test]
+    async fn pipeline_synthese_map_reduces_a_busy_period() {
+        let (pool, _dir) = setup_test_pool().await;
+        for i in 0..12 {
+            sqlx::query(
+                "INSERT INTO evenements_pedagogiques (eleve_id, domaine_id, periode_id, annee_scolaire_id,
+                    type, niveau_lsu, lecon, created_at) VALUES (1, 2, 1, 1, 'evaluation', ?, ?, ?)",
+            )
+            .bind(if i < 6 { "non_atteints" } else { "atteints" })
+            .bind(format!("Fractions {}", i % 6))
+            .bind(format!("2026-01-{:02}T09:00:00", i + 1))
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        for i in 0..40 {
+            sqlx::query(
+                "INSERT INTO evenements_pedagogiques (eleve_id, domaine_id, periode_id, annee_scolaire_id,
+                    type, observations, created_at) VALUES (1, 2, 1, 1, 'observation', ?, ?)",
+            )
+            .bind(format!("Seance {} : {}", i, "calcul pose ".repeat(12)))
+            .bind(format!("2026-02-{:02}T10:{:02}:00", (i % 28) + 1, i))
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        // Same plan as the pipeline: the mock tokenizer is deterministic
+        let probe = MockSidecar::spawn(Vec::new()).await;
+        let events = load_events_for_synthese(&pool, 1, 2, 1, 1).await.unwrap();
+        let profile = load_student_profile(&pool, 1).await.unwrap();
+        let (_, instructions) =
+            job_instructions(&pool, PromptJob::Synthese, &profile, "Léa", Some("Mathematiques")).await.unwrap();
+        let SynthesePlan::MapReduce { chunks, .. } = prompt_builder::plan_synthese(
+            &instructions,
+            &events,
+            "Mathematiques",
+            "Léa",
+            3072,
+            &LlamaServerBackend::new(&probe.base_url),
+        )
+        .await
+        else {
+            panic!("map-reduce attendu");
+        };
+        assert!(chunks.len() >= 2);
+
+        let mut recordings: Vec<Recording> = (0..chunks.len())
+            .map(|i| Recording::chat(&format!("{{\"resume\": \"Resume partie {}.\"}}", i + 1)))
+            .collect();
+        recordings.push(Recording::chat("{\"synthese\": \"Léa progresse nettement en calcul pose.\"}"));
+        let mock = MockSidecar::spawn(recordings).await;
+        let backend = LlamaServerBackend::new(&mock.base_url);
+
+        let result = generate_synthese_impl(&pool, &backend, 3072, 1, 2, 1, 1, "Léa", 1).await.unwrap();
+        assert_eq!(result.synthese, "Léa progresse nettement en calcul pose.");
+        assert!(result.usage.prompt_tokens <= result.usage.budget);
+
+        let requests = mock.requests("/v1/chat/completions");
+        assert_eq!(requests.len(), chunks.len() + 1);
+        assert!(requests[0].json()["grammar"].as_str().unwrap().contains("resume"));
+        let reduce = requests.last().unwrap().json();
+        let user = reduce["messages"][1]["content"].as_str().unwrap();
+        assert!(user.contains(&format!("- Partie {} : Resume partie {}.", chunks.len(), chunks.len())));
+        for lesson in 0..6 {
+            assert!(user.contains(&format!("- Lecon Fractions {} : non_atteints", lesson)), "{}", user);
+        }
+        assert!(!user.contains("Seance"));
+    }
+
+    #[tokio::test]
+    async fn pipeline_uses_the_cycle_template_and_reports_its_id() {
+        let (pool, _dir) = setup_test_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        let template = prompt_templates::save_template_impl(
+            &mut conn,
+            &prompt_templates::NewPromptTemplate {
+                job: PromptJob::Synthese,
+                cycle: 2,
+                contenu: "Ton bienveillant pour {eleve} ({niveau}, cycle {cycle}) en {domaine}, {longueur_max} caracteres."
+                    .to_string(),
+                longueur_max: Some(250),
+            },
+        )
+        .await
+        .unwrap();
+        drop(conn);
+
+        let mock = MockSidecar::spawn(vec![Recording::chat("{\"synthese\": \"Léa lit avec aisance.\"}")]).await;
+        let backend = LlamaServerBackend::new(&mock.base_url);
+        let result = generate_synthese_impl(&pool, &backend, 3072, 1, 1, 1, 1, "Léa", 1).await.unwrap();
+        assert_eq!(result.prompt_template_id, template.id);
+
+        let body = mock.requests("/v1/chat/completions")[0].json();
+        assert_eq!(
+            body["messages"][0]["content"].as_str().unwrap(),
+            "Ton bienveillant pour Léa (CE2, cycle 2) en Francais, 250 caracteres."
+        );
+    }
+
+    #[tokio::test]
+    async fn pipeline_rewrites_texts_breaking_the_lsu_rules() {
+        let (pool, _dir) = setup_test_pool().await;
+
+        // Forbidden word: the prompt is sent again with the failed rule
+        let mock = MockSidecar::spawn(vec![
+            Recording::chat("{\"synthese\": \"Léa a eu une punition mais lit mieux.\"}"),
+            Recording::chat("{\"synthese\": \"Léa lit mieux.\"}"),
+        ])
+        .await;
+        let backend = LlamaServerBackend::new(&mock.base_url);
+        let result = generate_synthese_impl(&pool, &backend, 3072, 1, 1, 1, 1, "Léa", 1).await.unwrap();
+        assert_eq!(result.synthese, "Léa lit mieux.");
+        assert!(result.violations.is_empty());
+        let requests = mock.requests("/v1/chat/completions");
+        assert_eq!(requests.len(), 2);
+        let first = requests[0].json()["messages"][1]["content"].as_str().unwrap().to_string();
+        let retry = requests[1].json()["messages"][1]["content"].as_str().unwrap().to_string();
+        assert!(retry.starts_with(&first));
+        assert!(retry.contains("- Mot interdit dans le LSU : \"punition\""));
+
+        // Too long every time: shortening passes, then the failed rule is reported
+        let long = "Léa progresse en lecture. ".repeat(20);
+        let mock = MockSidecar::spawn(vec![Recording::chat(&format!("{{\"synthese\": \"{}\"}}", long))]).await;
+        let backend = LlamaServerBackend::new(&mock.base_url);
+        let result = generate_synthese_impl(&pool, &backend, 3072, 1, 1, 1, 1, "Léa", 1).await.unwrap();
+        assert_eq!(result.synthese, long.trim());
+        assert_eq!(result.violations.len(), 1);
+        assert_eq!(result.violations[0].rule, Rule::Length);
+        let requests = mock.requests("/v1/chat/completions");
+        assert_eq!(requests.len(), 1 + MAX_REWRITES);
+        let shorten = requests[1].json()["messages"][1]["content"].as_str().unwrap().to_string();
+        assert!(shorten.starts_with("Texte a raccourcir :"));
+        assert!(shorten.contains("en 300 caracteres maximum"));
+
+        // A short rewrite with a disciplinary word weighs more than the length: kept out
+        let mock = MockSidecar::spawn(vec![
+            Recording::chat(&format!("{{\"synthese\": \"{}\"}}", long)),
+            Recording::chat("{\"synthese\": \"Léa a eu une punition.\"}"),
+        ])
+        .await;
+        let backend = LlamaServerBackend::new(&mock.base_url);
+        let result = generate_synthese_impl(&pool, &backend, 3072, 1, 1, 1, 1, "Léa", 1).await.unwrap();
+        assert_eq!(result.synthese, long.trim());
+        assert_eq!(result.violations[0].rule, Rule::Length);
+
+        // The server fails during the rewrite: the generated text is still returned
+        let mock = MockSidecar::spawn(vec![
+            Recording::chat(&format!("{{\"synthese\": \"{}\"}}", long)),
+            Recording::status("/v1/chat/completions", 500),
+        ])
+        .await;
+        let backend = LlamaServerBackend::new(&mock.base_url);
+        let result = generate_synthese_impl(&pool, &backend, 3072, 1, 1, 1, 1, "Léa", 1).await.unwrap();
+        assert_eq!(result.synthese, long.trim());
+        assert_eq!(result.violations[0].rule, Rule::Length);
+        assert_eq!(mock.requests("/v1/chat/completions").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn pipeline_ranks_candidates_by_validator_score() {
+        let (pool, _dir) = setup_test_pool().await;
+        let long = "Léa progresse en lecture. ".repeat(20);
+        let mock = MockSidecar::spawn(vec![
+            Recording::chat("{\"appreciation\": \"J'ai vu une sanction.\"}"),
+            Recording::chat(&format!("{{\"appreciation\": \"{}\"}}", long)),
+            Recording::chat("{\"appreciation\": \"Léa progresse avec regularite.\"}"),
+        ])
+        .await;
+        let backend = LlamaServerBackend::new(&mock.base_url);
+
+        let result = generate_appreciation_impl(&pool, &backend, 3072, 1, 1, 1, "Léa", 3).await.unwrap();
+        let texts: Vec<&str> = result.candidates.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec!["Léa progresse avec regularite.", long.trim(), "J'ai vu une sanction."]);
+        assert_eq!(result.appreciation, "Léa progresse avec regularite.");
+        assert!(result.violations.is_empty());
+        assert_eq!(result.candidates[0].penalty, 0);
+        assert!(result.candidates[1].penalty < result.candidates[2].penalty);
+
+        // One request per candidate, hotter with distinct seeds; no rewrite since one complies
+        let bodies: Vec<serde_json::Value> =
+            mock.requests("/v1/chat/completions").iter().map(|r| r.json()).collect();
+        assert_eq!(bodies.len(), 3);
+        assert!(bodies[0]["temperature"].as_f64().unwrap() < bodies[2]["temperature"].as_f64().unwrap());
+        assert_ne!(bodies[0]["seed"], bodies[1]["seed"]);
+        assert_eq!(result.candidates[2].temperature, CANDIDATE_TEMPERATURES[0]);
+
+        let err = generate_appreciation_impl(&pool, &backend, 3072, 1, 1, 1, "Léa", MAX_CANDIDATES + 1)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "Validation");
+    }
+
+    #[tokio::test]
+    async fn pipeline_reports_empty_answers_and_unavailable_server() {
+        let (pool, _dir) = setup_test_pool().await;
+
+        let mock = MockSidecar::replay("synthese_empty").await;
+        let backend = LlamaServerBackend::new(&mock.base_url);
+        let err = generate_synthese_impl(&pool, &backend, 3072, 1, 1, 1, 1, "Léa", 1).await.unwrap_err();
+        assert!(err.to_string().contains("vide"), "{}", err);
+
+        let mock = MockSidecar::replay("llama_loading").await;
+        let backend = LlamaServerBackend::new(&mock.base_url);
+        let err = classify_and_merge_impl(&pool, &backend, 3072, DICTEE, 1, 1).await.unwrap_err();
+        assert!(err.to_string().contains("503"), "{}", err);
+
+        // Unknown domain: rejected before any LLM call
+        let err = generate_synthese_impl(&pool, &backend, 3072, 1, 99, 1, 1, "Léa", 1).await.unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+        assert_eq!(mock.requests("/v1/chat/completions").len(), 1);
+    }
 }
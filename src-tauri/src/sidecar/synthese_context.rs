@@ -0,0 +1,257 @@
+/// Event selection for the synthese prompt (Job 2).
+///
+/// A period can hold more events than the llama ctx-size allows. Rather than
+/// dropping the oldest events, the selection:
+/// - keeps every evaluation, grouped per lesson; under budget pressure the
+///   intermediate positionings are summarised, the first and last are always kept;
+/// - merges repeated observations (same text once normalised) into one line with
+///   their count and date range.
+///
+/// `prompt_builder::plan_synthese` renders the selection against the token budget
+/// and switches to a two-pass map-reduce when observations would still be dropped.
+
+use super::prompt_builder::EventContext;
+
+/// Label of the evaluations recorded without a lesson
+const NO_LESSON: &str = "(lecon non precisee)";
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Selected context of a synthese: evaluations per lesson, deduplicated notes
+#[derive(Debug, Clone, Default)]
+pub struct SyntheseSelection {
+    /// In order of first evaluation
+    pub lessons: Vec<LessonPositionings>,
+    /// Observations and incidents, in order of first occurrence
+    pub notes: Vec<NoteGroup>,
+}
+
+/// Evaluations of one lesson, chronological
+#[derive(Debug, Clone)]
+pub struct LessonPositionings {
+    pub lecon: String,
+    pub positionings: Vec<Positioning>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Positioning {
+    pub niveau: String,
+    pub date: String,
+}
+
+/// An observation or incident, with its repetitions merged
+#[derive(Debug, Clone)]
+pub struct NoteGroup {
+    pub kind: NoteKind,
+    pub text: String,
+    pub first_date: String,
+    pub last_date: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteKind {
+    Observation,
+    Incident,
+}
+
+/// Detail kept in the evaluation section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LessonDetail {
+    /// Every positioning
+    All,
+    /// First and last positioning, the others counted
+    FirstAndLast,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Impls
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Group the events (chronological, oldest first) into lessons and notes
+pub fn select(events: &[EventContext]) -> SyntheseSelection {
+    let mut selection = SyntheseSelection::default();
+    let mut note_keys: Vec<(NoteKind, String)> = Vec::new();
+
+    for event in events {
+        let date = day_of(&event.created_at);
+        if event.event_type == "evaluation" {
+            let lecon = event
+                .lecon
+                .as_deref()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .unwrap_or(NO_LESSON);
+            let positioning = Positioning {
+                niveau: event.niveau_lsu.clone().unwrap_or_else(|| "?".to_string()),
+                date,
+            };
+            match selection.lessons.iter_mut().find(|l| l.lecon == lecon) {
+                Some(lesson) => lesson.positionings.push(positioning),
+                None => selection.lessons.push(LessonPositionings {
+                    lecon: lecon.to_string(),
+                    positionings: vec![positioning],
+                }),
+            }
+            continue;
+        }
+
+        let text = event.observations.as_deref().unwrap_or("").trim();
+        if text.is_empty() {
+            continue;
+        }
+        let kind = if event.event_type == "motif_sanction" {
+            NoteKind::Incident
+        } else {
+            NoteKind::Observation
+        };
+        let key = (kind, normalize(text));
+        match note_keys.iter().position(|k| *k == key) {
+            Some(i) => {
+                let group = &mut selection.notes[i];
+                group.count += 1;
+                group.last_date = date;
+            }
+            None => {
+                note_keys.push(key);
+                selection.notes.push(NoteGroup {
+                    kind,
+                    text: text.to_string(),
+                    first_date: date.clone(),
+                    last_date: date,
+                    count: 1,
+                });
+            }
+        }
+    }
+    selection
+}
+
+impl LessonPositionings {
+    pub fn line(&self, detail: LessonDetail) -> String {
+        let step = |p: &Positioning| format!("{} ({})", p.niveau, p.date);
+        let steps: Vec<String> = match (detail, self.positionings.as_slice()) {
+            (LessonDetail::FirstAndLast, [first, middle @ .., last]) if !middle.is_empty() => vec![
+                step(first),
+                format!("[{} positionnement(s) intermediaire(s)]", middle.len()),
+                step(last),
+            ],
+            (_, all) => all.iter().map(step).collect(),
+        };
+        format!("- Lecon {} : {}", self.lecon, steps.join(" -> "))
+    }
+}
+
+impl NoteGroup {
+    pub fn line(&self) -> String {
+        let label = match self.kind {
+            NoteKind::Observation => "Observation",
+            NoteKind::Incident => "Incident",
+        };
+        if self.count > 1 {
+            format!(
+                "- [{} -> {}] {} (x{}): {}",
+                self.first_date, self.last_date, label, self.count, self.text
+            )
+        } else {
+            format!("- [{}] {}: {}", self.first_date, label, self.text)
+        }
+    }
+}
+
+/// Day part of a `created_at` timestamp ("2026-01-15T10:00:00" -> "2026-01-15")
+fn day_of(created_at: &str) -> String {
+    created_at
+        .split(['T', ' '])
+        .next()
+        .unwrap_or(created_at)
+        .to_string()
+}
+
+/// Comparison key of a note: lowercase, accents folded, punctuation and extra spaces removed
+fn normalize(text: &str) -> String {
+    let folded: String = text
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'à' | 'â' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'î' | 'ï' => 'i',
+            'ô' | 'ö' => 'o',
+            'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            c if c.is_alphanumeric() => c,
+            _ => ' ',
+        })
+        .collect();
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_type: &str, text: Option<&str>, lecon: Option<&str>, niveau: Option<&str>, day: u32) -> EventContext {
+        EventContext {
+            event_type: event_type.to_string(),
+            observations: text.map(str::to_string),
+            niveau_lsu: niveau.map(str::to_string),
+            lecon: lecon.map(str::to_string),
+            created_at: format!("2026-01-{:02}T10:00:00", day),
+        }
+    }
+
+    #[test]
+    fn repeated_observations_are_merged() {
+        let events = vec![
+            event("observation", Some("Oublie son cahier."), None, None, 5),
+            event("observation", Some("Lit avec fluidite."), None, None, 6),
+            event("observation", Some("oublie son  cahier"), None, None, 12),
+            event("motif_sanction", Some("Oublie son cahier."), None, None, 13),
+            event("observation", Some("Oublie son cahier !"), None, None, 20),
+            event("observation", Some("  "), None, None, 21),
+        ];
+        let selection = select(&events);
+        assert_eq!(selection.notes.len(), 3);
+        assert_eq!(selection.notes[0].count, 3);
+        assert_eq!(selection.notes[0].text, "Oublie son cahier.");
+        assert_eq!(selection.notes[0].line(), "- [2026-01-05 -> 2026-01-20] Observation (x3): Oublie son cahier.");
+        assert_eq!(selection.notes[1].line(), "- [2026-01-06] Observation: Lit avec fluidite.");
+        // An incident is never merged with an observation
+        assert_eq!(selection.notes[2].kind, NoteKind::Incident);
+    }
+
+    #[test]
+    fn evaluations_are_grouped_per_lesson() {
+        let events = vec![
+            event("evaluation", None, Some("Fractions"), Some("non_atteints"), 3),
+            event("evaluation", None, Some("Tables"), Some("atteints"), 4),
+            event("evaluation", None, Some("Fractions"), Some("partiellement_atteints"), 10),
+            event("evaluation", None, None, Some("atteints"), 11),
+            event("evaluation", None, Some("Fractions"), Some("partiellement_atteints"), 17),
+            event("evaluation", None, Some("Fractions"), Some("atteints"), 24),
+        ];
+        let selection = select(&events);
+        assert!(selection.notes.is_empty());
+        let lecons: Vec<&str> = selection.lessons.iter().map(|l| l.lecon.as_str()).collect();
+        assert_eq!(lecons, vec!["Fractions", "Tables", NO_LESSON]);
+
+        let fractions = &selection.lessons[0];
+        assert_eq!(fractions.positionings.len(), 4);
+        assert!(fractions.line(LessonDetail::All).contains("partiellement_atteints (2026-01-17) -> atteints"));
+        assert_eq!(
+            fractions.line(LessonDetail::FirstAndLast),
+            "- Lecon Fractions : non_atteints (2026-01-03) -> [2 positionnement(s) intermediaire(s)] -> atteints (2026-01-24)"
+        );
+        assert_eq!(
+            selection.lessons[1].line(LessonDetail::FirstAndLast),
+            selection.lessons[1].line(LessonDetail::All)
+        );
+    }
+}
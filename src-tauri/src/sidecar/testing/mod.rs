@@ -0,0 +1,338 @@
+/// In-process stand-ins for whisper-server and llama-server (tests only).
+///
+/// `MockSidecar` is a minimal HTTP/1.1 server on 127.0.0.1 that replays recorded
+/// responses route by route (`/inference`, `/v1/chat/completions`, `/health`) and
+/// records every request it receives. An unscripted `/tokenize` counts one token
+/// per char of `content`, and a `stream: true` chat completion gets the recorded
+/// content back as server-sent events. Recorded sessions live in `recordings.json`:
+/// captured sidecar answers, including the truncated and empty ones the pipeline
+/// must survive. The sidecar layer points at a mock through its base URL
+/// (`LlamaServerBackend::new`, `send_inference_bytes`) or its port (healthchecks).
+
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const RECORDINGS: &str = include_str!("recordings.json");
+
+/// Content chars per server-sent event of a streamed completion
+const SSE_CHUNK_CHARS: usize = 4;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// One recorded response. A string body is sent as-is, anything else as JSON.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Recording {
+    pub route: String,
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+/// Request received by the mock
+#[derive(Debug, Clone)]
+pub struct SeenRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+pub struct MockSidecar {
+    pub base_url: String,
+    pub port: u16,
+    seen: Arc<Mutex<Vec<SeenRequest>>>,
+}
+
+/// Responses still to replay, per route. The last one of a route is replayed forever.
+type Script = Arc<Mutex<HashMap<String, VecDeque<Recording>>>>;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Impls
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Recorded session from `recordings.json` (panics on an unknown name)
+pub fn session(name: &str) -> Vec<Recording> {
+    let mut sessions: HashMap<String, Vec<Recording>> =
+        serde_json::from_str(RECORDINGS).expect("recordings.json invalide");
+    sessions
+        .remove(name)
+        .unwrap_or_else(|| panic!("Session enregistree inconnue : {}", name))
+}
+
+impl Recording {
+    /// llama-server chat completion whose assistant message is `content`
+    pub fn chat(content: &str) -> Self {
+        Recording {
+            route: "/v1/chat/completions".to_string(),
+            status: 200,
+            body: serde_json::json!({
+                "choices": [{ "index": 0, "message": { "role": "assistant", "content": content } }]
+            }),
+        }
+    }
+
+    /// whisper-server `/inference` answer
+    pub fn inference(text: &str) -> Self {
+        Recording {
+            route: "/inference".to_string(),
+            status: 200,
+            body: serde_json::json!({ "text": text }),
+        }
+    }
+
+    pub fn status(route: &str, status: u16) -> Self {
+        Recording { route: route.to_string(), status, body: serde_json::json!({}) }
+    }
+
+    fn payload(&self) -> String {
+        match &self.body {
+            serde_json::Value::String(raw) => raw.clone(),
+            other => other.to_string(),
+        }
+    }
+}
+
+impl SeenRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap_or_default()
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+}
+
+impl MockSidecar {
+    /// Serve `recordings` in order. Unscripted `/health` and `/` answer 200, `/tokenize`
+    /// counts chars, other routes answer 404.
+    pub async fn spawn(recordings: Vec<Recording>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let mut routes: HashMap<String, VecDeque<Recording>> = HashMap::new();
+        for recording in recordings {
+            routes.entry(recording.route.clone()).or_default().push_back(recording);
+        }
+        let script: Script = Arc::new(Mutex::new(routes));
+        let seen: Arc<Mutex<Vec<SeenRequest>>> = Arc::new(Mutex::new(Vec::new()));
+
+        let seen_clone = seen.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((socket, _)) = listener.accept().await else { break };
+                tokio::spawn(serve(socket, script.clone(), seen_clone.clone()));
+            }
+        });
+
+        MockSidecar { base_url: format!("http://127.0.0.1:{}", port), port, seen }
+    }
+
+    pub async fn replay(session_name: &str) -> Self {
+        Self::spawn(session(session_name)).await
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Requests received on `route`, oldest first
+    pub fn requests(&self, route: &str) -> Vec<SeenRequest> {
+        self.seen
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.path == route)
+            .cloned()
+            .collect()
+    }
+}
+
+fn next_response(script: &Script, request: &SeenRequest) -> (u16, String) {
+    let path = request.path.as_str();
+    let mut routes = script.lock().unwrap();
+    match routes.get_mut(path) {
+        Some(queue) if queue.len() > 1 => {
+            let recording = queue.pop_front().unwrap();
+            (recording.status, recording.payload())
+        }
+        Some(queue) if !queue.is_empty() => (queue[0].status, queue[0].payload()),
+        _ if path == "/health" || path == "/" => (200, r#"{"status":"ok"}"#.to_string()),
+        _ if path == "/tokenize" => {
+            let content = request.json()["content"].as_str().unwrap_or_default().to_string();
+            let tokens: Vec<usize> = (0..content.chars().count()).collect();
+            (200, serde_json::json!({ "tokens": tokens }).to_string())
+        }
+        _ => (404, r#"{"error":"route inconnue"}"#.to_string()),
+    }
+}
+
+async fn serve(mut socket: TcpStream, script: Script, seen: Arc<Mutex<Vec<SeenRequest>>>) {
+    let Some(request) = read_request(&mut socket).await else { return };
+    let (status, payload) = next_response(&script, &request);
+    let stream = request.path == "/v1/chat/completions" && request.json()["stream"] == true;
+    seen.lock().unwrap().push(request);
+
+    if stream && status == 200 {
+        return serve_events(socket, &payload).await;
+    }
+    let response = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        payload.len(),
+        payload
+    );
+    let _ = socket.write_all(response.as_bytes()).await;
+}
+
+/// Replay a chat completion as llama-server streams it: content deltas, then `[DONE]`.
+/// Events are written 1 ms apart so the client sees several reads.
+async fn serve_events(mut socket: TcpStream, payload: &str) {
+    let completion: serde_json::Value = serde_json::from_str(payload).unwrap_or_default();
+    let content: Vec<char> = completion["choices"][0]["message"]["content"]
+        .as_str()
+        .unwrap_or_default()
+        .chars()
+        .collect();
+
+    let head = "HTTP/1.1 200 Mock\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n";
+    if socket.write_all(head.as_bytes()).await.is_err() {
+        return;
+    }
+    for piece in content.chunks(SSE_CHUNK_CHARS) {
+        let delta: String = piece.iter().collect();
+        let event = serde_json::json!({ "choices": [{ "index": 0, "delta": { "content": delta } }] });
+        if socket.write_all(format!("data: {}\n\n", event).as_bytes()).await.is_err() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+    }
+    let _ = socket.write_all(b"data: [DONE]\n\n").await;
+}
+
+/// Read one request: headers, then a Content-Length or chunked body
+async fn read_request(socket: &mut TcpStream) -> Option<SeenRequest> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 8192];
+    let header_end = loop {
+        if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        let n = socket.read(&mut buf).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        data.extend_from_slice(&buf[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&data[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?;
+    let path = target.split('?').next().unwrap_or(target).to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|l| l.split_once(':'))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect();
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.to_ascii_lowercase())
+    };
+
+    let chunked = header("transfer-encoding").is_some_and(|v| v.contains("chunked"));
+    let length: usize = header("content-length").and_then(|v| v.parse().ok()).unwrap_or(0);
+    loop {
+        let body = &data[header_end..];
+        let complete = if chunked {
+            body.ends_with(b"0\r\n\r\n")
+        } else {
+            body.len() >= length
+        };
+        if complete {
+            break;
+        }
+        let n = socket.read(&mut buf).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..n]);
+    }
+
+    let raw = data[header_end..].to_vec();
+    let body = if chunked { decode_chunked(&raw) } else { raw };
+    Some(SeenRequest { method, path, headers, body })
+}
+
+fn decode_chunked(mut raw: &[u8]) -> Vec<u8> {
+    let mut body = Vec::new();
+    while let Some(pos) = raw.windows(2).position(|w| w == b"\r\n") {
+        let size_line = String::from_utf8_lossy(&raw[..pos]).to_string();
+        let Ok(size) = usize::from_str_radix(size_line.trim(), 16) else { break };
+        if size == 0 || raw.len() < pos + 2 + size {
+            break;
+        }
+        body.extend_from_slice(&raw[pos + 2..pos + 2 + size]);
+        raw = &raw[(pos + 4 + size).min(raw.len())..];
+    }
+    body
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn replays_routes_in_order_and_repeats_the_last() {
+        let mock = MockSidecar::replay("whisper_empty_then_text").await;
+        let client = reqwest::Client::new();
+        let mut texts = Vec::new();
+        for _ in 0..3 {
+            let json: serde_json::Value =
+                client.post(mock.url("/inference")).body("x").send().await.unwrap().json().await.unwrap();
+            texts.push(json["text"].as_str().unwrap().trim().to_string());
+        }
+        assert_eq!(texts, vec!["", "Noé participe en sciences.", "Noé participe en sciences."]);
+        assert_eq!(mock.requests("/inference").len(), 3);
+        assert_eq!(mock.requests("/inference")[0].text(), "x");
+
+        // Unscripted routes
+        let health = client.get(mock.url("/health")).send().await.unwrap();
+        assert!(health.status().is_success());
+        let tokens: serde_json::Value = client
+            .post(mock.url("/tokenize"))
+            .json(&serde_json::json!({ "content": "Noé lit" }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(tokens["tokens"].as_array().unwrap().len(), 7);
+        let unknown = client.get(mock.url("/v1/models")).send().await.unwrap();
+        assert_eq!(unknown.status().as_u16(), 404);
+    }
+
+    #[test]
+    fn every_recorded_session_parses() {
+        let sessions: HashMap<String, Vec<Recording>> = serde_json::from_str(RECORDINGS).unwrap();
+        assert!(sessions.values().all(|s| !s.is_empty()));
+        assert!(session("classification_truncated")[0].payload().contains("\\\"Lit avec ais\""));
+    }
+}
@@ -0,0 +1,307 @@
+/// LSU style rules checked on generated syntheses and appreciations (Jobs 2 and 3).
+///
+/// The prompt asks for a length and a tone, the model does not always comply.
+/// After generation the text is checked for:
+/// - length: at most the template's `longueur_max` characters;
+/// - third person: no first or second person pronoun or possessive;
+/// - vocabulary: no disciplinary words (sanction, punition...) in the LSU;
+/// - student name: no near-miss spelling of the student's first name ("Sarah" for "Sahra").
+///   A word written exactly like a classmate's first name is taken as that classmate.
+///
+/// `structuration` rewrites the text when a rule fails (shortening pass or
+/// constrained retry) and reports the rules still failing when it gives up.
+
+use serde::{Deserialize, Serialize};
+
+/// Words never written in a LSU text (compared accent-folded, lowercase)
+const FORBIDDEN_WORDS: &[&str] = &[
+    "sanction", "sanctions", "sanctionne", "sanctionnee", "sanctionnes", "sanctionnees",
+    "punition", "punitions", "puni", "punie", "punis", "punies", "punir",
+    "exclusion", "exclusions", "exclu", "exclue", "exclus", "exclues",
+];
+
+/// First and second person markers (elided forms keep their apostrophe)
+const NOT_THIRD_PERSON: &[&str] = &[
+    "je", "j'", "me", "m'", "moi", "mon", "ma", "mes", "nous", "notre", "nos",
+    "tu", "t'", "te", "toi", "ton", "ta", "tes", "vous", "votre", "vos",
+];
+
+/// Capitalised words that are never a student's first name (sentence starts)
+const COMMON_WORDS: &[&str] = &[
+    "elle", "elles", "il", "ils", "les", "des", "une", "son", "sa", "ses", "ce", "cet", "cette",
+    "ces", "en", "au", "aux", "par", "pour", "dans", "sur", "avec", "sans", "malgre", "grace",
+    "bien", "tres", "trop", "lors", "depuis", "apres", "avant", "enfin", "ainsi", "mais", "donc",
+    "cependant", "toutefois", "neanmoins", "pourtant", "encore", "toujours", "parfois", "souvent",
+    "leur", "leurs", "le", "la", "un", "de", "du", "et", "ou", "si",
+];
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Rules applied to one generated text
+#[derive(Debug, Clone, Copy)]
+pub struct TextRules<'a> {
+    /// Maximum length in characters (template `longueur_max`)
+    pub max_chars: usize,
+    /// Student first name as written in the roster; empty disables the name rule
+    pub student_name: &'a str,
+    /// First names of the rest of the roster, never reported as misspellings
+    pub other_names: &'a [String],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    Empty,
+    Length,
+    ThirdPerson,
+    ForbiddenWord,
+    StudentName,
+}
+
+/// A failed rule, with the French explanation shown to the teacher and sent to the LLM
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleViolation {
+    pub rule: Rule,
+    pub message: String,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Impls
+// ─────────────────────────────────────────────────────────────────────────────
+
+impl RuleViolation {
+    fn new(rule: Rule, message: String) -> Self {
+        RuleViolation { rule, message }
+    }
+}
+
+impl Rule {
+    /// Weight of a failure when ranking candidates: a length overrun is cheaper
+    /// for the teacher to fix than a disciplinary word or a first-person sentence
+    fn weight(self) -> u32 {
+        match self {
+            Rule::Empty => 10,
+            Rule::ForbiddenWord => 4,
+            Rule::ThirdPerson | Rule::StudentName => 3,
+            Rule::Length => 2,
+        }
+    }
+}
+
+/// Validator score of a text, 0 when compliant; lower ranks first
+pub fn penalty(violations: &[RuleViolation]) -> u32 {
+    violations.iter().map(|v| v.rule.weight()).sum()
+}
+
+/// Check a generated text; an empty result means every rule passes
+pub fn check(text: &str, rules: &TextRules) -> Vec<RuleViolation> {
+    let text = text.trim();
+    if text.is_empty() {
+        return vec![RuleViolation::new(Rule::Empty, "Texte vide".to_string())];
+    }
+
+    let mut violations = Vec::new();
+    let length = text.chars().count();
+    if length > rules.max_chars {
+        violations.push(RuleViolation::new(
+            Rule::Length,
+            format!("Texte trop long : {} caracteres (maximum {})", length, rules.max_chars),
+        ));
+    }
+
+    let words = words(text);
+    if let Some(word) = first_match(&words, NOT_THIRD_PERSON) {
+        violations.push(RuleViolation::new(
+            Rule::ThirdPerson,
+            format!("Ecrire a la troisieme personne (\"{}\" trouve)", word),
+        ));
+    }
+    if let Some(word) = first_match(&words, FORBIDDEN_WORDS) {
+        violations.push(RuleViolation::new(
+            Rule::ForbiddenWord,
+            format!("Mot interdit dans le LSU : \"{}\"", word),
+        ));
+    }
+    if let Some(found) = misspelled_name(&words, rules.student_name, rules.other_names) {
+        violations.push(RuleViolation::new(
+            Rule::StudentName,
+            format!("Prenom mal orthographie : \"{}\" au lieu de \"{}\"", found, first_name(rules.student_name)),
+        ));
+    }
+    violations
+}
+
+/// Words of the text as written; elisions are split after the apostrophe ("j'ai" -> "j'", "ai")
+fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for raw in text
+        .split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '’' || c == '-'))
+        .filter(|w| !w.is_empty())
+    {
+        let raw = raw.replace('’', "'");
+        match raw.split_once('\'') {
+            Some((elided, rest)) if !elided.is_empty() => {
+                words.push(format!("{}'", elided));
+                if !rest.is_empty() {
+                    words.push(rest.to_string());
+                }
+            }
+            _ => words.push(raw.trim_matches('\'').to_string()),
+        }
+    }
+    words
+}
+
+/// First word (as written) whose folded form is in `list`
+fn first_match<'w>(words: &'w [String], list: &[&str]) -> Option<&'w str> {
+    words
+        .iter()
+        .find(|w| list.contains(&fold(w).as_str()))
+        .map(String::as_str)
+}
+
+fn first_name(student_name: &str) -> &str {
+    student_name.split_whitespace().next().unwrap_or("")
+}
+
+/// A capitalised word close to the first name without being it (accents included),
+/// nor being a classmate's first name ("Lea" for "Léa" is kept when a Lea is in the class)
+fn misspelled_name<'w>(words: &'w [String], student_name: &str, other_names: &[String]) -> Option<&'w str> {
+    let name = first_name(student_name);
+    let name_len = name.chars().count();
+    if name_len < 3 {
+        return None;
+    }
+    let folded_name = fold(name);
+    let max_distance = if name_len >= 5 { 2 } else { 1 };
+
+    words.iter().map(String::as_str).find(|word| {
+        if *word == name
+            || !word.chars().next().is_some_and(char::is_uppercase)
+            || other_names.iter().any(|other| first_name(other) == *word)
+        {
+            return false;
+        }
+        let folded = fold(word);
+        if COMMON_WORDS.contains(&folded.as_str()) || folded.chars().next() != folded_name.chars().next() {
+            return false;
+        }
+        // Same letters with different accents ("Lea" for "Léa") or a near miss
+        folded == folded_name || distance(&folded, &folded_name) <= max_distance
+    })
+}
+
+/// Optimal string alignment distance (Levenshtein with adjacent transpositions)
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Lowercase with French accents folded
+fn fold(word: &str) -> String {
+    word.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'à' | 'â' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'î' | 'ï' => 'i',
+            'ô' | 'ö' => 'o',
+            'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            c => c,
+        })
+        .collect()
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(name: &str) -> TextRules<'_> {
+        TextRules { max_chars: 300, student_name: name, other_names: &[] }
+    }
+
+    fn failed(text: &str, name: &str) -> Vec<Rule> {
+        check(text, &rules(name)).into_iter().map(|v| v.rule).collect()
+    }
+
+    #[test]
+    fn compliant_text_passes() {
+        let text = "Sahra lit avec aisance. Elle progresse en comprehension et participe volontiers a l'oral.";
+        assert!(check(text, &rules("Sahra")).is_empty());
+        assert!(failed("Léa a-t-elle compris ? Oui, l'eleve s'applique.", "Léa").is_empty());
+    }
+
+    #[test]
+    fn length_and_empty_text_are_reported() {
+        let long = "Sahra progresse. ".repeat(30);
+        let violations = check(&long, &rules("Sahra"));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, Rule::Length);
+        assert!(violations[0].message.contains("maximum 300"));
+        assert_eq!(failed("   ", "Sahra"), vec![Rule::Empty]);
+    }
+
+    #[test]
+    fn first_person_and_forbidden_words_are_reported() {
+        assert_eq!(failed("J'ai constate que Sahra progresse.", "Sahra"), vec![Rule::ThirdPerson]);
+        assert_eq!(failed("Tu progresses bien, Sahra.", "Sahra"), vec![Rule::ThirdPerson]);
+        let violations = check("Sahra a recu une Punition mardi.", &rules("Sahra"));
+        assert_eq!(violations[0].rule, Rule::ForbiddenWord);
+        assert_eq!(violations[0].message, "Mot interdit dans le LSU : \"Punition\"");
+    }
+
+    #[test]
+    fn penalty_ranks_disciplinary_words_below_length() {
+        let long = check(&"Sahra progresse. ".repeat(30), &rules("Sahra"));
+        let forbidden = check("Sahra a eu une sanction.", &rules("Sahra"));
+        assert_eq!(penalty(&[]), 0);
+        assert!(penalty(&long) < penalty(&forbidden));
+    }
+
+    #[test]
+    fn near_miss_of_the_first_name_is_reported() {
+        let violations = check("Sarah lit avec aisance.", &rules("Sahra"));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].message, "Prenom mal orthographie : \"Sarah\" au lieu de \"Sahra\"");
+        assert_eq!(failed("Lea lit avec aisance.", "Léa"), vec![Rule::StudentName]);
+        // Sentence starts and unrelated capitalised words are not names
+        assert!(failed("Elle lit. Ella aussi ? Non.", "Eliott").is_empty());
+        assert!(failed("Elle progresse en Lecture.", "Ella").is_empty());
+        assert!(failed("Un eleve serieux.", "Al").is_empty());
+    }
+
+    #[test]
+    fn classmates_first_names_are_not_misspellings() {
+        let roster = ["Sarah".to_string(), "Lucas Martin".to_string()];
+        let sahra = TextRules { other_names: &roster, ..rules("Sahra") };
+        assert!(check("Sahra aide Sarah en Lecture.", &sahra).is_empty());
+        // Only an exact spelling is a classmate
+        assert_eq!(check("Sarha lit.", &sahra)[0].rule, Rule::StudentName);
+        let luca = TextRules { other_names: &roster, ..rules("Luca") };
+        assert!(check("Luca travaille avec Lucas.", &luca).is_empty());
+    }
+}
@@ -1,19 +1,20 @@
+use super::corrections::correct_transcription;
 use super::manager::SidecarManager;
 use super::types::{SidecarError, SidecarName, TranscriptionResult};
+use super::vocabulary::{inference_options, InferenceOptions};
+use crate::audio::preprocess::{preprocess_wav, PreprocessConfig};
+use crate::error::AppError;
+use crate::models::catalog::{self, ModelRole};
 use log::{info, warn};
 use std::path::PathBuf;
-use tauri::Manager;
 use tokio::time::Instant;
 
-/// Resolve the whisper model path in app_data_dir/models/ggml-small.bin.
+/// Resolve the active whisper model (models catalog) in app_data_dir/models/.
 /// Returns SidecarError::ModelNotFound if the file does not exist.
-fn resolve_model_path(app: &tauri::AppHandle) -> Result<PathBuf, SidecarError> {
-    let data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| SidecarError::Internal(format!("Impossible de trouver app_data_dir: {}", e)))?;
-
-    let model_path = data_dir.join("models").join("ggml-small.bin");
+pub(crate) async fn resolve_model_path(app: &tauri::AppHandle) -> Result<PathBuf, SidecarError> {
+    let (_, model_path) = catalog::resolve_active_model(app, ModelRole::Whisper)
+        .await
+        .map_err(|e| SidecarError::Internal(e.to_string()))?;
 
     if !model_path.exists() {
         return Err(SidecarError::ModelNotFound(
@@ -24,27 +25,51 @@ fn resolve_model_path(app: &tauri::AppHandle) -> Result<PathBuf, SidecarError> {
     Ok(model_path)
 }
 
-/// Send a WAV file to whisper-server /inference endpoint via multipart form.
-async fn send_inference_request(audio_path: &str) -> Result<String, SidecarError> {
-    let audio_data = tokio::fs::read(audio_path)
+/// Read a recording and run the audio preprocessing stage (resample, VAD trim, gain).
+/// Near-silent recordings are rejected here, before the sidecar is even started.
+async fn load_preprocessed_audio(audio_path: &str) -> Result<Vec<u8>, AppError> {
+    let raw = tokio::fs::read(audio_path)
         .await
-        .map_err(|e| SidecarError::TranscriptionFailed(format!("Lecture du fichier audio echouee: {}", e)))?;
+        .map_err(|e| AppError::io("Lecture du fichier audio echouee", e))?;
 
-    let file_name = std::path::Path::new(audio_path)
-        .file_name()
-        .unwrap_or_default()
-        .to_string_lossy()
-        .to_string();
+    let (wav, stats) =
+        tokio::task::spawn_blocking(move || preprocess_wav(&raw, &PreprocessConfig::default()))
+            .await
+            .map_err(|e| AppError::Internal(format!("Pretraitement audio interrompu : {}", e)))??;
 
+    info!(
+        "Audio pretraite: {}Hz/{}ch {}ms -> {}ms (parole {}ms, gain {:+.1}dB)",
+        stats.original_sample_rate,
+        stats.original_channels,
+        stats.original_duration_ms,
+        stats.duration_ms,
+        stats.speech_ms,
+        stats.gain_db
+    );
+    Ok(wav)
+}
+
+/// Send in-memory WAV bytes to whisper-server /inference endpoint via multipart form.
+/// `base_url` is the running sidecar's address (see `SidecarManager::base_url`);
+/// `options` carries the initial prompt and decoding settings (see `vocabulary`).
+pub(crate) async fn send_inference_bytes(
+    base_url: &str,
+    audio_data: Vec<u8>,
+    file_name: String,
+    options: &InferenceOptions,
+) -> Result<String, SidecarError> {
     let part = reqwest::multipart::Part::bytes(audio_data)
         .file_name(file_name)
         .mime_str("audio/wav")
         .map_err(|e| SidecarError::TranscriptionFailed(e.to_string()))?;
 
-    let form = reqwest::multipart::Form::new()
+    let mut form = reqwest::multipart::Form::new()
         .part("file", part)
         .text("language", "fr")
         .text("response_format", "json");
+    for (name, value) in options.form_fields() {
+        form = form.text(name, value);
+    }
 
     let client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(30))
@@ -52,7 +77,7 @@ async fn send_inference_request(audio_path: &str) -> Result<String, SidecarError
         .map_err(|e| SidecarError::Internal(e.to_string()))?;
 
     let response = client
-        .post("http://127.0.0.1:8081/inference")
+        .post(format!("{}/inference", base_url))
         .multipart(form)
         .send()
         .await
@@ -86,22 +111,43 @@ async fn send_inference_request(audio_path: &str) -> Result<String, SidecarError
     Ok(text)
 }
 
-/// Ensure whisper-server is running, starting it if needed.
-async fn ensure_whisper_running(
+/// Send the audio to whisper-server. An empty response restarts the sidecar once
+/// and retries (watchdog, Story 13.5); `restart` returns the new base URL, the
+/// restart may have moved whisper to another port.
+pub(crate) async fn transcribe_with_retry<F, Fut>(
+    base_url: &str,
+    audio: Vec<u8>,
+    file_name: String,
+    options: &InferenceOptions,
+    restart: F,
+) -> Result<String, SidecarError>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<String, SidecarError>>,
+{
+    let text = send_inference_bytes(base_url, audio.clone(), file_name.clone(), options).await?;
+    if !text.is_empty() {
+        return Ok(text);
+    }
+
+    warn!("Watchdog: reponse vide de whisper-server, redemarrage et nouvelle tentative");
+    let base_url = restart().await?;
+    let text = send_inference_bytes(&base_url, audio, file_name, options).await?;
+    if text.is_empty() {
+        warn!("Watchdog: reponse vide apres retry, retour au frontend");
+    }
+    Ok(text)
+}
+
+/// Ensure whisper-server is running with the active model, starting it if needed.
+pub(crate) async fn ensure_whisper_running(
     app: &tauri::AppHandle,
     state: &SidecarManager,
     model_path_str: &str,
-) -> Result<(), String> {
-    let status = state.get_status().await;
-    if !status.whisper.running {
-        info!("whisper-server non demarre, lancement automatique...");
-        state
-            .start(app, SidecarName::Whisper, model_path_str.to_string(), None)
-            .await
-            .map_err(|e| e.to_string())?;
-        info!("whisper-server demarre avec succes");
-    }
-    Ok(())
+) -> Result<(), SidecarError> {
+    state
+        .ensure_running(app, SidecarName::Whisper, model_path_str.to_string())
+        .await
 }
 
 /// Transcribe a WAV audio file to French text using whisper-server sidecar.
@@ -110,46 +156,54 @@ async fn ensure_whisper_running(
 /// - Empty response detection → restart + retry once
 /// - Post-transcription healthcheck → restart if 3 consecutive failures
 /// - Preventive restart after ~50 requests (Windows handle leak workaround)
+///
+/// The correction dictionary is applied to the text (`eleve_id` enables the
+/// student-scoped entries).
 #[tauri::command]
 pub async fn transcribe_audio(
     app: tauri::AppHandle,
     state: tauri::State<'_, SidecarManager>,
     audio_path: String,
-) -> Result<TranscriptionResult, String> {
+    eleve_id: Option<i64>,
+) -> Result<TranscriptionResult, AppError> {
     let start = Instant::now();
 
+    // Preprocess first: silent recordings never reach whisper (nor its watchdog)
+    let audio = load_preprocessed_audio(&audio_path).await?;
+    let file_name = std::path::Path::new(&audio_path)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
     // Resolve model path
-    let model_path = resolve_model_path(&app).map_err(|e| e.to_string())?;
+    let model_path = resolve_model_path(&app).await?;
     let model_path_str = model_path.to_string_lossy().to_string();
 
     // Ensure whisper is running
     ensure_whisper_running(&app, &state, &model_path_str).await?;
 
-    // Send audio for transcription
-    let mut text = send_inference_request(&audio_path)
-        .await
-        .map_err(|e| e.to_string())?;
+    // Send audio for transcription, biased towards the class vocabulary
+    let options = inference_options(&app).await;
+    let base_url = state.base_url(SidecarName::Whisper).await?;
 
     // Watchdog: empty response detection → restart + retry once
-    if text.is_empty() {
-        warn!("Watchdog: reponse vide de whisper-server, redemarrage et nouvelle tentative");
-        state
-            .restart(&app, SidecarName::Whisper, "Reponse vide")
-            .await
-            .map_err(|e| e.to_string())?;
-
-        text = send_inference_request(&audio_path)
-            .await
-            .map_err(|e| e.to_string())?;
-
-        if text.is_empty() {
-            warn!("Watchdog: reponse vide apres retry, retour au frontend");
-        }
-    }
+    let manager: &SidecarManager = &state;
+    let app_ref = &app;
+    let text = transcribe_with_retry(&base_url, audio, file_name, &options, move || async move {
+        manager.restart(app_ref, SidecarName::Whisper, "Reponse vide").await?;
+        manager.base_url(SidecarName::Whisper).await
+    })
+    .await?;
 
     // Increment request count
     state.increment_request_count(SidecarName::Whisper).await;
 
+    // Retention policy (keep_none): the recording is no longer needed once transcribed
+    if !text.is_empty() {
+        crate::audio::commands::apply_retention_after_transcription(&app, &audio_path);
+    }
+
     let duration_ms = start.elapsed().as_millis() as u64;
 
     info!(
@@ -167,7 +221,8 @@ pub async fn transcribe_audio(
     // ADR-002 / Story 13.4: Auto-stop whisper after task in sequential mode
     state.auto_stop_after_task(&app, SidecarName::Whisper).await;
 
-    Ok(TranscriptionResult { text, duration_ms })
+    let result = TranscriptionResult { text, duration_ms, corrections: Vec::new(), transcription_id: None };
+    Ok(correct_transcription(&app, result, eleve_id).await)
 }
 
 #[cfg(test)]
@@ -180,4 +235,89 @@ mod tests {
         let path = PathBuf::from("/nonexistent/models/ggml-small.bin");
         assert!(!path.exists());
     }
+
+    // ─── Mock whisper-server (recorded sessions) ───
+
+    use crate::sidecar::config::SidecarSettings;
+    use crate::sidecar::testing::{MockSidecar, Recording};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn wav() -> Vec<u8> {
+        b"RIFF\x24\x00\x00\x00WAVEfmt ".to_vec()
+    }
+
+    #[tokio::test]
+    async fn inference_sends_audio_and_options() {
+        let mock = MockSidecar::replay("whisper").await;
+        let options = InferenceOptions::new(&SidecarSettings::default(), Some("Élèves : Léa.".to_string()));
+
+        let text = send_inference_bytes(&mock.base_url, wav(), "dictee.wav".to_string(), &options)
+            .await
+            .unwrap();
+        assert_eq!(text, "Léa lit bien mais oublie ses accents.");
+
+        let request = &mock.requests("/inference")[0];
+        assert_eq!(request.method, "POST");
+        let form = request.text();
+        assert!(form.contains("filename=\"dictee.wav\""));
+        assert!(form.contains("name=\"language\"\r\n\r\nfr"));
+        assert!(form.contains("name=\"prompt\"\r\n\r\nÉlèves : Léa."));
+    }
+
+    #[tokio::test]
+    async fn empty_response_restarts_once_and_retries() {
+        // The restarted whisper listens on another port
+        let first = MockSidecar::replay("whisper_empty").await;
+        let restarted = MockSidecar::replay("whisper_empty_then_text").await;
+        let restarts = AtomicUsize::new(0);
+        let options = InferenceOptions::default();
+
+        let text = transcribe_with_retry(&first.base_url, wav(), "a.wav".to_string(), &options, || async {
+            restarts.fetch_add(1, Ordering::SeqCst);
+            Ok(restarted.base_url.clone())
+        })
+        .await
+        .unwrap();
+        assert_eq!(text, "", "Le second whisper rejoue d'abord une reponse vide");
+        assert_eq!(restarts.load(Ordering::SeqCst), 1, "Un seul redemarrage par requete");
+
+        let text = transcribe_with_retry(&first.base_url, wav(), "a.wav".to_string(), &options, || async {
+            restarts.fetch_add(1, Ordering::SeqCst);
+            Ok(restarted.base_url.clone())
+        })
+        .await
+        .unwrap();
+        assert_eq!(text, "Noé participe en sciences.");
+        assert_eq!(first.requests("/inference").len(), 2);
+        assert_eq!(restarted.requests("/inference").len(), 2);
+
+        // A non-empty answer never triggers the watchdog
+        let ok = MockSidecar::replay("whisper").await;
+        let text = transcribe_with_retry(&ok.base_url, wav(), "a.wav".to_string(), &options, || async {
+            panic!("Pas de redemarrage attendu")
+        })
+        .await
+        .unwrap();
+        assert!(!text.is_empty());
+    }
+
+    #[tokio::test]
+    async fn whisper_errors_are_reported() {
+        let mock = MockSidecar::spawn(vec![Recording::status("/inference", 500)]).await;
+        let err = send_inference_bytes(&mock.base_url, wav(), "a.wav".to_string(), &InferenceOptions::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SidecarError::TranscriptionFailed(_)));
+        assert!(err.to_string().contains("500"), "{}", err);
+
+        let failed_restart = transcribe_with_retry(
+            &MockSidecar::replay("whisper_empty").await.base_url,
+            wav(),
+            "a.wav".to_string(),
+            &InferenceOptions::default(),
+            || async { Err(SidecarError::NotRunning(SidecarName::Whisper)) },
+        )
+        .await;
+        assert!(matches!(failed_restart, Err(SidecarError::NotRunning(_))));
+    }
 }
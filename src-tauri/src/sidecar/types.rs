@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 use tauri_plugin_shell::process::CommandChild;
 
+use super::recovery::CrashRecord;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum SidecarName {
@@ -44,10 +46,14 @@ impl fmt::Display for SidecarName {
 
 pub struct SidecarProcess {
     pub child: CommandChild,
+    /// Identifies this process in the drain task's termination report
+    pub pid: u32,
     pub port: u16,
     pub request_count: u64,
     pub max_requests: u64,
     pub started_at: std::time::Instant,
+    /// Last request (or start), for the idle timeout of concurrent mode
+    pub last_used: std::time::Instant,
     /// Stored for watchdog restart with same parameters
     pub model_path: String,
     pub grammar_path: Option<String>,
@@ -67,6 +73,9 @@ pub enum SidecarError {
     #[error("Echec de l'arret du sidecar {0} : {1}")]
     StopFailed(SidecarName, String),
 
+    #[error("Le sidecar {0} n'est pas demarre")]
+    NotRunning(SidecarName),
+
     #[error("Modele IA introuvable : {0}. Lancez le script scripts/setup-whisper.sh pour installer les modeles.")]
     ModelNotFound(String),
 
@@ -81,6 +90,23 @@ pub enum SidecarError {
 pub struct TranscriptionResult {
     pub text: String,
     pub duration_ms: u64,
+    /// Dictionary substitutions applied to `text` (see `corrections`)
+    #[serde(default)]
+    pub corrections: Vec<AppliedCorrection>,
+    /// Id under which `corrections` are recorded (corrections_appliquees);
+    /// None when no substitution was applied or the record failed
+    #[serde(default)]
+    pub transcription_id: Option<String>,
+}
+
+/// One dictionary entry that matched a transcription
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedCorrection {
+    /// None once the dictionary entry has been deleted
+    pub correction_id: Option<i64>,
+    pub motif: String,
+    pub remplacement: String,
+    pub occurrences: u32,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -104,4 +130,8 @@ pub struct SidecarInstanceStatus {
     pub port: Option<u16>,
     pub request_count: Option<u64>,
     pub uptime_secs: Option<u64>,
+    /// Automatic restarts suspended after repeated crashes
+    pub crash_loop: bool,
+    /// Recent unexpected terminations, oldest first
+    pub crashes: Vec<CrashRecord>,
 }
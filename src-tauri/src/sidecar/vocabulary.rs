@@ -0,0 +1,366 @@
+/// Custom vocabulary for whisper: initial prompt and decoding options.
+///
+/// Whisper mangles first names and school jargon. whisper-server accepts a
+/// `prompt` field that biases decoding towards the words it contains: we build it
+/// from the active roster, the active domains and a user-editable glossary
+/// (whisper_glossaire, M018). Temperature and beam size come from SidecarSettings.
+
+use serde::Serialize;
+use std::collections::HashSet;
+
+use super::config::SidecarSettings;
+use crate::error::AppError;
+
+/// Character budget of the initial prompt. whisper keeps only the last 224 prompt
+/// tokens (half its text context); ~500 characters of French stays below that.
+pub const MAX_PROMPT_CHARS: usize = 500;
+
+/// Longest glossary term accepted
+const MAX_TERM_CHARS: usize = 60;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct GlossaryTerm {
+    pub id: i64,
+    pub terme: String,
+}
+
+/// Words whisper should recognise, by decreasing priority when the budget is short
+#[derive(Debug, Clone, Default)]
+pub struct Vocabulary {
+    pub students: Vec<String>,
+    pub glossary: Vec<String>,
+    pub domains: Vec<String>,
+}
+
+/// Per-request decoding options sent to whisper-server /inference
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InferenceOptions {
+    pub prompt: Option<String>,
+    pub temperature: f32,
+    /// 0 = greedy decoding (field not sent)
+    pub beam_size: u32,
+}
+
+impl InferenceOptions {
+    pub fn new(settings: &SidecarSettings, prompt: Option<String>) -> Self {
+        InferenceOptions {
+            prompt,
+            temperature: settings.whisper_temperature,
+            beam_size: settings.whisper_beam_size,
+        }
+    }
+
+    /// Multipart text fields, in addition to file/language/response_format
+    pub fn form_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![("temperature", self.temperature.to_string())];
+        if self.beam_size > 0 {
+            fields.push(("beam_size", self.beam_size.to_string()));
+        }
+        if let Some(prompt) = &self.prompt {
+            fields.push(("prompt", prompt.clone()));
+        }
+        fields
+    }
+}
+
+/// Build the initial prompt, e.g. "Élèves : Léa, Inès. Vocabulaire : EMC. Domaines : Français."
+/// Terms are trimmed and deduplicated (case-insensitive); those that would exceed
+/// `max_chars` are skipped, students first kept, domains first dropped.
+pub fn build_initial_prompt(vocabulary: &Vocabulary, max_chars: usize) -> Option<String> {
+    let sources = [
+        ("Élèves", &vocabulary.students),
+        ("Vocabulaire", &vocabulary.glossary),
+        ("Domaines", &vocabulary.domains),
+    ];
+    let mut seen = HashSet::new();
+    let mut used = 0;
+    let mut sections = Vec::new();
+
+    for (label, terms) in sources {
+        let mut kept: Vec<&str> = Vec::new();
+        for term in terms.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+            if !seen.insert(term.to_lowercase()) {
+                continue;
+            }
+            // "Label : " + ". " opens a section, ", " separates the following terms
+            let overhead = if kept.is_empty() { label.chars().count() + 5 } else { 2 };
+            let cost = overhead + term.chars().count();
+            if used + cost > max_chars {
+                continue;
+            }
+            used += cost;
+            kept.push(term);
+        }
+        if !kept.is_empty() {
+            sections.push(format!("{} : {}.", label, kept.join(", ")));
+        }
+    }
+
+    if sections.is_empty() {
+        None
+    } else {
+        Some(sections.join(" "))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Impls (testables, prennent une connexion)
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Active roster (students of the active year, or without year), active domains, glossary.
+pub async fn load_vocabulary_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+) -> Result<Vocabulary, AppError> {
+    let students: Vec<String> = sqlx::query_scalar(
+        "SELECT first_name FROM students
+         WHERE annee_scolaire_id IS NULL
+            OR annee_scolaire_id IN (SELECT id FROM annees_scolaires WHERE active = 1)
+         ORDER BY first_name ASC",
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur lecture des élèves", e))?;
+
+    let domains: Vec<String> = sqlx::query_scalar(
+        "SELECT nom FROM domaines_apprentissage WHERE actif = 1 ORDER BY ordre_affichage ASC",
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur lecture des domaines", e))?;
+
+    let glossary = list_glossary_impl(conn)
+        .await?
+        .into_iter()
+        .map(|t| t.terme)
+        .collect();
+
+    Ok(Vocabulary { students, glossary, domains })
+}
+
+pub async fn list_glossary_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+) -> Result<Vec<GlossaryTerm>, AppError> {
+    sqlx::query_as::<_, GlossaryTerm>(
+        "SELECT id, terme FROM whisper_glossaire ORDER BY terme COLLATE NOCASE ASC",
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| AppError::db("Erreur lecture du glossaire", e))
+}
+
+pub async fn add_glossary_term_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    terme: &str,
+) -> Result<GlossaryTerm, AppError> {
+    let terme = terme.trim();
+    if terme.is_empty() {
+        return Err(AppError::validation("Le terme ne peut pas être vide"));
+    }
+    if terme.chars().count() > MAX_TERM_CHARS {
+        return Err(AppError::validation(format!(
+            "Le terme ne doit pas dépasser {} caractères",
+            MAX_TERM_CHARS
+        )));
+    }
+
+    let exists: Option<i64> =
+        sqlx::query_scalar("SELECT id FROM whisper_glossaire WHERE terme = ? COLLATE NOCASE")
+            .bind(terme)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| AppError::db("Erreur lecture du glossaire", e))?;
+    if exists.is_some() {
+        return Err(AppError::validation("Ce terme est déjà dans le glossaire"));
+    }
+
+    let id = sqlx::query("INSERT INTO whisper_glossaire (terme) VALUES (?)")
+        .bind(terme)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::db("Erreur ajout au glossaire", e))?
+        .last_insert_rowid();
+
+    Ok(GlossaryTerm { id, terme: terme.to_string() })
+}
+
+pub async fn delete_glossary_term_impl(
+    conn: &mut sqlx::sqlite::SqliteConnection,
+    id: i64,
+) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM whisper_glossaire WHERE id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| AppError::db("Erreur suppression du glossaire", e))?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found("Terme introuvable"));
+    }
+    Ok(())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Helpers AppHandle
+// ─────────────────────────────────────────────────────────────────────────────
+
+async fn load_initial_prompt(app: &tauri::AppHandle) -> Result<Option<String>, AppError> {
+    let mut conn = crate::db::acquire(app).await?;
+    let vocabulary = load_vocabulary_impl(&mut conn).await?;
+    Ok(build_initial_prompt(&vocabulary, MAX_PROMPT_CHARS))
+}
+
+/// Options for the next whisper requests. A DB failure only drops the prompt:
+/// the vocabulary improves accuracy but must never block a dictation.
+pub async fn inference_options(app: &tauri::AppHandle) -> InferenceOptions {
+    use tauri::Manager;
+    let settings = app.state::<super::SidecarManager>().get_settings().await;
+    let prompt = load_initial_prompt(app).await.unwrap_or_else(|e| {
+        log::warn!("Vocabulaire whisper indisponible, transcription sans prompt : {}", e);
+        None
+    });
+    InferenceOptions::new(&settings, prompt)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Commandes Tauri
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_whisper_glossary(app: tauri::AppHandle) -> Result<Vec<GlossaryTerm>, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
+    list_glossary_impl(&mut conn).await
+}
+
+#[tauri::command]
+pub async fn add_whisper_glossary_term(
+    app: tauri::AppHandle,
+    terme: String,
+) -> Result<GlossaryTerm, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
+    add_glossary_term_impl(&mut conn, &terme).await
+}
+
+#[tauri::command]
+pub async fn delete_whisper_glossary_term(app: tauri::AppHandle, id: i64) -> Result<(), AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
+    delete_glossary_term_impl(&mut conn, id).await
+}
+
+/// Prompt currently sent to whisper (preview in the settings screen)
+#[tauri::command]
+pub async fn get_whisper_initial_prompt(app: tauri::AppHandle) -> Result<Option<String>, AppError> {
+    load_initial_prompt(&app).await
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::Connection;
+
+    fn words(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    async fn setup_test_db() -> (sqlx::sqlite::SqliteConnection, tempfile::NamedTempFile) {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let url = format!("sqlite:{}", tmp.path().display());
+        let mut conn = sqlx::sqlite::SqliteConnection::connect(&url)
+            .await
+            .expect("Impossible de creer la DB de test");
+        for sql in [
+            "CREATE TABLE annees_scolaires (id INTEGER PRIMARY KEY, label TEXT, active INTEGER DEFAULT 0)",
+            "CREATE TABLE students (id INTEGER PRIMARY KEY, first_name TEXT NOT NULL,
+                annee_scolaire_id INTEGER DEFAULT NULL)",
+            "CREATE TABLE domaines_apprentissage (id INTEGER PRIMARY KEY, nom TEXT NOT NULL UNIQUE,
+                ordre_affichage INTEGER DEFAULT 0, actif INTEGER DEFAULT 1)",
+            "CREATE TABLE whisper_glossaire (id INTEGER PRIMARY KEY AUTOINCREMENT,
+                terme TEXT NOT NULL UNIQUE COLLATE NOCASE, created_at TEXT DEFAULT (datetime('now')))",
+            "INSERT INTO annees_scolaires (id, label, active) VALUES (1, '2024-2025', 0), (2, '2025-2026', 1)",
+            "INSERT INTO students (first_name, annee_scolaire_id) VALUES
+                ('Maëlys', 2), ('Ancien', 1), ('Noé', NULL)",
+            "INSERT INTO domaines_apprentissage (nom, ordre_affichage, actif) VALUES
+                ('Mathématiques', 2, 1), ('Français', 1, 1), ('Anglais', 3, 0)",
+        ] {
+            sqlx::query(sql).execute(&mut conn).await.unwrap();
+        }
+        (conn, tmp)
+    }
+
+    #[test]
+    fn prompt_lists_sections_in_priority_order() {
+        let vocabulary = Vocabulary {
+            students: words(&["Léa", " Inès ", ""]),
+            glossary: words(&["EMC", "imparfait"]),
+            domains: words(&["Français"]),
+        };
+        assert_eq!(
+            build_initial_prompt(&vocabulary, MAX_PROMPT_CHARS).unwrap(),
+            "Élèves : Léa, Inès. Vocabulaire : EMC, imparfait. Domaines : Français."
+        );
+        assert_eq!(build_initial_prompt(&Vocabulary::default(), MAX_PROMPT_CHARS), None);
+    }
+
+    #[test]
+    fn prompt_dedups_and_respects_budget() {
+        let vocabulary = Vocabulary {
+            students: (0..200).map(|i| format!("Eleve{}", i)).collect(),
+            glossary: words(&["eleve0", "fractions décimales"]),
+            domains: words(&["Questionner le monde"]),
+        };
+        let prompt = build_initial_prompt(&vocabulary, MAX_PROMPT_CHARS).unwrap();
+        assert!(prompt.chars().count() <= MAX_PROMPT_CHARS, "{}", prompt.len());
+        assert!(prompt.starts_with("Élèves : Eleve0, Eleve1"));
+        assert!(!prompt.contains("eleve0"), "Doublon insensible a la casse");
+        assert!(!prompt.contains("Domaines"), "Les domaines sont sacrifies en premier");
+    }
+
+    #[test]
+    fn options_fields_follow_settings() {
+        let settings = SidecarSettings { whisper_temperature: 0.2, whisper_beam_size: 5, ..Default::default() };
+        let fields = InferenceOptions::new(&settings, Some("Élèves : Léa.".to_string())).form_fields();
+        assert!(fields.contains(&("temperature", "0.2".to_string())));
+        assert!(fields.contains(&("beam_size", "5".to_string())));
+        assert!(fields.contains(&("prompt", "Élèves : Léa.".to_string())));
+
+        let greedy = InferenceOptions::new(&SidecarSettings::default(), None).form_fields();
+        assert_eq!(greedy, vec![("temperature", "0".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_load_vocabulary_uses_active_roster_and_domains() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        add_glossary_term_impl(&mut conn, "EMC").await.unwrap();
+
+        let vocabulary = load_vocabulary_impl(&mut conn).await.unwrap();
+        assert_eq!(vocabulary.students, words(&["Maëlys", "Noé"]));
+        assert_eq!(vocabulary.domains, words(&["Français", "Mathématiques"]));
+        assert_eq!(vocabulary.glossary, words(&["EMC"]));
+    }
+
+    #[tokio::test]
+    async fn test_glossary_crud_and_validation() {
+        let (mut conn, _tmp) = setup_test_db().await;
+        let term = add_glossary_term_impl(&mut conn, "  passé composé ").await.unwrap();
+        assert_eq!(term.terme, "passé composé");
+
+        let dup = add_glossary_term_impl(&mut conn, "Passé Composé").await;
+        assert!(matches!(dup, Err(AppError::Validation(_))));
+        assert!(matches!(add_glossary_term_impl(&mut conn, "  ").await, Err(AppError::Validation(_))));
+        let long = "x".repeat(MAX_TERM_CHARS + 1);
+        assert!(matches!(add_glossary_term_impl(&mut conn, &long).await, Err(AppError::Validation(_))));
+
+        delete_glossary_term_impl(&mut conn, term.id).await.unwrap();
+        assert!(list_glossary_impl(&mut conn).await.unwrap().is_empty());
+        assert!(matches!(
+            delete_glossary_term_impl(&mut conn, term.id).await,
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
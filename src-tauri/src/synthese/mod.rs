@@ -4,10 +4,9 @@
 /// historique 5 versions, restauration d'une ancienne version.
 
 use serde::{Deserialize, Serialize};
-use sqlx::Connection;
 
 use crate::annee::check_annee_not_closed_impl;
-use crate::migrations::get_db_path;
+use crate::error::AppError;
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Types
@@ -23,6 +22,8 @@ pub struct SyntheseRow {
     pub version: i64,
     pub texte: String,
     pub generated_by: String,
+    /// Version du modele de prompt ayant produit le texte (generation LLM)
+    pub prompt_template_id: Option<i64>,
     pub created_at: String,
 }
 
@@ -32,6 +33,8 @@ pub struct SyntheseVersion {
     pub version: i64,
     pub texte: String,
     pub generated_by: String,
+    /// Version du modele de prompt ayant produit le texte (generation LLM)
+    pub prompt_template_id: Option<i64>,
     pub created_at: String,
 }
 
@@ -49,6 +52,7 @@ struct SyntheseDbRow {
     version: i64,
     texte: String,
     generated_by: String,
+    prompt_template_id: Option<i64>,
     created_at: String,
 }
 
@@ -63,6 +67,7 @@ impl From<SyntheseDbRow> for SyntheseRow {
             version: r.version,
             texte: r.texte,
             generated_by: r.generated_by,
+            prompt_template_id: r.prompt_template_id,
             created_at: r.created_at,
         }
     }
@@ -74,6 +79,7 @@ struct SyntheseVersionDbRow {
     version: i64,
     texte: String,
     generated_by: String,
+    prompt_template_id: Option<i64>,
     created_at: String,
 }
 
@@ -84,6 +90,7 @@ impl From<SyntheseVersionDbRow> for SyntheseVersion {
             version: r.version,
             texte: r.texte,
             generated_by: r.generated_by,
+            prompt_template_id: r.prompt_template_id,
             created_at: r.created_at,
         }
     }
@@ -95,6 +102,7 @@ impl From<SyntheseVersionDbRow> for SyntheseVersion {
 
 /// Sauvegarde une synthese (nouvelle version).
 /// Version = max existante + 1, cleanup garde les 5 dernieres.
+#[allow(clippy::too_many_arguments)]
 pub async fn save_synthese_impl(
     conn: &mut sqlx::sqlite::SqliteConnection,
     eleve_id: i64,
@@ -103,7 +111,8 @@ pub async fn save_synthese_impl(
     annee_scolaire_id: i64,
     texte: &str,
     generated_by: &str,
-) -> Result<SyntheseRow, String> {
+    prompt_template_id: Option<i64>,
+) -> Result<SyntheseRow, AppError> {
     check_annee_not_closed_impl(conn, annee_scolaire_id).await?;
 
     // Get current max version for this combo
@@ -117,15 +126,15 @@ pub async fn save_synthese_impl(
     .bind(annee_scolaire_id)
     .fetch_optional(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur lecture version max : {}", e))?
+    .map_err(|e| AppError::db("Erreur lecture version max", e))?
     .flatten();
 
     let next_version = max_version.unwrap_or(0) + 1;
 
     // Insert new version
     let insert_id = sqlx::query(
-        "INSERT INTO syntheses_lsu (eleve_id, domaine_id, periode_id, annee_scolaire_id, version, texte, generated_by)
-         VALUES (?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO syntheses_lsu (eleve_id, domaine_id, periode_id, annee_scolaire_id, version, texte, generated_by, prompt_template_id)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(eleve_id)
     .bind(domaine_id)
@@ -134,9 +143,10 @@ pub async fn save_synthese_impl(
     .bind(next_version)
     .bind(texte)
     .bind(generated_by)
+    .bind(prompt_template_id)
     .execute(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur insertion synthese : {}", e))?
+    .map_err(|e| AppError::db("Erreur insertion synthese", e))?
     .last_insert_rowid();
 
     // Cleanup: keep only 5 most recent versions
@@ -159,17 +169,17 @@ pub async fn save_synthese_impl(
     .bind(annee_scolaire_id)
     .execute(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur cleanup syntheses : {}", e))?;
+    .map_err(|e| AppError::db("Erreur cleanup syntheses", e))?;
 
     // Return inserted row
     let row: SyntheseDbRow = sqlx::query_as(
-        "SELECT id, eleve_id, domaine_id, periode_id, annee_scolaire_id, version, texte, generated_by, created_at
+        "SELECT id, eleve_id, domaine_id, periode_id, annee_scolaire_id, version, texte, generated_by, prompt_template_id, created_at
          FROM syntheses_lsu WHERE id = ?",
     )
     .bind(insert_id)
     .fetch_one(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur lecture synthese inseree : {}", e))?;
+    .map_err(|e| AppError::db("Erreur lecture synthese inseree", e))?;
 
     Ok(row.into())
 }
@@ -181,9 +191,9 @@ pub async fn load_synthese_current_impl(
     domaine_id: i64,
     periode_id: i64,
     annee_scolaire_id: i64,
-) -> Result<Option<SyntheseRow>, String> {
+) -> Result<Option<SyntheseRow>, AppError> {
     let row: Option<SyntheseDbRow> = sqlx::query_as(
-        "SELECT id, eleve_id, domaine_id, periode_id, annee_scolaire_id, version, texte, generated_by, created_at
+        "SELECT id, eleve_id, domaine_id, periode_id, annee_scolaire_id, version, texte, generated_by, prompt_template_id, created_at
          FROM syntheses_lsu
          WHERE eleve_id = ? AND domaine_id = ? AND periode_id = ? AND annee_scolaire_id = ?
          ORDER BY version DESC LIMIT 1",
@@ -194,7 +204,7 @@ pub async fn load_synthese_current_impl(
     .bind(annee_scolaire_id)
     .fetch_optional(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur chargement synthese courante : {}", e))?;
+    .map_err(|e| AppError::db("Erreur chargement synthese courante", e))?;
 
     Ok(row.map(|r| r.into()))
 }
@@ -206,9 +216,9 @@ pub async fn load_synthese_versions_impl(
     domaine_id: i64,
     periode_id: i64,
     annee_scolaire_id: i64,
-) -> Result<Vec<SyntheseVersion>, String> {
+) -> Result<Vec<SyntheseVersion>, AppError> {
     let rows: Vec<SyntheseVersionDbRow> = sqlx::query_as(
-        "SELECT id, version, texte, generated_by, created_at
+        "SELECT id, version, texte, generated_by, prompt_template_id, created_at
          FROM syntheses_lsu
          WHERE eleve_id = ? AND domaine_id = ? AND periode_id = ? AND annee_scolaire_id = ?
          ORDER BY version DESC LIMIT 5",
@@ -219,7 +229,7 @@ pub async fn load_synthese_versions_impl(
     .bind(annee_scolaire_id)
     .fetch_all(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur chargement versions synthese : {}", e))?;
+    .map_err(|e| AppError::db("Erreur chargement versions synthese", e))?;
 
     Ok(rows.into_iter().map(|r| r.into()).collect())
 }
@@ -232,7 +242,7 @@ pub async fn restore_synthese_version_impl(
     periode_id: i64,
     annee_scolaire_id: i64,
     version_id: i64,
-) -> Result<SyntheseRow, String> {
+) -> Result<SyntheseRow, AppError> {
     check_annee_not_closed_impl(conn, annee_scolaire_id).await?;
 
     // Fetch the texte from the version to restore
@@ -242,9 +252,9 @@ pub async fn restore_synthese_version_impl(
     .bind(version_id)
     .fetch_optional(&mut *conn)
     .await
-    .map_err(|e| format!("Erreur lecture version a restaurer : {}", e))?;
+    .map_err(|e| AppError::db("Erreur lecture version a restaurer", e))?;
 
-    let texte = texte.ok_or_else(|| format!("Version introuvable : id={}", version_id))?;
+    let texte = texte.ok_or_else(|| AppError::not_found(format!("Version introuvable : id={}", version_id)))?;
 
     // Save as new version with generated_by='manual'
     save_synthese_impl(
@@ -255,6 +265,7 @@ pub async fn restore_synthese_version_impl(
         annee_scolaire_id,
         &texte,
         "manual",
+        None,
     )
     .await
 }
@@ -272,13 +283,20 @@ pub async fn save_synthese(
     annee_scolaire_id: i64,
     texte: String,
     generated_by: String,
-) -> Result<SyntheseRow, String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
-    save_synthese_impl(&mut conn, eleve_id, domaine_id, periode_id, annee_scolaire_id, &texte, &generated_by).await
+    prompt_template_id: Option<i64>,
+) -> Result<SyntheseRow, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
+    save_synthese_impl(
+        &mut conn,
+        eleve_id,
+        domaine_id,
+        periode_id,
+        annee_scolaire_id,
+        &texte,
+        &generated_by,
+        prompt_template_id,
+    )
+    .await
 }
 
 #[tauri::command]
@@ -288,12 +306,8 @@ pub async fn load_synthese_current(
     domaine_id: i64,
     periode_id: i64,
     annee_scolaire_id: i64,
-) -> Result<Option<SyntheseRow>, String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
+) -> Result<Option<SyntheseRow>, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
     load_synthese_current_impl(&mut conn, eleve_id, domaine_id, periode_id, annee_scolaire_id).await
 }
 
@@ -304,12 +318,8 @@ pub async fn load_synthese_versions(
     domaine_id: i64,
     periode_id: i64,
     annee_scolaire_id: i64,
-) -> Result<Vec<SyntheseVersion>, String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
+) -> Result<Vec<SyntheseVersion>, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
     load_synthese_versions_impl(&mut conn, eleve_id, domaine_id, periode_id, annee_scolaire_id).await
 }
 
@@ -321,12 +331,8 @@ pub async fn restore_synthese_version(
     periode_id: i64,
     annee_scolaire_id: i64,
     version_id: i64,
-) -> Result<SyntheseRow, String> {
-    let db_path = get_db_path(&app)?;
-    let db_url = format!("sqlite:{}", db_path.display());
-    let mut conn = sqlx::sqlite::SqliteConnection::connect(&db_url)
-        .await
-        .map_err(|e| format!("Impossible d'ouvrir la DB : {}", e))?;
+) -> Result<SyntheseRow, AppError> {
+    let mut conn = crate::db::acquire(&app).await?;
     restore_synthese_version_impl(&mut conn, eleve_id, domaine_id, periode_id, annee_scolaire_id, version_id).await
 }
 
@@ -337,6 +343,7 @@ pub async fn restore_synthese_version(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sqlx::Connection;
 
     async fn setup_test_db() -> (sqlx::sqlite::SqliteConnection, tempfile::NamedTempFile) {
         let tmp = tempfile::NamedTempFile::new().unwrap();
@@ -411,6 +418,7 @@ mod tests {
                 version INTEGER NOT NULL DEFAULT 1,
                 texte TEXT NOT NULL,
                 generated_by TEXT DEFAULT 'manual' CHECK(generated_by IN ('llm', 'manual')),
+                prompt_template_id INTEGER DEFAULT NULL,
                 created_at TEXT DEFAULT (datetime('now'))
             )",
         )
@@ -435,13 +443,15 @@ mod tests {
     async fn test_save_increments_version() {
         let (mut conn, _tmp) = setup_test_db().await;
 
-        let s1 = save_synthese_impl(&mut conn, 1, 1, 1, 1, "Synthese v1", "llm").await.unwrap();
+        let s1 = save_synthese_impl(&mut conn, 1, 1, 1, 1, "Synthese v1", "llm", Some(4)).await.unwrap();
         assert_eq!(s1.version, 1);
+        assert_eq!(s1.prompt_template_id, Some(4));
 
-        let s2 = save_synthese_impl(&mut conn, 1, 1, 1, 1, "Synthese v2", "manual").await.unwrap();
+        let s2 = save_synthese_impl(&mut conn, 1, 1, 1, 1, "Synthese v2", "manual", None).await.unwrap();
         assert_eq!(s2.version, 2);
+        assert_eq!(s2.prompt_template_id, None);
 
-        let s3 = save_synthese_impl(&mut conn, 1, 1, 1, 1, "Synthese v3", "llm").await.unwrap();
+        let s3 = save_synthese_impl(&mut conn, 1, 1, 1, 1, "Synthese v3", "llm", None).await.unwrap();
         assert_eq!(s3.version, 3);
     }
 
@@ -454,7 +464,7 @@ mod tests {
             .await
             .unwrap();
 
-        let result = save_synthese_impl(&mut conn, 1, 1, 1, 1, "Synthese", "llm").await;
+        let result = save_synthese_impl(&mut conn, 1, 1, 1, 1, "Synthese", "llm", None).await;
         assert!(result.is_err(), "Annee cloturee doit retourner une erreur");
     }
 
@@ -462,9 +472,9 @@ mod tests {
     async fn test_load_current_retourne_derniere_version() {
         let (mut conn, _tmp) = setup_test_db().await;
 
-        save_synthese_impl(&mut conn, 1, 1, 1, 1, "Version 1", "llm").await.unwrap();
-        save_synthese_impl(&mut conn, 1, 1, 1, 1, "Version 2", "manual").await.unwrap();
-        save_synthese_impl(&mut conn, 1, 1, 1, 1, "Version 3", "llm").await.unwrap();
+        save_synthese_impl(&mut conn, 1, 1, 1, 1, "Version 1", "llm", None).await.unwrap();
+        save_synthese_impl(&mut conn, 1, 1, 1, 1, "Version 2", "manual", None).await.unwrap();
+        save_synthese_impl(&mut conn, 1, 1, 1, 1, "Version 3", "llm", None).await.unwrap();
 
         let current = load_synthese_current_impl(&mut conn, 1, 1, 1, 1).await.unwrap();
         assert!(current.is_some());
@@ -486,7 +496,7 @@ mod tests {
         let (mut conn, _tmp) = setup_test_db().await;
 
         for i in 1..=3 {
-            save_synthese_impl(&mut conn, 1, 1, 1, 1, &format!("v{}", i), "llm").await.unwrap();
+            save_synthese_impl(&mut conn, 1, 1, 1, 1, &format!("v{}", i), "llm", None).await.unwrap();
         }
 
         let versions = load_synthese_versions_impl(&mut conn, 1, 1, 1, 1).await.unwrap();
@@ -501,7 +511,7 @@ mod tests {
         let (mut conn, _tmp) = setup_test_db().await;
 
         for i in 1..=7 {
-            save_synthese_impl(&mut conn, 1, 1, 1, 1, &format!("v{}", i), "llm").await.unwrap();
+            save_synthese_impl(&mut conn, 1, 1, 1, 1, &format!("v{}", i), "llm", None).await.unwrap();
         }
 
         let versions = load_synthese_versions_impl(&mut conn, 1, 1, 1, 1).await.unwrap();
@@ -514,20 +524,21 @@ mod tests {
     async fn test_restore_copie_texte_dans_nouvelle_version() {
         let (mut conn, _tmp) = setup_test_db().await;
 
-        let v1 = save_synthese_impl(&mut conn, 1, 1, 1, 1, "Texte original", "llm").await.unwrap();
-        save_synthese_impl(&mut conn, 1, 1, 1, 1, "Version modifiee", "manual").await.unwrap();
+        let v1 = save_synthese_impl(&mut conn, 1, 1, 1, 1, "Texte original", "llm", Some(4)).await.unwrap();
+        save_synthese_impl(&mut conn, 1, 1, 1, 1, "Version modifiee", "manual", None).await.unwrap();
 
         let restored = restore_synthese_version_impl(&mut conn, 1, 1, 1, 1, v1.id).await.unwrap();
         assert_eq!(restored.version, 3, "Restauration cree une nouvelle version");
         assert_eq!(restored.texte, "Texte original", "Texte copie depuis v1");
         assert_eq!(restored.generated_by, "manual");
+        assert_eq!(restored.prompt_template_id, None);
     }
 
     #[tokio::test]
     async fn test_restore_annee_cloturee_retourne_erreur() {
         let (mut conn, _tmp) = setup_test_db().await;
 
-        let v1 = save_synthese_impl(&mut conn, 1, 1, 1, 1, "Texte", "llm").await.unwrap();
+        let v1 = save_synthese_impl(&mut conn, 1, 1, 1, 1, "Texte", "llm", None).await.unwrap();
 
         sqlx::query("UPDATE annees_scolaires SET cloturee = 1 WHERE id = 1")
             .execute(&mut conn)
@@ -543,6 +554,6 @@ mod tests {
         let (mut conn, _tmp) = setup_test_db().await;
 
         let result = restore_synthese_version_impl(&mut conn, 1, 1, 1, 1, 9999).await;
-        assert!(result.is_err(), "ID introuvable doit retourner une erreur");
+        assert_eq!(result.unwrap_err().code(), "NotFound", "ID introuvable doit retourner NotFound");
     }
 }
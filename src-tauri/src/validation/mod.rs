@@ -2,11 +2,10 @@ pub mod executor;
 pub mod schema;
 pub mod validator;
 
+use crate::error::AppError;
 use crate::sidecar::structuration::ObservationResult;
 use executor::{execute_validated_inserts, InsertResult};
 use log::info;
-use sqlx::sqlite::SqlitePoolOptions;
-use tauri::Manager;
 use validator::validate_observations;
 
 /// Validate LLM-generated observations (Layer 3) then insert into DB (Layer 4).
@@ -20,10 +19,9 @@ pub async fn validate_and_insert_observations(
     eleve_id: i64,
     periode_id: i64,
     original_text: Option<String>,
-) -> Result<InsertResult, String> {
+) -> Result<InsertResult, AppError> {
     // Layer 3: Pure validation
-    let validated = validate_observations(&observations, original_text.as_deref())
-        .map_err(|e| e.to_string())?;
+    let validated = validate_observations(&observations, original_text.as_deref())?;
 
     info!(
         "Layer 3 OK: {} observations validees pour eleve_id={}",
@@ -31,31 +29,11 @@ pub async fn validate_and_insert_observations(
         eleve_id
     );
 
-    // Resolve DB path from Tauri app data dir
-    let data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Impossible de trouver app_data_dir: {}", e))?;
-    let db_path = data_dir.join("comportement.db");
-    let db_url = format!("sqlite:{}", db_path.to_string_lossy());
-
-    // Open a pool for this request (lightweight for ~5 inserts/day)
-    let pool = SqlitePoolOptions::new()
-        .max_connections(1)
-        .connect(&db_url)
-        .await
-        .map_err(|e| format!("Connexion DB echouee: {}", e))?;
-
-    // Enable foreign keys (SQLite requires this per-connection)
-    sqlx::query("PRAGMA foreign_keys = ON;")
-        .execute(&pool)
-        .await
-        .map_err(|e| format!("PRAGMA foreign_keys echoue: {}", e))?;
+    // Shared pool (WAL, busy_timeout, foreign_keys set per connection)
+    let pool = crate::db::pool(&app).await?;
 
     // Layer 4: Prepared statement inserts in a transaction
-    let result = execute_validated_inserts(&pool, eleve_id, periode_id, &validated)
-        .await
-        .map_err(|e| e.to_string())?;
+    let result = execute_validated_inserts(&pool, eleve_id, periode_id, &validated).await?;
 
     info!(
         "Layer 4 OK: {} appreciations inserees pour eleve_id={} periode_id={}",
@@ -1,8 +1,9 @@
 // Story 19.2 — Global toolbar microphone (push-to-talk)
-// Replaces per-domain InlineDictation with a single mic button
+// Replaces per-domain InlineDictation with a single mic button.
+// Streaming transcription: the text appears while the teacher is still speaking.
 
 import { useCallback, useRef, useState, useEffect } from 'react';
-import { useTranscription } from '../../../shared/hooks/useTranscription';
+import { useStreamingTranscription } from '../../../shared/hooks/useStreamingTranscription';
 import { useDictationStore } from '../../../shared/stores/dictationStore';
 
 interface ToolbarMicProps {
@@ -12,8 +13,8 @@ interface ToolbarMicProps {
 }
 
 export function ToolbarMic({ eleveId, periodeId, disabled }: ToolbarMicProps) {
-  const { state, text, error, startRecording, stopAndTranscribe, retry, clearError } = useTranscription();
-  const { setState, setTranscribedText, setError, setContext, clear, classifyText,
+  const { state, text, audioPath, error, startRecording, stopAndTranscribe, reset } = useStreamingTranscription();
+  const { setState, setTranscribedText, setAudioPath, setError, setContext, clear, classifyText,
     state: dictationState } = useDictationStore();
   const stateRef = useRef(state);
   stateRef.current = state;
@@ -27,7 +28,7 @@ export function ToolbarMic({ eleveId, periodeId, disabled }: ToolbarMicProps) {
     else if (state === 'processing') setState('processing');
     else if (state === 'error') {
       setState('error');
-      setError(error?.message || 'Erreur inconnue');
+      setError(error || 'Erreur inconnue');
     }
   }, [state, error, setState, setError]);
 
@@ -35,12 +36,13 @@ export function ToolbarMic({ eleveId, periodeId, disabled }: ToolbarMicProps) {
   useEffect(() => {
     if (state === 'done' && text) {
       setTranscribedText(text);
+      setAudioPath(audioPath);
       setContext(eleveId, periodeId);
       setState('done');
-      retry(); // reset useTranscription for next recording
+      reset(); // reset useStreamingTranscription for next recording
       classifyText(); // auto-launch classification pipeline
     }
-  }, [state, text, eleveId, periodeId, setTranscribedText, setContext, setState, retry, classifyText]);
+  }, [state, text, audioPath, eleveId, periodeId, setTranscribedText, setAudioPath, setContext, setState, reset, classifyText]);
 
   const showTooltip = useCallback((msg: string) => {
     if (tooltipTimer.current) clearTimeout(tooltipTimer.current);
@@ -58,10 +60,10 @@ export function ToolbarMic({ eleveId, periodeId, disabled }: ToolbarMicProps) {
     }
     const s = stateRef.current;
     if (s === 'processing') return;
-    if (s === 'error') clearError();
+    if (s === 'error') reset();
     clear(); // reset dictation store for new recording
-    await startRecording();
-  }, [disabled, pipelineBusy, eleveId, showTooltip, startRecording, clearError, clear]);
+    await startRecording(eleveId);
+  }, [disabled, pipelineBusy, eleveId, showTooltip, startRecording, reset, clear]);
 
   const handlePointerUp = useCallback(async () => {
     if (stateRef.current !== 'recording') return;
@@ -77,7 +79,7 @@ export function ToolbarMic({ eleveId, periodeId, disabled }: ToolbarMicProps) {
     : isProcessing
       ? 'Transcription en cours...'
       : isError
-        ? error?.message || 'Erreur'
+        ? error || 'Erreur'
         : disabled
           ? 'Modele Whisper non installe'
           : eleveId === null
@@ -118,7 +120,10 @@ export function ToolbarMic({ eleveId, periodeId, disabled }: ToolbarMicProps) {
         )}
       </button>
 
-      {/* Pipeline progress indicator (Story 19.4) */}
+      {/* Partial text while the teacher speaks, then pipeline progress (Story 19.4) */}
+      {(isRecording || isProcessing) && text && (
+        <span className="text-xs text-slate-600 italic max-w-xs truncate" title={text}>{text}</span>
+      )}
       {dictationState === 'processing' && (
         <span className="text-xs text-blue-600 animate-pulse whitespace-nowrap">Transcription...</span>
       )}
@@ -15,7 +15,7 @@ interface ManualItem {
 }
 
 export function TranscriptPreview() {
-  const { state, transcribedText, classificationResults, error, clear } =
+  const { state, transcribedText, audioPath, classificationResults, error, clear, releaseRecording } =
     useDictationStore();
   const { domaines, appreciations, addAppreciation, updateAppreciation, loadAppreciations } =
     useAppreciationStore();
@@ -133,6 +133,7 @@ export function TranscriptPreview() {
             observations: finalText,
             texteDictation: transcribedText,
             source: 'vocal',
+            audioPath,
           };
           await addEvent(event);
         }
@@ -202,6 +203,7 @@ export function TranscriptPreview() {
       setEditedDomains({});
       setRemovedItems(new Set());
       setManualItems([]);
+      await releaseRecording();
       clear();
     } catch (e) {
       console.error('Erreur sauvegarde appreciations:', e);
@@ -215,8 +217,9 @@ export function TranscriptPreview() {
     setEditedDomains({});
     setRemovedItems(new Set());
     setManualItems([]);
+    releaseRecording();
     clear();
-  }, [clear]);
+  }, [clear, releaseRecording]);
 
   // Only show when there's something to display
   if (state === 'idle' || state === 'recording' || state === 'processing' || state === 'done') return null;
@@ -392,7 +395,10 @@ export function TranscriptPreview() {
                 Rejeter
               </button>
               {classificationResults.duration_ms > 0 && (
-                <span className="text-xs text-slate-400 ml-auto">
+                <span
+                  className="text-xs text-slate-400 ml-auto"
+                  title={`${classificationResults.usage.prompt_tokens}/${classificationResults.usage.budget} tokens${classificationResults.usage.exact ? '' : ' (estimation)'}`}
+                >
                   {(classificationResults.duration_ms / 1000).toFixed(1)}s
                 </span>
               )}
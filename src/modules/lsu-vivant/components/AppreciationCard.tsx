@@ -1,6 +1,7 @@
 import React, { useState, useEffect } from 'react';
 import { useAppreciationGeneraleStore } from '../../../shared/stores/appreciationGeneraleStore';
 import AppreciationVersionModal from './AppreciationVersionModal';
+import CandidatePicker from './CandidatePicker';
 
 interface AppreciationCardProps {
   eleveId: number;
@@ -10,6 +11,7 @@ interface AppreciationCardProps {
 }
 
 const MAX_CHARS = 1500;
+const CANDIDATE_COUNT = 3;
 
 export default function AppreciationCard({
   eleveId,
@@ -17,8 +19,20 @@ export default function AppreciationCard({
   anneeScolaireId,
   studentName,
 }: AppreciationCardProps) {
-  const { appreciation, isGenerating, generateAndSave, save, loadCurrent } =
-    useAppreciationGeneraleStore();
+  const {
+    appreciation,
+    violations,
+    candidates,
+    generation,
+    isGenerating,
+    generateAndSave,
+    generateCandidates,
+    chooseCandidate,
+    discardCandidates,
+    cancelGeneration,
+    save,
+    loadCurrent,
+  } = useAppreciationGeneraleStore();
 
   const [texte, setTexte] = useState(appreciation?.texte ?? '');
   const [isModalOpen, setIsModalOpen] = useState(false);
@@ -43,6 +57,26 @@ export default function AppreciationCard({
     }
   };
 
+  const handleGenerateCandidates = async () => {
+    try {
+      await generateCandidates(eleveId, periodeId, anneeScolaireId, studentName, CANDIDATE_COUNT);
+    } catch {
+      // error handled in store
+    }
+  };
+
+  const handleChoose = async (index: number) => {
+    setIsSaving(true);
+    try {
+      const result = await chooseCandidate(eleveId, periodeId, anneeScolaireId, index);
+      setTexte(result.texte);
+    } catch {
+      // error handled in store
+    } finally {
+      setIsSaving(false);
+    }
+  };
+
   const handleSave = async () => {
     if (!texte.trim()) return;
     setIsSaving(true);
@@ -77,6 +111,37 @@ export default function AppreciationCard({
         disabled={isGenerating}
       />
 
+      {generation && (
+        <div className="flex items-start justify-between gap-2 border border-dashed border-indigo-200 rounded p-2 bg-white">
+          <p className="text-sm text-slate-500 whitespace-pre-wrap">
+            {generation.text || 'En attente du modèle...'}
+          </p>
+          <button
+            onClick={() => cancelGeneration()}
+            className="shrink-0 text-xs text-slate-500 hover:text-slate-700"
+          >
+            Arrêter
+          </button>
+        </div>
+      )}
+
+      {candidates && (
+        <CandidatePicker
+          candidates={candidates.list}
+          onChoose={handleChoose}
+          onDiscard={discardCandidates}
+          disabled={isSaving}
+        />
+      )}
+
+      {violations.length > 0 && (
+        <ul className="text-xs text-amber-700 bg-amber-50 border border-amber-200 rounded px-2 py-1 list-disc list-inside">
+          {violations.map((v) => (
+            <li key={v.rule}>{v.message}</li>
+          ))}
+        </ul>
+      )}
+
       <div className="flex items-center justify-between">
         <span className={`text-xs ${charCount > MAX_CHARS * 0.9 ? 'text-amber-600' : 'text-slate-400'}`}>
           {charCount}/{MAX_CHARS}
@@ -98,6 +163,15 @@ export default function AppreciationCard({
             {isSaving ? 'Enregistrement...' : 'Enregistrer'}
           </button>
 
+          <button
+            onClick={handleGenerateCandidates}
+            disabled={isGenerating}
+            title="Générer plusieurs propositions et choisir la meilleure"
+            className="px-3 py-1.5 text-xs border border-indigo-300 rounded hover:bg-indigo-50 text-indigo-700 transition-colors disabled:opacity-50"
+          >
+            {CANDIDATE_COUNT} propositions
+          </button>
+
           <button
             onClick={handleGenerate}
             disabled={isGenerating}
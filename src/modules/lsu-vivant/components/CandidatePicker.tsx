@@ -0,0 +1,52 @@
+import React from 'react';
+import type { Candidate } from '../../../shared/types';
+
+interface CandidatePickerProps {
+  candidates: Candidate[];
+  onChoose: (index: number) => void;
+  onDiscard: () => void;
+  disabled?: boolean;
+}
+
+/** Propositions IA classées par le validateur LSU : la proposition choisie devient une version, les autres sont abandonnées. */
+export default function CandidatePicker({ candidates, onChoose, onDiscard, disabled }: CandidatePickerProps) {
+  return (
+    <div className="flex flex-col gap-2 border border-slate-200 rounded p-2 bg-slate-50">
+      <div className="flex items-center justify-between">
+        <span className="text-xs font-medium text-slate-600">
+          {candidates.length} propositions (la mieux notée en premier)
+        </span>
+        <button
+          onClick={onDiscard}
+          disabled={disabled}
+          className="text-xs text-slate-500 hover:text-slate-700 disabled:opacity-50"
+        >
+          Annuler
+        </button>
+      </div>
+
+      {candidates.map((candidate, index) => (
+        <div key={index} className="bg-white border border-slate-200 rounded p-2 flex flex-col gap-1">
+          <p className="text-sm text-slate-700 whitespace-pre-wrap">{candidate.text}</p>
+          {candidate.violations.length > 0 && (
+            <ul className="text-xs text-amber-700 list-disc list-inside">
+              {candidate.violations.map((v) => (
+                <li key={v.rule}>{v.message}</li>
+              ))}
+            </ul>
+          )}
+          <div className="flex items-center justify-between">
+            <span className="text-xs text-slate-400">{candidate.text.length} caractères</span>
+            <button
+              onClick={() => onChoose(index)}
+              disabled={disabled}
+              className="px-2 py-1 text-xs border border-slate-300 rounded hover:bg-slate-50 text-slate-700 transition-colors disabled:opacity-50"
+            >
+              Choisir
+            </button>
+          </div>
+        </div>
+      ))}
+    </div>
+  );
+}
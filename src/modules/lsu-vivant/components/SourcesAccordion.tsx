@@ -1,6 +1,7 @@
 import { useState } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import type { PedagogicalEvent, EventFilter } from '../../../shared/types';
+import { useEventStore } from '../../../shared/stores/eventStore';
 
 interface SourcesAccordionProps {
   eleveId: number;
@@ -24,6 +25,7 @@ interface RawEvent {
   source: string;
   created_at: string;
   synced_at: string | null;
+  has_audio: boolean;
 }
 
 function formatDate(iso: string) {
@@ -34,6 +36,37 @@ function formatDate(iso: string) {
   });
 }
 
+/** Réécoute de la dictée d'origine (pièce jointe audio) */
+function PlayDictationButton({ uuid }: { uuid: string }) {
+  const loadEventAudioUrl = useEventStore((s) => s.loadEventAudioUrl);
+  const [playing, setPlaying] = useState(false);
+
+  const handlePlay = async () => {
+    if (playing) return;
+    const url = await loadEventAudioUrl(uuid);
+    if (!url) return;
+    const audio = new Audio(url);
+    const release = () => {
+      URL.revokeObjectURL(url);
+      setPlaying(false);
+    };
+    audio.onended = release;
+    audio.onerror = release;
+    setPlaying(true);
+    await audio.play().catch(release);
+  };
+
+  return (
+    <button
+      onClick={handlePlay}
+      title="Réécouter la dictée"
+      className={`flex-shrink-0 ${playing ? 'text-blue-600 animate-pulse' : 'text-slate-400 hover:text-blue-600'}`}
+    >
+      🔊
+    </button>
+  );
+}
+
 const NIVEAU_LABELS: Record<string, string> = {
   'tres_bonne_maitrise': 'TBM',
   'bonne_maitrise': 'BM',
@@ -78,6 +111,7 @@ export default function SourcesAccordion({
           source: r.source as PedagogicalEvent['source'],
           createdAt: r.created_at,
           syncedAt: r.synced_at,
+          hasAudio: r.has_audio,
         }));
         setEvents(mapped);
         setLoaded(true);
@@ -124,6 +158,7 @@ export default function SourcesAccordion({
                   )}
                   <span className="text-slate-600">{ev.observations ?? '—'}</span>
                 </div>
+                {ev.hasAudio && <PlayDictationButton uuid={ev.uuid} />}
               </div>
             ))
           )}
@@ -2,6 +2,7 @@ import React, { useState, useEffect } from 'react';
 import { useSyntheseStore } from '../../../shared/stores/syntheseStore';
 import VersionModal from './VersionModal';
 import SourcesAccordion from './SourcesAccordion';
+import CandidatePicker from './CandidatePicker';
 
 interface SynthesisCardProps {
   eleveId: number;
@@ -13,6 +14,7 @@ interface SynthesisCardProps {
 }
 
 const MAX_CHARS = 1000;
+const CANDIDATE_COUNT = 3;
 
 export default function SynthesisCard({
   eleveId,
@@ -22,8 +24,24 @@ export default function SynthesisCard({
   anneeScolaireId,
   studentName,
 }: SynthesisCardProps) {
-  const { syntheses, isGenerating, generateAndSave, saveSynthese } = useSyntheseStore();
+  const {
+    syntheses,
+    violations,
+    candidates,
+    generations,
+    isGenerating,
+    generateAndSave,
+    generateCandidates,
+    chooseCandidate,
+    discardCandidates,
+    cancelGeneration,
+    saveSynthese,
+  } = useSyntheseStore();
   const synthese = syntheses[domaineId];
+  const domaineViolations = violations[domaineId] ?? [];
+  const pending = candidates[domaineId];
+  // Texte en cours de generation pour ce domaine (null si aucune generation ici)
+  const streamingText = generations[domaineId]?.text ?? null;
 
   const [texte, setTexte] = useState(synthese?.texte ?? '');
   const [isModalOpen, setIsModalOpen] = useState(false);
@@ -43,6 +61,26 @@ export default function SynthesisCard({
     }
   };
 
+  const handleGenerateCandidates = async () => {
+    try {
+      await generateCandidates(eleveId, domaineId, periodeId, anneeScolaireId, studentName, CANDIDATE_COUNT);
+    } catch {
+      // error handled in store
+    }
+  };
+
+  const handleChoose = async (index: number) => {
+    setIsSaving(true);
+    try {
+      const result = await chooseCandidate(eleveId, domaineId, periodeId, anneeScolaireId, index);
+      setTexte(result.texte);
+    } catch {
+      // error handled in store
+    } finally {
+      setIsSaving(false);
+    }
+  };
+
   const handleSave = async () => {
     if (!texte.trim()) return;
     setIsSaving(true);
@@ -77,6 +115,37 @@ export default function SynthesisCard({
         disabled={isGenerating}
       />
 
+      {streamingText !== null && (
+        <div className="flex items-start justify-between gap-2 border border-dashed border-blue-200 rounded p-2 bg-blue-50/40">
+          <p className="text-sm text-slate-500 whitespace-pre-wrap">
+            {streamingText || 'En attente du modele...'}
+          </p>
+          <button
+            onClick={() => cancelGeneration(domaineId)}
+            className="shrink-0 text-xs text-slate-500 hover:text-slate-700"
+          >
+            Arreter
+          </button>
+        </div>
+      )}
+
+      {pending && (
+        <CandidatePicker
+          candidates={pending.list}
+          onChoose={handleChoose}
+          onDiscard={() => discardCandidates(domaineId)}
+          disabled={isSaving}
+        />
+      )}
+
+      {domaineViolations.length > 0 && (
+        <ul className="text-xs text-amber-700 bg-amber-50 border border-amber-200 rounded px-2 py-1 list-disc list-inside">
+          {domaineViolations.map((v) => (
+            <li key={v.rule}>{v.message}</li>
+          ))}
+        </ul>
+      )}
+
       <div className="flex items-center justify-between">
         <span className={`text-xs ${charCount > MAX_CHARS * 0.9 ? 'text-amber-600' : 'text-slate-400'}`}>
           {charCount}/{MAX_CHARS}
@@ -98,6 +167,15 @@ export default function SynthesisCard({
             {isSaving ? 'Enregistrement...' : 'Enregistrer'}
           </button>
 
+          <button
+            onClick={handleGenerateCandidates}
+            disabled={isGenerating}
+            title="Generer plusieurs propositions et choisir la meilleure"
+            className="px-3 py-1.5 text-xs border border-blue-300 rounded hover:bg-blue-50 text-blue-700 transition-colors disabled:opacity-50"
+          >
+            {CANDIDATE_COUNT} propositions
+          </button>
+
           <button
             onClick={handleGenerate}
             disabled={isGenerating}
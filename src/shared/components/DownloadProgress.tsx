@@ -3,6 +3,7 @@ import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import type { DownloadProgress as DownloadProgressType } from '../types';
 import { useModelStore } from '../stores/modelStore';
+import { errorMessage } from '../utils/errors';
 
 function formatBytes(bytes: number): string {
   if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(0)} Ko`;
@@ -34,7 +35,7 @@ export function DownloadProgress() {
         await checkModels();
         setSetupStep('done');
       } catch (err) {
-        const msg = String(err);
+        const msg = errorMessage(err);
         setError(msg);
         setDownloadError(msg);
       }
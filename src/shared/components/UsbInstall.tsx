@@ -3,6 +3,7 @@ import { invoke } from '@tauri-apps/api/core';
 import { open } from '@tauri-apps/plugin-dialog';
 import { listen } from '@tauri-apps/api/event';
 import { useModelStore } from '../stores/modelStore';
+import { errorMessage } from '../utils/errors';
 
 interface InstallProgress {
   model_name: string;
@@ -55,7 +56,7 @@ export function UsbInstall() {
       await checkModels();
       setSetupStep('done');
     } catch (err) {
-      setError(String(err));
+      setError(errorMessage(err));
       setInstalling(false);
     }
   };
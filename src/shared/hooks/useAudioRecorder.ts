@@ -10,6 +10,7 @@ import { useState, useRef, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { startRecording as pluginStart, stopRecording as pluginStop } from 'tauri-plugin-mic-recorder-api';
 import { startWebAudioRecording, type WebAudioSession } from '../utils/webAudioRecorder';
+import type { SavedRecording } from '../types';
 
 export type RecordingState = 'idle' | 'recording' | 'processing' | 'error';
 export type AudioPlan = 'unknown' | 'plugin' | 'web-audio';
@@ -131,9 +132,10 @@ export function useAudioRecorder(): UseAudioRecorderReturn {
         webSessionRef.current = null;
 
         const wavBytes = await session.stop();
-        filePath = await invoke<string>('save_wav_file', {
+        const saved = await invoke<SavedRecording>('save_wav_file', {
           wavData: Array.from(wavBytes),
         });
+        filePath = saved.path;
       }
 
       setAudioPath(filePath);
@@ -0,0 +1,174 @@
+// Streaming dictation: partial text appears while the teacher is still speaking
+//
+// State machine: idle → recording → processing → done | error
+// Captures PCM with the Web Audio recorder, pushes chunks to the Rust segmenter
+// (push_transcription_chunk, raw little-endian bytes) and listens to
+// `transcription_partial` events. finish_transcription_stream returns the stitched
+// full text and the saved recording.
+
+import { useState, useCallback, useEffect, useRef } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { startWebAudioRecording, type WebAudioSession } from '../utils/webAudioRecorder';
+import type { StreamTranscriptionResult, TranscriptionPartial } from '../types';
+import { errorCode, errorMessage } from '../utils/errors';
+import type { TranscriptionState } from './useTranscription';
+
+/** ~250 ms at 16 kHz: keeps IPC calls infrequent without delaying segmentation */
+const PUSH_THRESHOLD_SAMPLES = 4000;
+
+interface UseStreamingTranscriptionReturn {
+  state: TranscriptionState;
+  /** Stitched text so far (live during recording, final once done) */
+  text: string;
+  /** Recording of the last dictation (for the retention policy and event attachment) */
+  audioPath: string | null;
+  error: string | null;
+  audioLevel: number;
+  /** eleveId enables the student-scoped corrections on the final text */
+  startRecording: (eleveId?: number | null) => Promise<void>;
+  stopAndTranscribe: () => Promise<void>;
+  cancel: () => Promise<void>;
+  /** Back to idle after a result or an error, ready for the next dictation */
+  reset: () => void;
+  setText: (text: string) => void;
+}
+
+export function useStreamingTranscription(): UseStreamingTranscriptionReturn {
+  const [state, setState] = useState<TranscriptionState>('idle');
+  const [text, setText] = useState('');
+  const [audioPath, setAudioPath] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const [audioLevel, setAudioLevel] = useState(0);
+
+  const sessionIdRef = useRef<string | null>(null);
+  const audioRef = useRef<WebAudioSession | null>(null);
+  const unlistenRef = useRef<UnlistenFn | null>(null);
+  const pendingRef = useRef<number[]>([]);
+  // Chunks are pushed sequentially so the segmenter sees audio in order
+  const pushChainRef = useRef<Promise<void>>(Promise.resolve());
+
+  const flushPending = useCallback(() => {
+    const sessionId = sessionIdRef.current;
+    if (!sessionId || pendingRef.current.length === 0) return;
+    // Raw body: typed arrays are little-endian on every supported platform
+    const bytes = new Uint8Array(Int16Array.from(pendingRef.current).buffer);
+    pendingRef.current = [];
+    pushChainRef.current = pushChainRef.current
+      .then(() => invoke<void>('push_transcription_chunk', bytes, { headers: { 'x-session-id': sessionId } }))
+      .catch((err) => console.error('push_transcription_chunk failed:', err));
+  }, []);
+
+  const cleanup = useCallback(() => {
+    unlistenRef.current?.();
+    unlistenRef.current = null;
+    sessionIdRef.current = null;
+    pendingRef.current = [];
+    setAudioLevel(0);
+  }, []);
+
+  useEffect(() => () => {
+    const sessionId = sessionIdRef.current;
+    audioRef.current?.stop().catch(() => undefined);
+    if (sessionId) {
+      invoke('cancel_transcription_stream', { sessionId }).catch(() => undefined);
+    }
+    unlistenRef.current?.();
+  }, []);
+
+  const startRecording = useCallback(async (eleveId: number | null = null) => {
+    setState('recording');
+    setError(null);
+    setText('');
+    setAudioPath(null);
+
+    try {
+      const sessionId = await invoke<string>('start_transcription_stream', { eleveId });
+      sessionIdRef.current = sessionId;
+      pushChainRef.current = Promise.resolve();
+
+      unlistenRef.current = await listen<TranscriptionPartial>('transcription_partial', (event) => {
+        if (event.payload.session_id === sessionIdRef.current) {
+          setText(event.payload.full_text);
+        }
+      });
+
+      audioRef.current = await startWebAudioRecording({
+        onAudioLevel: setAudioLevel,
+        onPcmChunk: (samples) => {
+          for (let i = 0; i < samples.length; i++) pendingRef.current.push(samples[i]);
+          if (pendingRef.current.length >= PUSH_THRESHOLD_SAMPLES) flushPending();
+        },
+      });
+    } catch (err) {
+      const sessionId = sessionIdRef.current;
+      if (sessionId) {
+        invoke('cancel_transcription_stream', { sessionId }).catch(() => undefined);
+      }
+      cleanup();
+      setState('error');
+      setError(errorMessage(err));
+    }
+  }, [cleanup, flushPending]);
+
+  const stopAndTranscribe = useCallback(async () => {
+    const sessionId = sessionIdRef.current;
+    const audio = audioRef.current;
+    audioRef.current = null;
+    if (!sessionId || !audio) return;
+
+    setState('processing');
+    try {
+      await audio.stop();
+      flushPending();
+      await pushChainRef.current;
+
+      const result = await invoke<StreamTranscriptionResult>('finish_transcription_stream', { sessionId });
+      setAudioPath(result.audio_path);
+      setText(result.text);
+      setState('done');
+    } catch (err) {
+      setState('error');
+      setError(
+        errorCode(err) === 'NoSpeech'
+          ? 'Aucune parole detectee. Parlez plus pres du microphone.'
+          : errorMessage(err)
+      );
+    } finally {
+      cleanup();
+    }
+  }, [cleanup, flushPending]);
+
+  const cancel = useCallback(async () => {
+    const sessionId = sessionIdRef.current;
+    const audio = audioRef.current;
+    audioRef.current = null;
+    cleanup();
+    await audio?.stop().catch(() => undefined);
+    if (sessionId) {
+      await invoke('cancel_transcription_stream', { sessionId }).catch(() => undefined);
+    }
+    setText('');
+    setState('idle');
+  }, [cleanup]);
+
+  const reset = useCallback(() => {
+    setText('');
+    setAudioPath(null);
+    setError(null);
+    setState('idle');
+  }, []);
+
+  return {
+    state,
+    text,
+    audioPath,
+    error,
+    audioLevel,
+    startRecording,
+    stopAndTranscribe,
+    cancel,
+    reset,
+    setText,
+  };
+}
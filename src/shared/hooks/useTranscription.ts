@@ -8,6 +8,7 @@ import { useState, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { useAudioRecorder } from './useAudioRecorder';
 import type { TranscriptionResult } from '../types';
+import { errorCode, errorMessage } from '../utils/errors';
 
 export type TranscriptionState = 'idle' | 'recording' | 'processing' | 'done' | 'error';
 
@@ -27,6 +28,8 @@ interface ErrorInfo {
 interface UseTranscriptionReturn {
   state: TranscriptionState;
   text: string;
+  /** Recording of the last dictation (for the retention policy) */
+  audioPath: string | null;
   error: ErrorInfo | null;
   /** Recording duration in seconds (live) */
   recordingDuration: number;
@@ -38,8 +41,9 @@ interface UseTranscriptionReturn {
   activePlan: 'unknown' | 'plugin' | 'web-audio';
   /** Start recording (call on mousedown/pointerdown) */
   startRecording: () => Promise<void>;
-  /** Stop recording and start transcription (call on mouseup/pointerup) */
-  stopAndTranscribe: () => Promise<void>;
+  /** Stop recording and start transcription (call on mouseup/pointerup).
+   *  eleveId enables the student-scoped corrections. */
+  stopAndTranscribe: (eleveId?: number | null) => Promise<void>;
   /** Re-dictate: clear text and go back to idle */
   retry: () => void;
   /** Clear text only, stay in done state */
@@ -52,7 +56,27 @@ interface UseTranscriptionReturn {
 
 const TRANSCRIPTION_TIMEOUT_MS = 30_000;
 
-function classifyError(errorStr: string): ErrorInfo {
+function classifyError(err: unknown): ErrorInfo {
+  switch (errorCode(err)) {
+    case 'ModelMissing':
+      return {
+        type: 'model_not_found',
+        message: 'Modeles IA non installes. Allez dans Parametres > Modeles IA.',
+      };
+    case 'NoSpeech':
+      return {
+        type: 'empty_response',
+        message: 'Aucune parole detectee. Parlez plus pres du microphone.',
+      };
+    case 'SidecarUnavailable':
+    case 'Network':
+      return {
+        type: 'network',
+        message: 'Connexion au serveur de transcription echouee. Reessayez.',
+      };
+  }
+
+  const errorStr = errorMessage(err);
   const lower = errorStr.toLowerCase();
 
   if (lower.includes('modele') && lower.includes('introuvable') || lower.includes('model') && lower.includes('not found') || lower.includes('modeles ia non installes') || lower.includes('modelnotfound')) {
@@ -94,6 +118,7 @@ export function useTranscription(): UseTranscriptionReturn {
 
   const [transcriptionState, setTranscriptionState] = useState<TranscriptionState>('idle');
   const [text, setText] = useState('');
+  const [audioPath, setAudioPath] = useState<string | null>(null);
   const [error, setError] = useState<ErrorInfo | null>(null);
   const [transcriptionDurationMs, setTranscriptionDurationMs] = useState<number | null>(null);
 
@@ -104,15 +129,17 @@ export function useTranscription(): UseTranscriptionReturn {
     setTranscriptionState('recording');
     setError(null);
     setText('');
+    setAudioPath(null);
     setTranscriptionDurationMs(null);
     await recorder.startRecording();
   }, [recorder]);
 
-  const stopAndTranscribe = useCallback(async () => {
+  const stopAndTranscribe = useCallback(async (eleveId: number | null = null) => {
     setTranscriptionState('processing');
 
     // Stop recording → get WAV file path
     const audioPath = await recorder.stopRecording();
+    setAudioPath(audioPath);
 
     if (!audioPath) {
       setTranscriptionState('error');
@@ -128,7 +155,7 @@ export function useTranscription(): UseTranscriptionReturn {
 
     try {
       const result = await Promise.race([
-        invoke<TranscriptionResult>('transcribe_audio', { audioPath }),
+        invoke<TranscriptionResult>('transcribe_audio', { audioPath, eleveId }),
         new Promise<never>((_, reject) => {
           const timer = setTimeout(
             () => reject(new Error('Transcription trop lente')),
@@ -152,9 +179,8 @@ export function useTranscription(): UseTranscriptionReturn {
       setTranscriptionDurationMs(result.duration_ms);
       setTranscriptionState('done');
     } catch (err) {
-      const message = err instanceof Error ? err.message : String(err);
       setTranscriptionState('error');
-      setError(classifyError(message));
+      setError(classifyError(err));
     } finally {
       abortRef.current = null;
     }
@@ -162,6 +188,7 @@ export function useTranscription(): UseTranscriptionReturn {
 
   const retry = useCallback(() => {
     setText('');
+    setAudioPath(null);
     setError(null);
     setTranscriptionDurationMs(null);
     setTranscriptionState('idle');
@@ -195,6 +222,7 @@ export function useTranscription(): UseTranscriptionReturn {
   return {
     state: effectiveState,
     text,
+    audioPath,
     error: effectiveError,
     recordingDuration: recorder.duration,
     audioLevel: recorder.audioLevel,
@@ -1,6 +1,7 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
 import type { AbsenceV2, NewAbsenceV2, AbsenceAlert, AbsenceTotaux, DemiJournee, TypeAbsence } from '../types';
+import { errorMessage } from '../utils/errors';
 
 interface AbsenceStore {
   absences: AbsenceV2[];
@@ -65,7 +66,7 @@ export const useAbsenceStore = create<AbsenceStore>((set, get) => ({
       });
       set({ absences: rows.map(mapRaw), isLoading: false });
     } catch (error) {
-      set({ error: String(error), isLoading: false });
+      set({ error: errorMessage(error), isLoading: false });
     }
   },
 
@@ -87,7 +88,7 @@ export const useAbsenceStore = create<AbsenceStore>((set, get) => ({
         await get().loadAbsences(lastFilter.anneeScolaireId, lastFilter.weekStart, lastFilter.weekEnd);
       }
     } catch (error) {
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -98,7 +99,7 @@ export const useAbsenceStore = create<AbsenceStore>((set, get) => ({
         await get().loadAbsences(lastFilter.anneeScolaireId, lastFilter.weekStart, lastFilter.weekEnd);
       }
     } catch (error) {
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -109,7 +110,7 @@ export const useAbsenceStore = create<AbsenceStore>((set, get) => ({
         await get().loadAbsences(lastFilter.anneeScolaireId, lastFilter.weekStart, lastFilter.weekEnd);
       }
     } catch (error) {
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -125,7 +126,7 @@ export const useAbsenceStore = create<AbsenceStore>((set, get) => ({
         await get().loadAbsences(lastFilter.anneeScolaireId, lastFilter.weekStart, lastFilter.weekEnd);
       }
     } catch (error) {
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -137,7 +138,7 @@ export const useAbsenceStore = create<AbsenceStore>((set, get) => ({
       });
       set({ alerts: rawAlerts.map((a) => ({ eleveId: a.eleve_id, count: a.count })) });
     } catch (error) {
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -150,7 +151,7 @@ export const useAbsenceStore = create<AbsenceStore>((set, get) => ({
       });
       return raw.map((t) => ({ eleveId: t.eleve_id, justifiees: t.justifiees, injustifiees: t.injustifiees }));
     } catch (error) {
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
       return [];
     }
   },
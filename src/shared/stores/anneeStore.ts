@@ -2,6 +2,7 @@ import { create } from 'zustand';
 import Database from '@tauri-apps/plugin-sql';
 import { invoke } from '@tauri-apps/api/core';
 import type { AnneeScolaire } from '../types';
+import { errorMessage } from '../utils/errors';
 
 interface AnneeStore {
   annees: AnneeScolaire[];
@@ -54,7 +55,7 @@ export const useAnneeStore = create<AnneeStore>((set, get) => ({
       set({ annees, activeAnnee, isLoading: false });
     } catch (error) {
       console.error('Error loading annees:', error);
-      set({ error: String(error), isLoading: false });
+      set({ error: errorMessage(error), isLoading: false });
     }
   },
 
@@ -72,7 +73,7 @@ export const useAnneeStore = create<AnneeStore>((set, get) => ({
       await get().loadAnnees();
     } catch (error) {
       console.error('Error creating annee:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -84,18 +85,18 @@ export const useAnneeStore = create<AnneeStore>((set, get) => ({
       await get().loadAnnees();
     } catch (error) {
       console.error('Error setting active annee:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
   cloturer: async (id) => {
     try {
-      const db = await getDb();
-      await db.execute('UPDATE annees_scolaires SET cloturee = 1 WHERE id = $1', [id]);
+      // Cote Rust : la cloture purge aussi les enregistrements audio de l'annee
+      await invoke('cloturer_annee', { anneeId: id });
       await get().loadAnnees();
     } catch (error) {
       console.error('Error closing annee:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -109,7 +110,7 @@ export const useAnneeStore = create<AnneeStore>((set, get) => ({
       await get().loadAnnees();
     } catch (error) {
       console.error('Error reopening annee:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -1,21 +1,40 @@
-import { create } from 'zustand';
+import { create, type StoreApi } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
-import type { AppreciationGenerale, AppreciationGeneraleVersion } from '../types';
+import type { AppreciationGenerale, AppreciationGeneraleVersion, AppreciationResult, Candidate, RuleViolation } from '../types';
+import { errorMessage } from '../utils/errors';
+import { cancelGeneration, generationError, streamGeneration } from '../utils/llmStream';
 
 interface AppreciationGeneraleStore {
   appreciation: AppreciationGenerale | null;
   versions: AppreciationGeneraleVersion[];
+  violations: RuleViolation[];   // regles LSU non respectees par la derniere generation
+  candidates: PendingCandidates | null;   // propositions en attente de choix
+  generation: StreamingGeneration | null;   // generation en cours (texte affiche au fil de l'eau)
   isGenerating: boolean;
   error: string | null;
 
   loadCurrent(eleveId: number, periodeId: number, anneeScolaireId: number): Promise<void>;
-  save(eleveId: number, periodeId: number, anneeScolaireId: number, texte: string, generatedBy: string): Promise<AppreciationGenerale>;
+  save(eleveId: number, periodeId: number, anneeScolaireId: number, texte: string, generatedBy: string, promptTemplateId?: number | null): Promise<AppreciationGenerale>;
   generateAndSave(eleveId: number, periodeId: number, anneeScolaireId: number, studentName: string): Promise<AppreciationGenerale>;
+  generateCandidates(eleveId: number, periodeId: number, anneeScolaireId: number, studentName: string, count: number): Promise<void>;
+  chooseCandidate(eleveId: number, periodeId: number, anneeScolaireId: number, index: number): Promise<AppreciationGenerale>;
+  discardCandidates(): void;
+  cancelGeneration(): Promise<void>;
   loadVersions(eleveId: number, periodeId: number, anneeScolaireId: number): Promise<void>;
   restoreVersion(eleveId: number, periodeId: number, anneeScolaireId: number, versionId: number): Promise<void>;
   clearState(): void;
 }
 
+interface PendingCandidates {
+  list: Candidate[];
+  promptTemplateId: number;
+}
+
+interface StreamingGeneration {
+  requestId: string;
+  text: string;
+}
+
 interface RawAppreciationGenerale {
   id: number;
   eleve_id: number;
@@ -24,6 +43,7 @@ interface RawAppreciationGenerale {
   version: number;
   texte: string;
   generated_by: string;
+  prompt_template_id: number | null;
   created_at: string;
 }
 
@@ -44,6 +64,7 @@ function mapRaw(r: RawAppreciationGenerale): AppreciationGenerale {
     version: r.version,
     texte: r.texte,
     generatedBy: r.generated_by as 'llm' | 'manual',
+    promptTemplateId: r.prompt_template_id,
     createdAt: r.created_at,
   };
 }
@@ -58,9 +79,26 @@ function mapRawVersion(r: RawAppreciationVersion): AppreciationGeneraleVersion {
   };
 }
 
+/** generate_appreciation streamed into `generation`, cleared once the command returns */
+function streamAppreciation(
+  set: StoreApi<AppreciationGeneraleStore>['setState'],
+  args: Record<string, unknown>
+): Promise<AppreciationResult> {
+  return streamGeneration(
+    (requestId) => {
+      set({ generation: { requestId, text: '' } });
+      return invoke<AppreciationResult>('generate_appreciation', { ...args, requestId });
+    },
+    (text) => set((state) => (state.generation ? { generation: { ...state.generation, text } } : {}))
+  ).finally(() => set({ generation: null }));
+}
+
 export const useAppreciationGeneraleStore = create<AppreciationGeneraleStore>((set, get) => ({
   appreciation: null,
   versions: [],
+  violations: [],
+  candidates: null,
+  generation: null,
   isGenerating: false,
   error: null,
 
@@ -74,11 +112,11 @@ export const useAppreciationGeneraleStore = create<AppreciationGeneraleStore>((s
       });
       set({ appreciation: raw ? mapRaw(raw) : null });
     } catch (error) {
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
-  save: async (eleveId, periodeId, anneeScolaireId, texte, generatedBy) => {
+  save: async (eleveId, periodeId, anneeScolaireId, texte, generatedBy, promptTemplateId = null) => {
     try {
       const raw = await invoke<RawAppreciationGenerale>('save_appreciation', {
         eleveId,
@@ -86,12 +124,13 @@ export const useAppreciationGeneraleStore = create<AppreciationGeneraleStore>((s
         anneeScolaireId,
         texte,
         generatedBy,
+        promptTemplateId,
       });
       const appreciation = mapRaw(raw);
       set({ appreciation });
       return appreciation;
     } catch (error) {
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
       throw error;
     }
   },
@@ -99,7 +138,7 @@ export const useAppreciationGeneraleStore = create<AppreciationGeneraleStore>((s
   generateAndSave: async (eleveId, periodeId, anneeScolaireId, studentName) => {
     set({ isGenerating: true, error: null });
     try {
-      const result = await invoke<{ appreciation: string; duration_ms: number }>('generate_appreciation', {
+      const result = await streamAppreciation(set, {
         eleveId,
         periodeId,
         anneeScolaireId,
@@ -110,16 +149,67 @@ export const useAppreciationGeneraleStore = create<AppreciationGeneraleStore>((s
         periodeId,
         anneeScolaireId,
         result.appreciation,
-        'llm'
+        'llm',
+        result.prompt_template_id
       );
-      set({ isGenerating: false });
+      set({ isGenerating: false, violations: result.violations });
       return appreciation;
     } catch (error) {
-      set({ isGenerating: false, error: String(error) });
+      set({ isGenerating: false, error: generationError(error) });
       throw error;
     }
   },
 
+  generateCandidates: async (eleveId, periodeId, anneeScolaireId, studentName, count) => {
+    set({ isGenerating: true, error: null });
+    try {
+      const result = await streamAppreciation(set, {
+        eleveId,
+        periodeId,
+        anneeScolaireId,
+        studentName,
+        candidates: count,
+      });
+      set({
+        isGenerating: false,
+        candidates: { list: result.candidates, promptTemplateId: result.prompt_template_id },
+      });
+    } catch (error) {
+      set({ isGenerating: false, error: generationError(error) });
+      throw error;
+    }
+  },
+
+  chooseCandidate: async (eleveId, periodeId, anneeScolaireId, index) => {
+    const pending = get().candidates;
+    const candidate = pending?.list[index];
+    if (!pending || !candidate) {
+      throw new Error('Proposition introuvable');
+    }
+    const appreciation = await get().save(
+      eleveId,
+      periodeId,
+      anneeScolaireId,
+      candidate.text,
+      'llm',
+      pending.promptTemplateId
+    );
+    // Les autres propositions sont abandonnees
+    set({ candidates: null, violations: candidate.violations });
+    return appreciation;
+  },
+
+  discardCandidates: () => {
+    set({ candidates: null });
+  },
+
+  cancelGeneration: async () => {
+    const generation = get().generation;
+    if (generation) {
+      await cancelGeneration(generation.requestId);
+    }
+  },
+
   loadVersions: async (eleveId, periodeId, anneeScolaireId) => {
     set({ error: null });
     try {
@@ -130,7 +220,7 @@ export const useAppreciationGeneraleStore = create<AppreciationGeneraleStore>((s
       });
       set({ versions: rows.map(mapRawVersion) });
     } catch (error) {
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -146,12 +236,12 @@ export const useAppreciationGeneraleStore = create<AppreciationGeneraleStore>((s
       // Refresh versions list
       await get().loadVersions(eleveId, periodeId, anneeScolaireId);
     } catch (error) {
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
       throw error;
     }
   },
 
   clearState: () => {
-    set({ appreciation: null, versions: [], isGenerating: false, error: null });
+    set({ appreciation: null, versions: [], violations: [], candidates: null, generation: null, isGenerating: false, error: null });
   },
 }));
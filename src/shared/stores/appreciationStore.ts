@@ -2,6 +2,7 @@ import { create } from 'zustand';
 import Database from '@tauri-apps/plugin-sql';
 import type { NiveauAcquisition, NiveauLsu } from '../types';
 import { DOMAINES_OFFICIELS, getDomaineNamesForCycle, type CycleNumber } from '../types/domaines-officiels';
+import { errorMessage } from '../utils/errors';
 
 export interface Domaine {
   id: number;
@@ -129,7 +130,7 @@ export const useAppreciationStore = create<AppreciationStore>((set, get) => ({
       });
     } catch (error) {
       console.error('Error loading domaines:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -194,7 +195,7 @@ export const useAppreciationStore = create<AppreciationStore>((set, get) => ({
       set({ appreciations: rows, isLoading: false });
     } catch (error) {
       console.error('Error loading appreciations:', error);
-      set({ error: String(error), isLoading: false });
+      set({ error: errorMessage(error), isLoading: false });
     }
   },
 
@@ -217,7 +218,7 @@ export const useAppreciationStore = create<AppreciationStore>((set, get) => ({
       return true;
     } catch (error) {
       console.error('Error adding appreciation:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
       return false;
     }
   },
@@ -254,7 +255,7 @@ export const useAppreciationStore = create<AppreciationStore>((set, get) => ({
       await get().loadAppreciations(appreciation.eleveId, appreciation.periodeId);
     } catch (error) {
       console.error('Error updating appreciation:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -270,7 +271,7 @@ export const useAppreciationStore = create<AppreciationStore>((set, get) => ({
       await get().loadAppreciations(appreciation.eleveId, appreciation.periodeId);
     } catch (error) {
       console.error('Error undoing appreciation:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -290,7 +291,7 @@ export const useAppreciationStore = create<AppreciationStore>((set, get) => ({
       return true;
     } catch (error) {
       console.error('Error batch saving appreciations:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
       return false;
     }
   },
@@ -307,7 +308,7 @@ export const useAppreciationStore = create<AppreciationStore>((set, get) => ({
       await get().loadDomaines();
     } catch (error) {
       console.error('Error adding domaine:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -318,7 +319,7 @@ export const useAppreciationStore = create<AppreciationStore>((set, get) => ({
       await get().loadDomaines();
     } catch (error) {
       console.error('Error updating domaine:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -332,7 +333,7 @@ export const useAppreciationStore = create<AppreciationStore>((set, get) => ({
       await get().loadDomaines();
     } catch (error) {
       console.error('Error toggling domaine:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -348,7 +349,7 @@ export const useAppreciationStore = create<AppreciationStore>((set, get) => ({
       await get().loadDomaines();
     } catch (error) {
       console.error('Error reordering domaines:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 }));
@@ -2,6 +2,7 @@ import { create } from 'zustand';
 import Database from '@tauri-apps/plugin-sql';
 import type { Periode, TypePeriode } from '../types';
 import { getCurrentSchoolYear, getActivePeriode } from '../utils/periodes';
+import { errorMessage } from '../utils/errors';
 
 interface ConfigStore {
   periodes: Periode[];
@@ -55,7 +56,7 @@ export const useConfigStore = create<ConfigStore>((set, get) => ({
       set({ periodes, activePeriode, isLoading: false });
     } catch (error) {
       console.error('Error loading periodes:', error);
-      set({ error: String(error), isLoading: false });
+      set({ error: errorMessage(error), isLoading: false });
     }
   },
 
@@ -83,7 +84,7 @@ export const useConfigStore = create<ConfigStore>((set, get) => ({
       await get().loadPeriodes();
     } catch (error) {
       console.error('Error saving periodes:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -4,12 +4,15 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
 import type { ClassificationResults } from '../types';
+import { errorMessage } from '../utils/errors';
 
 export type DictationState = 'idle' | 'recording' | 'processing' | 'done' | 'classifying' | 'classified' | 'error';
 
 interface DictationStoreState {
   state: DictationState;
   transcribedText: string;
+  /** Recording the text was transcribed from (kept per the audio retention policy) */
+  audioPath: string | null;
   error: string | null;
   eleveId: number | null;
   periodeId: number | null;
@@ -17,6 +20,9 @@ interface DictationStoreState {
 
   setState: (state: DictationState) => void;
   setTranscribedText: (text: string) => void;
+  setAudioPath: (audioPath: string | null) => void;
+  /** Dictation validated or rejected: the recording may be deleted (keep_until_validated) */
+  releaseRecording: () => Promise<void>;
   setError: (error: string | null) => void;
   setContext: (eleveId: number | null, periodeId: number | null) => void;
   classifyText: () => Promise<void>;
@@ -26,6 +32,7 @@ interface DictationStoreState {
 export const useDictationStore = create<DictationStoreState>((set, get) => ({
   state: 'idle',
   transcribedText: '',
+  audioPath: null,
   error: null,
   eleveId: null,
   periodeId: null,
@@ -33,6 +40,7 @@ export const useDictationStore = create<DictationStoreState>((set, get) => ({
 
   setState: (state) => set({ state }),
   setTranscribedText: (text) => set({ transcribedText: text }),
+  setAudioPath: (audioPath) => set({ audioPath }),
   setError: (error) => set({ error }),
   setContext: (eleveId, periodeId) => set({ eleveId, periodeId }),
 
@@ -54,13 +62,25 @@ export const useDictationStore = create<DictationStoreState>((set, get) => ({
       });
       set({ state: 'classified', classificationResults: results });
     } catch (e) {
-      set({ state: 'error', error: String(e) });
+      set({ state: 'error', error: errorMessage(e) });
+    }
+  },
+
+  releaseRecording: async () => {
+    const { audioPath } = get();
+    if (!audioPath) return;
+    set({ audioPath: null });
+    try {
+      await invoke('mark_recording_validated', { audioPath });
+    } catch (e) {
+      console.warn('Retention audio:', errorMessage(e));
     }
   },
 
   clear: () => set({
     state: 'idle',
     transcribedText: '',
+    audioPath: null,
     error: null,
     classificationResults: null,
   }),
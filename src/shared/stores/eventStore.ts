@@ -1,6 +1,7 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
 import type { PedagogicalEvent, NewEvent, EventFilter, EventType } from '../types';
+import { errorMessage } from '../utils/errors';
 
 interface EventStore {
   events: PedagogicalEvent[];
@@ -13,6 +14,9 @@ interface EventStore {
   // INSERT only — pas d'update ni delete (event sourcing, ADR-014)
   addEvent: (event: NewEvent) => Promise<number | null>;
 
+  // Enregistrement rattaché (réécoute) : URL blob à révoquer par l'appelant
+  loadEventAudioUrl: (uuid: string) => Promise<string | null>;
+
   // Helpers
   clearEvents: () => void;
 }
@@ -38,7 +42,7 @@ export const useEventStore = create<EventStore>((set) => ({
       const events: PedagogicalEvent[] = rows.map(mapRawEvent);
       set({ events, isLoading: false });
     } catch (error) {
-      set({ error: String(error), isLoading: false });
+      set({ error: errorMessage(error), isLoading: false });
     }
   },
 
@@ -56,12 +60,23 @@ export const useEventStore = create<EventStore>((set) => ({
         observations: event.observations,
         texte_dictation: event.texteDictation,
         source: event.source,
+        audio_path: event.audioPath ?? null,
       };
 
       const id = await invoke<number>('add_event', { event: rustEvent });
       return id;
     } catch (error) {
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
+      return null;
+    }
+  },
+
+  loadEventAudioUrl: async (uuid: string) => {
+    try {
+      const bytes = await invoke<ArrayBuffer>('get_event_audio', { eventUuid: uuid });
+      return URL.createObjectURL(new Blob([bytes], { type: 'audio/wav' }));
+    } catch (error) {
+      set({ error: errorMessage(error) });
       return null;
     }
   },
@@ -90,6 +105,7 @@ interface RawEvent {
   source: string;
   created_at: string;
   synced_at: string | null;
+  has_audio: boolean;
 }
 
 function mapRawEvent(r: RawEvent): PedagogicalEvent {
@@ -108,5 +124,6 @@ function mapRawEvent(r: RawEvent): PedagogicalEvent {
     source: r.source as PedagogicalEvent['source'],
     createdAt: r.created_at,
     syncedAt: r.synced_at,
+    hasAudio: r.has_audio,
   };
 }
@@ -1,6 +1,7 @@
 import { create } from 'zustand';
 import Database from '@tauri-apps/plugin-sql';
 import { useConfigStore } from './configStore';
+import { errorMessage } from '../utils/errors';
 
 export interface Incident {
   id: number;
@@ -85,7 +86,7 @@ export const useIncidentStore = create<IncidentStore>((set, get) => ({
       set({ incidents: rows, isLoading: false });
     } catch (error) {
       console.error('Error loading incidents:', error);
-      set({ error: String(error), isLoading: false });
+      set({ error: errorMessage(error), isLoading: false });
     }
   },
 
@@ -114,7 +115,7 @@ export const useIncidentStore = create<IncidentStore>((set, get) => ({
       return true;
     } catch (error) {
       console.error('Error adding incident:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
       return false;
     }
   },
@@ -151,7 +152,7 @@ export const useIncidentStore = create<IncidentStore>((set, get) => ({
       }
     } catch (error) {
       console.error('Error updating incident:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -162,7 +163,7 @@ export const useIncidentStore = create<IncidentStore>((set, get) => ({
       await get().loadIncidents(eleveId);
     } catch (error) {
       console.error('Error deleting incident:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 }));
@@ -2,6 +2,7 @@ import { create } from 'zustand';
 import Database from '@tauri-apps/plugin-sql';
 import type { StudentWithSanctions, WeekSummary, ExportData, Student, Sanction, DailyReward, Absence, NiveauCode } from '../types';
 import { getCurrentWeek, shouldResetWarnings, markResetDone, shouldResetSanctions, markSanctionResetDone, getCurrentWorkDay, getResetKey } from '../utils/date';
+import { errorMessage } from '../utils/errors';
 
 interface StudentStore {
   students: StudentWithSanctions[];
@@ -174,7 +175,7 @@ export const useStudentStore = create<StudentStore>((set, get) => ({
       set({ students: studentsWithSanctions, isLoading: false });
     } catch (error) {
       console.error('Error loading students:', error);
-      set({ error: String(error), isLoading: false });
+      set({ error: errorMessage(error), isLoading: false });
     }
   },
 
@@ -201,7 +202,7 @@ export const useStudentStore = create<StudentStore>((set, get) => ({
       return true;
     } catch (error) {
       console.error('Error adding student:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
       return false;
     }
   },
@@ -222,7 +223,7 @@ export const useStudentStore = create<StudentStore>((set, get) => ({
       await get().loadStudents();
     } catch (error) {
       console.error('Error updating student:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -233,7 +234,7 @@ export const useStudentStore = create<StudentStore>((set, get) => ({
       await get().loadStudents();
     } catch (error) {
       console.error('Error deleting student:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -264,7 +265,7 @@ export const useStudentStore = create<StudentStore>((set, get) => ({
       await get().loadStudents();
     } catch (error) {
       console.error('Error toggling absence:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -303,7 +304,7 @@ export const useStudentStore = create<StudentStore>((set, get) => ({
       }
     } catch (error) {
       console.error('Error adding warning:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
       return { thirdWarning: false };
     }
   },
@@ -322,7 +323,7 @@ export const useStudentStore = create<StudentStore>((set, get) => ({
       await get().loadStudents();
     } catch (error) {
       console.error('Error removing warning:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -372,7 +373,7 @@ export const useStudentStore = create<StudentStore>((set, get) => ({
       await get().loadStudents();
     } catch (error) {
       console.error('Error adding sanction:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -394,7 +395,7 @@ export const useStudentStore = create<StudentStore>((set, get) => ({
       await get().loadStudents();
     } catch (error) {
       console.error('Error removing sanction:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -408,7 +409,7 @@ export const useStudentStore = create<StudentStore>((set, get) => ({
       await get().loadStudents();
     } catch (error) {
       console.error('Error updating sanction reason:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -422,7 +423,7 @@ export const useStudentStore = create<StudentStore>((set, get) => ({
       await get().loadStudents();
     } catch (error) {
       console.error('Error updating student niveau:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -438,7 +439,7 @@ export const useStudentStore = create<StudentStore>((set, get) => ({
       await get().loadStudents();
     } catch (error) {
       console.error('Error batch updating niveaux:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -449,7 +450,7 @@ export const useStudentStore = create<StudentStore>((set, get) => ({
       await get().loadStudents();
     } catch (error) {
       console.error('Error resetting warnings:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -511,7 +512,7 @@ export const useStudentStore = create<StudentStore>((set, get) => ({
       console.log('Daily rewards triggered for day', dayOfWeek);
     } catch (error) {
       console.error('Error triggering daily rewards:', error);
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -1,21 +1,40 @@
-import { create } from 'zustand';
+import { create, type StoreApi } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
-import type { Synthese, SyntheseVersion } from '../types';
+import type { Candidate, RuleViolation, Synthese, SyntheseResult, SyntheseVersion } from '../types';
+import { errorMessage } from '../utils/errors';
+import { cancelGeneration, generationError, streamGeneration } from '../utils/llmStream';
 
 interface SyntheseStore {
   syntheses: Record<number, Synthese>;   // domaineId -> derniere synthese
   versions: SyntheseVersion[];
+  violations: Record<number, RuleViolation[]>;   // domaineId -> regles LSU non respectees par la derniere generation
+  candidates: Record<number, PendingCandidates>;   // domaineId -> propositions en attente de choix
+  generations: Record<number, StreamingGeneration>;   // domaineId -> generation en cours (texte affiche au fil de l'eau)
   isGenerating: boolean;
   error: string | null;
 
   loadForStudent(eleveId: number, periodeId: number, anneeScolaireId: number, domaineIds: number[]): Promise<void>;
-  saveSynthese(eleveId: number, domaineId: number, periodeId: number, anneeScolaireId: number, texte: string, generatedBy: string): Promise<Synthese>;
+  saveSynthese(eleveId: number, domaineId: number, periodeId: number, anneeScolaireId: number, texte: string, generatedBy: string, promptTemplateId?: number | null): Promise<Synthese>;
   generateAndSave(eleveId: number, domaineId: number, periodeId: number, anneeScolaireId: number, studentName: string): Promise<Synthese>;
+  generateCandidates(eleveId: number, domaineId: number, periodeId: number, anneeScolaireId: number, studentName: string, count: number): Promise<void>;
+  chooseCandidate(eleveId: number, domaineId: number, periodeId: number, anneeScolaireId: number, index: number): Promise<Synthese>;
+  discardCandidates(domaineId: number): void;
+  cancelGeneration(domaineId: number): Promise<void>;
   loadVersions(eleveId: number, domaineId: number, periodeId: number, anneeScolaireId: number): Promise<void>;
   restoreVersion(eleveId: number, domaineId: number, periodeId: number, anneeScolaireId: number, versionId: number): Promise<void>;
   clearState(): void;
 }
 
+interface PendingCandidates {
+  list: Candidate[];
+  promptTemplateId: number;
+}
+
+interface StreamingGeneration {
+  requestId: string;
+  text: string;
+}
+
 interface RawSynthese {
   id: number;
   eleve_id: number;
@@ -25,6 +44,7 @@ interface RawSynthese {
   version: number;
   texte: string;
   generated_by: string;
+  prompt_template_id: number | null;
   created_at: string;
 }
 
@@ -46,6 +66,7 @@ function mapRawSynthese(r: RawSynthese): Synthese {
     version: r.version,
     texte: r.texte,
     generatedBy: r.generated_by as 'llm' | 'manual',
+    promptTemplateId: r.prompt_template_id,
     createdAt: r.created_at,
   };
 }
@@ -60,9 +81,37 @@ function mapRawVersion(r: RawSyntheseVersion): SyntheseVersion {
   };
 }
 
+/** generate_synthese streamed into `generations[domaineId]`, cleared once the command returns */
+function streamSynthese(
+  set: StoreApi<SyntheseStore>['setState'],
+  domaineId: number,
+  args: Record<string, unknown>
+): Promise<SyntheseResult> {
+  return streamGeneration(
+    (requestId) => {
+      set((state) => ({ generations: { ...state.generations, [domaineId]: { requestId, text: '' } } }));
+      return invoke<SyntheseResult>('generate_synthese', { ...args, requestId });
+    },
+    (text) =>
+      set((state) => {
+        const generation = state.generations[domaineId];
+        return generation ? { generations: { ...state.generations, [domaineId]: { ...generation, text } } } : {};
+      })
+  ).finally(() =>
+    set((state) => {
+      const generations = { ...state.generations };
+      delete generations[domaineId];
+      return { generations };
+    })
+  );
+}
+
 export const useSyntheseStore = create<SyntheseStore>((set, get) => ({
   syntheses: {},
   versions: [],
+  violations: {},
+  candidates: {},
+  generations: {},
   isGenerating: false,
   error: null,
 
@@ -89,11 +138,11 @@ export const useSyntheseStore = create<SyntheseStore>((set, get) => ({
 
       set({ syntheses });
     } catch (error) {
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
-  saveSynthese: async (eleveId, domaineId, periodeId, anneeScolaireId, texte, generatedBy) => {
+  saveSynthese: async (eleveId, domaineId, periodeId, anneeScolaireId, texte, generatedBy, promptTemplateId = null) => {
     try {
       const raw = await invoke<RawSynthese>('save_synthese', {
         eleveId,
@@ -102,6 +151,7 @@ export const useSyntheseStore = create<SyntheseStore>((set, get) => ({
         anneeScolaireId,
         texte,
         generatedBy,
+        promptTemplateId,
       });
       const synthese = mapRawSynthese(raw);
       set((state) => ({
@@ -109,7 +159,7 @@ export const useSyntheseStore = create<SyntheseStore>((set, get) => ({
       }));
       return synthese;
     } catch (error) {
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
       throw error;
     }
   },
@@ -117,7 +167,7 @@ export const useSyntheseStore = create<SyntheseStore>((set, get) => ({
   generateAndSave: async (eleveId, domaineId, periodeId, anneeScolaireId, studentName) => {
     set({ isGenerating: true, error: null });
     try {
-      const result = await invoke<{ synthese: string; duration_ms: number }>('generate_synthese', {
+      const result = await streamSynthese(set, domaineId, {
         eleveId,
         domaineId,
         periodeId,
@@ -130,16 +180,83 @@ export const useSyntheseStore = create<SyntheseStore>((set, get) => ({
         periodeId,
         anneeScolaireId,
         result.synthese,
-        'llm'
+        'llm',
+        result.prompt_template_id
       );
-      set({ isGenerating: false });
+      set((state) => ({
+        isGenerating: false,
+        violations: { ...state.violations, [domaineId]: result.violations },
+      }));
       return synthese;
     } catch (error) {
-      set({ isGenerating: false, error: String(error) });
+      set({ isGenerating: false, error: generationError(error) });
       throw error;
     }
   },
 
+  generateCandidates: async (eleveId, domaineId, periodeId, anneeScolaireId, studentName, count) => {
+    set({ isGenerating: true, error: null });
+    try {
+      const result = await streamSynthese(set, domaineId, {
+        eleveId,
+        domaineId,
+        periodeId,
+        anneeScolaireId,
+        studentName,
+        candidates: count,
+      });
+      set((state) => ({
+        isGenerating: false,
+        candidates: {
+          ...state.candidates,
+          [domaineId]: { list: result.candidates, promptTemplateId: result.prompt_template_id },
+        },
+      }));
+    } catch (error) {
+      set({ isGenerating: false, error: generationError(error) });
+      throw error;
+    }
+  },
+
+  chooseCandidate: async (eleveId, domaineId, periodeId, anneeScolaireId, index) => {
+    const pending = get().candidates[domaineId];
+    const candidate = pending?.list[index];
+    if (!pending || !candidate) {
+      throw new Error('Proposition introuvable');
+    }
+    const synthese = await get().saveSynthese(
+      eleveId,
+      domaineId,
+      periodeId,
+      anneeScolaireId,
+      candidate.text,
+      'llm',
+      pending.promptTemplateId
+    );
+    // Les autres propositions sont abandonnees
+    set((state) => {
+      const candidates = { ...state.candidates };
+      delete candidates[domaineId];
+      return { candidates, violations: { ...state.violations, [domaineId]: candidate.violations } };
+    });
+    return synthese;
+  },
+
+  discardCandidates: (domaineId) => {
+    set((state) => {
+      const candidates = { ...state.candidates };
+      delete candidates[domaineId];
+      return { candidates };
+    });
+  },
+
+  cancelGeneration: async (domaineId) => {
+    const generation = get().generations[domaineId];
+    if (generation) {
+      await cancelGeneration(generation.requestId);
+    }
+  },
+
   loadVersions: async (eleveId, domaineId, periodeId, anneeScolaireId) => {
     set({ error: null });
     try {
@@ -151,7 +268,7 @@ export const useSyntheseStore = create<SyntheseStore>((set, get) => ({
       });
       set({ versions: rows.map(mapRawVersion) });
     } catch (error) {
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
     }
   },
 
@@ -171,12 +288,12 @@ export const useSyntheseStore = create<SyntheseStore>((set, get) => ({
       // Refresh versions list
       await get().loadVersions(eleveId, domaineId, periodeId, anneeScolaireId);
     } catch (error) {
-      set({ error: String(error) });
+      set({ error: errorMessage(error) });
       throw error;
     }
   },
 
   clearState: () => {
-    set({ syntheses: {}, versions: [], isGenerating: false, error: null });
+    set({ syntheses: {}, versions: [], violations: {}, candidates: {}, generations: {}, isGenerating: false, error: null });
   },
 }));
@@ -103,6 +103,82 @@ export interface AnneeScolaire {
 export interface TranscriptionResult {
   text: string;
   duration_ms: number;
+  /** Substitutions du dictionnaire de corrections appliquées au texte */
+  corrections: AppliedCorrection[];
+  /** Identifiant de l'enregistrement de ces substitutions (get_applied_corrections), null si aucune */
+  transcription_id: string | null;
+}
+
+export interface AppliedCorrection {
+  /** null une fois l'entrée du dictionnaire supprimée */
+  correction_id: number | null;
+  motif: string;
+  remplacement: string;
+  occurrences: number;
+}
+
+/** Entrée du dictionnaire de corrections (eleve_id null = toutes les dictées) */
+export interface TranscriptionCorrection {
+  id: number;
+  motif: string;
+  remplacement: string;
+  eleve_id: number | null;
+  eleve_prenom: string | null;
+}
+
+/** Returned by save_wav_file (Web Audio fallback) */
+export interface SavedRecording {
+  path: string;
+  duration_ms: number;
+  /** Saved file format: always 16000 Hz mono */
+  sample_rate: number;
+  channels: number;
+  /** Format received from the browser capture */
+  source_sample_rate: number;
+  source_channels: number;
+  source_bits_per_sample: number;
+  /** Input was converted to PCM16/16kHz/mono */
+  converted: boolean;
+}
+
+/** Terme du glossaire injecté dans le prompt initial de Whisper */
+export interface WhisperGlossaryTerm {
+  id: number;
+  terme: string;
+}
+
+/** Conservation des enregistrements de dictée (voix d'élèves) */
+export type AudioRetentionPolicy =
+  | { mode: 'keep_none' }
+  | { mode: 'keep_days'; days: number }
+  | { mode: 'keep_until_validated' };
+
+export interface AudioStorageUsage {
+  file_count: number;
+  total_bytes: number;
+  /** Age of the oldest recording, in seconds */
+  oldest_age_s: number | null;
+}
+
+export interface AudioPurgeReport {
+  deleted: number;
+  freed_bytes: number;
+}
+
+/** Payload of the `transcription_partial` event (streaming transcription) */
+export interface TranscriptionPartial {
+  session_id: string;
+  segment_index: number;
+  /** Text of this segment only */
+  text: string;
+  /** Stitched text of all segments so far */
+  full_text: string;
+}
+
+/** Result of finish_transcription_stream */
+export interface StreamTranscriptionResult extends TranscriptionResult {
+  /** Recording of the dictation, null when the retention policy keeps no audio */
+  audio_path: string | null;
 }
 
 export interface SidecarInstanceStatus {
@@ -110,6 +186,18 @@ export interface SidecarInstanceStatus {
   port: number | null;
   request_count: number | null;
   uptime_secs: number | null;
+  /** Automatic restarts suspended after repeated crashes */
+  crash_loop: boolean;
+  /** Recent unexpected terminations, oldest first */
+  crashes: SidecarCrashRecord[];
+}
+
+export interface SidecarCrashRecord {
+  at_unix_s: number;
+  exit_code: number | null;
+  signal: number | null;
+  uptime_secs: number;
+  stderr_tail: string[];
 }
 
 export interface SidecarStatusResponse {
@@ -155,9 +243,17 @@ export interface ClassificationResultItem {
   observation_after: string;
 }
 
+/** Prompt tokens against the input budget (exact: counted by the model's tokenizer) */
+export interface TokenUsage {
+  prompt_tokens: number;
+  budget: number;
+  exact: boolean;
+}
+
 export interface ClassificationResults {
   items: ClassificationResultItem[];
   duration_ms: number;
+  usage: TokenUsage;
 }
 
 // Event Sourcing — Journal Pedagogique (V2.1-rev2, ADR-014)
@@ -179,6 +275,8 @@ export interface PedagogicalEvent {
   source: EventSource;
   createdAt: string;
   syncedAt: string | null;
+  /** A dictation recording is attached (re-listen) */
+  hasAudio: boolean;
 }
 
 export interface NewEvent {
@@ -192,6 +290,8 @@ export interface NewEvent {
   observations: string | null;
   texteDictation: string | null;
   source: EventSource;
+  /** Recording of the dictation (vocal events), attached if the retention policy keeps audio */
+  audioPath?: string | null;
 }
 
 export interface EventFilter {
@@ -240,12 +340,31 @@ export interface AbsenceTotaux {
 }
 
 // Model Management (Epic 16)
+export type ModelRole = 'whisper' | 'llama';
+
+export interface ModelCatalogEntry {
+  name: string;
+  label: string;
+  role: ModelRole;
+  filename: string;
+  url: string;
+  sha256: string | null;
+  size_mb: number;
+  recommended_ram_gb: number;
+  ctx_size: number | null;
+  active: boolean;
+  audit_status: 'ok' | 'corrupted' | 'missing' | 'unverified' | null;
+  audited_at: string | null;
+}
+
 export interface ModelInfo {
   name: string;
+  model_name: string;
   filename: string;
   installed: boolean;
   path: string | null;
   expected_size_mb: number;
+  recommended_ram_gb: number;
 }
 
 export interface ModelsCheckResult {
@@ -253,6 +372,7 @@ export interface ModelsCheckResult {
   llama: ModelInfo;
   all_installed: boolean;
   models_dir: string;
+  catalog: ModelCatalogEntry[];
 }
 
 export interface DownloadProgress {
@@ -262,7 +382,7 @@ export interface DownloadProgress {
   percentage: number;
   current_model: number;
   total_models: number;
-  status: 'downloading' | 'verifying' | 'complete' | 'error';
+  status: 'downloading' | 'retrying' | 'verifying' | 'complete' | 'error';
 }
 
 // LSU Vivant — Syntheses (V2.1-rev2, Epic 25)
@@ -275,6 +395,7 @@ export interface Synthese {
   version: number;
   texte: string;
   generatedBy: 'llm' | 'manual';
+  promptTemplateId: number | null;
   createdAt: string;
 }
 
@@ -286,14 +407,64 @@ export interface SyntheseVersion {
   createdAt: string;
 }
 
+/** Règle LSU non respectée par un texte généré (src-tauri/src/sidecar/text_rules.rs) */
+export interface RuleViolation {
+  rule: 'empty' | 'length' | 'third_person' | 'forbidden_word' | 'student_name';
+  message: string;
+}
+
+/** Proposition générée, classée par score du validateur (0 = conforme) */
+export interface Candidate {
+  text: string;
+  violations: RuleViolation[];
+  penalty: number;
+  temperature: number;
+}
+
 export interface SyntheseResult {
   synthese: string;
   duration_ms: number;
+  usage: TokenUsage;
+  prompt_template_id: number;
+  /** Règles encore non respectées après les réécritures (vide si conforme) */
+  violations: RuleViolation[];
+  /** Toutes les propositions, la meilleure en premier */
+  candidates: Candidate[];
 }
 
 export interface AppreciationResult {
   appreciation: string;
   duration_ms: number;
+  usage: TokenUsage;
+  prompt_template_id: number;
+  /** Règles encore non respectées après les réécritures (vide si conforme) */
+  violations: RuleViolation[];
+  /** Toutes les propositions, la meilleure en premier */
+  candidates: Candidate[];
+}
+
+/** Payload of the `llm_delta` event (streamed synthese/appreciation generation) */
+export interface LlmDelta {
+  request_id: string;
+  /** LLM call of the generation, from 0 (candidates, then rewrites) */
+  call: number;
+  /** Raw JSON fragment received */
+  delta: string;
+  /** Text generated so far by this call */
+  text: string;
+}
+
+export type PromptJob = 'classification' | 'synthese' | 'appreciation';
+
+/** Version d'un modèle de prompt (cycle 0 = tous cycles, contenu null = surcharge réinitialisée) */
+export interface PromptTemplate {
+  id: number;
+  job: PromptJob;
+  cycle: number;
+  version: number;
+  contenu: string | null;
+  longueur_max: number;
+  created_at: string;
 }
 
 // LSU Vivant — Appreciation Générale versionnée (V2.1-rev2, Story 25.4)
@@ -305,6 +476,7 @@ export interface AppreciationGenerale {
   version: number;
   texte: string;
   generatedBy: 'llm' | 'manual';
+  promptTemplateId: number | null;
   createdAt: string;
 }
 
@@ -315,3 +487,23 @@ export interface AppreciationGeneraleVersion {
   generatedBy: 'llm' | 'manual';
   createdAt: string;
 }
+
+// Erreur typée renvoyée par les commandes Rust (src-tauri/src/error.rs)
+export type AppErrorCode =
+  | 'YearClosed'
+  | 'NotFound'
+  | 'Validation'
+  | 'SidecarUnavailable'
+  | 'ModelMissing'
+  | 'Db'
+  | 'Io'
+  | 'Network'
+  | 'NoSpeech'
+  | 'Cancelled'
+  | 'Internal';
+
+export interface AppError {
+  code: AppErrorCode;
+  message: string;
+  details: string | null;
+}
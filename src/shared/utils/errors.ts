@@ -0,0 +1,29 @@
+import type { AppError, AppErrorCode } from '../types';
+
+/**
+ * Check if a rejected value is a typed error from a Rust command
+ */
+export function isAppError(error: unknown): error is AppError {
+  return (
+    typeof error === 'object' &&
+    error !== null &&
+    typeof (error as AppError).code === 'string' &&
+    typeof (error as AppError).message === 'string'
+  );
+}
+
+/**
+ * Stable error code, or null for untyped errors (plugin SQL, JS exceptions)
+ */
+export function errorCode(error: unknown): AppErrorCode | null {
+  return isAppError(error) ? error.code : null;
+}
+
+/**
+ * Human-readable message for any rejected value
+ */
+export function errorMessage(error: unknown): string {
+  if (isAppError(error)) return error.message;
+  if (error instanceof Error) return error.message;
+  return String(error);
+}
@@ -0,0 +1,38 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import type { LlmDelta } from '../types';
+import { errorCode, errorMessage } from './errors';
+
+/**
+ * Run a streamed LLM generation: `run` invokes the command with a fresh request id,
+ * `onText` receives the text generated so far while the answer streams in
+ */
+export async function streamGeneration<T>(
+  run: (requestId: string) => Promise<T>,
+  onText: (text: string) => void
+): Promise<T> {
+  const requestId = crypto.randomUUID();
+  const unlisten = await listen<LlmDelta>('llm_delta', (event) => {
+    if (event.payload.request_id === requestId) {
+      onText(event.payload.text);
+    }
+  });
+  try {
+    return await run(requestId);
+  } finally {
+    unlisten();
+  }
+}
+
+/**
+ * Stop a generation, queued or running: the request id is its job id in the LLM
+ * queue (the command then rejects with a `Cancelled` error)
+ */
+export function cancelGeneration(requestId: string): Promise<void> {
+  return invoke<void>('cancel_llm_job', { jobId: requestId });
+}
+
+/** Store error for a failed generation: none when the teacher cancelled it */
+export function generationError(error: unknown): string | null {
+  return errorCode(error) === 'Cancelled' ? null : errorMessage(error);
+}
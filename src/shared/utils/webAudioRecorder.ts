@@ -12,6 +12,8 @@ export interface WebAudioSession {
 
 export interface WebAudioRecordingOptions {
   onAudioLevel?: (level: number) => void;
+  /** Live 16kHz PCM chunks, for streaming transcription */
+  onPcmChunk?: (samples: Int16Array) => void;
 }
 
 /**
@@ -60,6 +62,13 @@ export async function startWebAudioRecording(
     const inputData = event.inputBuffer.getChannelData(0);
     chunks.push(new Float32Array(inputData));
 
+    if (options?.onPcmChunk) {
+      const pcm = audioContext.sampleRate !== TARGET_SAMPLE_RATE
+        ? resample(inputData, audioContext.sampleRate, TARGET_SAMPLE_RATE)
+        : inputData;
+      options.onPcmChunk(float32ToInt16(pcm));
+    }
+
     // Compute RMS audio level (0-1) for VU meter
     if (options?.onAudioLevel) {
       let sum = 0;